@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 // ---- Column Types ----
@@ -78,6 +78,20 @@ pub struct ColumnDef {
     /// For relation: target database folder name
     #[serde(skip_serializing_if = "Option::is_none")]
     pub target: Option<String>,
+    /// For relation: what deleting a target row does to this column (default set_null)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_delete: Option<RelationOnDelete>,
+}
+
+/// Behavior of a relation column when a row it points at is deleted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationOnDelete {
+    /// Remove the deleted row's id from referencing cells
+    #[default]
+    SetNull,
+    /// Refuse to delete a row that is still referenced
+    Restrict,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -105,6 +119,9 @@ pub struct ViewDef {
     /// Sort direction
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sort_desc: Option<bool>,
+    /// Saved filters (array of `{ field, operator, value }`, as accepted by db_query)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filters: Option<JsonValue>,
 }
 
 /// A named row template stored in the database schema.
@@ -136,6 +153,19 @@ pub struct DatabaseSchema {
     /// Auto-incrementing counter for row filenames
     #[serde(default = "default_next_row_id")]
     pub next_row_id: u32,
+    /// Last-used view id, restored when the database is reopened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_view: Option<String>,
+}
+
+impl DatabaseSchema {
+    /// The view to open with: the recorded default if it still exists, else the first view.
+    pub fn active_view(&self) -> Option<&str> {
+        self.default_view
+            .as_deref()
+            .filter(|id| self.views.iter().any(|v| v.id == *id))
+            .or_else(|| self.views.first().map(|v| v.id.as_str()))
+    }
 }
 
 fn default_next_row_id() -> u32 {
@@ -157,8 +187,23 @@ pub struct DatabaseRow {
     pub path: String,
     /// Last modified timestamp (unix seconds)
     pub modified: i64,
+    /// Creation time (RFC 3339), stored in the row's frontmatter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    /// Last update time (RFC 3339), stored in the row's frontmatter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    /// Manual sort position set by `reorder_row`, stored in the row's frontmatter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<f64>,
 }
 
+/// Frontmatter keys holding row timestamps (alongside the schema columns)
+const CREATED_AT_KEY: &str = "created_at";
+const UPDATED_AT_KEY: &str = "updated_at";
+/// Frontmatter key holding the manual sort position
+pub const POSITION_KEY: &str = "position";
+
 /// Summary info about a database (returned by list/scan)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseInfo {
@@ -174,6 +219,13 @@ pub struct DatabaseInfo {
     pub path: String,
 }
 
+/// Database summary together with its full schema (returned by list_with_schemas)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseWithSchema {
+    pub info: DatabaseInfo,
+    pub schema: DatabaseSchema,
+}
+
 // ---- Parsing ----
 
 /// Split YAML frontmatter from markdown body.
@@ -276,9 +328,25 @@ pub fn parse_row(content: &str, path: &str, schema: &DatabaseSchema) -> Result<D
         body,
         path: path.to_string(),
         modified,
+        created_at: raw_timestamp(&raw, CREATED_AT_KEY),
+        updated_at: raw_timestamp(&raw, UPDATED_AT_KEY),
+        position: raw_position(&raw),
     })
 }
 
+/// A non-empty string timestamp from raw row frontmatter.
+fn raw_timestamp(raw: &HashMap<String, JsonValue>, key: &str) -> Option<String> {
+    raw.get(key)
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+}
+
+/// A finite manual position from raw row frontmatter.
+fn raw_position(raw: &HashMap<String, JsonValue>) -> Option<f64> {
+    raw.get(POSITION_KEY).and_then(|v| v.as_f64()).filter(|n| n.is_finite())
+}
+
 /// Serialize a DatabaseRow back to markdown file content.
 pub fn serialize_row(row: &DatabaseRow, schema: &DatabaseSchema) -> Result<String, String> {
     // Build ordered YAML map matching schema column order
@@ -292,6 +360,16 @@ pub fn serialize_row(row: &DatabaseRow, schema: &DatabaseSchema) -> Result<Strin
             yaml_lines.push(format!("{}: {}", col.id, col.col_type.default_value()));
         }
     }
+    for (key, value) in [(CREATED_AT_KEY, &row.created_at), (UPDATED_AT_KEY, &row.updated_at)] {
+        // A user column of the same name takes precedence
+        if let Some(ts) = value.as_ref().filter(|_| !schema.columns.iter().any(|c| c.id == key)) {
+            yaml_lines.push(format!("{}: \"{}\"", key, ts));
+        }
+    }
+    // A user column named "position" takes precedence here too
+    if let Some(pos) = row.position.filter(|_| !schema.columns.iter().any(|c| c.id == POSITION_KEY)) {
+        yaml_lines.push(format!("{}: {}", POSITION_KEY, pos));
+    }
 
     let yaml_block = yaml_lines.join("\n");
     if row.body.is_empty() {
@@ -315,6 +393,15 @@ fn json_value_to_yaml_line(key: &str, value: &JsonValue, col_type: &ColumnType)
                 format!("{}: []", key)
             }
         }
+        ColumnType::Relation if value.is_array() => {
+            let items: Vec<String> = value
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|v| format!("\"{}\"", v.as_str().unwrap_or("")))
+                .collect();
+            format!("{}: [{}]", key, items.join(", "))
+        }
         ColumnType::Checkbox => {
             let b = value.as_bool().unwrap_or(false);
             format!("{}: {}", key, b)
@@ -346,6 +433,26 @@ pub fn is_database_folder(folder: &Path) -> bool {
     folder.join("_schema.md").is_file()
 }
 
+/// Database id for a file inside a database folder (schema or row), if any.
+/// `_schema.md` always counts so that deleting the schema is still reported.
+pub fn database_id_for_path(notes_folder: &Path, path: &Path) -> Option<String> {
+    let rel_dir = path.strip_prefix(notes_folder).ok()?.parent()?;
+    if rel_dir.as_os_str().is_empty() {
+        return None;
+    }
+
+    let is_schema = path.file_name().is_some_and(|n| n == "_schema.md");
+    if !is_schema && !is_database_folder(&notes_folder.join(rel_dir)) {
+        return None;
+    }
+
+    let parts: Vec<String> = rel_dir
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    Some(parts.join("/"))
+}
+
 /// Load the schema from a database folder.
 pub fn load_schema(db_folder: &Path) -> Result<DatabaseSchema, String> {
     let schema_path = db_folder.join("_schema.md");
@@ -385,7 +492,10 @@ fn list_row_files(db_folder: &Path) -> Result<Vec<PathBuf>, String> {
 
 /// Load all rows from a database folder.
 pub fn load_rows(db_folder: &Path, schema: &DatabaseSchema) -> Result<Vec<DatabaseRow>, String> {
-    let row_files = list_row_files(db_folder)?;
+    load_row_files(list_row_files(db_folder)?, schema)
+}
+
+fn load_row_files(row_files: Vec<PathBuf>, schema: &DatabaseSchema) -> Result<Vec<DatabaseRow>, String> {
     let mut rows = Vec::new();
 
     for path in row_files {
@@ -412,13 +522,21 @@ fn next_row_filename(schema: &mut DatabaseSchema) -> String {
 
 /// Scan notes folder for all database folders.
 pub fn scan_databases(notes_folder: &Path) -> Result<Vec<DatabaseInfo>, String> {
+    Ok(scan_databases_with_schemas(notes_folder)?
+        .into_iter()
+        .map(|db| db.info)
+        .collect())
+}
+
+/// Scan notes folder for all database folders, keeping each loaded schema.
+pub fn scan_databases_with_schemas(notes_folder: &Path) -> Result<Vec<DatabaseWithSchema>, String> {
     let mut databases = Vec::new();
 
     if !notes_folder.exists() {
         return Ok(databases);
     }
 
-    fn scan_dir(dir: &Path, base: &Path, results: &mut Vec<DatabaseInfo>) {
+    fn scan_dir(dir: &Path, base: &Path, results: &mut Vec<DatabaseWithSchema>) {
         if let Ok(entries) = std::fs::read_dir(dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
@@ -431,13 +549,14 @@ pub fn scan_databases(notes_folder: &Path) -> Result<Vec<DatabaseInfo>, String>
                             let rel_path = path.strip_prefix(base)
                                 .map(|p| p.to_string_lossy().to_string())
                                 .unwrap_or_else(|_| name.clone());
-                            results.push(DatabaseInfo {
+                            let info = DatabaseInfo {
                                 id: rel_path.clone(),
                                 name: schema.name.clone(),
                                 row_count,
                                 column_count: schema.columns.len(),
                                 path: path.to_string_lossy().to_string(),
-                            });
+                            };
+                            results.push(DatabaseWithSchema { info, schema });
                         }
                     }
                     // Recurse (but not too deep)
@@ -448,7 +567,7 @@ pub fn scan_databases(notes_folder: &Path) -> Result<Vec<DatabaseInfo>, String>
     }
 
     scan_dir(notes_folder, notes_folder, &mut databases);
-    databases.sort_by(|a, b| a.name.cmp(&b.name));
+    databases.sort_by(|a, b| a.info.name.cmp(&b.info.name));
     Ok(databases)
 }
 
@@ -478,6 +597,7 @@ pub fn create_database(
             columns: None,
             sort_by: None,
             sort_desc: None,
+            filters: None,
         }]
     });
 
@@ -487,6 +607,7 @@ pub fn create_database(
         views: default_views,
         templates: HashMap::new(),
         next_row_id: 1,
+        default_view: None,
     };
 
     save_schema(&db_folder, &schema)?;
@@ -512,6 +633,27 @@ pub fn get_database(notes_folder: &Path, db_id: &str) -> Result<(DatabaseSchema,
     Ok((schema, rows))
 }
 
+/// Get one page of a database (rows in filename order) plus the total row count.
+/// Only the requested row files are read, so large databases can be lazy-loaded.
+pub fn get_database_page(
+    notes_folder: &Path,
+    db_id: &str,
+    limit: usize,
+    offset: usize,
+) -> Result<(DatabaseSchema, Vec<DatabaseRow>, usize), String> {
+    let db_folder = notes_folder.join(db_id);
+    if !is_database_folder(&db_folder) {
+        return Err(format!("'{}' is not a database folder", db_id));
+    }
+
+    let schema = load_schema(&db_folder)?;
+    let row_files = list_row_files(&db_folder)?;
+    let total = row_files.len();
+    let page: Vec<PathBuf> = row_files.into_iter().skip(offset).take(limit).collect();
+    let rows = load_row_files(page, &schema)?;
+    Ok((schema, rows, total))
+}
+
 /// Create a new row in a database.
 pub fn create_row(
     notes_folder: &Path,
@@ -530,12 +672,16 @@ pub fn create_row(
     // Save updated schema (incremented next_row_id)
     save_schema(&db_folder, &schema)?;
 
+    let now = now_rfc3339();
     let row = DatabaseRow {
         id: row_filename.clone(),
         fields,
         body: body.unwrap_or_default(),
         path: db_folder.join(format!("{}.md", row_filename)).to_string_lossy().to_string(),
         modified: now_unix_secs(),
+        created_at: Some(now.clone()),
+        updated_at: Some(now),
+        position: None,
     };
 
     let content = serialize_row(&row, &schema)?;
@@ -578,12 +724,17 @@ pub fn update_row(
         merged_fields.insert(k, v);
     }
 
+    // Rows written before timestamps existed fall back to their last mtime
+    let created_at = existing.created_at.or_else(|| unix_to_rfc3339(existing.modified));
     let row = DatabaseRow {
         id: row_id.to_string(),
         fields: merged_fields,
         body: updated_body,
         path: row_path.to_string_lossy().to_string(),
         modified: now_unix_secs(),
+        created_at,
+        updated_at: Some(now_rfc3339()),
+        position: existing.position,
     };
 
     let content = serialize_row(&row, &schema)?;
@@ -593,18 +744,192 @@ pub fn update_row(
     Ok(row)
 }
 
-/// Delete a row from a database.
+/// Check that every field names a schema column and holds a value of that column's type.
+/// Select values must be one of the column's options (an empty select is allowed).
+pub fn validate_fields(schema: &DatabaseSchema, fields: &HashMap<String, JsonValue>) -> Result<(), String> {
+    for (key, value) in fields {
+        let col = schema
+            .columns
+            .iter()
+            .find(|c| c.id == *key)
+            .ok_or_else(|| format!("Unknown column: '{}'", key))?;
+        let options = col.options.as_deref().unwrap_or(&[]);
+        let valid = match col.col_type {
+            ColumnType::Number => value.is_number() || value.is_null(),
+            ColumnType::Checkbox => value.is_boolean(),
+            ColumnType::Select => value
+                .as_str()
+                .is_some_and(|v| v.is_empty() || options.iter().any(|o| o == v)),
+            ColumnType::MultiSelect => value.as_array().is_some_and(|items| {
+                items
+                    .iter()
+                    .all(|item| item.as_str().is_some_and(|v| options.iter().any(|o| o == v)))
+            }),
+            // Relations hold one row id, or a list of them for multi-value relations
+            ColumnType::Relation => {
+                value.is_string() || value.as_array().is_some_and(|items| items.iter().all(|i| i.is_string()))
+            }
+            ColumnType::Text | ColumnType::Date | ColumnType::Url => value.is_string(),
+        };
+        if !valid {
+            return Err(format!(
+                "Invalid value for {} column '{}': {}",
+                col.col_type.as_str(),
+                key,
+                value
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Delete a row from a database. Relation columns (in any database) that point at
+/// the row follow their `on_delete` setting: `restrict` refuses the delete while
+/// references remain, `set_null` removes the id from referencing cells afterwards.
 pub fn delete_row(notes_folder: &Path, db_id: &str, row_id: &str) -> Result<(), String> {
+    delete_rows(notes_folder, db_id, &[row_id.to_string()])
+}
+
+/// Delete several rows at once, with the same `on_delete` handling as `delete_row`.
+/// Restrictions are checked for every row before any file is removed, and databases
+/// are scanned once for the whole batch. References from rows in the batch don't count.
+pub fn delete_rows(notes_folder: &Path, db_id: &str, row_ids: &[String]) -> Result<(), String> {
     let db_folder = notes_folder.join(db_id);
-    let row_path = db_folder.join(format!("{}.md", row_id));
+    for row_id in row_ids {
+        if !db_folder.join(format!("{}.md", row_id)).exists() {
+            return Err(format!("Row '{}' not found in database '{}'", row_id, db_id));
+        }
+    }
+    let removed: HashSet<&str> = row_ids.iter().map(String::as_str).collect();
+
+    // (database id, row id, column id) -> on_delete, for every cell referencing a removed row,
+    // plus (removed row, referencing cell) pairs for restrict errors
+    let mut back_refs: HashMap<(String, String, String), RelationOnDelete> = HashMap::new();
+    let mut restricted: Vec<(String, String)> = Vec::new();
+    for db in scan_databases_with_schemas(notes_folder)? {
+        let columns: Vec<&ColumnDef> = db
+            .schema
+            .columns
+            .iter()
+            .filter(|c| c.col_type == ColumnType::Relation && c.target.as_deref() == Some(db_id))
+            .collect();
+        if columns.is_empty() {
+            continue;
+        }
+        for row in load_rows(Path::new(&db.info.path), &db.schema)? {
+            if db.info.id == db_id && removed.contains(row.id.as_str()) {
+                continue;
+            }
+            for col in &columns {
+                let Some(value) = row.fields.get(&col.id) else { continue };
+                let on_delete = col.on_delete.unwrap_or_default();
+                for target in relation_ids(value).into_iter().filter(|id| removed.contains(id)) {
+                    if on_delete == RelationOnDelete::Restrict {
+                        restricted.push((
+                            target.to_string(),
+                            format!("{}/{} ({})", db.info.id, row.id, col.id),
+                        ));
+                    }
+                    back_refs.insert((db.info.id.clone(), row.id.clone(), col.id.clone()), on_delete);
+                }
+            }
+        }
+    }
 
-    if !row_path.exists() {
-        return Err(format!("Row '{}' not found in database '{}'", row_id, db_id));
+    if !restricted.is_empty() {
+        let mut by_row: Vec<(String, Vec<String>)> = Vec::new();
+        for (target, cell) in restricted {
+            match by_row.iter_mut().find(|(t, _)| *t == target) {
+                Some((_, cells)) => cells.push(cell),
+                None => by_row.push((target, vec![cell])),
+            }
+        }
+        let messages: Vec<String> = by_row
+            .into_iter()
+            .map(|(target, cells)| format!("Row '{}' is still referenced by {}", target, cells.join(", ")))
+            .collect();
+        return Err(messages.join("; "));
+    }
+
+    for row_id in row_ids {
+        std::fs::remove_file(db_folder.join(format!("{}.md", row_id)))
+            .map_err(|e| format!("Failed to delete row: {}", e))?;
+    }
+
+    let mut cells: Vec<(String, String, String)> = back_refs.into_keys().collect();
+    cells.sort();
+    for (ref_db, ref_row, column) in cells {
+        // Re-read per cell: a row can reference removed rows from several columns
+        let ref_path = notes_folder.join(&ref_db).join(format!("{}.md", ref_row));
+        let schema = load_schema(&notes_folder.join(&ref_db))?;
+        let content = std::fs::read_to_string(&ref_path)
+            .map_err(|e| format!("Failed to read row: {}", e))?;
+        let row = parse_row(&content, &ref_path.to_string_lossy(), &schema)?;
+        if let Some(value) = row.fields.get(&column) {
+            let cleared = strip_relation_ids(value, &removed, None);
+            update_row(notes_folder, &ref_db, &ref_row, HashMap::from([(column, cleared)]), None)?;
+        }
     }
 
-    std::fs::remove_file(&row_path)
-        .map_err(|e| format!("Failed to delete row: {}", e))?;
+    Ok(())
+}
 
+/// Move a row to `new_index` (clamped) in the manual order given by `position`.
+/// The moved row gets the midpoint of its new neighbours' positions; when any row
+/// has no position yet (first reorder) or the midpoint can't be represented, all
+/// rows are renumbered 1, 2, 3, ... in their current order instead.
+pub fn reorder_row(
+    notes_folder: &Path,
+    db_id: &str,
+    row_id: &str,
+    new_index: usize,
+) -> Result<(), String> {
+    let (schema, rows) = get_database(notes_folder, db_id)?;
+    // Positioned rows first, the rest in file order
+    let mut ordered: Vec<DatabaseRow> = rows;
+    ordered.sort_by(|a, b| {
+        a.position
+            .unwrap_or(f64::MAX)
+            .partial_cmp(&b.position.unwrap_or(f64::MAX))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let from = ordered
+        .iter()
+        .position(|r| r.id == row_id)
+        .ok_or_else(|| format!("Row '{}' not found in database '{}'", row_id, db_id))?;
+    let moved = ordered.remove(from);
+    let to = new_index.min(ordered.len());
+
+    let prev = to.checked_sub(1).and_then(|i| ordered[i].position);
+    let next = ordered.get(to).and_then(|r| r.position);
+    let all_positioned = ordered.iter().all(|r| r.position.is_some());
+    let midpoint = match (prev, next) {
+        (Some(p), Some(n)) => Some((p + n) / 2.0),
+        (Some(p), None) if to == ordered.len() => Some(p + 1.0),
+        (None, Some(n)) if to == 0 => Some(n - 1.0),
+        (None, None) if ordered.is_empty() => Some(1.0),
+        _ => None,
+    }
+    .filter(|m| all_positioned && prev.is_none_or(|p| *m > p) && next.is_none_or(|n| *m < n));
+
+    let write_position = |row: &DatabaseRow, position: f64| -> Result<(), String> {
+        // Reordering isn't a content edit, so updated_at is left alone
+        let updated = DatabaseRow { position: Some(position), ..row.clone() };
+        std::fs::write(&row.path, serialize_row(&updated, &schema)?)
+            .map_err(|e| format!("Failed to write row file: {}", e))
+    };
+
+    if let Some(position) = midpoint {
+        return write_position(&moved, position);
+    }
+    ordered.insert(to, moved);
+    for (idx, row) in ordered.iter().enumerate() {
+        let position = (idx + 1) as f64;
+        if row.position != Some(position) {
+            write_position(row, position)?;
+        }
+    }
     Ok(())
 }
 
@@ -629,6 +954,33 @@ fn substitute_variables(template_str: &str, variables: &HashMap<String, String>)
     result
 }
 
+/// Variable names a template expects (from `{{name}}` tokens in its title, body and
+/// string fields), in first-seen order. Built-ins like `{{date}}` are excluded.
+pub fn template_variables(template: &RowTemplate) -> Vec<String> {
+    let re = regex::Regex::new(r"\{\{([^}]+)\}\}").unwrap();
+    let mut texts: Vec<&str> = Vec::new();
+    texts.extend(template.title.as_deref());
+    texts.extend(template.body.as_deref());
+    let mut field_keys: Vec<&String> = template.fields.keys().collect();
+    field_keys.sort();
+    for key in field_keys {
+        if let Some(JsonValue::String(s)) = template.fields.get(key) {
+            texts.push(s);
+        }
+    }
+
+    let mut names: Vec<String> = Vec::new();
+    for text in texts {
+        for cap in re.captures_iter(text) {
+            let name = cap[1].to_string();
+            if name != "date" && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
 /// Create a new row from a named template in the database schema.
 /// Variables are substituted into the template's title and body.
 pub fn create_row_from_template(
@@ -692,12 +1044,16 @@ pub fn create_row_from_template(
     let row_filename = next_row_filename(&mut schema);
     save_schema(&db_folder, &schema)?;
 
+    let now = now_rfc3339();
     let row = DatabaseRow {
         id: row_filename.clone(),
         fields,
         body,
         path: db_folder.join(format!("{}.md", row_filename)).to_string_lossy().to_string(),
         modified: now_unix_secs(),
+        created_at: Some(now.clone()),
+        updated_at: Some(now),
+        position: None,
     };
 
     let content = serialize_row(&row, &schema)?;
@@ -725,6 +1081,22 @@ pub fn list_row_templates(
     Ok(templates)
 }
 
+/// Record the last-used view so reopening the database restores it.
+pub fn set_default_view(notes_folder: &Path, db_id: &str, view_id: &str) -> Result<DatabaseSchema, String> {
+    let db_folder = notes_folder.join(db_id);
+    if !is_database_folder(&db_folder) {
+        return Err(format!("'{}' is not a database folder", db_id));
+    }
+
+    let mut schema = load_schema(&db_folder)?;
+    if !schema.views.iter().any(|v| v.id == view_id) {
+        return Err(format!("View '{}' not found in database '{}'", view_id, db_id));
+    }
+    schema.default_view = Some(view_id.to_string());
+    save_schema(&db_folder, &schema)?;
+    Ok(schema)
+}
+
 /// Delete an entire database folder.
 pub fn delete_database(notes_folder: &Path, db_id: &str) -> Result<(), String> {
     let db_folder = notes_folder.join(db_id);
@@ -738,6 +1110,482 @@ pub fn delete_database(notes_folder: &Path, db_id: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Rename a database to `new_name`. With `rename_folder`, the folder (and so the id)
+/// also moves to the new name's slug next to the old one, and relation columns in
+/// every database that targeted the old id are repointed.
+pub fn rename_database(
+    notes_folder: &Path,
+    db_id: &str,
+    new_name: &str,
+    rename_folder: bool,
+) -> Result<DatabaseInfo, String> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        return Err("Database name cannot be empty".to_string());
+    }
+    let mut db_folder = notes_folder.join(db_id);
+    if !is_database_folder(&db_folder) {
+        return Err(format!("'{}' is not a database folder", db_id));
+    }
+
+    let old_schema = load_schema(&db_folder)?;
+    let mut new_id = db_id.to_string();
+    let old_folder = db_folder.clone();
+    if rename_folder {
+        let slug = slugify(new_name);
+        if slug.is_empty() {
+            return Err(format!("'{}' does not produce a valid folder name", new_name));
+        }
+        new_id = match db_id.rsplit_once('/') {
+            Some((parent, _)) => format!("{}/{}", parent, slug),
+            None => slug,
+        };
+        if new_id != db_id {
+            let new_folder = notes_folder.join(&new_id);
+            if new_folder.exists() {
+                return Err(format!("Database folder '{}' already exists", new_id));
+            }
+            std::fs::rename(&db_folder, &new_folder)
+                .map_err(|e| format!("Failed to rename database folder: {}", e))?;
+            db_folder = new_folder;
+        }
+    }
+
+    let mut schema = old_schema.clone();
+    schema.name = new_name.to_string();
+    // Schemas already rewritten, with their previous contents, so a failure can be undone
+    let mut written: Vec<(PathBuf, DatabaseSchema)> = Vec::new();
+    let result = save_schema(&db_folder, &schema).and_then(|_| {
+        if new_id == db_id {
+            return Ok(());
+        }
+        for db in scan_databases_with_schemas(notes_folder)? {
+            let mut other = db.schema.clone();
+            let mut changed = false;
+            for col in other.columns.iter_mut() {
+                if col.col_type == ColumnType::Relation && col.target.as_deref() == Some(db_id) {
+                    col.target = Some(new_id.clone());
+                    changed = true;
+                }
+            }
+            if changed {
+                let path = PathBuf::from(&db.info.path);
+                save_schema(&path, &other)?;
+                written.push((path, db.schema));
+            }
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        for (path, previous) in written.iter().rev() {
+            let _ = save_schema(path, previous);
+        }
+        let _ = save_schema(&db_folder, &old_schema);
+        if db_folder != old_folder {
+            let _ = std::fs::rename(&db_folder, &old_folder);
+        }
+        return Err(e);
+    }
+
+    Ok(DatabaseInfo {
+        id: new_id,
+        name: schema.name.clone(),
+        row_count: list_row_files(&db_folder)?.len(),
+        column_count: schema.columns.len(),
+        path: db_folder.to_string_lossy().to_string(),
+    })
+}
+
+/// Copy a database's schema (columns, views, templates) into a new folder named after
+/// `new_name`, optionally with all rows. Copied rows are renumbered from row-001 and
+/// keep their manual position and created/updated timestamps.
+pub fn duplicate_database(
+    notes_folder: &Path,
+    db_id: &str,
+    new_name: &str,
+    include_rows: bool,
+) -> Result<DatabaseInfo, String> {
+    let (source, rows) = get_database(notes_folder, db_id)?;
+    let mut info = create_database(notes_folder, new_name, source.columns.clone(), Some(source.views.clone()))?;
+
+    let db_folder = notes_folder.join(&info.id);
+    let mut schema = load_schema(&db_folder)?;
+    schema.templates = source.templates.clone();
+    schema.default_view = source.default_view.clone();
+    let copies: Vec<DatabaseRow> = if include_rows {
+        rows.into_iter()
+            .map(|row| {
+                let id = next_row_filename(&mut schema);
+                let path = db_folder.join(format!("{}.md", id)).to_string_lossy().to_string();
+                DatabaseRow { id, path, modified: now_unix_secs(), ..row }
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    save_schema(&db_folder, &schema)?;
+
+    for row in &copies {
+        let content = serialize_row(row, &schema)?;
+        std::fs::write(&row.path, &content)
+            .map_err(|e| format!("Failed to write row file: {}", e))?;
+    }
+    info.row_count = copies.len();
+    Ok(info)
+}
+
+// ---- Relation Integrity ----
+
+/// Relation values rewritten by the "flag" repair strategy start with this, so they
+/// stay visible in the table but are no longer reported as dangling.
+pub const FLAGGED_RELATION_PREFIX: &str = "missing:";
+
+/// A relation cell value that names a row missing from the target database
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelationIssue {
+    pub row_id: String,
+    pub column: String,
+    pub dangling_value: String,
+}
+
+/// Row ids held by a relation cell: a single string or a list of strings.
+fn relation_ids(value: &JsonValue) -> Vec<&str> {
+    match value {
+        JsonValue::String(id) => vec![id.as_str()],
+        JsonValue::Array(items) => items.iter().filter_map(|v| v.as_str()).collect(),
+        _ => Vec::new(),
+    }
+    .into_iter()
+    .filter(|id| !id.is_empty() && !id.starts_with(FLAGGED_RELATION_PREFIX))
+    .collect()
+}
+
+/// Remove `ids` from a relation cell (a single-value cell becomes ""), or with
+/// `flag_prefix` keep them but prefixed.
+fn strip_relation_ids(value: &JsonValue, ids: &HashSet<&str>, flag_prefix: Option<&str>) -> JsonValue {
+    let replace = |id: &str| flag_prefix.map(|prefix| format!("{}{}", prefix, id));
+    match value {
+        JsonValue::Array(items) => JsonValue::Array(
+            items
+                .iter()
+                .filter_map(|v| match v.as_str() {
+                    Some(id) if ids.contains(id) => replace(id).map(JsonValue::String),
+                    _ => Some(v.clone()),
+                })
+                .collect(),
+        ),
+        JsonValue::String(id) if ids.contains(id.as_str()) => {
+            JsonValue::String(replace(id).unwrap_or_default())
+        }
+        other => other.clone(),
+    }
+}
+
+/// Find relation values in `db_id` that point at rows (or databases) that don't exist.
+pub fn check_relations(notes_folder: &Path, db_id: &str) -> Result<Vec<RelationIssue>, String> {
+    let (schema, rows) = get_database(notes_folder, db_id)?;
+    let mut target_rows: HashMap<String, HashSet<String>> = HashMap::new();
+    for col in schema.columns.iter().filter(|c| c.col_type == ColumnType::Relation) {
+        let Some(target) = col.target.as_deref() else { continue };
+        if target_rows.contains_key(target) {
+            continue;
+        }
+        // A missing target database leaves every value dangling
+        let ids = list_row_files(&notes_folder.join(target))
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
+            .collect();
+        target_rows.insert(target.to_string(), ids);
+    }
+
+    let mut issues = Vec::new();
+    for row in &rows {
+        for col in schema.columns.iter().filter(|c| c.col_type == ColumnType::Relation) {
+            let Some(existing) = col.target.as_deref().and_then(|t| target_rows.get(t)) else {
+                continue;
+            };
+            let Some(value) = row.fields.get(&col.id) else { continue };
+            for id in relation_ids(value) {
+                if !existing.contains(id) {
+                    issues.push(RelationIssue {
+                        row_id: row.id.clone(),
+                        column: col.id.clone(),
+                        dangling_value: id.to_string(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(issues)
+}
+
+/// Fix the dangling relation values found by `check_relations`. `"clear"` removes them
+/// (emptying single-value cells); `"flag"` prefixes them with `FLAGGED_RELATION_PREFIX`.
+/// Returns the issues that were repaired.
+pub fn repair_relations(
+    notes_folder: &Path,
+    db_id: &str,
+    strategy: &str,
+) -> Result<Vec<RelationIssue>, String> {
+    let flag = match strategy {
+        "clear" => false,
+        "flag" => true,
+        other => return Err(format!("Unknown repair strategy '{}'. Use 'clear' or 'flag'.", other)),
+    };
+    let issues = check_relations(notes_folder, db_id)?;
+    if issues.is_empty() {
+        return Ok(issues);
+    }
+
+    let (_, rows) = get_database(notes_folder, db_id)?;
+    for row in &rows {
+        let mut patch = HashMap::new();
+        for issue in issues.iter().filter(|i| i.row_id == row.id) {
+            if patch.contains_key(&issue.column) {
+                continue;
+            }
+            let dangling: HashSet<&str> = issues
+                .iter()
+                .filter(|i| i.row_id == row.id && i.column == issue.column)
+                .map(|i| i.dangling_value.as_str())
+                .collect();
+            let prefix = flag.then_some(FLAGGED_RELATION_PREFIX);
+            let repaired = strip_relation_ids(&row.fields[&issue.column], &dangling, prefix);
+            patch.insert(issue.column.clone(), repaired);
+        }
+        if !patch.is_empty() {
+            update_row(notes_folder, db_id, &row.id, patch, None)?;
+        }
+    }
+    Ok(issues)
+}
+
+// ---- Calendar Export ----
+
+// Escape TEXT values per RFC 5545 (backslash, comma, semicolon, newline)
+fn ics_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// Fold a content line at 75 octets (continuations start with a space), never
+// splitting a UTF-8 character
+fn ics_fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out
+}
+
+/// `DTSTART`/`DTEND` properties for a date cell: `YYYY-MM-DD` becomes an all-day event,
+/// values with a time become a one-hour event in floating local time.
+fn ics_event_times(value: &str) -> Option<[String; 2]> {
+    let value = value.trim();
+    for fmt in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(start) = chrono::NaiveDateTime::parse_from_str(value, fmt) {
+            let end = start + chrono::Duration::hours(1);
+            return Some([
+                format!("DTSTART:{}", start.format("%Y%m%dT%H%M%S")),
+                format!("DTEND:{}", end.format("%Y%m%dT%H%M%S")),
+            ]);
+        }
+    }
+    let date = chrono::NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()?;
+    let next = date.succ_opt()?;
+    Some([
+        format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")),
+        format!("DTEND;VALUE=DATE:{}", next.format("%Y%m%d")),
+    ])
+}
+
+/// Render a database as an iCalendar feed with one VEVENT per row that has a date in
+/// `date_column`, titled by `title_column`. Both default to the first date / text
+/// column in the schema.
+pub fn database_to_ics(
+    notes_folder: &Path,
+    db_id: &str,
+    date_column: Option<&str>,
+    title_column: Option<&str>,
+) -> Result<String, String> {
+    let (schema, rows) = get_database(notes_folder, db_id)?;
+    let pick = |requested: Option<&str>, col_type: ColumnType| -> Result<Option<String>, String> {
+        match requested {
+            Some(id) => schema
+                .columns
+                .iter()
+                .find(|c| c.id == id)
+                .map(|c| Some(c.id.clone()))
+                .ok_or_else(|| format!("Unknown column: '{}'", id)),
+            None => Ok(schema.columns.iter().find(|c| c.col_type == col_type).map(|c| c.id.clone())),
+        }
+    };
+    let date_col = pick(date_column, ColumnType::Date)?
+        .ok_or_else(|| format!("Database '{}' has no date column", db_id))?;
+    let title_col = pick(title_column, ColumnType::Text)?;
+
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Scratch//Database Calendar//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", ics_escape(&schema.name)),
+    ];
+    for row in &rows {
+        let Some(times) = row.fields.get(&date_col).and_then(|v| v.as_str()).and_then(ics_event_times) else {
+            continue;
+        };
+        let title = title_col
+            .as_ref()
+            .and_then(|c| row.fields.get(c))
+            .and_then(|v| v.as_str())
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or(&row.id);
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}-{}@scratch", slugify(db_id), row.id));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.extend(times);
+        lines.push(format!("SUMMARY:{}", ics_escape(title)));
+        if !row.body.trim().is_empty() {
+            lines.push(format!("DESCRIPTION:{}", ics_escape(row.body.trim())));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut ics: String = lines.iter().map(|l| ics_fold(l)).collect::<Vec<_>>().join("\r\n");
+    ics.push_str("\r\n");
+    Ok(ics)
+}
+
+// ---- JSON Export / Import ----
+
+/// A row as stored in a JSON export (no machine-specific path or mtime)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedRow {
+    pub id: String,
+    pub fields: HashMap<String, JsonValue>,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<f64>,
+}
+
+/// Lossless single-file form of a database: full schema plus every row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseExport {
+    pub schema: DatabaseSchema,
+    pub rows: Vec<ExportedRow>,
+}
+
+/// Serialize a database (schema + rows) to pretty JSON.
+pub fn export_database_json(notes_folder: &Path, db_id: &str) -> Result<String, String> {
+    let (schema, rows) = get_database(notes_folder, db_id)?;
+    let export = DatabaseExport {
+        schema,
+        rows: rows
+            .into_iter()
+            .map(|r| ExportedRow {
+                id: r.id,
+                fields: r.fields,
+                body: r.body,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+                position: r.position,
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&export).map_err(|e| format!("Failed to serialize database: {}", e))
+}
+
+/// Folder name for a new database: the slugified name, with `-2`, `-3`, ... appended
+/// while a folder of that name already exists.
+fn unique_database_slug(notes_folder: &Path, name: &str) -> String {
+    let mut base = slugify(name);
+    if base.is_empty() {
+        base = "database".to_string();
+    }
+    let mut slug = base.clone();
+    let mut n = 2;
+    while notes_folder.join(&slug).exists() {
+        slug = format!("{}-{}", base, n);
+        n += 1;
+    }
+    slug
+}
+
+/// Recreate a database from `export_database_json` output in a new folder.
+pub fn import_database_json(notes_folder: &Path, json: &str) -> Result<DatabaseInfo, String> {
+    let export: DatabaseExport =
+        serde_json::from_str(json).map_err(|e| format!("Invalid database JSON: {}", e))?;
+    // Round-trip through the schema parser so imports get the same validation as _schema.md
+    let mut schema = parse_schema(&serialize_schema(&export.schema)?)?;
+
+    // Ids name the row files, so a repeat (even in case only) would overwrite an earlier row
+    let mut seen_ids = HashSet::new();
+    for row in &export.rows {
+        if row.id.is_empty() || row.id.contains(['/', '\\']) || row.id.starts_with('.') {
+            return Err(format!("Invalid row id: '{}'", row.id));
+        }
+        if !seen_ids.insert(row.id.to_lowercase()) {
+            return Err(format!("Duplicate row id: '{}'", row.id));
+        }
+    }
+    // Keep the filename counter ahead of every imported row-NNN id
+    let max_row_num = export
+        .rows
+        .iter()
+        .filter_map(|r| r.id.strip_prefix("row-")?.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0);
+    schema.next_row_id = schema.next_row_id.max(max_row_num + 1);
+
+    let slug = unique_database_slug(notes_folder, &schema.name);
+    let db_folder = notes_folder.join(&slug);
+    std::fs::create_dir_all(&db_folder)
+        .map_err(|e| format!("Failed to create database folder: {}", e))?;
+    save_schema(&db_folder, &schema)?;
+
+    for exported in &export.rows {
+        let row = DatabaseRow {
+            id: exported.id.clone(),
+            fields: exported.fields.clone(),
+            body: exported.body.clone(),
+            path: String::new(),
+            modified: 0,
+            created_at: exported.created_at.clone(),
+            updated_at: exported.updated_at.clone(),
+            position: exported.position,
+        };
+        let content = serialize_row(&row, &schema)?;
+        std::fs::write(db_folder.join(format!("{}.md", row.id)), content)
+            .map_err(|e| format!("Failed to write row file: {}", e))?;
+    }
+
+    Ok(DatabaseInfo {
+        id: slug,
+        name: schema.name.clone(),
+        row_count: export.rows.len(),
+        column_count: schema.columns.len(),
+        path: db_folder.to_string_lossy().to_string(),
+    })
+}
+
 // ---- Schema Migration ----
 
 /// Add a new column to a database schema and update all existing rows.
@@ -910,6 +1758,9 @@ pub fn rename_column(
             body,
             path: path_str,
             modified: now_unix_secs(),
+            created_at: raw_timestamp(&raw, CREATED_AT_KEY),
+            updated_at: raw_timestamp(&raw, UPDATED_AT_KEY),
+            position: raw_position(&raw),
         };
 
         let updated_content = serialize_row(&row, &schema)?;
@@ -920,6 +1771,294 @@ pub fn rename_column(
     Ok(schema)
 }
 
+/// Result of `change_column_type`: the updated schema plus rows whose value could
+/// not be converted (those keep their raw value).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnTypeChange {
+    #[serde(flatten)]
+    pub schema: DatabaseSchema,
+    pub unconverted_rows: Vec<String>,
+}
+
+/// Whether converting `from` -> `to` can drop information for some values.
+fn is_lossy_conversion(from: &ColumnType, to: &ColumnType) -> bool {
+    use ColumnType::*;
+    !matches!(
+        (from, to),
+        (Select, MultiSelect)
+            | (Select, Text)
+            | (MultiSelect, Text)
+            | (Text, Number)
+            | (Text, Date)
+            | (Text, Url)
+            | (Text, Checkbox)
+            | (Text, Select)
+            | (Number, Text)
+            | (Date, Text)
+            | (Url, Text)
+            | (Checkbox, Text)
+    )
+}
+
+/// Convert one cell value. `None` means it cannot be converted safely (keep raw).
+fn convert_cell(value: &JsonValue, from: &ColumnType, to: &ColumnType) -> Option<JsonValue> {
+    use ColumnType::*;
+    let text = |v: &JsonValue| match v {
+        JsonValue::String(s) => s.clone(),
+        JsonValue::Null => String::new(),
+        other => other.to_string(),
+    };
+    match (from, to) {
+        (Select, MultiSelect) => Some(match value {
+            JsonValue::Array(_) => value.clone(),
+            JsonValue::String(s) if s.is_empty() => JsonValue::Array(Vec::new()),
+            JsonValue::Null => JsonValue::Array(Vec::new()),
+            other => JsonValue::Array(vec![JsonValue::String(text(other))]),
+        }),
+        (MultiSelect, Select) => match value {
+            JsonValue::Array(items) => Some(items.first().cloned().unwrap_or(JsonValue::String(String::new()))),
+            other => Some(JsonValue::String(text(other))),
+        },
+        (MultiSelect, Text) => match value {
+            JsonValue::Array(items) => Some(JsonValue::String(
+                items.iter().map(text).collect::<Vec<_>>().join(", "),
+            )),
+            other => Some(JsonValue::String(text(other))),
+        },
+        (Text, Number) => {
+            let s = text(value);
+            let s = s.trim();
+            if s.is_empty() {
+                return Some(default_json_value(&Number));
+            }
+            let n: f64 = s.parse().ok()?;
+            if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+                Some(JsonValue::from(n as i64))
+            } else {
+                serde_json::Number::from_f64(n).map(JsonValue::Number)
+            }
+        }
+        (Text, Date) => {
+            let s = text(value);
+            let s = s.trim();
+            if s.is_empty() || chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok() {
+                Some(JsonValue::String(s.to_string()))
+            } else {
+                None
+            }
+        }
+        (Text, Checkbox) => match text(value).trim().to_lowercase().as_str() {
+            "true" | "yes" | "1" | "x" => Some(JsonValue::Bool(true)),
+            "false" | "no" | "0" | "" => Some(JsonValue::Bool(false)),
+            _ => None,
+        },
+        (Checkbox, Text) => Some(JsonValue::String(match value {
+            JsonValue::Bool(b) => b.to_string(),
+            other => text(other),
+        })),
+        (_, Text) | (_, Url) | (_, Select) => Some(JsonValue::String(text(value))),
+        // Anything else cannot be mapped; a forced conversion resets the value
+        _ => None,
+    }
+}
+
+/// Change a column's type, converting every row's value. Lossy conversions need `force`
+/// (forced values that cannot be mapped are reset to the new type's default). All row
+/// files are written and synced first, then swapped in with renames; if a swap fails the
+/// original rows and schema are restored.
+pub fn change_column_type(
+    notes_folder: &Path,
+    db_id: &str,
+    column_id: &str,
+    new_type: ColumnType,
+    force: bool,
+) -> Result<ColumnTypeChange, String> {
+    let db_folder = notes_folder.join(db_id);
+    if !is_database_folder(&db_folder) {
+        return Err(format!("'{}' is not a database folder", db_id));
+    }
+    let mut schema = load_schema(&db_folder)?;
+    let old_schema = schema.clone();
+    let old_type = schema
+        .columns
+        .iter()
+        .find(|c| c.id == column_id)
+        .map(|c| c.col_type.clone())
+        .ok_or_else(|| format!("Column '{}' not found", column_id))?;
+
+    if old_type == new_type {
+        return Ok(ColumnTypeChange { schema, unconverted_rows: Vec::new() });
+    }
+    if new_type == ColumnType::Relation {
+        return Err("Converting to a relation column is not supported".to_string());
+    }
+    if is_lossy_conversion(&old_type, &new_type) && !force {
+        return Err(format!(
+            "Converting '{}' from {} to {} may lose data; pass force to convert anyway",
+            column_id,
+            old_type.as_str(),
+            new_type.as_str()
+        ));
+    }
+
+    // Convert every row in memory first
+    let mut converted: Vec<(PathBuf, String, HashMap<String, JsonValue>, bool)> = Vec::new();
+    let mut unconverted_rows = Vec::new();
+    let mut seen_options: Vec<String> = Vec::new();
+    for path in list_row_files(&db_folder)? {
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read row: {}", e))?;
+        let (yaml_str, body) = split_frontmatter(&content)
+            .map_err(|e| format!("Row file '{}': {}", path.display(), e))?;
+        let mut raw: HashMap<String, JsonValue> = serde_yaml::from_str(&yaml_str)
+            .map_err(|e| format!("Row file '{}': {}", path.display(), e))?;
+
+        let value = raw.get(column_id).cloned().unwrap_or(JsonValue::Null);
+        let mut keep_raw = false;
+        let new_value = match convert_cell(&value, &old_type, &new_type) {
+            Some(v) => v,
+            None if force && is_lossy_conversion(&old_type, &new_type) => default_json_value(&new_type),
+            None => {
+                unconverted_rows.push(
+                    path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+                );
+                keep_raw = true;
+                value
+            }
+        };
+
+        let option_values: Vec<&str> = match &new_value {
+            JsonValue::String(s) => vec![s.as_str()],
+            JsonValue::Array(items) => items.iter().filter_map(|v| v.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        for v in option_values {
+            if !v.is_empty() && !seen_options.iter().any(|o| o == v) {
+                seen_options.push(v.to_string());
+            }
+        }
+
+        raw.insert(column_id.to_string(), new_value);
+        converted.push((path, body, raw, keep_raw));
+    }
+
+    // Update the column definition; select types keep/derive their options
+    for col in &mut schema.columns {
+        if col.id != column_id {
+            continue;
+        }
+        col.col_type = new_type.clone();
+        match new_type {
+            ColumnType::Select | ColumnType::MultiSelect => {
+                let mut options = col.options.clone().unwrap_or_default();
+                for v in &seen_options {
+                    if !options.contains(v) {
+                        options.push(v.clone());
+                    }
+                }
+                if options.is_empty() {
+                    return Err(format!(
+                        "Column '{}' has no values to derive {} options from",
+                        column_id,
+                        new_type.as_str()
+                    ));
+                }
+                col.options = Some(options);
+            }
+            _ => col.options = None,
+        }
+    }
+
+    // Stage every row file next to the original, then swap them in
+    let mut staged: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for (path, body, raw, keep_raw) in converted {
+        let path_str = path.to_string_lossy().to_string();
+        let mut fields = HashMap::new();
+        for col in &schema.columns {
+            if let Some(val) = raw.get(&col.id) {
+                fields.insert(col.id.clone(), val.clone());
+            }
+        }
+        let row = DatabaseRow {
+            id: path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+            fields,
+            body,
+            path: path_str,
+            modified: now_unix_secs(),
+            created_at: raw_timestamp(&raw, CREATED_AT_KEY),
+            updated_at: raw_timestamp(&raw, UPDATED_AT_KEY),
+            position: raw_position(&raw),
+        };
+        let tmp = path.with_extension("md.tmp");
+        // Unconverted values are written in their old form so they survive as-is
+        let row_schema = if keep_raw { &old_schema } else { &schema };
+        let write = serialize_row(&row, row_schema).and_then(|content| write_synced(&tmp, &content));
+        if let Err(e) = write {
+            for (t, _) in &staged {
+                let _ = std::fs::remove_file(t);
+            }
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e);
+        }
+        staged.push((tmp, path));
+    }
+    if let Err(e) = save_schema(&db_folder, &schema) {
+        for (tmp, _) in &staged {
+            let _ = std::fs::remove_file(tmp);
+        }
+        return Err(e);
+    }
+    if let Err(e) = swap_in_staged_rows(&staged) {
+        let _ = save_schema(&db_folder, &old_schema);
+        return Err(e);
+    }
+
+    Ok(ColumnTypeChange { schema, unconverted_rows })
+}
+
+/// Write a file and flush it to disk before returning.
+fn write_synced(path: &Path, content: &str) -> Result<(), String> {
+    use std::io::Write;
+    let mut file = std::fs::File::create(path).map_err(|e| format!("Failed to write row: {}", e))?;
+    file.write_all(content.as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write row: {}", e))
+}
+
+/// Move staged `(tmp, path)` files over their originals. Each original is kept as
+/// `.md.bak` until every swap succeeds; on failure the originals are restored.
+fn swap_in_staged_rows(staged: &[(PathBuf, PathBuf)]) -> Result<(), String> {
+    let mut swapped: Vec<(&PathBuf, PathBuf)> = Vec::new();
+    let mut result = Ok(());
+    for (tmp, path) in staged {
+        let bak = path.with_extension("md.bak");
+        if let Err(e) = std::fs::rename(path, &bak) {
+            result = Err(format!("Failed to update row: {}", e));
+            break;
+        }
+        if let Err(e) = std::fs::rename(tmp, path) {
+            let _ = std::fs::rename(&bak, path);
+            result = Err(format!("Failed to update row: {}", e));
+            break;
+        }
+        swapped.push((path, bak));
+    }
+
+    if result.is_err() {
+        for (path, bak) in swapped.iter().rev() {
+            let _ = std::fs::rename(bak, path);
+        }
+        for (tmp, _) in staged {
+            let _ = std::fs::remove_file(tmp);
+        }
+    } else {
+        for (_, bak) in &swapped {
+            let _ = std::fs::remove_file(bak);
+        }
+    }
+    result
+}
+
 /// Update schema (columns, views, name) — full replacement.
 /// Handles migrations: adds default values for new columns, removes old columns from rows.
 pub fn update_schema(
@@ -935,6 +2074,9 @@ pub fn update_schema(
     if schema.next_row_id == 1 && old_schema.next_row_id > 1 {
         schema.next_row_id = old_schema.next_row_id;
     }
+    if schema.default_view.is_none() {
+        schema.default_view = old_schema.default_view.clone();
+    }
 
     // Determine added and removed columns
     let old_col_ids: std::collections::HashSet<&str> = old_schema.columns.iter().map(|c| c.id.as_str()).collect();
@@ -987,6 +2129,9 @@ pub fn update_schema(
                 body,
                 path: path_str,
                 modified: now_unix_secs(),
+                created_at: raw_timestamp(&raw, CREATED_AT_KEY),
+                updated_at: raw_timestamp(&raw, UPDATED_AT_KEY),
+                position: raw_position(&raw),
             };
 
             let updated_content = serialize_row(&row, &schema)?;
@@ -1017,6 +2162,16 @@ pub fn slugify(name: &str) -> String {
     slug.trim_end_matches('-').to_string()
 }
 
+fn now_rfc3339() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+fn unix_to_rfc3339(secs: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp(secs, 0)
+        .filter(|_| secs > 0)
+        .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
+
 fn now_unix_secs() -> i64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -1113,6 +2268,7 @@ next_row_id: 1
                     col_type: ColumnType::Text,
                     options: None,
                     target: None,
+                    on_delete: None,
                 },
                 ColumnDef {
                     id: "done".to_string(),
@@ -1120,11 +2276,13 @@ next_row_id: 1
                     col_type: ColumnType::Checkbox,
                     options: None,
                     target: None,
+                    on_delete: None,
                 },
             ],
             views: vec![],
             templates: HashMap::new(),
             next_row_id: 2,
+            default_view: None,
         };
 
         let content = "---\ntitle: \"Buy groceries\"\ndone: false\n---\n\nRemember to get milk.\n";
@@ -1146,6 +2304,7 @@ next_row_id: 1
                     col_type: ColumnType::Text,
                     options: None,
                     target: None,
+                    on_delete: None,
                 },
                 ColumnDef {
                     id: "count".to_string(),
@@ -1153,6 +2312,7 @@ next_row_id: 1
                     col_type: ColumnType::Number,
                     options: None,
                     target: None,
+                    on_delete: None,
                 },
                 ColumnDef {
                     id: "tags".to_string(),
@@ -1160,11 +2320,13 @@ next_row_id: 1
                     col_type: ColumnType::MultiSelect,
                     options: Some(vec!["a".into(), "b".into()]),
                     target: None,
+                    on_delete: None,
                 },
             ],
             views: vec![],
             templates: HashMap::new(),
             next_row_id: 1,
+            default_view: None,
         };
 
         let mut fields = HashMap::new();
@@ -1178,6 +2340,9 @@ next_row_id: 1
             body: "Some notes here.\n".to_string(),
             path: "/tmp/test/row-001.md".to_string(),
             modified: 0,
+            created_at: None,
+            updated_at: None,
+            position: None,
         };
 
         let output = serialize_row(&row, &schema).unwrap();
@@ -1326,13 +2491,14 @@ next_row_id: 1
         let schema = DatabaseSchema {
             name: "Test DB".to_string(),
             columns: vec![
-                ColumnDef { id: "title".to_string(), name: "Title".to_string(), col_type: ColumnType::Text, options: None, target: None },
-                ColumnDef { id: "status".to_string(), name: "Status".to_string(), col_type: ColumnType::Select, options: Some(vec!["Backlog".into(), "Done".into()]), target: None },
-                ColumnDef { id: "tags".to_string(), name: "Tags".to_string(), col_type: ColumnType::MultiSelect, options: Some(vec!["bug".into(), "feature".into()]), target: None },
+                ColumnDef { id: "title".to_string(), name: "Title".to_string(), col_type: ColumnType::Text, options: None, target: None, on_delete: None },
+                ColumnDef { id: "status".to_string(), name: "Status".to_string(), col_type: ColumnType::Select, options: Some(vec!["Backlog".into(), "Done".into()]), target: None, on_delete: None },
+                ColumnDef { id: "tags".to_string(), name: "Tags".to_string(), col_type: ColumnType::MultiSelect, options: Some(vec!["bug".into(), "feature".into()]), target: None, on_delete: None },
             ],
             views: vec![],
             templates,
             next_row_id: 1,
+            default_view: None,
         };
 
         save_schema(&db_dir, &schema).unwrap();
@@ -1371,12 +2537,13 @@ next_row_id: 1
         let schema = DatabaseSchema {
             name: "Test".to_string(),
             columns: vec![
-                ColumnDef { id: "title".to_string(), name: "Title".to_string(), col_type: ColumnType::Text, options: None, target: None },
-                ColumnDef { id: "status".to_string(), name: "Status".to_string(), col_type: ColumnType::Select, options: Some(vec!["Backlog".into()]), target: None },
+                ColumnDef { id: "title".to_string(), name: "Title".to_string(), col_type: ColumnType::Text, options: None, target: None, on_delete: None },
+                ColumnDef { id: "status".to_string(), name: "Status".to_string(), col_type: ColumnType::Select, options: Some(vec!["Backlog".into()]), target: None, on_delete: None },
             ],
             views: vec![],
             templates,
             next_row_id: 1,
+            default_view: None,
         };
 
         let serialized = serialize_schema(&schema).unwrap();
@@ -1387,4 +2554,513 @@ next_row_id: 1
         assert_eq!(tmpl.name, "Feature Request");
         assert_eq!(tmpl.title.as_deref(), Some("Feature: {{title}}"));
     }
+
+    #[test]
+    fn test_database_id_for_path() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-dbpath-{}", std::process::id()));
+        let db_dir = dir.join("work").join("tasks");
+        std::fs::create_dir_all(&db_dir).unwrap();
+        std::fs::write(db_dir.join("_schema.md"), "---\nname: Tasks\ncolumns: []\n---\n").unwrap();
+
+        assert_eq!(
+            database_id_for_path(&dir, &db_dir.join("row-001.md")).as_deref(),
+            Some("work/tasks")
+        );
+        assert_eq!(database_id_for_path(&dir, &dir.join("work").join("note.md")), None);
+        assert_eq!(database_id_for_path(&dir, &dir.join("top.md")), None);
+
+        // A removed schema is still attributed to its database
+        std::fs::remove_file(db_dir.join("_schema.md")).unwrap();
+        assert_eq!(
+            database_id_for_path(&dir, &db_dir.join("_schema.md")).as_deref(),
+            Some("work/tasks")
+        );
+        assert_eq!(database_id_for_path(&dir, &db_dir.join("row-001.md")), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_template_variables() {
+        let template = RowTemplate {
+            name: "Meeting".to_string(),
+            title: Some("{{topic}} with {{attendee}}".to_string()),
+            fields: {
+                let mut f = HashMap::new();
+                f.insert("owner".to_string(), json!("{{attendee}}"));
+                f.insert("room".to_string(), json!("{{room}}"));
+                f.insert("count".to_string(), json!(3));
+                f
+            },
+            body: Some("Held on {{date}} about {{topic}}".to_string()),
+        };
+        assert_eq!(template_variables(&template), vec!["topic", "attendee", "room"]);
+
+        // Missing variables substitute empty, unknown ones are ignored
+        let mut vars = HashMap::new();
+        vars.insert("topic".to_string(), "Planning".to_string());
+        vars.insert("extra".to_string(), "unused".to_string());
+        assert_eq!(
+            substitute_variables(template.title.as_deref().unwrap(), &vars),
+            "Planning with "
+        );
+    }
+
+    #[test]
+    fn test_set_default_view() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-view-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let columns = vec![ColumnDef { id: "title".to_string(), name: "Title".to_string(), col_type: ColumnType::Text, options: None, target: None, on_delete: None }];
+        let info = create_database(&dir, "Views DB", columns, None).unwrap();
+
+        let (schema, _) = get_database(&dir, &info.id).unwrap();
+        assert_eq!(schema.default_view, None);
+        assert_eq!(schema.active_view(), Some("default-table"));
+
+        assert!(set_default_view(&dir, &info.id, "missing").is_err());
+        set_default_view(&dir, &info.id, "default-table").unwrap();
+
+        let (schema, _) = get_database(&dir, &info.id).unwrap();
+        assert_eq!(schema.default_view.as_deref(), Some("default-table"));
+        assert!(serialize_schema(&schema).unwrap().contains("default_view: default-table"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_view_filters_roundtrip() {
+        let content = r#"---
+name: Tasks
+columns:
+  - id: title
+    name: Title
+    type: text
+  - id: priority
+    name: Priority
+    type: select
+    options: [High, Low]
+views:
+  - id: mine
+    name: My open high-priority
+    type: table
+    sort_by: title
+    filters:
+      - field: priority
+        operator: eq
+        value: High
+      - field: done
+        operator: neq
+        value: true
+---
+"#;
+        let schema = parse_schema(content).unwrap();
+        let expected = json!([
+            { "field": "priority", "operator": "eq", "value": "High" },
+            { "field": "done", "operator": "neq", "value": true },
+        ]);
+        assert_eq!(schema.views[0].filters.as_ref(), Some(&expected));
+
+        let reparsed = parse_schema(&serialize_schema(&schema).unwrap()).unwrap();
+        assert_eq!(reparsed.views[0].filters.as_ref(), Some(&expected));
+        assert_eq!(reparsed.views[0].sort_by.as_deref(), Some("title"));
+    }
+
+    #[test]
+    fn test_get_database_page() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-page-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let columns = vec![ColumnDef { id: "title".to_string(), name: "Title".to_string(), col_type: ColumnType::Text, options: None, target: None, on_delete: None }];
+        let info = create_database(&dir, "Paged", columns, None).unwrap();
+        for i in 0..5 {
+            let mut fields = HashMap::new();
+            fields.insert("title".to_string(), json!(format!("Row {}", i)));
+            create_row(&dir, &info.id, fields, None).unwrap();
+        }
+
+        let (_, rows, total) = get_database_page(&dir, &info.id, 2, 1).unwrap();
+        assert_eq!(total, 5);
+        let ids: Vec<&str> = rows.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["row-002", "row-003"]);
+
+        let (_, rows, total) = get_database_page(&dir, &info.id, 10, 4).unwrap();
+        assert_eq!((rows.len(), total), (1, 5));
+        let (_, rows, _) = get_database_page(&dir, &info.id, 10, 50).unwrap();
+        assert!(rows.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_change_column_type() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-coltype-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let columns = vec![
+            ColumnDef { id: "title".to_string(), name: "Title".to_string(), col_type: ColumnType::Text, options: None, target: None, on_delete: None },
+            ColumnDef { id: "status".to_string(), name: "Status".to_string(), col_type: ColumnType::Select, options: Some(vec!["Todo".into(), "Done".into()]), target: None, on_delete: None },
+            ColumnDef { id: "estimate".to_string(), name: "Estimate".to_string(), col_type: ColumnType::Text, options: None, target: None, on_delete: None },
+        ];
+        let info = create_database(&dir, "Convert", columns, None).unwrap();
+        for (status, estimate) in [("Todo", "3"), ("", "2.5"), ("Done", "soon")] {
+            let mut fields = HashMap::new();
+            fields.insert("title".to_string(), json!("Task"));
+            fields.insert("status".to_string(), json!(status));
+            fields.insert("estimate".to_string(), json!(estimate));
+            create_row(&dir, &info.id, fields, None).unwrap();
+        }
+
+        let change = change_column_type(&dir, &info.id, "status", ColumnType::MultiSelect, false).unwrap();
+        assert!(change.unconverted_rows.is_empty());
+        let (_, rows) = get_database(&dir, &info.id).unwrap();
+        assert_eq!(rows[0].fields["status"], json!(["Todo"]));
+        assert_eq!(rows[1].fields["status"], json!([]));
+
+        // Rows that fail to parse keep their raw value and are reported
+        let change = change_column_type(&dir, &info.id, "estimate", ColumnType::Number, false).unwrap();
+        assert_eq!(change.unconverted_rows, vec!["row-003"]);
+        let (_, rows) = get_database(&dir, &info.id).unwrap();
+        assert_eq!(rows[0].fields["estimate"], json!(3));
+        assert_eq!(rows[1].fields["estimate"], json!(2.5));
+        assert_eq!(rows[2].fields["estimate"], json!("soon"));
+
+        // Lossy conversions need force
+        assert!(change_column_type(&dir, &info.id, "status", ColumnType::Checkbox, false).is_err());
+        assert!(!dir.join(&info.id).join("row-001.md.tmp").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_change_column_type_rolls_back_failed_swap() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-coltype-rollback-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let columns = vec![
+            ColumnDef { id: "estimate".to_string(), name: "Estimate".to_string(), col_type: ColumnType::Text, options: None, target: None, on_delete: None },
+        ];
+        let info = create_database(&dir, "Rollback", columns, None).unwrap();
+        for estimate in ["1", "2", "3"] {
+            let mut fields = HashMap::new();
+            fields.insert("estimate".to_string(), json!(estimate));
+            create_row(&dir, &info.id, fields, None).unwrap();
+        }
+        let db_folder = dir.join(&info.id);
+        let schema_before = std::fs::read_to_string(db_folder.join("_schema.md")).unwrap();
+        let rows_before: Vec<String> = list_row_files(&db_folder)
+            .unwrap()
+            .iter()
+            .map(|p| std::fs::read_to_string(p).unwrap())
+            .collect();
+
+        // A non-empty directory where the second row's backup goes makes its swap fail
+        std::fs::create_dir_all(db_folder.join("row-002.md.bak").join("blocker")).unwrap();
+        assert!(change_column_type(&dir, &info.id, "estimate", ColumnType::Number, false).is_err());
+
+        assert_eq!(std::fs::read_to_string(db_folder.join("_schema.md")).unwrap(), schema_before);
+        let rows_after: Vec<String> = list_row_files(&db_folder)
+            .unwrap()
+            .iter()
+            .map(|p| std::fs::read_to_string(p).unwrap())
+            .collect();
+        assert_eq!(rows_after, rows_before);
+        for row in ["row-001", "row-002", "row-003"] {
+            assert!(!db_folder.join(format!("{}.md.tmp", row)).exists());
+        }
+        assert!(!db_folder.join("row-001.md.bak").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_scan_databases_with_schemas() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-scan-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let columns = vec![ColumnDef { id: "title".to_string(), name: "Title".to_string(), col_type: ColumnType::Text, options: None, target: None, on_delete: None }];
+        create_database(&dir, "Zeta", columns.clone(), None).unwrap();
+        create_database(&dir, "Alpha", columns, None).unwrap();
+
+        let dbs = scan_databases_with_schemas(&dir).unwrap();
+        let names: Vec<&str> = dbs.iter().map(|db| db.info.name.as_str()).collect();
+        assert_eq!(names, vec!["Alpha", "Zeta"]);
+        assert_eq!(dbs[0].schema.columns[0].id, "title");
+        assert_eq!(scan_databases(&dir).unwrap().len(), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_database_json_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-json-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let columns = vec![
+            ColumnDef { id: "title".to_string(), name: "Title".to_string(), col_type: ColumnType::Text, options: None, target: None, on_delete: None },
+            ColumnDef { id: "tags".to_string(), name: "Tags".to_string(), col_type: ColumnType::MultiSelect, options: Some(vec!["a".into(), "b".into()]), target: None, on_delete: None },
+        ];
+        let info = create_database(&dir, "Shared", columns, None).unwrap();
+        let mut fields = HashMap::new();
+        fields.insert("title".to_string(), json!("First"));
+        fields.insert("tags".to_string(), json!(["a", "b"]));
+        create_row(&dir, &info.id, fields, Some("Notes here".to_string())).unwrap();
+
+        let exported = export_database_json(&dir, &info.id).unwrap();
+        let imported = import_database_json(&dir, &exported).unwrap();
+        assert_eq!(imported.id, "shared-2");
+        assert_eq!(imported.row_count, 1);
+
+        let (schema, rows) = get_database(&dir, &imported.id).unwrap();
+        assert_eq!(schema.columns[1].col_type, ColumnType::MultiSelect);
+        assert_eq!(schema.next_row_id, 2);
+        assert_eq!(rows[0].id, "row-001");
+        assert_eq!(rows[0].fields["tags"], json!(["a", "b"]));
+        assert_eq!(rows[0].body, "Notes here");
+        assert_eq!(import_database_json(&dir, &exported).unwrap().id, "shared-3");
+
+        // Repeated row ids are rejected before anything is written
+        let mut doubled: JsonValue = serde_json::from_str(&exported).unwrap();
+        let row = doubled["rows"][0].clone();
+        doubled["rows"].as_array_mut().unwrap().push(row);
+        let err = import_database_json(&dir, &doubled.to_string()).unwrap_err();
+        assert!(err.contains("Duplicate row id: 'row-001'"), "{}", err);
+        assert!(!dir.join("shared-4").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_duplicate_database() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-dup-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let columns = vec![ColumnDef { id: "title".to_string(), name: "Title".to_string(), col_type: ColumnType::Text, options: None, target: None, on_delete: None }];
+        let info = create_database(&dir, "Tasks", columns, None).unwrap();
+        for title in ["One", "Two"] {
+            let mut fields = HashMap::new();
+            fields.insert("title".to_string(), json!(title));
+            create_row(&dir, &info.id, fields, None).unwrap();
+        }
+
+        let empty = duplicate_database(&dir, &info.id, "Project Tasks", false).unwrap();
+        assert_eq!((empty.id.as_str(), empty.row_count), ("project-tasks", 0));
+        let (schema, rows) = get_database(&dir, &empty.id).unwrap();
+        assert_eq!(schema.next_row_id, 1);
+        assert!(rows.is_empty());
+
+        // Manual order and timestamps carry over to the copy
+        reorder_row(&dir, &info.id, "row-002", 0).unwrap();
+        let (_, source_rows) = get_database(&dir, &info.id).unwrap();
+        let full = duplicate_database(&dir, &info.id, "Copy", true).unwrap();
+        assert_eq!(full.row_count, 2);
+        let (schema, mut rows) = get_database(&dir, &full.id).unwrap();
+        assert_eq!(schema.name, "Copy");
+        assert_eq!(schema.next_row_id, 3);
+        assert_eq!(rows[1].fields["title"], json!("Two"));
+        for (copy, original) in rows.iter().zip(&source_rows) {
+            assert_eq!(copy.position, original.position);
+            assert_eq!(copy.created_at, original.created_at);
+            assert_eq!(copy.updated_at, original.updated_at);
+        }
+        rows.sort_by(|a, b| a.position.partial_cmp(&b.position).unwrap());
+        let titles: Vec<&JsonValue> = rows.iter().map(|r| &r.fields["title"]).collect();
+        assert_eq!(titles, vec![&json!("Two"), &json!("One")]);
+
+        assert!(duplicate_database(&dir, &info.id, "Copy", false).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rename_database() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-rename-db-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let title = ColumnDef { id: "title".to_string(), name: "Title".to_string(), col_type: ColumnType::Text, options: None, target: None, on_delete: None };
+        let tasks = create_database(&dir, "Tasks", vec![title.clone()], None).unwrap();
+        let link = ColumnDef { id: "task".to_string(), name: "Task".to_string(), col_type: ColumnType::Relation, options: None, target: Some("tasks".to_string()), on_delete: None };
+        let projects = create_database(&dir, "Projects", vec![title, link], None).unwrap();
+
+        let info = rename_database(&dir, &tasks.id, "Team Tasks", false).unwrap();
+        assert_eq!((info.id.as_str(), info.name.as_str()), ("tasks", "Team Tasks"));
+
+        let info = rename_database(&dir, "tasks", "Team Tasks", true).unwrap();
+        assert_eq!(info.id, "team-tasks");
+        assert!(!dir.join("tasks").exists());
+        let schema = load_schema(&dir.join(&projects.id)).unwrap();
+        assert_eq!(schema.columns[1].target.as_deref(), Some("team-tasks"));
+
+        assert!(rename_database(&dir, "team-tasks", "Projects", true).is_err());
+        assert!(rename_database(&dir, "team-tasks", "  ", false).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_validate_fields() {
+        let schema = DatabaseSchema {
+            name: "Tasks".to_string(),
+            columns: vec![
+                ColumnDef { id: "status".to_string(), name: "Status".to_string(), col_type: ColumnType::Select, options: Some(vec!["Todo".into(), "Done".into()]), target: None, on_delete: None },
+                ColumnDef { id: "points".to_string(), name: "Points".to_string(), col_type: ColumnType::Number, options: None, target: None, on_delete: None },
+            ],
+            views: Vec::new(),
+            templates: HashMap::new(),
+            next_row_id: 1,
+            default_view: None,
+        };
+        let fields = |k: &str, v: JsonValue| HashMap::from([(k.to_string(), v)]);
+
+        assert!(validate_fields(&schema, &fields("status", json!("Done"))).is_ok());
+        assert!(validate_fields(&schema, &fields("status", json!(""))).is_ok());
+        assert!(validate_fields(&schema, &fields("points", json!(3))).is_ok());
+        assert!(validate_fields(&schema, &fields("status", json!("Blocked"))).is_err());
+        assert!(validate_fields(&schema, &fields("points", json!("3"))).is_err());
+        assert!(validate_fields(&schema, &fields("owner", json!("me"))).is_err());
+    }
+
+    #[test]
+    fn test_check_and_repair_relations() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-relations-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let title = ColumnDef { id: "title".to_string(), name: "Title".to_string(), col_type: ColumnType::Text, options: None, target: None, on_delete: None };
+        let people = create_database(&dir, "People", vec![title.clone()], None).unwrap();
+        let alice = create_row(&dir, &people.id, HashMap::from([("title".to_string(), json!("Alice"))]), None).unwrap();
+        let link = |id: &str| ColumnDef { id: id.to_string(), name: id.to_string(), col_type: ColumnType::Relation, options: None, target: Some("people".to_string()), on_delete: None };
+        let tasks = create_database(&dir, "Tasks", vec![title, link("owner"), link("helpers")], None).unwrap();
+        let mut fields = HashMap::new();
+        fields.insert("owner".to_string(), json!("row-009"));
+        fields.insert("helpers".to_string(), json!([alice.id.clone(), "row-042"]));
+        let task = create_row(&dir, &tasks.id, fields, None).unwrap();
+
+        let issues = check_relations(&dir, &tasks.id).unwrap();
+        let dangling: Vec<(&str, &str)> = issues.iter().map(|i| (i.column.as_str(), i.dangling_value.as_str())).collect();
+        assert_eq!(dangling, vec![("owner", "row-009"), ("helpers", "row-042")]);
+
+        assert_eq!(repair_relations(&dir, &tasks.id, "flag").unwrap().len(), 2);
+        let (_, rows) = get_database(&dir, &tasks.id).unwrap();
+        assert_eq!(rows[0].fields["owner"], json!("missing:row-009"));
+        assert_eq!(rows[0].fields["helpers"], json!([alice.id.clone(), "missing:row-042"]));
+        assert!(check_relations(&dir, &tasks.id).unwrap().is_empty());
+
+        update_row(&dir, &tasks.id, &task.id, HashMap::from([("helpers".to_string(), json!(["row-042"]))]), None).unwrap();
+        repair_relations(&dir, &tasks.id, "clear").unwrap();
+        let (_, rows) = get_database(&dir, &tasks.id).unwrap();
+        assert_eq!(rows[0].fields["helpers"], json!([]));
+        assert!(repair_relations(&dir, &tasks.id, "delete").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_delete_row_relation_on_delete() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-on-delete-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let title = ColumnDef { id: "title".to_string(), name: "Title".to_string(), col_type: ColumnType::Text, options: None, target: None, on_delete: None };
+        let people = create_database(&dir, "People", vec![title.clone()], None).unwrap();
+        let alice = create_row(&dir, &people.id, HashMap::from([("title".to_string(), json!("Alice"))]), None).unwrap();
+        let bob = create_row(&dir, &people.id, HashMap::from([("title".to_string(), json!("Bob"))]), None).unwrap();
+        let link = |id: &str, on_delete| ColumnDef { id: id.to_string(), name: id.to_string(), col_type: ColumnType::Relation, options: None, target: Some("people".to_string()), on_delete };
+        let tasks = create_database(&dir, "Tasks", vec![title, link("owner", None), link("reviewer", Some(RelationOnDelete::Restrict))], None).unwrap();
+        let mut fields = HashMap::new();
+        fields.insert("owner".to_string(), json!([alice.id.clone(), bob.id.clone()]));
+        fields.insert("reviewer".to_string(), json!(bob.id.clone()));
+        create_row(&dir, &tasks.id, fields, None).unwrap();
+
+        // set_null (the default) clears the id from the owner list
+        delete_row(&dir, &people.id, &alice.id).unwrap();
+        let (_, rows) = get_database(&dir, &tasks.id).unwrap();
+        assert_eq!(rows[0].fields["owner"], json!([bob.id.clone()]));
+
+        // restrict on reviewer blocks deleting Bob
+        let err = delete_row(&dir, &people.id, &bob.id).unwrap_err();
+        assert!(err.contains("tasks/row-001 (reviewer)"), "{}", err);
+        assert!(dir.join("people").join(format!("{}.md", bob.id)).exists());
+
+        // A restricted row in a batch stops the whole batch before anything is deleted
+        let carol = create_row(&dir, &people.id, HashMap::from([("title".to_string(), json!("Carol"))]), None).unwrap();
+        let mut fields = HashMap::new();
+        fields.insert("owner".to_string(), json!([carol.id.clone()]));
+        create_row(&dir, &tasks.id, fields, None).unwrap();
+        let err = delete_rows(&dir, &people.id, &[carol.id.clone(), bob.id.clone()]).unwrap_err();
+        assert!(err.contains(&format!("Row '{}'", bob.id)), "{}", err);
+        assert!(dir.join("people").join(format!("{}.md", carol.id)).exists());
+        let (_, rows) = get_database(&dir, &tasks.id).unwrap();
+        assert_eq!(rows[1].fields["owner"], json!([carol.id.clone()]));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_reorder_row() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-reorder-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let title = ColumnDef { id: "title".to_string(), name: "Title".to_string(), col_type: ColumnType::Text, options: None, target: None, on_delete: None };
+        let info = create_database(&dir, "Checklist", vec![title], None).unwrap();
+        for name in ["a", "b", "c"] {
+            create_row(&dir, &info.id, HashMap::from([("title".to_string(), json!(name))]), None).unwrap();
+        }
+        let order = || {
+            let (_, mut rows) = get_database(&dir, &info.id).unwrap();
+            rows.sort_by(|a, b| a.position.partial_cmp(&b.position).unwrap());
+            rows.iter().map(|r| (r.fields["title"].as_str().unwrap().to_string(), r.position.unwrap())).collect::<Vec<_>>()
+        };
+
+        // First reorder numbers every row by file order
+        reorder_row(&dir, &info.id, "row-003", 0).unwrap();
+        assert_eq!(order(), vec![("c".into(), 1.0), ("a".into(), 2.0), ("b".into(), 3.0)]);
+
+        // Later moves only touch the moved row
+        reorder_row(&dir, &info.id, "row-002", 1).unwrap();
+        assert_eq!(order(), vec![("c".into(), 1.0), ("b".into(), 1.5), ("a".into(), 2.0)]);
+        reorder_row(&dir, &info.id, "row-003", 10).unwrap();
+        assert_eq!(order(), vec![("b".into(), 1.5), ("a".into(), 2.0), ("c".into(), 3.0)]);
+
+        let (_, rows) = get_database(&dir, &info.id).unwrap();
+        assert!(rows.iter().all(|r| r.updated_at == r.created_at));
+        assert!(reorder_row(&dir, &info.id, "row-404", 0).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_database_to_ics() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-ics-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let col = |id: &str, col_type| ColumnDef { id: id.to_string(), name: id.to_string(), col_type, options: None, target: None, on_delete: None };
+        let info = create_database(&dir, "Tasks", vec![col("title", ColumnType::Text), col("due", ColumnType::Date)], None).unwrap();
+        let row = |title: &str, due: &str| HashMap::from([("title".to_string(), json!(title)), ("due".to_string(), json!(due))]);
+        create_row(&dir, &info.id, row("Ship, then rest", "2026-03-09"), None).unwrap();
+        create_row(&dir, &info.id, row("Standup", "2026-03-10T09:30"), None).unwrap();
+        create_row(&dir, &info.id, row("Someday", ""), None).unwrap();
+
+        let ics = database_to_ics(&dir, &info.id, None, None).unwrap();
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n") && ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("SUMMARY:Ship\\, then rest\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20260309\r\nDTEND;VALUE=DATE:20260310"));
+        assert!(ics.contains("DTSTART:20260310T093000\r\nDTEND:20260310T103000"));
+        assert!(ics.contains("UID:tasks-row-001@scratch"));
+
+        assert!(database_to_ics(&dir, &info.id, Some("missing"), None).is_err());
+        assert!(database_to_ics(&dir, &info.id, Some("title"), None).unwrap().matches("BEGIN:VEVENT").count() == 0);
+        assert_eq!(ics_fold(&"x".repeat(80)), format!("{}\r\n {}", "x".repeat(75), "x".repeat(5)));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_row_timestamps() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-timestamps-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let columns = vec![ColumnDef { id: "title".to_string(), name: "Title".to_string(), col_type: ColumnType::Text, options: None, target: None, on_delete: None }];
+        let info = create_database(&dir, "Stamped", columns, None).unwrap();
+
+        let row = create_row(&dir, &info.id, HashMap::new(), None).unwrap();
+        assert!(row.created_at.is_some());
+        assert_eq!(row.created_at, row.updated_at);
+
+        // An existing created_at is preserved across updates
+        let row_path = dir.join(&info.id).join("row-001.md");
+        std::fs::write(&row_path, "---\ntitle: \"Old\"\ncreated_at: \"2020-01-02T03:04:05Z\"\n---\n").unwrap();
+        let mut fields = HashMap::new();
+        fields.insert("title".to_string(), json!("New"));
+        let updated = update_row(&dir, &info.id, "row-001", fields, None).unwrap();
+        assert_eq!(updated.created_at.as_deref(), Some("2020-01-02T03:04:05Z"));
+        assert_ne!(updated.updated_at.as_deref(), Some("2020-01-02T03:04:05Z"));
+
+        let (_, rows) = get_database(&dir, &info.id).unwrap();
+        assert_eq!(rows[0].created_at.as_deref(), Some("2020-01-02T03:04:05Z"));
+        assert_eq!(rows[0].updated_at, updated.updated_at);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -46,12 +46,52 @@ pub fn git_init(path: &Path) -> Result<(), String> {
         .map_err(|e| format!("Failed to run git init: {}", e))?;
 
     if output.status.success() {
-        Ok(())
+        ensure_gitignore(path)
     } else {
         Err(String::from_utf8_lossy(&output.stderr).to_string())
     }
 }
 
+/// Caches and OS files that shouldn't be committed with the notes
+const GITIGNORE_ENTRIES: &[&str] = &[
+    ".scratch/search_index/",
+    ".scratch/thumbnails/",
+    ".scratch/url_cache.json",
+    ".DS_Store",
+    "Thumbs.db",
+    "desktop.ini",
+];
+
+/// Entries from GITIGNORE_ENTRIES not already present in `existing`
+pub fn missing_gitignore_entries(existing: &str) -> Vec<&'static str> {
+    let present: std::collections::HashSet<&str> = existing.lines().map(|l| l.trim()).collect();
+    GITIGNORE_ENTRIES
+        .iter()
+        .copied()
+        .filter(|entry| !present.contains(entry))
+        .collect()
+}
+
+/// Write or extend `.gitignore`, appending only the missing entries
+pub fn ensure_gitignore(path: &Path) -> Result<(), String> {
+    let gitignore = path.join(".gitignore");
+    let existing = std::fs::read_to_string(&gitignore).unwrap_or_default();
+    let missing = missing_gitignore_entries(&existing);
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let mut content = existing;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    for entry in missing {
+        content.push_str(entry);
+        content.push('\n');
+    }
+    std::fs::write(&gitignore, content).map_err(|e| format!("Failed to write .gitignore: {}", e))
+}
+
 /// Get the current git status
 pub fn get_status(path: &Path) -> GitStatus {
     if !is_git_repo(path) {
@@ -139,21 +179,84 @@ pub fn get_status(path: &Path) -> GitStatus {
     status
 }
 
+/// Per-file status as (XY code, repo-relative path) from `git status --porcelain`.
+/// Returns an empty list for non-repos or if git fails.
+pub fn status_entries(path: &Path) -> Vec<(String, String)> {
+    if !is_git_repo(path) {
+        return Vec::new();
+    }
+
+    Command::new("git")
+        .args(["status", "--porcelain", "-z", "--untracked-files=all"])
+        .current_dir(path)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| parse_porcelain_z(&String::from_utf8_lossy(&o.stdout)))
+        .unwrap_or_default()
+}
+
+/// Parse NUL-separated porcelain v1 output. Renames and copies are followed by
+/// their original path, which is skipped.
+pub fn parse_porcelain_z(stdout: &str) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+    let mut fields = stdout.split('\0');
+    while let Some(field) = fields.next() {
+        if field.len() < 4 {
+            continue;
+        }
+        let (code, file) = field.split_at(3);
+        let code = &code[..2];
+        if code.contains('R') || code.contains('C') {
+            fields.next();
+        }
+        entries.push((code.to_string(), file.to_string()));
+    }
+    entries
+}
+
 /// Stage all changes and commit
 pub fn commit_all(path: &Path, message: &str) -> GitResult {
-    // Stage all changes
+    if let Some(failed) = stage(path, &["-A"]) {
+        return failed;
+    }
+    commit(path, message, &[])
+}
+
+/// Stage and commit only the given repo-relative paths, leaving other changes alone
+pub fn commit_paths(path: &Path, files: &[String], message: &str) -> GitResult {
+    if files.is_empty() {
+        return GitResult {
+            success: false,
+            message: None,
+            error: Some("No notes selected".to_string()),
+        };
+    }
+
+    let mut add_args = vec!["-A", "--"];
+    add_args.extend(files.iter().map(|f| f.as_str()));
+    if let Some(failed) = stage(path, &add_args) {
+        return failed;
+    }
+    let pathspec: Vec<&str> = files.iter().map(|f| f.as_str()).collect();
+    commit(path, message, &pathspec)
+}
+
+// Run `git add` with the given args; returns the failure result if staging failed
+fn stage(path: &Path, args: &[&str]) -> Option<GitResult> {
     let stage_output = match Command::new("git")
-        .args(["add", "-A"])
+        .arg("add")
+        .args(args)
         .current_dir(path)
         .output()
     {
         Ok(output) => output,
         Err(e) => {
-            return GitResult {
+            return Some(GitResult {
                 success: false,
                 message: None,
                 error: Some(format!("Failed to run git add: {}", e)),
-            };
+            });
         }
     };
 
@@ -161,7 +264,7 @@ pub fn commit_all(path: &Path, message: &str) -> GitResult {
     if !stage_output.status.success() {
         let stderr = String::from_utf8_lossy(&stage_output.stderr).to_string();
         let stdout = String::from_utf8_lossy(&stage_output.stdout).to_string();
-        return GitResult {
+        return Some(GitResult {
             success: false,
             message: None,
             error: Some(format!(
@@ -169,14 +272,19 @@ pub fn commit_all(path: &Path, message: &str) -> GitResult {
                 stderr,
                 if stdout.is_empty() { String::new() } else { format!("\n{}", stdout) }
             )),
-        };
+        });
     }
+    None
+}
 
-    // Commit
-    let commit_output = Command::new("git")
-        .args(["commit", "-m", message])
-        .current_dir(path)
-        .output();
+// Commit staged changes, limited to `pathspec` when non-empty
+fn commit(path: &Path, message: &str, pathspec: &[&str]) -> GitResult {
+    let mut cmd = Command::new("git");
+    cmd.args(["commit", "-m", message]);
+    if !pathspec.is_empty() {
+        cmd.arg("--").args(pathspec);
+    }
+    let commit_output = cmd.current_dir(path).output();
 
     match commit_output {
         Ok(output) => {
@@ -360,3 +468,188 @@ fn parse_push_error(stderr: &str) -> String {
         stderr.trim().to_string()
     }
 }
+
+/// Fetch from the remote without touching the working tree
+pub fn fetch(path: &Path) -> GitResult {
+    match Command::new("git").args(["fetch"]).current_dir(path).output() {
+        Ok(output) if output.status.success() => GitResult {
+            success: true,
+            message: Some("Fetched successfully".to_string()),
+            error: None,
+        },
+        Ok(output) => GitResult {
+            success: false,
+            message: None,
+            error: Some(parse_push_error(&String::from_utf8_lossy(&output.stderr))),
+        },
+        Err(e) => GitResult {
+            success: false,
+            message: None,
+            error: Some(format!("Failed to fetch: {}", e)),
+        },
+    }
+}
+
+fn diff_names(path: &Path, range: &str) -> Vec<String> {
+    Command::new("git")
+        .args(["diff", "--name-only", range])
+        .current_dir(path)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .filter(|l| !l.is_empty())
+                .map(|l| l.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Files a pull would overwrite: changed upstream since the merge base, and the
+/// working tree copy differs from the fetched upstream version. Call after `fetch`.
+pub fn incoming_changed_files(path: &Path) -> Vec<String> {
+    let differs_locally: std::collections::HashSet<String> =
+        diff_names(path, "@{upstream}").into_iter().collect();
+    diff_names(path, "HEAD...@{upstream}")
+        .into_iter()
+        .filter(|f| differs_locally.contains(f))
+        .collect()
+}
+
+/// Pull from the tracked upstream
+pub fn pull(path: &Path) -> GitResult {
+    let output = Command::new("git")
+        .args(["pull", "--no-rebase"])
+        .current_dir(path)
+        .output();
+
+    match output {
+        Ok(output) => {
+            if output.status.success() {
+                GitResult {
+                    success: true,
+                    message: Some("Pulled successfully".to_string()),
+                    error: None,
+                }
+            } else {
+                GitResult {
+                    success: false,
+                    message: None,
+                    error: Some(parse_push_error(&String::from_utf8_lossy(&output.stderr))),
+                }
+            }
+        }
+        Err(e) => GitResult {
+            success: false,
+            message: None,
+            error: Some(format!("Failed to pull: {}", e)),
+        },
+    }
+}
+
+/// List local branch names
+pub fn list_branches(path: &Path) -> Vec<String> {
+    if !is_git_repo(path) {
+        return Vec::new();
+    }
+
+    Command::new("git")
+        .args(["branch", "--format=%(refname:short)"])
+        .current_dir(path)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .map(|l| l.trim().to_string())
+                .filter(|l| !l.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Create a branch at HEAD without switching to it
+pub fn create_branch(path: &Path, name: &str) -> GitResult {
+    let valid = Command::new("git")
+        .args(["check-ref-format", "--branch", name])
+        .current_dir(path)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    if !valid || name.starts_with('-') {
+        return GitResult {
+            success: false,
+            message: None,
+            error: Some(format!("Invalid branch name: {}", name)),
+        };
+    }
+
+    match Command::new("git").args(["branch", name]).current_dir(path).output() {
+        Ok(output) if output.status.success() => GitResult {
+            success: true,
+            message: Some(format!("Created branch {}", name)),
+            error: None,
+        },
+        Ok(output) => GitResult {
+            success: false,
+            message: None,
+            error: Some(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        },
+        Err(e) => GitResult {
+            success: false,
+            message: None,
+            error: Some(format!("Failed to create branch: {}", e)),
+        },
+    }
+}
+
+/// Switch to an existing branch. Never forces: if uncommitted changes would be
+/// overwritten, fails with the list of those files.
+pub fn checkout_branch(path: &Path, name: &str) -> GitResult {
+    if !list_branches(path).iter().any(|b| b == name) {
+        return GitResult {
+            success: false,
+            message: None,
+            error: Some(format!("Branch not found: {}", name)),
+        };
+    }
+
+    match Command::new("git").args(["checkout", name]).current_dir(path).output() {
+        Ok(output) if output.status.success() => GitResult {
+            success: true,
+            message: Some(format!("Switched to branch {}", name)),
+            error: None,
+        },
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let dirty = parse_checkout_conflicts(&stderr);
+            GitResult {
+                success: false,
+                message: None,
+                error: Some(if dirty.is_empty() {
+                    stderr.trim().to_string()
+                } else {
+                    format!("Uncommitted changes would be overwritten: {}", dirty.join(", "))
+                }),
+            }
+        }
+        Err(e) => GitResult {
+            success: false,
+            message: None,
+            error: Some(format!("Failed to checkout: {}", e)),
+        },
+    }
+}
+
+/// Files listed by git as blocking a checkout (tab-indented lines in stderr)
+pub fn parse_checkout_conflicts(stderr: &str) -> Vec<String> {
+    stderr
+        .lines()
+        .filter(|l| l.starts_with('\t'))
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
@@ -4,9 +4,9 @@ use anyhow::Result;
 use base64::Engine;
 use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
 use std::time::{Duration, Instant};
 use tantivy::collector::TopDocs;
 use tantivy::query::QueryParser;
@@ -34,6 +34,9 @@ pub struct NoteMetadata {
     pub modified: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub icon: Option<String>,
+    // Manual sort position from the `order:` frontmatter field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<f64>,
 }
 
 // Full note content
@@ -84,7 +87,7 @@ impl Default for ThemeSettings {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct EditorFontSettings {
-    pub base_font_family: Option<String>, // "system-sans" | "serif" | "monospace"
+    pub base_font_family: Option<String>, // "system-sans" | "serif" | "monospace" | installed family
     pub base_font_size: Option<f32>,      // in px, default 16
     pub bold_weight: Option<i32>,         // 600, 700, 800 for headings and bold
     pub line_height: Option<f32>,         // default 1.6
@@ -106,10 +109,30 @@ pub struct Settings {
     pub git_enabled: Option<bool>,
     #[serde(rename = "pinnedNoteIds")]
     pub pinned_note_ids: Option<Vec<String>>,
+    #[serde(rename = "legacyFileChangeEvents")]
+    pub legacy_file_change_events: Option<bool>, // also emit per-file "file-change" events
     #[serde(rename = "mcpEnabled")]
     pub mcp_enabled: Option<bool>,
     #[serde(rename = "mcpPort")]
     pub mcp_port: Option<u16>,
+    #[serde(rename = "mcpHost")]
+    pub mcp_host: Option<String>, // defaults to 127.0.0.1
+    #[serde(rename = "mcpAllowRemote")]
+    pub mcp_allow_remote: Option<bool>, // required to bind to a non-loopback host
+    #[serde(rename = "mcpToken")]
+    pub mcp_token: Option<String>, // when set, MCP/HTTP requests need `Authorization: Bearer <token>`
+    #[serde(rename = "imageMaxWidth")]
+    pub image_max_width: Option<u32>,
+    #[serde(rename = "imageJpegQuality")]
+    pub image_jpeg_quality: Option<u8>, // 1-100, default 85
+    #[serde(rename = "imageFormat")]
+    pub image_format: Option<String>, // "png" | "webp" for pasted images, default "png"
+    #[serde(rename = "dailyNoteFormat")]
+    pub daily_note_format: Option<String>, // daily note id pattern, template date tokens, default "YYYY-MM-DD"
+    #[serde(rename = "previewLength")]
+    pub preview_length: Option<usize>, // search result preview length in chars, default 100
+    #[serde(rename = "snapshotOnPull")]
+    pub snapshot_on_pull: Option<bool>, // snapshot notes a git pull will overwrite, default true
 }
 
 // Search result
@@ -184,6 +207,12 @@ impl SearchIndex {
         })
     }
 
+    // (live document count, segment count) as seen by the current reader
+    fn stats(&self) -> (u64, usize) {
+        let searcher = self.reader.searcher();
+        (searcher.num_docs(), searcher.segment_readers().len())
+    }
+
     fn index_note(&self, id: &str, title: &str, content: &str, modified: i64) -> Result<()> {
         let mut writer = self.writer.lock().expect("search writer mutex");
 
@@ -211,7 +240,31 @@ impl SearchIndex {
         Ok(())
     }
 
-    fn search(&self, query_str: &str, limit: usize) -> Result<Vec<SearchResult>> {
+    // Upsert (id, title, content, modified) and delete ids with a single commit
+    fn apply_batch(&self, upserts: &[(String, String, String, i64)], deletes: &[String]) -> Result<()> {
+        if upserts.is_empty() && deletes.is_empty() {
+            return Ok(());
+        }
+        let mut writer = self.writer.lock().expect("search writer mutex");
+
+        for id in deletes {
+            writer.delete_term(tantivy::Term::from_field_text(self.id_field, id));
+        }
+        for (id, title, content, modified) in upserts {
+            writer.delete_term(tantivy::Term::from_field_text(self.id_field, id));
+            writer.add_document(doc!(
+                self.id_field => id.as_str(),
+                self.title_field => title.as_str(),
+                self.content_field => content.as_str(),
+                self.modified_field => *modified,
+            ))?;
+        }
+
+        writer.commit()?;
+        Ok(())
+    }
+
+    fn search(&self, query_str: &str, limit: usize, preview_length: usize) -> Result<Vec<SearchResult>> {
         let searcher = self.reader.searcher();
         let query_parser =
             QueryParser::for_index(&self.index, vec![self.title_field, self.content_field]);
@@ -249,7 +302,7 @@ impl SearchIndex {
                 .and_then(|v| v.as_i64())
                 .unwrap_or(0);
 
-            let preview = generate_preview(content);
+            let preview = generate_preview_with_length(content, preview_length);
 
             results.push(SearchResult {
                 id,
@@ -356,13 +409,23 @@ fn extract_wikilink_context(content: &str, match_pos: usize) -> String {
     let stripped = strip_markdown(line.trim());
 
     // Truncate to reasonable length
-    if stripped.len() > 120 {
-        format!("{}...", &stripped[..120])
+    let truncated = truncate_chars(&stripped, 120);
+    if truncated.len() < stripped.len() {
+        format!("{}...", truncated)
     } else {
         stripped
     }
 }
 
+// Utility: The first `max_chars` characters of `s`, cut on a char boundary
+// (byte-index slicing panics inside multi-byte UTF-8 sequences)
+fn truncate_chars(s: &str, max_chars: usize) -> &str {
+    match s.char_indices().nth(max_chars) {
+        Some((idx, _)) => &s[..idx],
+        None => s,
+    }
+}
+
 /// Get the path for the backlinks index file.
 fn get_backlinks_index_path(notes_folder: &str) -> PathBuf {
     let scratch_dir = PathBuf::from(notes_folder).join(".scratch");
@@ -467,6 +530,24 @@ fn remove_backlinks_for_note(index: &mut BacklinksIndex, note_id: &str) {
     index.links.retain(|_, v| !v.is_empty());
 }
 
+/// Re-read one note from disk and refresh its outgoing links; a missing file drops them.
+/// Returns whether the note still exists.
+fn refresh_backlinks_for_note(index: &mut BacklinksIndex, notes_folder: &str, note_id: &str) -> bool {
+    let content = resolve_note_path(notes_folder, note_id)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok());
+    match content {
+        Some(content) => {
+            update_backlinks_for_note(index, note_id, &extract_title(&content), &content);
+            true
+        }
+        None => {
+            remove_backlinks_for_note(index, note_id);
+            false
+        }
+    }
+}
+
 // Inner state shared between Tauri and MCP server via Arc
 pub struct AppStateInner {
     pub app_config: RwLock<AppConfig>,  // notes_folder path (stored in app data)
@@ -475,9 +556,11 @@ pub struct AppStateInner {
     pub file_watcher: Mutex<Option<FileWatcherState>>,
     pub search_index: Mutex<Option<SearchIndex>>,
     pub backlinks_index: RwLock<BacklinksIndex>,
-    pub debounce_map: Arc<Mutex<HashMap<PathBuf, Instant>>>,
+    pub watch_coalescer: Arc<Mutex<WatchCoalescer>>, // pending watcher events + self-write echoes
     pub mcp_server_handle: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
     pub story_index: Mutex<Option<stories::StoryIndex>>,
+    pub mcp_call_log: Mutex<VecDeque<mcp::McpCall>>, // bounded MCP tool-call history
+    pub checksum_cache: Mutex<HashMap<PathBuf, CachedChecksum>>, // note etags keyed by file path
 }
 
 // App state wrapper that is Clone-able for sharing with axum
@@ -500,9 +583,11 @@ impl Default for AppState {
             file_watcher: Mutex::new(None),
             search_index: Mutex::new(None),
             backlinks_index: RwLock::new(BacklinksIndex::default()),
-            debounce_map: Arc::new(Mutex::new(HashMap::new())),
+            watch_coalescer: Arc::new(Mutex::new(WatchCoalescer::default())),
             mcp_server_handle: Mutex::new(None),
             story_index: Mutex::new(None),
+            mcp_call_log: Mutex::new(VecDeque::new()),
+            checksum_cache: Mutex::new(HashMap::new()),
         }))
     }
 }
@@ -573,6 +658,42 @@ fn resolve_note_path(folder: &str, id: &str) -> Result<PathBuf, String> {
     Ok(path)
 }
 
+// Utility: Write a file atomically. Content goes to a temp file in the same
+// directory, is fsynced, then renamed over the target; on Unix the directory is
+// fsynced too so the rename itself survives power loss.
+fn write_file_atomic(path: &std::path::Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let dir = path
+        .parent()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Path has no parent directory"))?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Path has no file name"))?;
+    let tmp_path = dir.join(format!(".{}.tmp", file_name.to_string_lossy()));
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&tmp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        drop(file);
+        std::fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+        return result;
+    }
+
+    #[cfg(unix)]
+    {
+        if let Ok(dir_file) = std::fs::File::open(dir) {
+            let _ = dir_file.sync_all();
+        }
+    }
+
+    Ok(())
+}
+
 // Directories to skip during recursive traversal
 const EXCLUDED_DIRS: &[&str] = &[".scratch", ".git", ".assets", "assets", "node_modules"];
 
@@ -713,8 +834,98 @@ fn extract_icon(content: &str) -> Option<String> {
     None
 }
 
+// Utility: Extract the numeric manual sort position from frontmatter (order: 3)
+fn extract_order(content: &str) -> Option<f64> {
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.is_empty() || lines[0].trim() != "---" {
+        return None;
+    }
+    for line in &lines[1..] {
+        let trimmed = line.trim();
+        if trimmed == "---" {
+            break;
+        }
+        if let Some(rest) = trimmed.strip_prefix("order:") {
+            return rest
+                .trim()
+                .trim_matches('"')
+                .trim_matches('\'')
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite());
+        }
+    }
+    None
+}
+
+// Utility: Set (or with `None`, remove) a top-level frontmatter field, adding a
+// frontmatter block when the note has none. `value` is written as-is.
+fn set_frontmatter_field(content: &str, key: &str, value: Option<&str>) -> String {
+    let prefix = format!("{}:", key);
+    let lines: Vec<&str> = content.lines().collect();
+    let close = if lines.first().is_some_and(|l| l.trim() == "---") {
+        lines.iter().skip(1).position(|l| l.trim() == "---").map(|i| i + 1)
+    } else {
+        None
+    };
+
+    let mut out: Vec<String> = Vec::new();
+    match close {
+        Some(close) => {
+            let mut written = false;
+            out.push(lines[0].to_string());
+            for line in &lines[1..close] {
+                // Only top-level keys; indented lines belong to nested values
+                if line.starts_with(&prefix) {
+                    if let (Some(v), false) = (value, written) {
+                        out.push(format!("{} {}", prefix, v));
+                        written = true;
+                    }
+                } else {
+                    out.push(line.to_string());
+                }
+            }
+            if let (Some(v), false) = (value, written) {
+                out.push(format!("{} {}", prefix, v));
+            }
+            // Drop a frontmatter block that is now empty
+            if out.len() == 1 {
+                let mut rest = lines[close + 1..].join("\n").trim_start_matches('\n').to_string();
+                if content.ends_with('\n') && !rest.is_empty() {
+                    rest.push('\n');
+                }
+                return rest;
+            }
+            out.extend(lines[close..].iter().map(|l| l.to_string()));
+        }
+        None => {
+            let Some(v) = value else {
+                return content.to_string();
+            };
+            out.push("---".to_string());
+            out.push(format!("{} {}", prefix, v));
+            out.push("---".to_string());
+            out.extend(lines.iter().map(|l| l.to_string()));
+        }
+    }
+
+    let mut result = out.join("\n");
+    if content.ends_with('\n') || content.is_empty() {
+        result.push('\n');
+    }
+    result
+}
+
+const DEFAULT_PREVIEW_LENGTH: usize = 100;
+const MAX_PREVIEW_LENGTH: usize = 2000;
+
 // Utility: Generate preview from content (strip markdown formatting)
 fn generate_preview(content: &str) -> String {
+    generate_preview_with_length(content, DEFAULT_PREVIEW_LENGTH)
+}
+
+// Utility: Preview capped at `max_chars` characters (never splits a multi-byte char)
+fn generate_preview_with_length(content: &str, max_chars: usize) -> String {
     let lines: Vec<&str> = content.lines().collect();
     let mut start = 0;
 
@@ -730,25 +941,79 @@ fn generate_preview(content: &str) -> String {
 
     // Skip the title line, find first non-empty content line
     let mut skipped_title = false;
+    let mut in_fence = false;
     for line in lines.iter().skip(start) {
         let trimmed = line.trim();
+
+        // Skip fenced code blocks entirely (a leading fence also stands in for the title)
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            skipped_title = true;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
         if !trimmed.is_empty() {
             if !skipped_title {
                 skipped_title = true;
                 continue;
             }
-            let stripped = strip_markdown(trimmed);
+            let stripped = strip_markdown(strip_callout_marker(trimmed));
             if !stripped.is_empty() {
-                return stripped.chars().take(100).collect();
+                return truncate_chars(&stripped, max_chars).to_string();
             }
         }
     }
     String::new()
 }
 
+// Utility: Remove a leading blockquote / callout marker (`> [!NOTE]`) from a line
+fn strip_callout_marker(line: &str) -> &str {
+    let Some(rest) = line.strip_prefix('>') else {
+        return line;
+    };
+    let rest = rest.trim_start();
+    if rest.starts_with("[!") {
+        if let Some(end) = rest.find(']') {
+            return rest[end + 1..].trim_start();
+        }
+    }
+    rest
+}
+
+/// Compute the list preview for raw note content (same logic as the notes list).
+#[tauri::command]
+fn preview_for(content: String) -> String {
+    generate_preview(&content)
+}
+
 // Strip common markdown formatting from text
 fn strip_markdown(text: &str) -> String {
-    let mut result = text.to_string();
+    // Runs for every note in the list, so the patterns are compiled once
+    static SPAN_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+        regex::Regex::new(r"`([^`]+)`|\$\$([^$]+)\$\$|\$([^$\s](?:[^$]*[^$\s])?)\$").unwrap()
+    });
+    static IMG_RE: LazyLock<regex::Regex> =
+        LazyLock::new(|| regex::Regex::new(r"!\[([^\]]*)\]\([^)]+\)").unwrap());
+    static LINK_RE: LazyLock<regex::Regex> =
+        LazyLock::new(|| regex::Regex::new(r"\[([^\]]+)\]\([^)]+\)").unwrap());
+    static LIST_RE: LazyLock<regex::Regex> =
+        LazyLock::new(|| regex::Regex::new(r"^(\s*[-+*]|\s*\d+\.)\s+").unwrap());
+
+    // Pull inline code and math spans out as atomic tokens first so the
+    // emphasis stripping below can't eat `*`/`_` inside them
+    let mut spans: Vec<String> = Vec::new();
+    let mut result = SPAN_RE
+        .replace_all(text, |caps: &regex::Captures| {
+            let inner = caps.get(1).or_else(|| caps.get(2)).or_else(|| caps.get(3))
+                .map(|m| m.as_str())
+                .unwrap_or("");
+            spans.push(inner.to_string());
+            format!("\u{E000}{}\u{E001}", spans.len() - 1)
+        })
+        .to_string();
 
     // Remove heading markers (##, ###, etc.)
     let trimmed = result.trim_start();
@@ -784,23 +1049,11 @@ fn strip_markdown(text: &str) -> String {
         }
     }
 
-    // Remove inline code (`code`)
-    while let Some(start) = result.find('`') {
-        if let Some(end) = result[start + 1..].find('`') {
-            let inner = &result[start + 1..start + 1 + end];
-            result = format!("{}{}{}", &result[..start], inner, &result[start + 2 + end..]);
-        } else {
-            break;
-        }
-    }
-
     // Remove images ![alt](url) - must come before links
-    let img_re = regex::Regex::new(r"!\[([^\]]*)\]\([^)]+\)").unwrap();
-    result = img_re.replace_all(&result, "$1").to_string();
+    result = IMG_RE.replace_all(&result, "$1").to_string();
 
     // Remove links [text](url)
-    let link_re = regex::Regex::new(r"\[([^\]]+)\]\([^)]+\)").unwrap();
-    result = link_re.replace_all(&result, "$1").to_string();
+    result = LINK_RE.replace_all(&result, "$1").to_string();
 
     // Remove italic (*text* or _text_) - simple approach after bold is removed
     // Match *text* where text doesn't contain *
@@ -837,8 +1090,12 @@ fn strip_markdown(text: &str) -> String {
         .replace("- [X] ", "");
 
     // Remove list markers at start (-, *, +, 1.)
-    let list_re = regex::Regex::new(r"^(\s*[-+*]|\s*\d+\.)\s+").unwrap();
-    result = list_re.replace(&result, "").to_string();
+    result = LIST_RE.replace(&result, "").to_string();
+
+    // Restore the protected code/math spans (delimiters dropped)
+    for (i, span) in spans.iter().enumerate() {
+        result = result.replace(&format!("\u{E000}{}\u{E001}", i), span);
+    }
 
     result.trim().to_string()
 }
@@ -911,13 +1168,6 @@ fn save_settings(notes_folder: &str, settings: &Settings) -> Result<()> {
     Ok(())
 }
 
-// Clean up old entries from debounce map (entries older than 5 seconds)
-fn cleanup_debounce_map(map: &Mutex<HashMap<PathBuf, Instant>>) {
-    let mut map = map.lock().expect("debounce map mutex");
-    let now = Instant::now();
-    map.retain(|_, last| now.duration_since(*last) < Duration::from_secs(5));
-}
-
 // TAURI COMMANDS
 
 #[tauri::command]
@@ -1041,6 +1291,7 @@ pub async fn list_notes_impl(
                     preview: generate_preview(&content),
                     modified,
                     icon: extract_icon(&content),
+                    order: extract_order(&content),
                 });
             }
         }
@@ -1069,6 +1320,7 @@ pub async fn list_notes_impl(
                             preview: generate_preview(&content),
                             modified,
                             icon: extract_icon(&content),
+                            order: extract_order(&content),
                         });
                     }
                 }
@@ -1112,7 +1364,18 @@ pub async fn list_notes_impl(
 
 #[tauri::command]
 async fn list_notes(state: State<'_, AppState>) -> Result<Vec<NoteMetadata>, String> {
-    list_notes_impl(&state, None, true).await
+    let notes = list_notes_impl(&state, None, true).await?;
+
+    // A full listing is the authoritative set of notes, so stale UI state can go
+    let notes_folder = state.app_config.read().expect("app_config read lock").notes_folder.clone();
+    if let Some(notes_folder) = notes_folder {
+        let ids: HashSet<String> = notes.iter().map(|n| n.id.clone()).collect();
+        if let Err(e) = prune_ui_state(&notes_folder, &ids) {
+            eprintln!("Failed to prune UI state: {}", e);
+        }
+    }
+
+    Ok(notes)
 }
 
 #[tauri::command]
@@ -1120,9 +1383,48 @@ async fn list_folders(parent: Option<String>, state: State<'_, AppState>) -> Res
     list_folders_impl(parent, &state).await
 }
 
+/// Manual ordering: notes with an `order` first (ascending), the rest after, ties by title.
+fn sort_notes_manual(notes: &mut [NoteMetadata]) {
+    notes.sort_by(|a, b| {
+        let by_order = match (a.order, b.order) {
+            (Some(x), Some(y)) => x.total_cmp(&y),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        };
+        by_order.then_with(|| a.title.to_lowercase().cmp(&b.title.to_lowercase()))
+    });
+}
+
+/// `sort` is "modified" (default: pinned first, then newest) or "manual".
+#[tauri::command]
+async fn list_notes_in_folder(folder: Option<String>, sort: Option<String>, state: State<'_, AppState>) -> Result<Vec<NoteMetadata>, String> {
+    let mut notes = list_notes_impl(&state, folder.as_deref(), false).await?;
+    match sort.as_deref() {
+        None | Some("modified") => {}
+        Some("manual") => sort_notes_manual(&mut notes),
+        Some(other) => return Err(format!("Unknown sort mode: {}", other)),
+    }
+    Ok(notes)
+}
+
+pub async fn set_note_order_impl(id: String, order: Option<f64>, state: &AppState) -> Result<Note, String> {
+    if order.is_some_and(|n| !n.is_finite()) {
+        return Err("Order must be a finite number".to_string());
+    }
+    let existing = read_note_impl(id.clone(), state).await?;
+    let value = order.map(|n| n.to_string());
+    let new_content = set_frontmatter_field(&existing.content, "order", value.as_deref());
+    if new_content == existing.content {
+        return Ok(existing);
+    }
+    save_note_impl(Some(id), new_content, state).await
+}
+
+/// Set a note's manual sort position; `null` clears it.
 #[tauri::command]
-async fn list_notes_in_folder(folder: Option<String>, state: State<'_, AppState>) -> Result<Vec<NoteMetadata>, String> {
-    list_notes_impl(&state, folder.as_deref(), false).await
+async fn set_note_order(id: String, order: Option<f64>, state: State<'_, AppState>) -> Result<Note, String> {
+    set_note_order_impl(id, order, &state).await
 }
 
 #[tauri::command]
@@ -1187,6 +1489,8 @@ async fn rename_folder(old_path: String, new_name: String, state: State<'_, AppS
         cache.clear();
     }
 
+    log_activity(&notes_folder, "rename_folder", &new_rel, serde_json::json!({ "from": old_path }));
+
     Ok(new_rel)
 }
 
@@ -1246,6 +1550,8 @@ async fn delete_folder(folder_path: String, state: State<'_, AppState>) -> Resul
         .await
         .map_err(|e| format!("Failed to delete folder: {}", e))?;
 
+    log_activity(&notes_folder, "delete_folder", &folder_path, serde_json::json!({ "noteIds": note_ids }));
+
     Ok(())
 }
 
@@ -1309,6 +1615,7 @@ pub async fn save_note_impl(
             .ok_or("Notes folder not set")?
     };
     let folder_path = PathBuf::from(&folder);
+    let is_new = id.is_none();
 
     let title = extract_title(&content);
     let desired_basename = sanitize_filename(&title);
@@ -1397,14 +1704,14 @@ pub async fn save_note_impl(
         }
     }
 
-    // Write the file to the new path
-    fs::write(&file_path, &content)
-        .await
-        .map_err(|e| e.to_string())?;
+    // Write the file to the new path (temp file + fsync + rename, so a crash can't truncate it)
+    note_self_write(state, &file_path);
+    write_file_atomic(&file_path, content.as_bytes()).map_err(|e| e.to_string())?;
 
     // Delete old file AFTER successful write (to prevent data loss)
     if let Some((_, ref old_file_path)) = old_id {
         if old_file_path.exists() && *old_file_path != file_path {
+            note_self_write(state, old_file_path);
             let _ = fs::remove_file(old_file_path).await;
         }
     }
@@ -1453,6 +1760,12 @@ pub async fn save_note_impl(
         cache.remove(old_id_str);
     }
 
+    let details = match old_id {
+        Some((ref old_id_str, _)) => serde_json::json!({ "renamedFrom": old_id_str }),
+        None => serde_json::Value::Null,
+    };
+    log_activity(&folder, if is_new { "create" } else { "save" }, &final_id, details);
+
     Ok(Note {
         id: final_id,
         title,
@@ -1462,12 +1775,51 @@ pub async fn save_note_impl(
     })
 }
 
+/// Optimistic concurrency check for saves: fail with CONFLICT if the file on disk
+/// changed since the editor loaded it. The on-disk version is snapshotted first so
+/// it stays recoverable if the caller then forces the overwrite.
+fn check_note_unchanged(id: &str, expected_modified: i64, state: &AppState) -> Result<(), String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config
+            .notes_folder
+            .clone()
+            .ok_or("Notes folder not set")?
+    };
+    let file_path = resolve_note_path(&folder, id)?;
+    let Ok(metadata) = std::fs::metadata(&file_path) else {
+        return Ok(()); // Deleted externally: nothing to overwrite
+    };
+    let current_modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    if current_modified != expected_modified {
+        if let Ok(existing_content) = std::fs::read_to_string(&file_path) {
+            maybe_snapshot_note(&folder, id, &existing_content);
+        }
+        return Err(format!(
+            "CONFLICT: note modified on disk. Expected modified '{}', got '{}'. Reload the note to merge changes.",
+            expected_modified, current_modified
+        ));
+    }
+
+    Ok(())
+}
+
 #[tauri::command]
 async fn save_note(
     id: Option<String>,
     content: String,
+    expected_modified: Option<i64>,
     state: State<'_, AppState>,
 ) -> Result<Note, String> {
+    if let (Some(ref existing_id), Some(expected)) = (&id, expected_modified) {
+        check_note_unchanged(existing_id, expected, &state)?;
+    }
     save_note_impl(id, content, &state).await
 }
 
@@ -1482,6 +1834,7 @@ pub async fn delete_note_impl(id: String, state: &AppState) -> Result<(), String
 
     let file_path = resolve_note_path(&folder, &id)?;
     if file_path.exists() {
+        note_self_write(state, &file_path);
         fs::remove_file(&file_path)
             .await
             .map_err(|e| e.to_string())?;
@@ -1508,6 +1861,8 @@ pub async fn delete_note_impl(id: String, state: &AppState) -> Result<(), String
         let _ = save_backlinks_index(&folder, &bl_index);
     }
 
+    log_activity(&folder, "delete", &id, serde_json::Value::Null);
+
     Ok(())
 }
 
@@ -1573,6 +1928,8 @@ pub async fn create_note_impl(
         }
     }
 
+    log_activity(&folder, "create", &final_id, serde_json::Value::Null);
+
     Ok(Note {
         id: final_id,
         title: "Untitled".to_string(),
@@ -1592,6 +1949,109 @@ async fn create_note_in_folder(folder: String, state: State<'_, AppState>) -> Re
     create_note_impl(Some(folder), &state).await
 }
 
+// Utility: Append text to note content on its own line, keeping the note's
+// trailing-newline convention (a note that ended with a newline still does)
+fn append_note_content(existing: &str, addition: &str) -> String {
+    let mut result = existing.to_string();
+    if !result.is_empty() && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    result.push_str(addition);
+    if existing.ends_with('\n') && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+pub async fn append_to_note_impl(id: String, content: String, state: &AppState) -> Result<Note, String> {
+    let existing = read_note_impl(id.clone(), state).await?;
+    let new_content = append_note_content(&existing.content, &content);
+    save_note_impl(Some(id), new_content, state).await
+}
+
+#[tauri::command]
+async fn append_to_note(id: String, content: String, state: State<'_, AppState>) -> Result<Note, String> {
+    append_to_note_impl(id, content, &state).await
+}
+
+// Utility: Insert text at the start or end of the section under a heading.
+// `heading` may include the `#` markers to pin the level (e.g. "## Tasks").
+fn insert_under_heading_content(
+    content: &str,
+    heading: &str,
+    addition: &str,
+    position: &str,
+) -> Result<String, String> {
+    let wanted_level = heading.trim().chars().take_while(|c| *c == '#').count();
+    let wanted_title = heading.trim().trim_start_matches('#').trim().to_lowercase();
+
+    let outline = build_outline(content);
+    let section = outline
+        .iter()
+        .find(|s| {
+            s.title.to_lowercase() == wanted_title && (wanted_level == 0 || s.level == wanted_level)
+        })
+        .ok_or_else(|| {
+            let available: Vec<String> = outline
+                .iter()
+                .map(|s| format!("{} {}", "#".repeat(s.level), s.title))
+                .collect();
+            format!(
+                "Heading '{}' not found. Available headings: {}",
+                heading.trim(),
+                if available.is_empty() { "(none)".to_string() } else { available.join(", ") }
+            )
+        })?;
+
+    let mut lines: Vec<&str> = content.lines().collect();
+    let insert_at = match position {
+        "start" => section.start_line,
+        "end" => {
+            // After the last non-blank line of the section, so trailing spacing before
+            // the next heading is kept
+            let mut idx = section.end_line;
+            while idx > section.start_line && lines[idx - 1].trim().is_empty() {
+                idx -= 1;
+            }
+            idx
+        }
+        _ => return Err(format!("Invalid position '{}'. Must be 'start' or 'end'", position)),
+    };
+
+    let new_lines: Vec<&str> = addition.trim_end_matches('\n').lines().collect();
+    lines.splice(insert_at..insert_at, new_lines);
+
+    let mut result = lines.join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+pub async fn insert_under_heading_impl(
+    id: String,
+    heading: String,
+    content: String,
+    position: String,
+    state: &AppState,
+) -> Result<Note, String> {
+    let existing = read_note_impl(id.clone(), state).await?;
+    let new_content = insert_under_heading_content(&existing.content, &heading, &content, &position)?;
+    save_note_impl(Some(id), new_content, state).await
+}
+
+#[tauri::command]
+async fn insert_under_heading(
+    id: String,
+    heading: String,
+    content: String,
+    position: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Note, String> {
+    let position = position.unwrap_or_else(|| "end".to_string());
+    insert_under_heading_impl(id, heading, content, position, &state).await
+}
+
 // ── Template system ──────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1713,7 +2173,15 @@ fn strip_template_vars(text: &str) -> String {
         .trim_start_matches("- ").trim().to_string()
 }
 
-fn substitute_template_variables(content: &str, title: &str) -> (String, Option<usize>) {
+// `{{selection}}` is inserted verbatim (its text is never expanded). The cursor lands
+// on `{{cursor}}` when present; otherwise it lands right after the inserted selection.
+fn substitute_template_variables(
+    content: &str,
+    title: &str,
+    selection: Option<&str>,
+) -> (String, Option<usize>) {
+    const SELECTION_MARK: &str = "\u{E002}";
+    const CURSOR_MARK: &str = "\u{E003}";
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default();
@@ -1723,22 +2191,53 @@ fn substitute_template_variables(content: &str, title: &str) -> (String, Option<
                        "July", "August", "September", "October", "November", "December"];
     let month_short = &month_names[month as usize - 1][..3];
     let month_full = month_names[month as usize - 1];
-    let mut result = content.to_string();
+    let mut result = content.replace("{{selection}}", SELECTION_MARK);
     let date_format_re = regex::Regex::new(r"\{\{date:([^}]+)\}\}").unwrap();
     result = date_format_re.replace_all(&result, |caps: &regex::Captures| {
         format_date_pattern(&caps[1], year, month, day, month_full, month_short)
     }).to_string();
     result = result.replace("{{date}}", &format!("{:04}-{:02}-{:02}", year, month, day));
     result = result.replace("{{time}}", &format!("{:02}:{:02}", hour, minute));
+    // Unique values are generated per occurrence so ids in one template never collide
+    let uuid_re = regex::Regex::new(r"\{\{uuid\}\}").unwrap();
+    result = uuid_re.replace_all(&result, |_: &regex::Captures| {
+        uuid::Uuid::new_v4().to_string()
+    }).to_string();
+    let random_re = regex::Regex::new(r"\{\{random(?::(\d+))?\}\}").unwrap();
+    result = random_re.replace_all(&result, |caps: &regex::Captures| {
+        let len = caps.get(1).and_then(|m| m.as_str().parse().ok()).unwrap_or(8);
+        random_alphanumeric(len.min(256))
+    }).to_string();
     result = result.replace("{{title}}", title);
-    let cursor_pos = result.find("{{cursor}}");
-    result = result.replace("{{cursor}}", "");
-    let cursor_line = cursor_pos.map(|pos| {
-        result[..pos].lines().count().saturating_sub(1)
-    });
+    if !result.contains("{{cursor}}") {
+        result = result.replacen(SELECTION_MARK, &format!("{}{{{{cursor}}}}", SELECTION_MARK), 1);
+    }
+    result = result.replacen("{{cursor}}", CURSOR_MARK, 1).replace("{{cursor}}", "");
+    result = result.replace(SELECTION_MARK, selection.unwrap_or(""));
+    let cursor_pos = result.find(CURSOR_MARK);
+    result = result.replace(CURSOR_MARK, "");
+    // 0-based line of the cursor: `lines()` would drop a trailing empty line
+    // when the cursor sits at the start of a line, so count newlines instead
+    let cursor_line = cursor_pos.map(|pos| result[..pos].matches('\n').count());
     (result, cursor_line)
 }
 
+// Random [A-Za-z0-9] string, drawing bytes from v4 UUIDs (OS randomness)
+fn random_alphanumeric(len: usize) -> String {
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    let mut out = String::with_capacity(len);
+    while out.len() < len {
+        for (i, b) in uuid::Uuid::new_v4().as_bytes().iter().enumerate() {
+            // Bytes 6 and 8 carry the fixed version/variant bits, so skip them; reject
+            // the biased tail so every character is equally likely
+            if i != 6 && i != 8 && *b < 248 && out.len() < len {
+                out.push(CHARSET[(*b % 62) as usize] as char);
+            }
+        }
+    }
+    out
+}
+
 fn unix_to_datetime(secs: i64) -> (i32, u32, u32, u32, u32) {
     let days = (secs / 86400) as i32;
     let time_of_day = (secs % 86400) as u32;
@@ -1780,6 +2279,13 @@ fn format_date_pattern(pattern: &str, year: i32, month: u32, day: u32, month_ful
     result
 }
 
+fn validate_template_id(id: &str) -> Result<(), String> {
+    if id.trim().is_empty() || id.contains('/') || id.contains('\\') || id.contains("..") || id.contains('\0') {
+        return Err("Invalid template ID".to_string());
+    }
+    Ok(())
+}
+
 pub async fn list_templates_impl(state: &AppState) -> Result<Vec<TemplateInfo>, String> {
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
@@ -1819,9 +2325,7 @@ pub async fn read_template_impl(id: String, state: &AppState) -> Result<String,
         app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
     let templates_dir = ensure_templates_dir(&folder)?;
-    if id.contains('/') || id.contains('\\') || id.contains("..") || id.contains('\0') {
-        return Err("Invalid template ID".to_string());
-    }
+    validate_template_id(&id)?;
     let path = templates_dir.join(format!("{}.md", id));
     if !path.exists() {
         return Err(format!("Template not found: {}", id));
@@ -1844,6 +2348,7 @@ pub struct TemplateNoteResult {
 pub async fn create_note_from_template_impl(
     template_id: String,
     title: Option<String>,
+    selection: Option<String>,
     state: &AppState,
 ) -> Result<TemplateNoteResult, String> {
     let folder = {
@@ -1851,16 +2356,15 @@ pub async fn create_note_from_template_impl(
         app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
     let templates_dir = ensure_templates_dir(&folder)?;
-    if template_id.contains('/') || template_id.contains('\\') || template_id.contains("..") || template_id.contains('\0') {
-        return Err("Invalid template ID".to_string());
-    }
+    validate_template_id(&template_id)?;
     let template_path = templates_dir.join(format!("{}.md", template_id));
     if !template_path.exists() {
         return Err(format!("Template not found: {}", template_id));
     }
     let template_content = fs::read_to_string(&template_path).await.map_err(|e| e.to_string())?;
     let note_title = title.unwrap_or_else(|| "Untitled".to_string());
-    let (content, cursor_line) = substitute_template_variables(&template_content, &note_title);
+    let (content, cursor_line) =
+        substitute_template_variables(&template_content, &note_title, selection.as_deref());
     let actual_title = extract_title(&content);
     let base_name = sanitize_filename(&actual_title);
     let folder_path = PathBuf::from(&folder);
@@ -1885,6 +2389,8 @@ pub async fn create_note_from_template_impl(
     // Auto-create any referenced databases that don't exist yet
     ensure_template_databases(&folder_path, &content);
 
+    log_activity(&folder, "create", &file_name, serde_json::json!({ "template": template_id }));
+
     Ok(TemplateNoteResult {
         note: Note {
             id: file_name,
@@ -1916,6 +2422,7 @@ fn ensure_template_databases(notes_folder: &std::path::Path, content: &str) {
                 col_type: database::ColumnType::Text,
                 options: None,
                 target: None,
+                on_delete: None,
             },
             database::ColumnDef {
                 id: "status".to_string(),
@@ -1927,6 +2434,7 @@ fn ensure_template_databases(notes_folder: &std::path::Path, content: &str) {
                     "Done".to_string(),
                 ]),
                 target: None,
+                on_delete: None,
             },
             database::ColumnDef {
                 id: "priority".to_string(),
@@ -1938,6 +2446,7 @@ fn ensure_template_databases(notes_folder: &std::path::Path, content: &str) {
                     "Low".to_string(),
                 ]),
                 target: None,
+                on_delete: None,
             },
             database::ColumnDef {
                 id: "due".to_string(),
@@ -1945,6 +2454,7 @@ fn ensure_template_databases(notes_folder: &std::path::Path, content: &str) {
                 col_type: database::ColumnType::Date,
                 options: None,
                 target: None,
+                on_delete: None,
             },
             database::ColumnDef {
                 id: "done".to_string(),
@@ -1952,6 +2462,7 @@ fn ensure_template_databases(notes_folder: &std::path::Path, content: &str) {
                 col_type: database::ColumnType::Checkbox,
                 options: None,
                 target: None,
+                on_delete: None,
             },
         ];
 
@@ -2006,51 +2517,279 @@ fn ensure_template_databases(notes_folder: &std::path::Path, content: &str) {
 async fn create_note_from_template(
     template_id: String,
     title: Option<String>,
+    selection: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<TemplateNoteResult, String> {
-    create_note_from_template_impl(template_id, title, &state).await
+    create_note_from_template_impl(template_id, title, selection, &state).await
 }
 
-// ── End template system ──────────────────────────────────────────────────────
-
-// List folders under the notes root, optionally under a parent folder.
-pub async fn list_folders_impl(
-    parent: Option<String>,
-    state: &AppState,
-) -> Result<Vec<String>, String> {
+pub async fn save_template_impl(id: String, content: String, state: &AppState) -> Result<TemplateInfo, String> {
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
+    validate_template_id(&id)?;
+    let templates_dir = ensure_templates_dir(&folder)?;
+    let path = templates_dir.join(format!("{}.md", id));
+    write_file_atomic(&path, content.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(TemplateInfo {
+        name: extract_template_name(&id, &content),
+        description: extract_template_description(&content),
+        is_builtin: BUILTIN_TEMPLATES.iter().any(|(name, _)| *name == id),
+        id,
+    })
+}
 
-    let base_path = PathBuf::from(&folder);
-    let scan_path = if let Some(ref parent_id) = parent {
-        let validated = validate_note_id(parent_id)?;
-        let p = base_path.join(&validated);
-        if !p.exists() || !p.is_dir() {
-            return Err(format!("Folder not found: {}", parent_id));
-        }
-        p
-    } else {
-        base_path.clone()
-    };
+#[tauri::command]
+async fn save_template(id: String, content: String, state: State<'_, AppState>) -> Result<TemplateInfo, String> {
+    save_template_impl(id, content, &state).await
+}
 
-    let mut folders = Vec::new();
-    let mut entries = fs::read_dir(&scan_path).await.map_err(|e| e.to_string())?;
+pub async fn delete_template_impl(id: String, state: &AppState) -> Result<(), String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    validate_template_id(&id)?;
+    if BUILTIN_TEMPLATES.iter().any(|(name, _)| *name == id) {
+        return Err(format!("Cannot delete built-in template: {}", id));
+    }
+    let path = ensure_templates_dir(&folder)?.join(format!("{}.md", id));
+    if !path.exists() {
+        return Err(format!("Template not found: {}", id));
+    }
+    fs::remove_file(&path).await.map_err(|e| e.to_string())
+}
 
-    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
-        let path = entry.path();
-        if path.is_dir() {
-            let name = entry.file_name().to_string_lossy().to_string();
-            if !should_skip_dir(&name) {
-                if let Ok(rel) = path.strip_prefix(&base_path) {
-                    folders.push(rel.to_string_lossy().replace('\\', "/"));
-                }
+#[tauri::command]
+async fn delete_template(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    delete_template_impl(id, &state).await
+}
+
+// Utility: Turn the first `# ` heading (after frontmatter) into `# {{title}}`
+fn templatize_title(content: &str) -> String {
+    let mut in_frontmatter = false;
+    let mut replaced = false;
+    let mut out: Vec<String> = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if i == 0 && line.trim() == "---" {
+            in_frontmatter = true;
+        } else if in_frontmatter {
+            if line.trim() == "---" {
+                in_frontmatter = false;
             }
+        } else if !replaced && line.starts_with("# ") {
+            out.push("# {{title}}".to_string());
+            replaced = true;
+            continue;
         }
+        out.push(line.to_string());
     }
+    let mut result = out.join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
 
-    folders.sort();
+pub async fn create_template_from_note_impl(
+    note_id: String,
+    template_id: String,
+    replace_title: bool,
+    state: &AppState,
+) -> Result<TemplateInfo, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    validate_template_id(&template_id)?;
+    if ensure_templates_dir(&folder)?.join(format!("{}.md", template_id)).exists() {
+        return Err(format!("Template already exists: {}", template_id));
+    }
+    let note = read_note_impl(note_id, state).await?;
+    let content = if replace_title { templatize_title(&note.content) } else { note.content };
+    save_template_impl(template_id, content, state).await
+}
+
+#[tauri::command]
+async fn create_template_from_note(
+    note_id: String,
+    template_id: String,
+    replace_title: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<TemplateInfo, String> {
+    create_template_from_note_impl(note_id, template_id, replace_title.unwrap_or(true), &state).await
+}
+
+// Utility: Body of a rendered template for appending to an existing note —
+// frontmatter and the leading `# ` title are dropped since the note has its own.
+fn template_body_for_append(rendered: &str) -> String {
+    let body = strip_frontmatter(rendered);
+    let mut lines = body.lines().skip_while(|l| l.trim().is_empty()).peekable();
+    if lines.peek().is_some_and(|l| l.trim_start().starts_with("# ")) {
+        lines.next();
+    }
+    let rest: Vec<&str> = lines.skip_while(|l| l.trim().is_empty()).collect();
+    rest.join("\n")
+}
+
+pub async fn apply_template_to_note_impl(
+    note_id: String,
+    template_id: String,
+    state: &AppState,
+) -> Result<Note, String> {
+    let note = read_note_impl(note_id.clone(), state).await?;
+    let template_content = read_template_impl(template_id, state).await?;
+    let (rendered, _) = substitute_template_variables(&template_content, &note.title, None);
+    let body = template_body_for_append(&rendered);
+    if body.trim().is_empty() {
+        return Ok(note);
+    }
+
+    // Keep a blank line between the existing content and the template section
+    let existing = note.content.trim_end_matches('\n');
+    let separated = if existing.is_empty() { String::new() } else { format!("{}\n\n", existing) };
+    let mut new_content = append_note_content(&separated, &body);
+    if !new_content.ends_with('\n') {
+        new_content.push('\n');
+    }
+    save_note_impl(Some(note_id), new_content, state).await
+}
+
+#[tauri::command]
+async fn apply_template_to_note(
+    note_id: String,
+    template_id: String,
+    state: State<'_, AppState>,
+) -> Result<Note, String> {
+    apply_template_to_note_impl(note_id, template_id, &state).await
+}
+
+// Snippets are small boilerplate pieces inserted mid-note (vs. templates, which create notes)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetInfo {
+    pub id: String,
+    pub name: String,
+    pub preview: String,
+    pub is_builtin: bool,
+}
+
+const BUILTIN_SNIPPETS: &[(&str, &str)] = &[
+    ("signature", "— Written on {{date:MMMM D, YYYY}} at {{time}}\n"),
+    ("divider", "\n---\n\n"),
+];
+
+fn ensure_snippets_dir(notes_folder: &str) -> Result<PathBuf, String> {
+    let snippets_dir = PathBuf::from(notes_folder).join(".scratch").join("snippets");
+    std::fs::create_dir_all(&snippets_dir).map_err(|e| e.to_string())?;
+    for (name, content) in BUILTIN_SNIPPETS {
+        let path = snippets_dir.join(format!("{}.md", name));
+        if !path.exists() {
+            std::fs::write(&path, content).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(snippets_dir)
+}
+
+// Expand date/time variables; `{{title}}` is left for the caller and `{{cursor}}` is dropped
+fn expand_snippet(content: &str) -> String {
+    substitute_template_variables(content, "{{title}}", None).0
+}
+
+pub async fn list_snippets_impl(state: &AppState) -> Result<Vec<SnippetInfo>, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let snippets_dir = ensure_snippets_dir(&folder)?;
+    let mut snippets = Vec::new();
+    let mut entries = fs::read_dir(&snippets_dir).await.map_err(|e| e.to_string())?;
+    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "md") {
+            let filename = path.file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let content = fs::read_to_string(&path).await.map_err(|e| e.to_string())?;
+            let preview = strip_template_vars(content.trim()).chars().take(100).collect();
+            snippets.push(SnippetInfo {
+                name: extract_template_name(&filename, ""),
+                preview,
+                is_builtin: BUILTIN_SNIPPETS.iter().any(|(name, _)| *name == filename),
+                id: filename,
+            });
+        }
+    }
+    snippets.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(snippets)
+}
+
+#[tauri::command]
+async fn list_snippets(state: State<'_, AppState>) -> Result<Vec<SnippetInfo>, String> {
+    list_snippets_impl(&state).await
+}
+
+pub async fn get_snippet_impl(id: String, state: &AppState) -> Result<String, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let snippets_dir = ensure_snippets_dir(&folder)?;
+    validate_template_id(&id)?;
+    let path = snippets_dir.join(format!("{}.md", id));
+    if !path.exists() {
+        return Err(format!("Snippet not found: {}", id));
+    }
+    let content = fs::read_to_string(&path).await.map_err(|e| e.to_string())?;
+    Ok(expand_snippet(&content))
+}
+
+#[tauri::command]
+async fn get_snippet(id: String, state: State<'_, AppState>) -> Result<String, String> {
+    get_snippet_impl(id, &state).await
+}
+
+// ── End template system ──────────────────────────────────────────────────────
+
+// List folders under the notes root, optionally under a parent folder.
+pub async fn list_folders_impl(
+    parent: Option<String>,
+    state: &AppState,
+) -> Result<Vec<String>, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+
+    let base_path = PathBuf::from(&folder);
+    let scan_path = if let Some(ref parent_id) = parent {
+        let validated = validate_note_id(parent_id)?;
+        let p = base_path.join(&validated);
+        if !p.exists() || !p.is_dir() {
+            return Err(format!("Folder not found: {}", parent_id));
+        }
+        p
+    } else {
+        base_path.clone()
+    };
+
+    let mut folders = Vec::new();
+    let mut entries = fs::read_dir(&scan_path).await.map_err(|e| e.to_string())?;
+
+    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+        let path = entry.path();
+        if path.is_dir() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !should_skip_dir(&name) {
+                if let Ok(rel) = path.strip_prefix(&base_path) {
+                    folders.push(rel.to_string_lossy().replace('\\', "/"));
+                }
+            }
+        }
+    }
+
+    folders.sort();
     Ok(folders)
 }
 
@@ -2164,6 +2903,8 @@ pub async fn move_note_impl(
         cache.remove(&id);
     }
 
+    log_activity(&notes_folder, "move", &new_id, serde_json::json!({ "from": id }));
+
     read_note_impl(new_id, state).await
 }
 
@@ -2201,11 +2942,24 @@ fn levenshtein_distance(a: &str, b: &str) -> usize {
     prev[b_len]
 }
 
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
 /// Compute best fuzzy match score for `query` within `text` (word-level).
 /// Returns (best_distance, best_matching_fragment) or None if no match within threshold.
-fn fuzzy_match_line(query: &str, line: &str, max_distance: usize, case_sensitive: bool) -> Option<(usize, usize, usize)> {
-    let q = if case_sensitive { query.to_string() } else { query.to_lowercase() };
-    let l = if case_sensitive { line.to_string() } else { line.to_lowercase() };
+/// With `whole_word`, only windows that start and end on word boundaries are considered.
+fn fuzzy_match_line(
+    query: &str,
+    line: &str,
+    max_distance: usize,
+    case_sensitive: bool,
+    whole_word: bool,
+) -> Option<(usize, usize, usize)> {
+    // Lowercase per char (first char of the mapping) so window positions stay
+    // aligned with the characters of the original line
+    let fold = |c: char| if case_sensitive { c } else { c.to_lowercase().next().unwrap_or(c) };
+    let q: String = query.chars().map(fold).collect();
 
     let q_len = q.chars().count();
     if q_len == 0 {
@@ -2213,7 +2967,7 @@ fn fuzzy_match_line(query: &str, line: &str, max_distance: usize, case_sensitive
     }
 
     // Sliding window over text at character level
-    let l_chars: Vec<char> = l.chars().collect();
+    let l_chars: Vec<char> = line.chars().map(fold).collect();
     let l_len = l_chars.len();
     if l_len == 0 {
         return None;
@@ -2227,6 +2981,13 @@ fn fuzzy_match_line(query: &str, line: &str, max_distance: usize, case_sensitive
 
     for win_size in min_win..=max_win {
         for start in 0..=(l_len.saturating_sub(win_size)) {
+            let end = start + win_size;
+            if whole_word
+                && ((start > 0 && is_word_char(l_chars[start - 1]))
+                    || (end < l_len && is_word_char(l_chars[end])))
+            {
+                continue;
+            }
             let window: String = l_chars[start..start + win_size].iter().collect();
             let dist = levenshtein_distance(&q, &window);
             if dist <= max_distance {
@@ -2245,6 +3006,128 @@ fn fuzzy_match_line(query: &str, line: &str, max_distance: usize, case_sensitive
     best
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickMatch {
+    pub kind: String, // "note" | "folder" | "template" | "database"
+    pub id: String,
+    pub title: String,
+    pub score: usize, // lower is better
+}
+
+/// Rank `candidate` against `query` for the quick switcher (lower is better).
+fn quick_switch_score(query: &str, candidate: &str) -> Option<usize> {
+    let q = query.trim().to_lowercase();
+    if q.is_empty() {
+        return Some(0);
+    }
+    let c = candidate.to_lowercase();
+    if c == q {
+        Some(0)
+    } else if c.starts_with(&q) {
+        Some(1)
+    } else if c.contains(&q) {
+        Some(2)
+    } else {
+        let max_distance = (q.chars().count() / 3).max(1);
+        fuzzy_match_line(&q, &c, max_distance, true, false).map(|(dist, _, _)| 3 + dist)
+    }
+}
+
+/// Score and rank candidates; ties keep their input order (e.g. notes by recency).
+fn rank_quick_matches(query: &str, candidates: Vec<QuickMatch>, limit: usize) -> Vec<QuickMatch> {
+    let mut matches: Vec<QuickMatch> = candidates
+        .into_iter()
+        .filter_map(|mut m| {
+            let by_title = quick_switch_score(query, &m.title);
+            let by_id = quick_switch_score(query, &m.id);
+            m.score = match (by_title, by_id) {
+                (Some(a), Some(b)) => a.min(b),
+                (a, b) => a.or(b)?,
+            };
+            Some(m)
+        })
+        .collect();
+    matches.sort_by_key(|m| m.score);
+    matches.truncate(limit);
+    matches
+}
+
+// All folders under the notes root (relative, '/'-separated), skipping database folders
+fn collect_folders_sync(base: &std::path::Path, current: &std::path::Path, out: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(current) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !path.is_dir() || should_skip_dir(&name) {
+            continue;
+        }
+        if !database::is_database_folder(&path) {
+            if let Ok(rel) = path.strip_prefix(base) {
+                out.push(rel.to_string_lossy().replace('\\', "/"));
+            }
+        }
+        collect_folders_sync(base, &path, out);
+    }
+}
+
+/// Fuzzy-match notes, folders, templates and databases in one ranked list.
+pub async fn quick_switch_impl(
+    query: String,
+    limit: usize,
+    state: &AppState,
+) -> Result<Vec<QuickMatch>, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let base = PathBuf::from(&folder);
+    let candidate = |kind: &str, id: String, title: String| QuickMatch {
+        kind: kind.to_string(),
+        id,
+        title,
+        score: 0,
+    };
+
+    let mut candidates: Vec<QuickMatch> = list_notes_impl(state, None, true)
+        .await?
+        .into_iter()
+        .map(|n| candidate("note", n.id, n.title))
+        .collect();
+
+    let mut folders = Vec::new();
+    collect_folders_sync(&base, &base, &mut folders);
+    folders.sort();
+    candidates.extend(folders.into_iter().map(|f| {
+        let name = f.rsplit('/').next().unwrap_or(&f).to_string();
+        candidate("folder", f, name)
+    }));
+
+    candidates.extend(
+        list_templates_impl(state)
+            .await?
+            .into_iter()
+            .map(|t| candidate("template", t.id, t.name)),
+    );
+
+    candidates.extend(
+        database::scan_databases(&base)?
+            .into_iter()
+            .map(|db| candidate("database", db.id, db.name)),
+    );
+
+    Ok(rank_quick_matches(&query, candidates, limit))
+}
+
+#[tauri::command]
+async fn quick_switch(
+    query: String,
+    limit: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<QuickMatch>, String> {
+    quick_switch_impl(query, limit, &state).await
+}
+
 /// List directory contents within the notes folder.
 pub async fn list_directory_impl(
     path: Option<String>,
@@ -2356,7 +3239,25 @@ pub async fn read_file_impl(
         .map_err(|e| format!("Failed to read file: {}", e))
 }
 
+// Char offsets for a byte range within a line (for editor cursor positions)
+fn char_columns(line: &str, byte_start: usize, byte_end: usize) -> (usize, usize) {
+    let col = |byte: usize| line.char_indices().take_while(|(i, _)| *i < byte).count();
+    (col(byte_start), col(byte_end))
+}
+
+/// Regex matching `query` literally as a whole word. `\b` is only added on sides
+/// where the query starts/ends with a word character, so "#tag" still matches.
+fn whole_word_regex(query: &str, case_sensitive: bool) -> Result<regex::Regex, String> {
+    let prefix = if query.chars().next().is_some_and(is_word_char) { r"\b" } else { "" };
+    let suffix = if query.chars().last().is_some_and(is_word_char) { r"\b" } else { "" };
+    regex::RegexBuilder::new(&format!("{}{}{}", prefix, regex::escape(query), suffix))
+        .case_insensitive(!case_sensitive)
+        .build()
+        .map_err(|e| format!("Failed to build pattern: {}", e))
+}
+
 /// Powerful find across notes with exact, fuzzy, and regex modes.
+#[allow(clippy::too_many_arguments)]
 pub async fn find_in_notes_impl(
     query: String,
     mode: String,
@@ -2364,6 +3265,7 @@ pub async fn find_in_notes_impl(
     case_sensitive: bool,
     context_lines: usize,
     max_distance: Option<usize>,
+    whole_word: bool, // exact/fuzzy modes: only match complete words
     state: &AppState,
 ) -> Result<serde_json::Value, String> {
     let notes_folder = {
@@ -2401,6 +3303,17 @@ pub async fn find_in_notes_impl(
                 .build()
                 .map_err(|e| format!("Invalid regex: {}", e))?,
         )
+    } else if mode == "exact" && whole_word {
+        Some(whole_word_regex(&query, case_sensitive)?)
+    } else if mode == "exact" && !case_sensitive {
+        // Matching on a lowercased copy would give offsets that don't line up
+        // with the original line when lowercasing changes byte lengths
+        Some(
+            regex::RegexBuilder::new(&regex::escape(&query))
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| format!("Failed to build pattern: {}", e))?,
+        )
     } else {
         None
     };
@@ -2425,15 +3338,13 @@ pub async fn find_in_notes_impl(
         for (line_idx, line) in lines.iter().enumerate() {
             let match_info: Option<(usize, usize, f64)> = match mode.as_str() {
                 "exact" => {
-                    if case_sensitive {
-                        line.find(&query).map(|pos| (pos, pos + query.len(), 1.0))
+                    if let Some(ref re) = compiled_regex {
+                        re.find(line).map(|m| (m.start(), m.end(), 1.0))
                     } else {
-                        line.to_lowercase()
-                            .find(&query.to_lowercase())
-                            .map(|pos| (pos, pos + query.len(), 1.0))
+                        line.find(&query).map(|pos| (pos, pos + query.len(), 1.0))
                     }
                 }
-                "fuzzy" => fuzzy_match_line(&query, line, max_dist, case_sensitive)
+                "fuzzy" => fuzzy_match_line(&query, line, max_dist, case_sensitive, whole_word)
                     .map(|(dist, start, end)| {
                         let similarity = 1.0
                             - (dist as f64 / query.chars().count().max(1) as f64);
@@ -2462,11 +3373,8 @@ pub async fn find_in_notes_impl(
                     .map(|s| s.to_string())
                     .collect();
 
-                let matched_text = if match_end <= line.len() {
-                    &line[match_start..match_end]
-                } else {
-                    ""
-                };
+                let matched_text = line.get(match_start..match_end).unwrap_or("");
+                let (match_col_start, match_col_end) = char_columns(line, match_start, match_end);
 
                 all_matches.push(serde_json::json!({
                     "note_id": note_id,
@@ -2475,6 +3383,8 @@ pub async fn find_in_notes_impl(
                     "line_content": line,
                     "match_start": match_start,
                     "match_end": match_end,
+                    "match_col_start": match_col_start,
+                    "match_col_end": match_col_end,
                     "matched_text": matched_text,
                     "similarity": similarity,
                     "context_before": context_before,
@@ -2500,43 +3410,100 @@ pub async fn find_in_notes_impl(
     }))
 }
 
-/// Replace text within a note. Supports first, all, and regex modes.
-pub async fn replace_in_note_impl(
-    id: String,
-    find: String,
-    replace_with: String,
-    mode: String,
-    case_sensitive: bool,
-    state: &AppState,
-) -> Result<serde_json::Value, String> {
-    let notes_folder = {
-        let app_config = state.app_config.read().expect("app_config read lock");
-        app_config
-            .notes_folder
-            .clone()
-            .ok_or("Notes folder not set")?
-    };
+/// Check that every `$n` / `$name` / `${name}` in a regex replacement refers to a
+/// group the pattern actually has (`$$` is a literal dollar sign).
+fn validate_capture_refs(re: &regex::Regex, replacement: &str) -> Result<(), String> {
+    let bytes = replacement.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'$' {
+            i += 1;
+            continue;
+        }
+        if bytes.get(i + 1) == Some(&b'$') {
+            i += 2;
+            continue;
+        }
+        let (name, next) = if bytes.get(i + 1) == Some(&b'{') {
+            match replacement[i + 2..].find('}') {
+                Some(end) => (&replacement[i + 2..i + 2 + end], i + 3 + end),
+                None => (&replacement[i + 2..i + 2], i + 1), // unterminated: kept literally
+            }
+        } else {
+            let end = replacement[i + 1..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .map_or(bytes.len(), |e| i + 1 + e);
+            (&replacement[i + 1..end], end)
+        };
+        i = next.max(i + 1);
+        if name.is_empty() {
+            continue;
+        }
 
-    let file_path = resolve_note_path(&notes_folder, &id)?;
-    if !file_path.exists() {
-        return Err(format!("Note not found: {}", id));
+        let group_count = re.captures_len() - 1;
+        match name.parse::<usize>() {
+            Ok(n) if n > group_count => {
+                return Err(format!(
+                    "Replacement references group ${} but the pattern has only {} capture group(s)",
+                    n, group_count
+                ));
+            }
+            Ok(_) => {}
+            Err(_) if re.capture_names().flatten().any(|g| g == name) => {}
+            Err(_) => {
+                return Err(format!(
+                    "Replacement references unknown group '{}' (use ${{1}} or ${{name}} to separate a group from following text)",
+                    name
+                ));
+            }
+        }
     }
+    Ok(())
+}
 
-    let content = fs::read_to_string(&file_path)
-        .await
-        .map_err(|e| e.to_string())?;
+// Regex replace-all. With `literal`, `$` in the replacement is inserted as-is
+// instead of expanding capture groups.
+fn regex_replace_all(
+    content: &str,
+    pattern: &str,
+    replace_with: &str,
+    case_sensitive: bool,
+    literal: bool,
+) -> Result<(String, usize), String> {
+    let re = regex::RegexBuilder::new(pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid regex: {}", e))?;
+    let count = re.find_iter(content).count();
+    let replaced = if literal {
+        re.replace_all(content, regex::NoExpand(replace_with)).to_string()
+    } else {
+        validate_capture_refs(&re, replace_with)?;
+        re.replace_all(content, replace_with).to_string()
+    };
+    Ok((replaced, count))
+}
 
-    let (new_content, count) = match mode.as_str() {
+// Apply a first/all/regex replacement to a note's text; returns the new text and match count
+fn replace_in_content(
+    content: &str,
+    find: &str,
+    replace_with: &str,
+    mode: &str,
+    case_sensitive: bool,
+    literal: bool,
+) -> Result<(String, usize), String> {
+    let result = match mode {
         "first" => {
             if case_sensitive {
-                if let Some(pos) = content.find(&find) {
+                if let Some(pos) = content.find(find) {
                     let mut result = String::with_capacity(content.len());
                     result.push_str(&content[..pos]);
-                    result.push_str(&replace_with);
+                    result.push_str(replace_with);
                     result.push_str(&content[pos + find.len()..]);
                     (result, 1)
                 } else {
-                    (content.clone(), 0)
+                    (content.to_string(), 0)
                 }
             } else {
                 let lower_content = content.to_lowercase();
@@ -2544,55 +3511,169 @@ pub async fn replace_in_note_impl(
                 if let Some(pos) = lower_content.find(&lower_find) {
                     let mut result = String::with_capacity(content.len());
                     result.push_str(&content[..pos]);
-                    result.push_str(&replace_with);
+                    result.push_str(replace_with);
                     result.push_str(&content[pos + find.len()..]);
                     (result, 1)
                 } else {
-                    (content.clone(), 0)
+                    (content.to_string(), 0)
                 }
             }
         }
         "all" => {
             if case_sensitive {
-                let count = content.matches(&find).count();
-                (content.replace(&find, &replace_with), count)
+                let count = content.matches(find).count();
+                (content.replace(find, replace_with), count)
             } else {
                 // Case-insensitive replace all
-                let re = regex::RegexBuilder::new(&regex::escape(&find))
+                let re = regex::RegexBuilder::new(&regex::escape(find))
                     .case_insensitive(true)
                     .build()
                     .map_err(|e| format!("Failed to build pattern: {}", e))?;
-                let count = re.find_iter(&content).count();
-                (re.replace_all(&content, replace_with.as_str()).to_string(), count)
+                let count = re.find_iter(content).count();
+                (re.replace_all(content, regex::NoExpand(replace_with)).to_string(), count)
             }
         }
-        "regex" => {
-            let re = regex::RegexBuilder::new(&find)
-                .case_insensitive(!case_sensitive)
-                .build()
-                .map_err(|e| format!("Invalid regex: {}", e))?;
-            let count = re.find_iter(&content).count();
-            (re.replace_all(&content, replace_with.as_str()).to_string(), count)
-        }
+        "regex" => regex_replace_all(content, find, replace_with, case_sensitive, literal)?,
         _ => return Err(format!("Unknown replace mode: {}. Use 'first', 'all', or 'regex'.", mode)),
     };
-
-    if count == 0 {
-        return Ok(serde_json::json!({
-            "note_id": id,
-            "replacements_made": 0,
-            "message": "No matches found"
-        }));
-    }
-
-    // Save the updated content
-    let note = save_note_impl(Some(id.clone()), new_content, state).await?;
-
-    Ok(serde_json::json!({
-        "note_id": note.id,
-        "replacements_made": count,
-        "note": note
-    }))
+    Ok(result)
+}
+
+/// Replace text within a note. Supports first, all, and regex modes.
+pub async fn replace_in_note_impl(
+    id: String,
+    find: String,
+    replace_with: String,
+    mode: String,
+    case_sensitive: bool,
+    literal: bool, // regex mode: don't expand $1 / ${name} in replace_with
+    state: &AppState,
+) -> Result<serde_json::Value, String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config
+            .notes_folder
+            .clone()
+            .ok_or("Notes folder not set")?
+    };
+
+    let file_path = resolve_note_path(&notes_folder, &id)?;
+    if !file_path.exists() {
+        return Err(format!("Note not found: {}", id));
+    }
+
+    let content = fs::read_to_string(&file_path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let (new_content, count) =
+        replace_in_content(&content, &find, &replace_with, &mode, case_sensitive, literal)?;
+
+    if count == 0 {
+        return Ok(serde_json::json!({
+            "note_id": id,
+            "replacements_made": 0,
+            "message": "No matches found"
+        }));
+    }
+
+    // Save the updated content
+    let note = save_note_impl(Some(id.clone()), new_content, state).await?;
+
+    Ok(serde_json::json!({
+        "note_id": note.id,
+        "replacements_made": count,
+        "note": note
+    }))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteReplacement {
+    pub note_id: String,
+    pub replacements: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceReport {
+    pub folder: String,
+    pub dry_run: bool,
+    pub notes_scanned: usize,
+    pub total_replacements: usize,
+    pub changed: Vec<NoteReplacement>, // notes with at least one match
+}
+
+/// Find and replace across every note under `folder` (recursively). With `dry_run`
+/// nothing is written; the report lists what would change.
+pub async fn replace_in_folder_impl(
+    folder: String,
+    find: String,
+    replace_with: String,
+    mode: String,
+    case_sensitive: bool,
+    dry_run: bool,
+    state: &AppState,
+) -> Result<ReplaceReport, String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    if find.is_empty() {
+        return Err("Search text is required".to_string());
+    }
+    let folder = validate_note_id(folder.trim_matches('/'))?;
+    let base = PathBuf::from(&notes_folder);
+    let folder_path = base.join(&folder);
+    if !folder_path.is_dir() {
+        return Err(format!("Folder not found: {}", folder));
+    }
+
+    let mut report = ReplaceReport {
+        folder: folder.clone(),
+        dry_run,
+        notes_scanned: 0,
+        total_replacements: 0,
+        changed: Vec::new(),
+    };
+    for file_path in walk_md_files(&base, &folder_path).await? {
+        let Some(note_id) = path_to_note_id(&base, &file_path) else { continue };
+        if !in_search_folder(&note_id, Some(&folder)) {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&file_path).await else { continue };
+        report.notes_scanned += 1;
+
+        let (new_content, count) =
+            replace_in_content(&content, &find, &replace_with, &mode, case_sensitive, false)?;
+        if count == 0 {
+            continue;
+        }
+        let note_id = if dry_run {
+            note_id
+        } else {
+            save_note_impl(Some(note_id), new_content, state).await?.id
+        };
+        report.total_replacements += count;
+        report.changed.push(NoteReplacement {
+            note_id,
+            replacements: count,
+        });
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+async fn replace_in_folder(
+    folder: String,
+    find: String,
+    replace_with: String,
+    mode: String,
+    case_sensitive: bool,
+    dry_run: bool,
+    state: State<'_, AppState>,
+) -> Result<ReplaceReport, String> {
+    replace_in_folder_impl(folder, find, replace_with, mode, case_sensitive, dry_run, &state).await
 }
 
 pub fn get_settings_impl(state: &AppState) -> Settings {
@@ -2632,16 +3713,313 @@ fn update_settings(
     update_settings_impl(new_settings, &state)
 }
 
+// ---- Theme Presets ----
+
+// Static palette for a built-in preset; converted to ThemeColors when applied
+struct PresetPalette {
+    bg: &'static str,
+    bg_secondary: &'static str,
+    bg_muted: &'static str,
+    bg_emphasis: &'static str,
+    text: &'static str,
+    text_muted: &'static str,
+    text_inverse: &'static str,
+    border: &'static str,
+    accent: &'static str,
+}
+
+impl PresetPalette {
+    fn to_colors(&self) -> ThemeColors {
+        ThemeColors {
+            bg: Some(self.bg.to_string()),
+            bg_secondary: Some(self.bg_secondary.to_string()),
+            bg_muted: Some(self.bg_muted.to_string()),
+            bg_emphasis: Some(self.bg_emphasis.to_string()),
+            text: Some(self.text.to_string()),
+            text_muted: Some(self.text_muted.to_string()),
+            text_inverse: Some(self.text_inverse.to_string()),
+            border: Some(self.border.to_string()),
+            accent: Some(self.accent.to_string()),
+        }
+    }
+}
+
+struct ThemePreset {
+    name: &'static str,
+    light: PresetPalette,
+    dark: PresetPalette,
+}
+
+const THEME_PRESETS: &[ThemePreset] = &[
+    ThemePreset {
+        name: "Solarized",
+        light: PresetPalette {
+            bg: "#fdf6e3",
+            bg_secondary: "#eee8d5",
+            bg_muted: "#e4ddc8",
+            bg_emphasis: "#d9d2bc",
+            text: "#586e75",
+            text_muted: "#93a1a1",
+            text_inverse: "#fdf6e3",
+            border: "#d3cbb7",
+            accent: "#268bd2",
+        },
+        dark: PresetPalette {
+            bg: "#002b36",
+            bg_secondary: "#073642",
+            bg_muted: "#0a3f4c",
+            bg_emphasis: "#0f4a58",
+            text: "#93a1a1",
+            text_muted: "#657b83",
+            text_inverse: "#002b36",
+            border: "#1c4f5c",
+            accent: "#268bd2",
+        },
+    },
+    ThemePreset {
+        name: "Nord",
+        light: PresetPalette {
+            bg: "#eceff4",
+            bg_secondary: "#e5e9f0",
+            bg_muted: "#d8dee9",
+            bg_emphasis: "#cdd3de",
+            text: "#2e3440",
+            text_muted: "#4c566a",
+            text_inverse: "#eceff4",
+            border: "#d8dee9",
+            accent: "#5e81ac",
+        },
+        dark: PresetPalette {
+            bg: "#2e3440",
+            bg_secondary: "#3b4252",
+            bg_muted: "#434c5e",
+            bg_emphasis: "#4c566a",
+            text: "#eceff4",
+            text_muted: "#d8dee9",
+            text_inverse: "#2e3440",
+            border: "#4c566a",
+            accent: "#88c0d0",
+        },
+    },
+    ThemePreset {
+        name: "Gruvbox",
+        light: PresetPalette {
+            bg: "#fbf1c7",
+            bg_secondary: "#f2e5bc",
+            bg_muted: "#ebdbb2",
+            bg_emphasis: "#d5c4a1",
+            text: "#3c3836",
+            text_muted: "#7c6f64",
+            text_inverse: "#fbf1c7",
+            border: "#d5c4a1",
+            accent: "#af3a03",
+        },
+        dark: PresetPalette {
+            bg: "#282828",
+            bg_secondary: "#32302f",
+            bg_muted: "#3c3836",
+            bg_emphasis: "#504945",
+            text: "#ebdbb2",
+            text_muted: "#a89984",
+            text_inverse: "#282828",
+            border: "#504945",
+            accent: "#fe8019",
+        },
+    },
+    ThemePreset {
+        name: "Sepia",
+        light: PresetPalette {
+            bg: "#f4ecd8",
+            bg_secondary: "#ede3cc",
+            bg_muted: "#e4d8bd",
+            bg_emphasis: "#d9cba9",
+            text: "#5b4636",
+            text_muted: "#8a735f",
+            text_inverse: "#f4ecd8",
+            border: "#d8c8a8",
+            accent: "#a0522d",
+        },
+        dark: PresetPalette {
+            bg: "#2b241c",
+            bg_secondary: "#342c22",
+            bg_muted: "#3d3428",
+            bg_emphasis: "#4a3f31",
+            text: "#e8dcc4",
+            text_muted: "#b3a48a",
+            text_inverse: "#2b241c",
+            border: "#4a3f31",
+            accent: "#d19a66",
+        },
+    },
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ThemePresetInfo {
+    pub name: String,
+}
+
+// Copy a preset's light or dark palette into the matching custom colors slot.
+// The theme mode itself is left alone.
+fn apply_theme_preset_to(theme: &mut ThemeSettings, name: &str, mode: &str) -> Result<(), String> {
+    let preset = THEME_PRESETS
+        .iter()
+        .find(|p| p.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("Unknown theme preset: {}", name))?;
+    match mode {
+        "light" => theme.custom_light_colors = Some(preset.light.to_colors()),
+        "dark" => theme.custom_dark_colors = Some(preset.dark.to_colors()),
+        _ => return Err(format!("Invalid mode '{}': expected \"light\" or \"dark\"", mode)),
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn list_theme_presets() -> Vec<ThemePresetInfo> {
+    THEME_PRESETS
+        .iter()
+        .map(|p| ThemePresetInfo {
+            name: p.name.to_string(),
+        })
+        .collect()
+}
+
+#[tauri::command]
+fn apply_theme_preset(
+    name: String,
+    mode: String,
+    state: State<AppState>,
+) -> Result<ThemeSettings, String> {
+    let mut settings = get_settings_impl(&state);
+    apply_theme_preset_to(&mut settings.theme, &name, &mode)?;
+    let theme = settings.theme.clone();
+    update_settings_impl(settings, &state)?;
+    Ok(theme)
+}
+
+// ---- Editor Fonts ----
+
+// Generic families the editor maps to its own font stacks
+const GENERIC_FONT_FAMILIES: &[&str] = &["system-sans", "serif", "monospace"];
+
+// Installed font family names, sorted and deduplicated
+fn system_font_families() -> Vec<String> {
+    let mut db = fontdb::Database::new();
+    db.load_system_fonts();
+    let mut families: Vec<String> = db
+        .faces()
+        .flat_map(|face| face.families.iter().map(|(name, _)| name.clone()))
+        .collect();
+    families.sort_by_key(|name| name.to_lowercase());
+    families.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    families
+}
+
+// Warning for a configured family that is neither generic nor installed
+fn unavailable_font_warning(family: &str, available: &[String]) -> Option<String> {
+    let family = family.trim();
+    if family.is_empty()
+        || GENERIC_FONT_FAMILIES.contains(&family)
+        || available.iter().any(|name| name.eq_ignore_ascii_case(family))
+    {
+        return None;
+    }
+    Some(format!(
+        "Editor font '{}' is not installed; the editor will fall back to its default font",
+        family
+    ))
+}
+
+// Installed families, enumerated once per run; only used for warnings, so staleness is fine
+fn cached_system_font_families() -> &'static [String] {
+    static FAMILIES: std::sync::OnceLock<Vec<String>> = std::sync::OnceLock::new();
+    FAMILIES.get_or_init(system_font_families)
+}
+
+/// Warning to show when an editor font family isn't installed, if any.
+#[tauri::command]
+async fn check_editor_font(family: String) -> Result<Option<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        unavailable_font_warning(&family, cached_system_font_families())
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_available_fonts() -> Result<Vec<String>, String> {
+    tauri::async_runtime::spawn_blocking(system_font_families)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ---- Settings Export / Import ----
+
+// Settings as written to an export file: the MCP token is a local secret and is left out
+fn settings_for_export(settings: &Settings) -> Settings {
+    Settings {
+        mcp_token: None,
+        ..settings.clone()
+    }
+}
+
+// Imported settings with the local MCP token kept, whatever the file contains
+fn merge_imported_settings(imported: Settings, current: &Settings) -> Settings {
+    Settings {
+        mcp_token: current.mcp_token.clone(),
+        ..imported
+    }
+}
+
+fn mcp_settings_changed(a: &Settings, b: &Settings) -> bool {
+    a.mcp_enabled != b.mcp_enabled
+        || a.mcp_port != b.mcp_port
+        || a.mcp_host != b.mcp_host
+        || a.mcp_allow_remote != b.mcp_allow_remote
+}
+
+// Settings only: the notes folder path lives in app config and is machine-specific
+#[tauri::command]
+fn export_settings(dest: String, state: State<AppState>) -> Result<(), String> {
+    let settings = settings_for_export(&get_settings_impl(&state));
+    let content = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    std::fs::write(&dest, content).map_err(|e| format!("Failed to write {}: {}", dest, e))
+}
+
+#[tauri::command]
+fn import_settings(path: String, state: State<AppState>) -> Result<Settings, String> {
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let imported: Settings =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid settings file: {}", e))?;
+    let current = get_settings_impl(&state);
+    let settings = merge_imported_settings(imported, &current);
+    update_settings_impl(settings.clone(), &state)?;
+    if mcp_settings_changed(&current, &settings) {
+        restart_mcp_server(&state)?;
+    }
+    Ok(settings)
+}
+
+// Search preview length from settings, clamped to a sane range
+fn search_preview_length(state: &AppState) -> usize {
+    let settings = state.settings.read().expect("settings read lock");
+    settings
+        .preview_length
+        .unwrap_or(DEFAULT_PREVIEW_LENGTH)
+        .clamp(1, MAX_PREVIEW_LENGTH)
+}
+
 pub async fn search_notes_impl(query: String, state: &AppState) -> Result<Vec<SearchResult>, String> {
     if query.trim().is_empty() {
         return Ok(vec![]);
     }
+    let preview_length = search_preview_length(state);
 
     // Check if search index is available and use it (scoped to drop lock before await)
     let search_result = {
         let index = state.search_index.lock().expect("search index mutex");
         if let Some(ref search_index) = *index {
-            Some(search_index.search(&query, 20).map_err(|e| e.to_string()))
+            Some(search_index.search(&query, 20, preview_length).map_err(|e| e.to_string()))
         } else {
             None
         }
@@ -2651,7 +4029,7 @@ pub async fn search_notes_impl(query: String, state: &AppState) -> Result<Vec<Se
         result
     } else {
         // Fallback to simple search if index not available
-        fallback_search(&query, state).await
+        fallback_search(&query, preview_length, state).await
     }
 }
 
@@ -2661,7 +4039,7 @@ async fn search_notes(query: String, state: State<'_, AppState>) -> Result<Vec<S
 }
 
 // Fallback search when Tantivy index isn't available - searches title and full content
-async fn fallback_search(query: &str, state: &AppState) -> Result<Vec<SearchResult>, String> {
+async fn fallback_search(query: &str, preview_length: usize, state: &AppState) -> Result<Vec<SearchResult>, String> {
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config.notes_folder.clone()
@@ -2691,7 +4069,7 @@ async fn fallback_search(query: &str, state: &AppState) -> Result<Vec<SearchResu
     let query_lower = query.to_lowercase();
     let mut results: Vec<SearchResult> = Vec::new();
 
-    for (id, title, preview, modified) in cache_data {
+    for (id, title, mut preview, modified) in cache_data {
         let title_lower = title.to_lowercase();
 
         let mut score = 0.0f32;
@@ -2702,6 +4080,9 @@ async fn fallback_search(query: &str, state: &AppState) -> Result<Vec<SearchResu
         // Read file content asynchronously and search in it
         let file_path = PathBuf::from(&folder).join(format!("{}.md", &id));
         if let Ok(content) = tokio::fs::read_to_string(&file_path).await {
+            if preview_length != DEFAULT_PREVIEW_LENGTH {
+                preview = generate_preview_with_length(&content, preview_length);
+            }
             let content_lower = content.to_lowercase();
             if content_lower.contains(&query_lower) {
                 // Higher score if in title, lower if only in content
@@ -2730,6 +4111,44 @@ async fn fallback_search(query: &str, state: &AppState) -> Result<Vec<SearchResu
     Ok(results)
 }
 
+/// Run a search and save the hits as a new note of `[[Title]]` links with previews.
+pub async fn export_search_results_impl(
+    query: String,
+    dest_title: String,
+    state: &AppState,
+) -> Result<Note, String> {
+    let title = dest_title.trim();
+    if title.is_empty() {
+        return Err("Destination title is required".to_string());
+    }
+
+    let results = search_notes_impl(query.clone(), state).await?;
+
+    let mut content = format!("# {}\n\nSearch results for `{}`\n\n", title, query.trim());
+    if results.is_empty() {
+        content.push_str("_No matching notes._\n");
+    }
+    for result in &results {
+        if result.preview.is_empty() {
+            content.push_str(&format!("- [[{}]]\n", result.title));
+        } else {
+            content.push_str(&format!("- [[{}]] — {}\n", result.title, result.preview));
+        }
+    }
+
+    // A new note (no id) gets a unique filename derived from its title
+    save_note_impl(None, content, state).await
+}
+
+#[tauri::command]
+async fn export_search_results(
+    query: String,
+    dest_title: String,
+    state: State<'_, AppState>,
+) -> Result<Note, String> {
+    export_search_results_impl(query, dest_title, &state).await
+}
+
 // --- Stories _impl functions ---
 
 pub async fn epics_list_impl(
@@ -3307,10 +4726,119 @@ pub async fn db_get_schema_impl(
     }))
 }
 
+// Utility: Keep rows matching every `{ field, operator, value }` filter (db_query semantics)
+fn filter_db_rows(rows: &mut Vec<database::DatabaseRow>, filter_val: &serde_json::Value) -> Result<(), String> {
+    if let Some(filter_arr) = filter_val.as_array() {
+        for filter in filter_arr {
+            let field = filter
+                .get("field")
+                .and_then(|v| v.as_str())
+                .ok_or("Filter missing 'field'")?;
+            let operator = filter
+                .get("operator")
+                .and_then(|v| v.as_str())
+                .ok_or("Filter missing 'operator'")?;
+            let value = filter.get("value");
+
+            rows.retain(|row| {
+                let row_val = row.fields.get(field);
+                match operator {
+                    "eq" => match (row_val, value) {
+                        (Some(rv), Some(fv)) => rv == fv,
+                        (None, None) => true,
+                        _ => false,
+                    },
+                    "neq" => match (row_val, value) {
+                        (Some(rv), Some(fv)) => rv != fv,
+                        (None, None) => false,
+                        _ => true,
+                    },
+                    "gt" => compare_values(row_val, value, |a, b| a > b),
+                    "gte" => compare_values(row_val, value, |a, b| a >= b),
+                    "lt" => compare_values(row_val, value, |a, b| a < b),
+                    "lte" => compare_values(row_val, value, |a, b| a <= b),
+                    "contains" => {
+                        let needle = value
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .to_lowercase();
+                        match row_val {
+                            Some(serde_json::Value::String(s)) => {
+                                s.to_lowercase().contains(&needle)
+                            }
+                            Some(serde_json::Value::Array(arr)) => arr.iter().any(|item| {
+                                item.as_str()
+                                    .map(|s| s.to_lowercase() == needle)
+                                    .unwrap_or(false)
+                            }),
+                            _ => false,
+                        }
+                    }
+                    "not_contains" => {
+                        let needle = value
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .to_lowercase();
+                        match row_val {
+                            Some(serde_json::Value::String(s)) => {
+                                !s.to_lowercase().contains(&needle)
+                            }
+                            Some(serde_json::Value::Array(arr)) => !arr.iter().any(|item| {
+                                item.as_str()
+                                    .map(|s| s.to_lowercase() == needle)
+                                    .unwrap_or(false)
+                            }),
+                            None => true,
+                            _ => true,
+                        }
+                    }
+                    "starts_with" => {
+                        let prefix = value
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .to_lowercase();
+                        row_val
+                            .and_then(|v| v.as_str())
+                            .map(|s| s.to_lowercase().starts_with(&prefix))
+                            .unwrap_or(false)
+                    }
+                    "ends_with" => {
+                        let suffix = value
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .to_lowercase();
+                        row_val
+                            .and_then(|v| v.as_str())
+                            .map(|s| s.to_lowercase().ends_with(&suffix))
+                            .unwrap_or(false)
+                    }
+                    "is_empty" => match row_val {
+                        None => true,
+                        Some(serde_json::Value::String(s)) => s.is_empty(),
+                        Some(serde_json::Value::Array(a)) => a.is_empty(),
+                        Some(serde_json::Value::Null) => true,
+                        _ => false,
+                    },
+                    "is_not_empty" => match row_val {
+                        None => false,
+                        Some(serde_json::Value::String(s)) => !s.is_empty(),
+                        Some(serde_json::Value::Array(a)) => !a.is_empty(),
+                        Some(serde_json::Value::Null) => false,
+                        _ => true,
+                    },
+                    _ => true, // Unknown operator: no filter
+                }
+            });
+        }
+    }
+    Ok(())
+}
+
 pub async fn db_query_impl(
     database_id: String,
     filters: Option<serde_json::Value>,
     sort: Option<serde_json::Value>,
+    aggregates: Option<serde_json::Value>,
     limit: usize,
     offset: usize,
     state: &AppState,
@@ -3328,109 +4856,7 @@ pub async fn db_query_impl(
 
     // Apply filters
     if let Some(filter_val) = filters {
-        if let Some(filter_arr) = filter_val.as_array() {
-            for filter in filter_arr {
-                let field = filter
-                    .get("field")
-                    .and_then(|v| v.as_str())
-                    .ok_or("Filter missing 'field'")?;
-                let operator = filter
-                    .get("operator")
-                    .and_then(|v| v.as_str())
-                    .ok_or("Filter missing 'operator'")?;
-                let value = filter.get("value");
-
-                rows.retain(|row| {
-                    let row_val = row.fields.get(field);
-                    match operator {
-                        "eq" => match (row_val, value) {
-                            (Some(rv), Some(fv)) => rv == fv,
-                            (None, None) => true,
-                            _ => false,
-                        },
-                        "neq" => match (row_val, value) {
-                            (Some(rv), Some(fv)) => rv != fv,
-                            (None, None) => false,
-                            _ => true,
-                        },
-                        "gt" => compare_values(row_val, value, |a, b| a > b),
-                        "gte" => compare_values(row_val, value, |a, b| a >= b),
-                        "lt" => compare_values(row_val, value, |a, b| a < b),
-                        "lte" => compare_values(row_val, value, |a, b| a <= b),
-                        "contains" => {
-                            let needle = value
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("")
-                                .to_lowercase();
-                            match row_val {
-                                Some(serde_json::Value::String(s)) => {
-                                    s.to_lowercase().contains(&needle)
-                                }
-                                Some(serde_json::Value::Array(arr)) => arr.iter().any(|item| {
-                                    item.as_str()
-                                        .map(|s| s.to_lowercase() == needle)
-                                        .unwrap_or(false)
-                                }),
-                                _ => false,
-                            }
-                        }
-                        "not_contains" => {
-                            let needle = value
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("")
-                                .to_lowercase();
-                            match row_val {
-                                Some(serde_json::Value::String(s)) => {
-                                    !s.to_lowercase().contains(&needle)
-                                }
-                                Some(serde_json::Value::Array(arr)) => !arr.iter().any(|item| {
-                                    item.as_str()
-                                        .map(|s| s.to_lowercase() == needle)
-                                        .unwrap_or(false)
-                                }),
-                                None => true,
-                                _ => true,
-                            }
-                        }
-                        "starts_with" => {
-                            let prefix = value
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("")
-                                .to_lowercase();
-                            row_val
-                                .and_then(|v| v.as_str())
-                                .map(|s| s.to_lowercase().starts_with(&prefix))
-                                .unwrap_or(false)
-                        }
-                        "ends_with" => {
-                            let suffix = value
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("")
-                                .to_lowercase();
-                            row_val
-                                .and_then(|v| v.as_str())
-                                .map(|s| s.to_lowercase().ends_with(&suffix))
-                                .unwrap_or(false)
-                        }
-                        "is_empty" => match row_val {
-                            None => true,
-                            Some(serde_json::Value::String(s)) => s.is_empty(),
-                            Some(serde_json::Value::Array(a)) => a.is_empty(),
-                            Some(serde_json::Value::Null) => true,
-                            _ => false,
-                        },
-                        "is_not_empty" => match row_val {
-                            None => false,
-                            Some(serde_json::Value::String(s)) => !s.is_empty(),
-                            Some(serde_json::Value::Array(a)) => !a.is_empty(),
-                            Some(serde_json::Value::Null) => false,
-                            _ => true,
-                        },
-                        _ => true, // Unknown operator: no filter
-                    }
-                });
-            }
-        }
+        filter_db_rows(&mut rows, &filter_val)?;
     }
 
     // Apply sorting
@@ -3447,9 +4873,9 @@ pub async fn db_query_impl(
 
         if !sort_field.is_empty() {
             rows.sort_by(|a, b| {
-                let va = a.fields.get(sort_field);
-                let vb = b.fields.get(sort_field);
-                let ord = compare_json_values(va, vb);
+                let va = db_row_sort_value(a, sort_field);
+                let vb = db_row_sort_value(b, sort_field);
+                let ord = compare_json_values(va.as_ref(), vb.as_ref());
                 if sort_desc {
                     ord.reverse()
                 } else {
@@ -3461,6 +4887,12 @@ pub async fn db_query_impl(
 
     let total = rows.len();
 
+    // Aggregates cover all filtered rows, not just the requested page
+    let aggregate_results = match aggregates {
+        Some(specs) => Some(compute_aggregates(&schema, &rows, &specs)?),
+        None => None,
+    };
+
     // Apply pagination
     let paginated: Vec<_> = rows.into_iter().skip(offset).take(limit).collect();
 
@@ -3477,11 +4909,13 @@ pub async fn db_query_impl(
                 "body": row.body,
                 "etag": etag,
                 "modified": row.modified,
+                "created_at": row.created_at,
+                "updated_at": row.updated_at,
             })
         })
         .collect();
 
-    Ok(serde_json::json!({
+    let mut result = serde_json::json!({
         "database_id": database_id,
         "total": total,
         "offset": offset,
@@ -3492,38 +4926,165 @@ pub async fn db_query_impl(
             "name": c.name,
             "type": c.col_type.as_str(),
         })).collect::<Vec<_>>(),
-    }))
+    });
+    if let Some(aggregate_results) = aggregate_results {
+        result["aggregates"] = aggregate_results;
+    }
+    Ok(result)
 }
 
-/// Compare two JSON values for filter operations (gt, gte, lt, lte).
-fn compare_values(
-    row_val: Option<&serde_json::Value>,
-    filter_val: Option<&serde_json::Value>,
-    cmp: fn(f64, f64) -> bool,
-) -> bool {
-    match (row_val, filter_val) {
-        (Some(rv), Some(fv)) => {
-            // Try numeric comparison first
-            let rn = rv.as_f64();
-            let fn_ = fv.as_f64();
-            if let (Some(a), Some(b)) = (rn, fn_) {
-                return cmp(a, b);
-            }
-            // Fall back to string comparison
-            let rs = rv.as_str().unwrap_or("");
-            let fs = fv.as_str().unwrap_or("");
-            let ord = rs.cmp(fs);
-            match ord {
-                std::cmp::Ordering::Less => cmp(-1.0, 0.0),
-                std::cmp::Ordering::Equal => cmp(0.0, 0.0),
-                std::cmp::Ordering::Greater => cmp(1.0, 0.0),
-            }
+// Utility: Value to sort a row by; created_at/updated_at/position fall back to the row metadata
+// when no column has that id
+fn db_row_sort_value(row: &database::DatabaseRow, field: &str) -> Option<serde_json::Value> {
+    if let Some(v) = row.fields.get(field) {
+        return Some(v.clone());
+    }
+    match field {
+        "created_at" => row.created_at.clone().map(serde_json::Value::String),
+        "updated_at" => row.updated_at.clone().map(serde_json::Value::String),
+        // Rows that were never reordered keep file order after the positioned ones
+        database::POSITION_KEY => Some(serde_json::json!(row.position.unwrap_or(f64::MAX))),
+        _ => None,
+    }
+}
+
+// Utility: Evaluate `[{ column, fn }]` aggregate specs over rows. sum/avg/min/max use
+// numeric values only (avg/min/max are null when there are none); count counts non-empty values.
+fn compute_aggregates(
+    schema: &database::DatabaseSchema,
+    rows: &[database::DatabaseRow],
+    specs: &serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let specs = specs.as_array().ok_or("Aggregates must be an array")?;
+    let mut results = Vec::new();
+    for spec in specs {
+        let column = spec
+            .get("column")
+            .and_then(|v| v.as_str())
+            .ok_or("Aggregate missing 'column'")?;
+        let func = spec
+            .get("fn")
+            .and_then(|v| v.as_str())
+            .ok_or("Aggregate missing 'fn'")?;
+        if !schema.columns.iter().any(|c| c.id == column) {
+            return Err(format!("Unknown aggregate column: '{}'", column));
         }
-        _ => false,
+
+        let values = rows.iter().filter_map(|r| r.fields.get(column));
+        let numbers: Vec<f64> = values.clone().filter_map(|v| v.as_f64()).collect();
+        let value = match func {
+            "sum" => serde_json::json!(numbers.iter().sum::<f64>()),
+            "avg" if numbers.is_empty() => serde_json::Value::Null,
+            "avg" => serde_json::json!(numbers.iter().sum::<f64>() / numbers.len() as f64),
+            "min" => numbers.iter().copied().reduce(f64::min).map_or(serde_json::Value::Null, |n| serde_json::json!(n)),
+            "max" => numbers.iter().copied().reduce(f64::max).map_or(serde_json::Value::Null, |n| serde_json::json!(n)),
+            "count" => serde_json::json!(values
+                .filter(|v| match v {
+                    serde_json::Value::Null => false,
+                    serde_json::Value::String(s) => !s.is_empty(),
+                    serde_json::Value::Array(a) => !a.is_empty(),
+                    _ => true,
+                })
+                .count()),
+            other => return Err(format!("Unknown aggregate function: '{}'", other)),
+        };
+        results.push(serde_json::json!({ "column": column, "fn": func, "value": value }));
     }
+    Ok(serde_json::Value::Array(results))
 }
 
-/// Compare two optional JSON values for sorting.
+// Utility: Map the editor's saved-filter shape ({ column, operator: "equals" | "is" | ... })
+// onto the { field, operator: "eq" | ... } shape db_query_impl understands
+fn normalize_view_filters(filters: &serde_json::Value) -> serde_json::Value {
+    let Some(arr) = filters.as_array() else {
+        return serde_json::json!([]);
+    };
+    let normalized: Vec<serde_json::Value> = arr
+        .iter()
+        .filter_map(|f| {
+            let field = f.get("field").or_else(|| f.get("column"))?.as_str()?;
+            let operator = match f.get("operator")?.as_str()? {
+                "equals" | "is" => "eq",
+                "not_equals" | "is_not" => "neq",
+                "before" => "lt",
+                "after" => "gt",
+                other => other,
+            };
+            let mut out = serde_json::json!({ "field": field, "operator": operator });
+            if let Some(value) = f.get("value") {
+                out["value"] = value.clone();
+            }
+            Some(out)
+        })
+        .collect();
+    serde_json::Value::Array(normalized)
+}
+
+/// Run a saved view: its filters and sort applied through db_query_impl.
+pub async fn db_query_view_impl(
+    database_id: String,
+    view_id: String,
+    limit: usize,
+    offset: usize,
+    state: &AppState,
+) -> Result<serde_json::Value, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config
+            .notes_folder
+            .clone()
+            .ok_or("Notes folder not set")?
+    };
+    let db_folder = PathBuf::from(&folder).join(&database_id);
+    let schema = database::load_schema(&db_folder)?;
+    let view = schema
+        .views
+        .iter()
+        .find(|v| v.id == view_id)
+        .ok_or_else(|| format!("View '{}' not found in database '{}'", view_id, database_id))?;
+
+    let filters = view.filters.as_ref().map(normalize_view_filters);
+    let sort = view.sort_by.as_ref().map(|field| {
+        serde_json::json!({
+            "field": field,
+            "direction": if view.sort_desc.unwrap_or(false) { "desc" } else { "asc" },
+        })
+    });
+
+    let mut result = db_query_impl(database_id, filters, sort, None, limit, offset, state).await?;
+    result["view_id"] = serde_json::json!(view_id);
+    Ok(result)
+}
+
+/// Compare two JSON values for filter operations (gt, gte, lt, lte).
+fn compare_values(
+    row_val: Option<&serde_json::Value>,
+    filter_val: Option<&serde_json::Value>,
+    cmp: fn(f64, f64) -> bool,
+) -> bool {
+    match (row_val, filter_val) {
+        (Some(rv), Some(fv)) => {
+            // Try numeric comparison first
+            let rn = rv.as_f64();
+            let fn_ = fv.as_f64();
+            if let (Some(a), Some(b)) = (rn, fn_) {
+                return cmp(a, b);
+            }
+            // Fall back to string comparison
+            let rs = rv.as_str().unwrap_or("");
+            let fs = fv.as_str().unwrap_or("");
+            let ord = rs.cmp(fs);
+            match ord {
+                std::cmp::Ordering::Less => cmp(-1.0, 0.0),
+                std::cmp::Ordering::Equal => cmp(0.0, 0.0),
+                std::cmp::Ordering::Greater => cmp(1.0, 0.0),
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Compare two optional JSON values for sorting.
 fn compare_json_values(
     a: Option<&serde_json::Value>,
     b: Option<&serde_json::Value>,
@@ -3618,7 +5179,7 @@ pub async fn db_update_row_impl(
 
     if current_etag != etag {
         return Err(format!(
-            "CONFLICT: etag mismatch. Expected '{}', got '{}'. Refetch the row via db_query to get the latest etag.",
+            "CONFLICT: etag mismatch. Expected '{}', got '{}'. Refetch the row via db_get_row to get the latest etag.",
             etag, current_etag
         ));
     }
@@ -3650,6 +5211,48 @@ pub async fn db_update_row_impl(
     }))
 }
 
+/// Fetch one row with its current etag, e.g. to retry after an etag conflict.
+pub async fn db_get_row_impl(
+    database_id: String,
+    row_id: String,
+    state: &AppState,
+) -> Result<serde_json::Value, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config
+            .notes_folder
+            .clone()
+            .ok_or("Notes folder not set")?
+    };
+    let db_folder = PathBuf::from(&folder).join(&database_id);
+    if !database::is_database_folder(&db_folder) {
+        return Err(format!("'{}' is not a database folder", database_id));
+    }
+
+    let row_path = db_folder.join(format!("{}.md", row_id));
+    if row_id.contains(['/', '\\']) || !row_path.exists() {
+        return Err(format!(
+            "Row '{}' not found in database '{}'",
+            row_id, database_id
+        ));
+    }
+
+    let schema = database::load_schema(&db_folder)?;
+    let content = std::fs::read_to_string(&row_path)
+        .map_err(|e| format!("Failed to read row file: {}", e))?;
+    let row = database::parse_row(&content, &row_path.to_string_lossy(), &schema)?;
+
+    Ok(serde_json::json!({
+        "id": row.id,
+        "fields": row.fields,
+        "body": row.body,
+        "etag": stories::compute_etag(&content),
+        "modified": row.modified,
+        "created_at": row.created_at,
+        "updated_at": row.updated_at,
+    }))
+}
+
 pub async fn db_delete_row_impl(
     database_id: String,
     row_id: String,
@@ -3719,6 +5322,12 @@ pub async fn db_create_impl(
             .get("target")
             .and_then(|v| v.as_str())
             .map(String::from);
+        let on_delete = match col_val.get("on_delete") {
+            None | Some(serde_json::Value::Null) => None,
+            Some(v) => Some(serde_json::from_value(v.clone()).map_err(|_| {
+                format!("Invalid on_delete {} for column '{}': expected \"set_null\" or \"restrict\"", v, id)
+            })?),
+        };
 
         columns.push(database::ColumnDef {
             id,
@@ -3726,6 +5335,7 @@ pub async fn db_create_impl(
             col_type,
             options,
             target,
+            on_delete,
         });
     }
 
@@ -3744,10 +5354,273 @@ struct FileChangeEvent {
     changed_ids: Vec<String>,
 }
 
+// Emitted when a database's schema or rows change on disk
+#[derive(Clone, Serialize)]
+struct DatabaseChangedEvent {
+    db_id: String,
+}
+
+// Batched watcher payload: one event per flush instead of one per file
+#[derive(Clone, Serialize, Debug, Default, PartialEq)]
+struct NotesChangedBatch {
+    created: Vec<String>,
+    modified: Vec<String>,
+    deleted: Vec<String>,
+}
+
+impl NotesChangedBatch {
+    fn from_changes(changes: &[WatchChange]) -> Self {
+        let mut batch = Self::default();
+        for change in changes {
+            let ids = match change.kind {
+                WatchChangeKind::Created => &mut batch.created,
+                WatchChangeKind::Modified => &mut batch.modified,
+                WatchChangeKind::Deleted => &mut batch.deleted,
+            };
+            ids.push(change.note_id.clone());
+        }
+        batch.created.sort();
+        batch.modified.sort();
+        batch.deleted.sort();
+        batch
+    }
+}
+
+// Quiet period after the last event for a note before it is re-indexed
+const WATCH_COALESCE_WINDOW: Duration = Duration::from_millis(300);
+// Events for paths Scratch wrote within this window are treated as our own echo
+const SELF_WRITE_SUPPRESS_WINDOW: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WatchChangeKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+impl WatchChangeKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            WatchChangeKind::Created => "created",
+            WatchChangeKind::Modified => "modified",
+            WatchChangeKind::Deleted => "deleted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct WatchChange {
+    note_id: String,
+    path: PathBuf,
+    kind: WatchChangeKind,
+}
+
+struct PendingWatchChange {
+    path: PathBuf,
+    kind: WatchChangeKind,
+    last_event: Instant,
+}
+
+/// Collects raw watcher events per note id so an editor's create+modify+rename
+/// burst becomes one re-index, and drops echoes of Scratch's own writes.
+#[derive(Default)]
+pub struct WatchCoalescer {
+    pending: HashMap<String, PendingWatchChange>,
+    self_writes: HashMap<PathBuf, Instant>,
+    flush_scheduled: bool,
+}
+
+impl WatchCoalescer {
+    /// Remember that Scratch itself just wrote (or removed) `path`.
+    fn note_self_write(&mut self, path: PathBuf, now: Instant) {
+        if self.self_writes.len() > 100 {
+            self.self_writes
+                .retain(|_, at| now.duration_since(*at) < SELF_WRITE_SUPPRESS_WINDOW);
+        }
+        self.self_writes.insert(path, now);
+    }
+
+    /// Record an event; returns true when the caller must start a flush thread.
+    fn record(&mut self, note_id: String, path: PathBuf, kind: WatchChangeKind, now: Instant) -> bool {
+        if let Some(at) = self.self_writes.get(&path) {
+            if now.duration_since(*at) < SELF_WRITE_SUPPRESS_WINDOW {
+                return false;
+            }
+            self.self_writes.remove(&path);
+        }
+
+        match self.pending.get_mut(&note_id) {
+            Some(pending) => {
+                // A note created in this window stays "created" however often it is touched
+                pending.kind = match (pending.kind, kind) {
+                    (WatchChangeKind::Created, WatchChangeKind::Modified) => WatchChangeKind::Created,
+                    (WatchChangeKind::Deleted, WatchChangeKind::Created) => WatchChangeKind::Modified,
+                    (_, kind) => kind,
+                };
+                pending.path = path;
+                pending.last_event = now;
+            }
+            None => {
+                self.pending.insert(note_id, PendingWatchChange { path, kind, last_event: now });
+            }
+        }
+
+        if self.flush_scheduled {
+            false
+        } else {
+            self.flush_scheduled = true;
+            true
+        }
+    }
+
+    /// Take every change that has been quiet for the coalesce window.
+    fn drain_ready(&mut self, now: Instant) -> Vec<WatchChange> {
+        let ready: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, p)| now.duration_since(p.last_event) >= WATCH_COALESCE_WINDOW)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        ready
+            .into_iter()
+            .filter_map(|id| {
+                self.pending.remove(&id).map(|p| WatchChange {
+                    note_id: id,
+                    path: p.path,
+                    kind: p.kind,
+                })
+            })
+            .collect()
+    }
+}
+
+// Record a Scratch-initiated write so the watcher ignores its echo
+fn note_self_write(state: &AppState, path: &std::path::Path) {
+    state
+        .watch_coalescer
+        .lock()
+        .expect("watch coalescer mutex")
+        .note_self_write(path.to_path_buf(), Instant::now());
+}
+
+// Flush coalesced watcher events until none are pending
+fn schedule_watch_flush(app_handle: AppHandle, coalescer: Arc<Mutex<WatchCoalescer>>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(WATCH_COALESCE_WINDOW);
+        let ready = {
+            let mut c = coalescer.lock().expect("watch coalescer mutex");
+            let ready = c.drain_ready(Instant::now());
+            if ready.is_empty() && c.pending.is_empty() {
+                c.flush_scheduled = false;
+                return;
+            }
+            ready
+        };
+        if !ready.is_empty() {
+            apply_watch_changes(&app_handle, ready);
+        }
+    });
+}
+
+/// Bring backlinks in line with externally changed notes. Scratch's own writes never get
+/// here: the coalescer drops their echoes.
+fn apply_watch_changes_to_backlinks(index: &mut BacklinksIndex, notes_folder: &str, changes: &[WatchChange]) {
+    for change in changes {
+        refresh_backlinks_for_note(index, notes_folder, &change.note_id);
+    }
+}
+
+// Re-index coalesced changes with a single commit, then notify the frontend
+fn apply_watch_changes(app_handle: &AppHandle, changes: Vec<WatchChange>) {
+    let changes: Vec<WatchChange> = changes
+        .into_iter()
+        .map(|mut change| {
+            // The file's final state wins over the event sequence that got us here
+            change.kind = match (change.path.exists(), change.kind) {
+                (false, _) => WatchChangeKind::Deleted,
+                (true, WatchChangeKind::Deleted) => WatchChangeKind::Modified,
+                (true, kind) => kind,
+            };
+            change
+        })
+        .collect();
+
+    let mut legacy_events = false;
+    let mut changed_dbs: Vec<String> = Vec::new();
+    if let Some(state) = app_handle.try_state::<AppState>() {
+        // Keep backlinks current; schema and row files under database folders
+        // also invalidate their database
+        let notes_folder = state.app_config.read().expect("app_config read lock").notes_folder.clone();
+        if let Some(folder) = notes_folder {
+            if !changes.is_empty() {
+                let mut bl_index = state.backlinks_index.write().expect("backlinks write lock");
+                apply_watch_changes_to_backlinks(&mut bl_index, &folder, &changes);
+                let _ = save_backlinks_index(&folder, &bl_index);
+            }
+
+            let folder = PathBuf::from(folder);
+            for change in &changes {
+                if let Some(db_id) = database::database_id_for_path(&folder, &change.path) {
+                    if !changed_dbs.contains(&db_id) {
+                        changed_dbs.push(db_id);
+                    }
+                }
+            }
+        }
+
+        legacy_events = state
+            .settings
+            .read()
+            .expect("settings read lock")
+            .legacy_file_change_events
+            .unwrap_or(false);
+
+        let mut upserts = Vec::new();
+        let mut deletes = Vec::new();
+        for change in &changes {
+            if change.kind == WatchChangeKind::Deleted {
+                deletes.push(change.note_id.clone());
+            } else if let Ok(content) = std::fs::read_to_string(&change.path) {
+                let modified = std::fs::metadata(&change.path)
+                    .ok()
+                    .and_then(|m| m.modified().ok())
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or(0);
+                upserts.push((change.note_id.clone(), extract_title(&content), content, modified));
+            }
+        }
+
+        let index = state.search_index.lock().expect("search index mutex");
+        if let Some(ref search_index) = *index {
+            let _ = search_index.apply_batch(&upserts, &deletes);
+        }
+    }
+
+    let _ = app_handle.emit("notes-changed-batch", NotesChangedBatch::from_changes(&changes));
+    for db_id in changed_dbs {
+        let _ = app_handle.emit("database-changed", DatabaseChangedEvent { db_id });
+    }
+
+    if legacy_events {
+        for change in changes {
+            let _ = app_handle.emit(
+                "file-change",
+                FileChangeEvent {
+                    kind: change.kind.as_str().to_string(),
+                    path: change.path.to_string_lossy().into_owned(),
+                    changed_ids: vec![change.note_id],
+                },
+            );
+        }
+    }
+}
+
 fn setup_file_watcher(
     app: AppHandle,
     notes_folder: &str,
-    debounce_map: Arc<Mutex<HashMap<PathBuf, Instant>>>,
+    coalescer: Arc<Mutex<WatchCoalescer>>,
 ) -> Result<FileWatcherState, String> {
     let folder_path = PathBuf::from(notes_folder);
     let app_handle = app.clone();
@@ -3756,6 +5629,13 @@ fn setup_file_watcher(
     let watcher = RecommendedWatcher::new(
         move |res: Result<notify::Event, notify::Error>| {
             if let Ok(event) = res {
+                let kind = match event.kind {
+                    notify::EventKind::Create(_) => WatchChangeKind::Created,
+                    notify::EventKind::Modify(_) => WatchChangeKind::Modified,
+                    notify::EventKind::Remove(_) => WatchChangeKind::Deleted,
+                    _ => return,
+                };
+
                 for path in event.paths.iter() {
                     // Handle .md files
                     if path.extension().is_some_and(|ext| ext == "md") {
@@ -3768,31 +5648,6 @@ fn setup_file_watcher(
                             continue;
                         }
 
-                        // Debounce with cleanup
-                        {
-                            let mut map = debounce_map.lock().expect("debounce map mutex");
-                            let now = Instant::now();
-
-                            // Clean up old entries periodically
-                            if map.len() > 100 {
-                                map.retain(|_, last| now.duration_since(*last) < Duration::from_secs(5));
-                            }
-
-                            if let Some(last) = map.get(path) {
-                                if now.duration_since(*last) < Duration::from_millis(500) {
-                                    continue;
-                                }
-                            }
-                            map.insert(path.clone(), now);
-                        }
-
-                        let kind = match event.kind {
-                            notify::EventKind::Create(_) => "created",
-                            notify::EventKind::Modify(_) => "modified",
-                            notify::EventKind::Remove(_) => "deleted",
-                            _ => continue,
-                        };
-
                         // Extract note ID as relative path from notes folder
                         let note_id = path_to_note_id(&watcher_folder, path)
                             .unwrap_or_else(|| {
@@ -3802,39 +5657,14 @@ fn setup_file_watcher(
                                     .unwrap_or_default()
                             });
 
-                        // Update search index for external file changes
-                        if let Some(state) = app_handle.try_state::<AppState>() {
-                            let index = state.search_index.lock().expect("search index mutex");
-                            if let Some(ref search_index) = *index {
-                                match kind {
-                                    "created" | "modified" => {
-                                        if let Ok(content) = std::fs::read_to_string(path) {
-                                            let title = extract_title(&content);
-                                            let modified = std::fs::metadata(path)
-                                                .ok()
-                                                .and_then(|m| m.modified().ok())
-                                                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                                                .map(|d| d.as_secs() as i64)
-                                                .unwrap_or(0);
-                                            let _ = search_index.index_note(&note_id, &title, &content, modified);
-                                        }
-                                    }
-                                    "deleted" => {
-                                        let _ = search_index.delete_note(&note_id);
-                                    }
-                                    _ => {}
-                                }
-                            }
+                        // Coalesce per note; the flush thread re-indexes once with the final content
+                        let needs_flush = coalescer
+                            .lock()
+                            .expect("watch coalescer mutex")
+                            .record(note_id, path.clone(), kind, Instant::now());
+                        if needs_flush {
+                            schedule_watch_flush(app_handle.clone(), Arc::clone(&coalescer));
                         }
-
-                        let _ = app_handle.emit(
-                            "file-change",
-                            FileChangeEvent {
-                                kind: kind.to_string(),
-                                path: path.to_string_lossy().into_owned(),
-                                changed_ids: vec![note_id.clone()],
-                            },
-                        );
                     }
                 }
             }
@@ -3863,13 +5693,10 @@ fn start_file_watcher(app: AppHandle, state: State<AppState>) -> Result<(), Stri
             .ok_or("Notes folder not set")?
     };
 
-    // Clean up debounce map before starting
-    cleanup_debounce_map(&state.debounce_map);
-
     let watcher_state = setup_file_watcher(
         app,
         &folder,
-        Arc::clone(&state.debounce_map),
+        Arc::clone(&state.watch_coalescer),
     )?;
 
     let mut file_watcher = state.file_watcher.lock().expect("file watcher mutex");
@@ -3883,6 +5710,89 @@ fn copy_to_clipboard(app: AppHandle, text: String) -> Result<(), String> {
     app.clipboard().write_text(text).map_err(|e| e.to_string())
 }
 
+/// Copy a note as rich text so formatting survives pasting into mail or docs.
+/// The markdown source rides along as the plain-text flavor, and is written on its
+/// own if the platform clipboard rejects HTML.
+#[tauri::command]
+async fn copy_note_as_html(app: AppHandle, id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let note = read_note_impl(id, &state).await?;
+    let markdown = strip_frontmatter(&note.content);
+    let html = markdown_to_html_body(&markdown);
+
+    if let Err(e) = app.clipboard().write_html(html, Some(markdown.clone())) {
+        eprintln!("HTML clipboard write failed, copying markdown instead: {}", e);
+        app.clipboard().write_text(markdown).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Decode image bytes with their EXIF orientation applied to the pixels. Re-encoding
+/// drops the tag, so without this rotated camera photos would come out sideways.
+fn decode_oriented_image(data: &[u8], format: image::ImageFormat) -> Option<image::DynamicImage> {
+    use image::ImageDecoder;
+    let mut decoder = image::ImageReader::with_format(std::io::Cursor::new(data), format)
+        .into_decoder()
+        .ok()?;
+    let orientation = decoder.orientation().unwrap_or(image::metadata::Orientation::NoTransforms);
+    let mut img = image::DynamicImage::from_decoder(decoder).ok()?;
+    img.apply_orientation(orientation);
+    Some(img)
+}
+
+/// Downscale an image so its width is at most `max_width`, preserving aspect ratio.
+/// PNG stays PNG; JPEG is re-encoded at `jpeg_quality`. Anything that fails to
+/// decode, isn't PNG/JPEG, or is already small enough is returned unchanged.
+fn downscale_image_bytes(data: Vec<u8>, max_width: u32, jpeg_quality: u8) -> Vec<u8> {
+    let Ok(format) = image::guess_format(&data) else {
+        return data;
+    };
+    if format != image::ImageFormat::Png && format != image::ImageFormat::Jpeg {
+        return data;
+    }
+    let Some(img) = decode_oriented_image(&data, format) else {
+        return data;
+    };
+    if max_width == 0 || img.width() <= max_width {
+        return data;
+    }
+
+    let resized = img.resize(max_width, img.height(), image::imageops::FilterType::Lanczos3);
+    let mut out = Vec::new();
+    let encoded = if format == image::ImageFormat::Jpeg {
+        let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, jpeg_quality.clamp(1, 100));
+        resized.to_rgb8().write_with_encoder(encoder)
+    } else {
+        resized.write_to(&mut std::io::Cursor::new(&mut out), image::ImageFormat::Png)
+    };
+
+    match encoded {
+        Ok(()) => out,
+        Err(_) => data,
+    }
+}
+
+/// Re-encode image bytes as (lossless) WebP. Returns None if the input can't be decoded.
+fn encode_webp(data: &[u8]) -> Option<Vec<u8>> {
+    let img = decode_oriented_image(data, image::guess_format(data).ok()?)?;
+    let mut out = Vec::new();
+    img.to_rgba8()
+        .write_to(&mut std::io::Cursor::new(&mut out), image::ImageFormat::WebP)
+        .ok()?;
+    Some(out)
+}
+
+/// Apply the `imageMaxWidth` / `imageJpegQuality` settings to incoming image bytes.
+fn downscale_for_settings(data: Vec<u8>, state: &AppState) -> Vec<u8> {
+    let (max_width, quality) = {
+        let settings = state.settings.read().expect("settings read lock");
+        (settings.image_max_width, settings.image_jpeg_quality.unwrap_or(85))
+    };
+    match max_width {
+        Some(w) => downscale_image_bytes(data, w, quality),
+        None => data,
+    }
+}
+
 #[tauri::command]
 async fn save_clipboard_image(
     base64_data: String,
@@ -3911,6 +5821,16 @@ async fn save_clipboard_image(
         return Err("Decoded image data is empty".to_string());
     }
 
+    let image_data = downscale_for_settings(image_data, &state);
+
+    // Optionally re-encode to WebP (PNG stays the default)
+    let wants_webp = state.settings.read().expect("settings read lock")
+        .image_format.as_deref() == Some("webp");
+    let (image_data, extension) = match wants_webp.then(|| encode_webp(&image_data)).flatten() {
+        Some(webp) => (webp, "webp"),
+        None => (image_data, "png"),
+    };
+
     // Create assets folder path
     let assets_dir = PathBuf::from(&folder).join("assets");
     fs::create_dir_all(&assets_dir)
@@ -3923,12 +5843,12 @@ async fn save_clipboard_image(
         .map(|d| d.as_secs())
         .unwrap_or(0);
 
-    let mut target_name = format!("screenshot-{}.png", timestamp);
+    let mut target_name = format!("screenshot-{}.{}", timestamp, extension);
     let mut counter = 1;
     let mut target_path = assets_dir.join(&target_name);
 
     while target_path.exists() {
-        target_name = format!("screenshot-{}-{}.png", timestamp, counter);
+        target_name = format!("screenshot-{}-{}.{}", timestamp, counter, extension);
         target_path = assets_dir.join(&target_name);
         counter += 1;
     }
@@ -3960,11 +5880,20 @@ async fn copy_image_to_assets(
         return Err("Source image file does not exist".to_string());
     }
 
-    // Get file extension
-    let extension = source
-        .extension()
-        .and_then(|e| e.to_str())
-        .ok_or("Invalid file extension")?;
+    let data = fs::read(&source)
+        .await
+        .map_err(|e| format!("Failed to read image: {}", e))?;
+
+    // Detect the format from content rather than trusting the extension;
+    // fall back to the extension for formats we can't sniff (e.g. SVG)
+    let extension = match image::guess_format(&data) {
+        Ok(format) => format.extensions_str().first().copied().unwrap_or("png").to_string(),
+        Err(_) => source
+            .extension()
+            .and_then(|e| e.to_str())
+            .ok_or("Invalid file extension")?
+            .to_string(),
+    };
 
     // Get original filename (without extension)
     let original_name = source
@@ -3992,8 +5921,9 @@ async fn copy_image_to_assets(
         counter += 1;
     }
 
-    // Copy the file
-    fs::copy(&source, &target_path)
+    // Copy the file, downscaling first if a max width is configured
+    let data = downscale_for_settings(data, &state);
+    fs::write(&target_path, &data)
         .await
         .map_err(|e| format!("Failed to copy image: {}", e))?;
 
@@ -4001,8 +5931,58 @@ async fn copy_image_to_assets(
     Ok(format!("assets/{}", target_name))
 }
 
+// ---- Asset Commands ----
+
+/// Validate a bare asset filename so it can't escape the `assets/` folder.
+fn validate_asset_name(name: &str) -> Result<String, String> {
+    let trimmed = name.trim();
+    let bare = trimmed.strip_prefix("assets/").unwrap_or(trimmed);
+    if bare.is_empty() || bare.starts_with('.') || bare.contains('/') || bare.contains('\\') {
+        return Err(format!("Invalid asset name: {}", name));
+    }
+    Ok(bare.to_string())
+}
+
+/// Percent-encode an asset filename the way links reference it (e.g. spaces as %20).
+fn encode_asset_name(name: &str) -> String {
+    let mut out = String::new();
+    for b in name.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// Rewrite references to an asset in note content: `assets/<name>` inside markdown
+/// links/images/HTML and bare `![[name]]` embeds, in both raw and percent-encoded form.
+fn rewrite_asset_references(content: &str, old_name: &str, new_name: &str) -> String {
+    let mut forms = vec![(old_name.to_string(), new_name.to_string())];
+    let encoded = (encode_asset_name(old_name), encode_asset_name(new_name));
+    if encoded.0 != old_name {
+        forms.push(encoded);
+    }
+
+    let mut result = content.to_string();
+    for (old, new) in &forms {
+        let path_re = regex::Regex::new(&format!(r#"assets/{}([)>\s"'|\]#?]|$)"#, regex::escape(old))).unwrap();
+        result = path_re
+            .replace_all(&result, |caps: &regex::Captures| format!("assets/{}{}", new, &caps[1]))
+            .to_string();
+
+        let embed_re = regex::Regex::new(&format!(r"!\[\[{}([|\]])", regex::escape(old))).unwrap();
+        result = embed_re
+            .replace_all(&result, |caps: &regex::Captures| format!("![[{}{}", new, &caps[1]))
+            .to_string();
+    }
+    result
+}
+
+/// Rename a file in `assets/` and rewrite every note that references it.
+/// Returns the number of notes updated.
 #[tauri::command]
-fn rebuild_search_index(app: AppHandle, state: State<AppState>) -> Result<(), String> {
+async fn rename_asset(old: String, new: String, state: State<'_, AppState>) -> Result<usize, String> {
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config
@@ -4011,31 +5991,164 @@ fn rebuild_search_index(app: AppHandle, state: State<AppState>) -> Result<(), St
             .ok_or("Notes folder not set")?
     };
 
-    let index_path = get_search_index_path(&app).map_err(|e| e.to_string())?;
+    let old_name = validate_asset_name(&old)?;
+    let new_name = validate_asset_name(&new)?;
+    if old_name == new_name {
+        return Ok(0);
+    }
 
-    // Create new index
-    let search_index = SearchIndex::new(&index_path).map_err(|e| e.to_string())?;
-    search_index
-        .rebuild_index(&PathBuf::from(&folder))
-        .map_err(|e| e.to_string())?;
+    let assets_dir = PathBuf::from(&folder).join("assets");
+    let old_path = assets_dir.join(&old_name);
+    let new_path = assets_dir.join(&new_name);
+    if !old_path.is_file() {
+        return Err(format!("Asset not found: {}", old_name));
+    }
+    if new_path.exists() {
+        return Err(format!("An asset named '{}' already exists", new_name));
+    }
 
-    let mut index = state.search_index.lock().expect("search index mutex");
-    *index = Some(search_index);
+    fs::rename(&old_path, &new_path)
+        .await
+        .map_err(|e| format!("Failed to rename asset: {}", e))?;
 
-    Ok(())
+    // Rewrite references (through save_note_impl so index, backlinks and history stay in sync)
+    let folder_path = PathBuf::from(&folder);
+    let files = walk_md_files(&folder_path, &folder_path).await?;
+    let mut updated = 0;
+
+    for file_path in files {
+        let Ok(content) = fs::read_to_string(&file_path).await else {
+            continue;
+        };
+        let rewritten = rewrite_asset_references(&content, &old_name, &new_name);
+        if rewritten == content {
+            continue;
+        }
+        if let Some(id) = path_to_note_id(&folder_path, &file_path) {
+            save_note_impl(Some(id), rewritten, &state).await?;
+            updated += 1;
+        }
+    }
+
+    Ok(updated)
 }
 
-// --- Backlinks ---
+/// Decode `%XX` escapes in an asset reference.
+fn decode_asset_name(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(b) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// Drop a `?query` or `#fragment` from a raw link target.
+fn strip_asset_suffix(target: &str) -> &str {
+    target.split(['?', '#']).next().unwrap_or(target)
+}
+
+/// Extract the (decoded) asset filenames a note references, from `assets/<name>`
+/// paths and bare `![[name]]` embeds. Link targets (`](...)`, `](<...>)`, quoted
+/// attributes) are taken whole, so names with spaces aren't cut short.
+fn extract_asset_references(content: &str) -> Vec<String> {
+    let target_re = regex::Regex::new(
+        r#"\]\(\s*<(?:\./)?assets/([^>]+)>|\]\(\s*(?:\./)?assets/([^)]+?)(?:\s+"[^"]*"|\s+'[^']*')?\s*\)|"(?:\./)?assets/([^"]+)"|'(?:\./)?assets/([^']+)'"#,
+    )
+    .unwrap();
+    let path_re = regex::Regex::new(r#"assets/([^)\s"'|\]#?]+)"#).unwrap();
+    let embed_re = regex::Regex::new(r"!\[\[([^|\]]+)").unwrap();
+
+    let mut names: Vec<String> = target_re
+        .captures_iter(content)
+        .filter_map(|cap| cap.iter().skip(1).flatten().next().map(|m| m.as_str()))
+        .map(|target| decode_asset_name(strip_asset_suffix(target.trim())))
+        .collect();
+    names.extend(path_re.captures_iter(content).map(|cap| decode_asset_name(&cap[1])));
+    for cap in embed_re.captures_iter(content) {
+        let target = cap[1].trim();
+        let target = target.strip_prefix("assets/").unwrap_or(target);
+        names.push(decode_asset_name(target));
+    }
+    names
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrphanedAsset {
+    pub name: String,
+    pub size: u64,
+}
+
+/// Find files in `assets/` that no note references. References from trashed notes
+/// still count (so restoring a note doesn't break it) unless `ignore_trash` is set.
+fn find_orphaned_assets(notes_folder: &str, ignore_trash: bool) -> Result<Vec<OrphanedAsset>, String> {
+    let folder_path = PathBuf::from(notes_folder);
+    let assets_dir = folder_path.join("assets");
+    if !assets_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = walk_md_files_sync(&folder_path, &folder_path)?;
+    if !ignore_trash {
+        if let Ok(entries) = std::fs::read_dir(get_trash_dir(notes_folder)) {
+            files.extend(
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().is_some_and(|ext| ext == "md")),
+            );
+        }
+    }
+
+    let mut referenced = HashSet::new();
+    for file_path in &files {
+        if let Ok(content) = std::fs::read_to_string(file_path) {
+            referenced.extend(extract_asset_references(&content));
+        }
+    }
+
+    let mut orphans = Vec::new();
+    for entry in std::fs::read_dir(&assets_dir).map_err(|e| e.to_string())?.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !path.is_file() || name.starts_with('.') || referenced.contains(&name) {
+            continue;
+        }
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        orphans.push(OrphanedAsset { name, size });
+    }
+
+    orphans.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(orphans)
+}
 
 #[tauri::command]
-fn get_backlinks(note_title: String, state: State<AppState>) -> Vec<BacklinkEntry> {
-    let bl_index = state.backlinks_index.read().expect("backlinks read lock");
-    let key = note_title.to_lowercase();
-    bl_index.links.get(&key).cloned().unwrap_or_default()
+fn list_orphaned_assets(
+    ignore_trash: Option<bool>,
+    state: State<AppState>,
+) -> Result<Vec<OrphanedAsset>, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config
+            .notes_folder
+            .clone()
+            .ok_or("Notes folder not set")?
+    };
+    find_orphaned_assets(&folder, ignore_trash.unwrap_or(false))
 }
 
 #[tauri::command]
-fn rebuild_backlinks(state: State<AppState>) -> Result<(), String> {
+fn delete_orphaned_assets(ignore_trash: Option<bool>, state: State<AppState>) -> Result<usize, String> {
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config
@@ -4044,921 +6157,3283 @@ fn rebuild_backlinks(state: State<AppState>) -> Result<(), String> {
             .ok_or("Notes folder not set")?
     };
 
-    let new_index = rebuild_backlinks_index_from_folder(&folder);
-    let mut bl_index = state.backlinks_index.write().expect("backlinks write lock");
-    *bl_index = new_index;
+    delete_orphaned_assets_in(&folder, ignore_trash.unwrap_or(false))
+}
 
-    Ok(())
+fn delete_orphaned_assets_in(notes_folder: &str, ignore_trash: bool) -> Result<usize, String> {
+    let assets_dir = PathBuf::from(notes_folder).join("assets");
+    let mut deleted = 0;
+    for asset in find_orphaned_assets(notes_folder, ignore_trash)? {
+        if std::fs::remove_file(assets_dir.join(&asset.name)).is_ok() {
+            deleted += 1;
+        }
+    }
+    Ok(deleted)
 }
 
-// UI helper commands - wrap Tauri plugins for consistent invoke-based API
+/// Build (or reuse) a cached PNG thumbnail for an asset. Cache files live in
+/// `.scratch/thumbnails/` and embed the source mtime, so edits invalidate them.
+fn asset_thumbnail_bytes(notes_folder: &str, name: &str, max_size: u32) -> Result<Vec<u8>, String> {
+    let source = PathBuf::from(notes_folder).join("assets").join(name);
+    let source_modified = std::fs::metadata(&source)
+        .map_err(|_| format!("Asset not found: {}", name))?
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
 
-#[tauri::command]
-async fn open_folder_dialog(
-    app: AppHandle,
-    default_path: Option<String>,
-) -> Result<Option<String>, String> {
-    use tauri_plugin_dialog::DialogExt;
+    let thumbs_dir = PathBuf::from(notes_folder).join(".scratch").join("thumbnails");
+    let prefix = format!("{}-{}-", encode_asset_name(name), max_size);
+    let cache_path = thumbs_dir.join(format!("{}{}.png", prefix, source_modified));
+    if let Ok(cached) = std::fs::read(&cache_path) {
+        return Ok(cached);
+    }
 
-    // Run blocking dialog on a separate thread to avoid blocking the async runtime
-    let result = tauri::async_runtime::spawn_blocking(move || {
-        let mut builder = app.dialog().file().set_can_create_directories(true);
+    let data = std::fs::read(&source).map_err(|e| format!("Failed to read asset: {}", e))?;
+    let img = image::load_from_memory(&data).map_err(|_| format!("Not an image: {}", name))?;
+    let mut out = Vec::new();
+    img.thumbnail(max_size.max(1), max_size.max(1))
+        .write_to(&mut std::io::Cursor::new(&mut out), image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode thumbnail: {}", e))?;
 
-        if let Some(path) = default_path {
-            builder = builder.set_directory(path);
+    // Drop stale thumbnails for this asset/size before caching the new one
+    std::fs::create_dir_all(&thumbs_dir).map_err(|e| e.to_string())?;
+    if let Ok(entries) = std::fs::read_dir(&thumbs_dir) {
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                let _ = std::fs::remove_file(entry.path());
+            }
         }
+    }
+    let _ = std::fs::write(&cache_path, &out);
 
-        builder.blocking_pick_folder()
-    })
-    .await
-    .map_err(|e| format!("Dialog task failed: {}", e))?;
-
-    Ok(result.map(|p| p.to_string()))
+    Ok(out)
 }
 
+/// Return a downscaled image from `assets/` as a base64 PNG data URL.
 #[tauri::command]
-async fn reveal_in_file_manager(path: String) -> Result<(), String> {
-    let path_buf = PathBuf::from(&path);
-    if !path_buf.exists() {
-        return Err("Path does not exist".to_string());
-    }
+async fn get_asset_thumbnail(name: String, max_size: u32, state: State<'_, AppState>) -> Result<String, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config
+            .notes_folder
+            .clone()
+            .ok_or("Notes folder not set")?
+    };
+    let name = validate_asset_name(&name)?;
 
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open")
-            .args(["-R", &path])
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
+    let bytes = tauri::async_runtime::spawn_blocking(move || asset_thumbnail_bytes(&folder, &name, max_size))
+        .await
+        .map_err(|e| e.to_string())??;
 
-    #[cfg(target_os = "windows")]
-    {
-        // Windows explorer /select requires backslashes
-        let windows_path = path.replace("/", "\\");
-        std::process::Command::new("explorer")
-            .args(["/select,", &windows_path])
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
+    Ok(format!(
+        "data:image/png;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        // Linux: open containing directory (most file managers don't support selecting)
-        let parent = path_buf
-            .parent()
-            .ok_or_else(|| "Cannot determine parent directory".to_string())?;
-        let parent_str = parent
-            .to_str()
-            .ok_or_else(|| "Path contains invalid UTF-8".to_string())?;
-        std::process::Command::new("xdg-open")
-            .arg(parent_str)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
+#[tauri::command]
+fn rebuild_search_index(app: AppHandle, state: State<AppState>) -> Result<(), String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config
+            .notes_folder
+            .clone()
+            .ok_or("Notes folder not set")?
+    };
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
-    {
-        return Err("Unsupported platform".to_string());
-    }
+    let index_path = get_search_index_path(&app).map_err(|e| e.to_string())?;
+
+    // Create new index
+    let search_index = SearchIndex::new(&index_path).map_err(|e| e.to_string())?;
+    search_index
+        .rebuild_index(&PathBuf::from(&folder))
+        .map_err(|e| e.to_string())?;
+
+    let mut index = state.search_index.lock().expect("search index mutex");
+    *index = Some(search_index);
 
     Ok(())
 }
 
-#[tauri::command]
-async fn open_url_safe(url: String) -> Result<(), String> {
-    // Validate URL scheme - only allow http, https, mailto
-    let parsed = url::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
+fn reindex_note_impl(id: &str, state: &AppState) -> Result<(), String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let file_path = resolve_note_path(&folder, id)?;
+    let content = std::fs::read_to_string(&file_path).map_err(|_| format!("Note not found: {}", id))?;
+    let modified = file_path
+        .metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
 
-    match parsed.scheme() {
-        "http" | "https" | "mailto" => {}
-        scheme => {
-            return Err(format!(
-                "URL scheme '{}' is not allowed. Only http, https, and mailto are permitted.",
-                scheme
-            ))
-        }
-    }
+    let index = state.search_index.lock().expect("search index mutex");
+    let search_index = index.as_ref().ok_or("Search index not initialized")?;
+    search_index
+        .index_note(id, &extract_title(&content), &content, modified)
+        .map_err(|e| e.to_string())
+}
 
-    // Use system opener
-    open::that(&url).map_err(|e| format!("Failed to open URL: {}", e))
+fn reindex_deleted_impl(id: &str, state: &AppState) -> Result<(), String> {
+    let id = validate_note_id(id)?;
+    let index = state.search_index.lock().expect("search index mutex");
+    let search_index = index.as_ref().ok_or("Search index not initialized")?;
+    search_index.delete_note(&id).map_err(|e| e.to_string())
 }
 
-/// URL metadata returned by fetch_url_metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct UrlMetadata {
-    pub title: String,
-    pub description: String,
-    pub image: String,
-    pub favicon: String,
-    pub domain: String,
+/// Re-index one note from disk, for tools that write note files directly.
+#[tauri::command]
+fn reindex_note(id: String, state: State<AppState>) -> Result<(), String> {
+    reindex_note_impl(&id, &state)
 }
 
+/// Drop a note from the search index after its file was removed externally.
 #[tauri::command]
-async fn fetch_url_metadata(url: String) -> Result<UrlMetadata, String> {
-    // Validate URL scheme
-    let parsed = url::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
-    match parsed.scheme() {
-        "http" | "https" => {}
-        scheme => {
-            return Err(format!(
-                "URL scheme '{}' is not allowed. Only http and https are permitted.",
-                scheme
-            ))
+fn reindex_deleted(id: String, state: State<AppState>) -> Result<(), String> {
+    reindex_deleted_impl(&id, &state)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexDiagnostics {
+    pub available: bool,
+    pub doc_count: u64,
+    pub segment_count: usize,
+    pub size_bytes: u64,
+    pub note_count: usize,
+    pub in_sync: bool,
+    // doc_count - note_count; positive means stale documents, negative means missing notes
+    pub divergence: Option<i64>,
+}
+
+impl IndexDiagnostics {
+    fn new(doc_count: u64, segment_count: usize, size_bytes: u64, note_count: usize) -> Self {
+        let divergence = doc_count as i64 - note_count as i64;
+        IndexDiagnostics {
+            available: true,
+            doc_count,
+            segment_count,
+            size_bytes,
+            note_count,
+            in_sync: divergence == 0,
+            divergence: (divergence != 0).then_some(divergence),
         }
     }
+}
 
-    let domain = parsed.host_str().unwrap_or("").to_string();
+// Total size of the files in a directory tree
+fn dir_size(path: &std::path::Path) -> u64 {
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| match entry.metadata() {
+                    Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+                    Ok(meta) => meta.len(),
+                    Err(_) => 0,
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
 
-    // Build a favicon URL from the domain
-    let favicon_default = format!(
-        "https://www.google.com/s2/favicons?domain={}&sz=32",
-        domain
-    );
+/// Read-only health check of the search index against the notes on disk.
+#[tauri::command]
+fn search_index_diagnostics(app: AppHandle, state: State<AppState>) -> Result<IndexDiagnostics, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let base = PathBuf::from(&folder);
+    let note_count = walk_md_files_sync(&base, &base)?.len();
 
-    // Fetch the HTML
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .user_agent("Mozilla/5.0 (compatible; Scratch/1.0)")
-        .redirect(reqwest::redirect::Policy::limited(5))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let stats = {
+        let index = state.search_index.lock().expect("search index mutex");
+        index.as_ref().map(|search_index| search_index.stats())
+    };
+    let Some((doc_count, segment_count)) = stats else {
+        return Ok(IndexDiagnostics {
+            available: false,
+            in_sync: false,
+            ..IndexDiagnostics::new(0, 0, 0, note_count)
+        });
+    };
 
-    let response = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to fetch URL: {}", e))?;
+    let size_bytes = get_search_index_path(&app).map(|p| dir_size(&p)).unwrap_or(0);
+    Ok(IndexDiagnostics::new(doc_count, segment_count, size_bytes, note_count))
+}
 
-    if !response.status().is_success() {
-        return Err(format!("HTTP error: {}", response.status()));
-    }
+// --- Backlinks ---
 
-    let html = response
-        .text()
-        .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+#[tauri::command]
+fn get_backlinks(note_title: String, state: State<AppState>) -> Vec<BacklinkEntry> {
+    let bl_index = state.backlinks_index.read().expect("backlinks read lock");
+    let key = note_title.to_lowercase();
+    bl_index.links.get(&key).cloned().unwrap_or_default()
+}
 
-    // Parse HTML with scraper
-    let document = scraper::Html::parse_document(&html);
+// Utility: Read `aliases:` from frontmatter, as a single string or a list
+fn extract_aliases(content: &str) -> Vec<String> {
+    frontmatter_list(content, "aliases")
+}
 
-    // Helper to get meta content by property or name
-    let get_meta = |attr: &str, value: &str| -> Option<String> {
-        let selector_str = format!("meta[{}=\"{}\"]", attr, value);
-        let result = if let Ok(selector) = scraper::Selector::parse(&selector_str) {
-            document
-                .select(&selector)
-                .next()
-                .and_then(|el| el.value().attr("content"))
-                .map(|s| s.to_string())
-        } else {
-            None
-        };
-        result
+// Utility: Read `tags:` from frontmatter, as a single string or a list
+fn extract_tags(content: &str) -> Vec<String> {
+    frontmatter_list(content, "tags")
+}
+
+// Utility: Read a frontmatter field holding a single string or a list of strings
+fn frontmatter_list(content: &str, key: &str) -> Vec<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.first().map(|l| l.trim()) != Some("---") {
+        return Vec::new();
+    }
+    let Some(close) = lines.iter().skip(1).position(|l| l.trim() == "---") else {
+        return Vec::new();
+    };
+    let yaml = lines[1..close + 1].join("\n");
+    let Ok(serde_yaml::Value::Mapping(map)) = serde_yaml::from_str::<serde_yaml::Value>(&yaml) else {
+        return Vec::new();
     };
+    // Numbers and bools count too, so `tags: [2024, work]` keeps its 2024
+    let scalar = |v: &serde_yaml::Value| match v {
+        serde_yaml::Value::String(item) => Some(item.clone()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        serde_yaml::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    };
+    match map.get(key) {
+        Some(serde_yaml::Value::Sequence(items)) => items.iter().filter_map(scalar).collect(),
+        Some(item) => scalar(item).into_iter().collect(),
+        None => Vec::new(),
+    }
+    .into_iter()
+    .filter(|a| !a.trim().is_empty())
+    .collect()
+}
 
-    // Extract title: og:title > twitter:title > <title>
-    let title = get_meta("property", "og:title")
-        .or_else(|| get_meta("name", "twitter:title"))
-        .or_else(|| {
-            scraper::Selector::parse("title")
-                .ok()
-                .and_then(|sel| document.select(&sel).next())
-                .map(|el| el.text().collect::<String>())
-        })
-        .unwrap_or_default()
-        .trim()
-        .to_string();
+/// Gather backlinks stored under any of `names`, keeping one entry per source note + context.
+fn collect_backlinks(index: &BacklinksIndex, names: &[String]) -> Vec<BacklinkEntry> {
+    let mut seen_keys = HashSet::new();
+    let mut seen_entries = HashSet::new();
+    let mut results = Vec::new();
+    for name in names {
+        let key = name.trim().to_lowercase();
+        if !seen_keys.insert(key.clone()) {
+            continue;
+        }
+        for entry in index.links.get(&key).into_iter().flatten() {
+            if seen_entries.insert((entry.note_id.clone(), entry.context.clone())) {
+                results.push(entry.clone());
+            }
+        }
+    }
+    results
+}
 
-    // Extract description: og:description > twitter:description > meta description
-    let description = get_meta("property", "og:description")
-        .or_else(|| get_meta("name", "twitter:description"))
-        .or_else(|| get_meta("name", "description"))
-        .unwrap_or_default()
-        .trim()
-        .to_string();
+// Names a wikilink may use to reach a note: its title, aliases, id, and file stem
+fn backlink_names(note_id: &str, title: &str, content: &str) -> Vec<String> {
+    let mut names = vec![title.to_string()];
+    names.extend(extract_aliases(content));
+    names.push(note_id.to_string());
+    if let Some(stem) = note_id.rsplit('/').next() {
+        names.push(stem.to_string());
+    }
+    names
+}
 
-    // Extract image: og:image > twitter:image
-    let image = get_meta("property", "og:image")
-        .or_else(|| get_meta("name", "twitter:image"))
-        .unwrap_or_default()
-        .trim()
-        .to_string();
+/// Backlinks for a note by id: links may target its current title, an alias, or the id itself.
+fn backlinks_by_id_impl(note_id: &str, state: &AppState) -> Result<Vec<BacklinkEntry>, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let cached_title = state
+        .notes_cache
+        .read()
+        .expect("cache read lock")
+        .get(note_id)
+        .map(|n| n.title.clone());
 
-    // Extract favicon: link[rel~=icon] > default
-    let favicon = scraper::Selector::parse("link[rel~=\"icon\"], link[rel=\"shortcut icon\"]")
-        .ok()
-        .and_then(|sel| {
-            document
-                .select(&sel)
-                .next()
-                .and_then(|el| el.value().attr("href"))
-                .map(|href| {
-                    // Resolve relative URLs
-                    if href.starts_with("http") {
-                        href.to_string()
-                    } else if href.starts_with("//") {
-                        format!("https:{}", href)
-                    } else {
-                        format!("{}://{}{}", parsed.scheme(), domain, if href.starts_with('/') { href.to_string() } else { format!("/{}", href) })
-                    }
-                })
-        })
-        .unwrap_or(favicon_default);
+    let content = std::fs::read_to_string(resolve_note_path(&folder, note_id)?).unwrap_or_default();
+    let title = cached_title.unwrap_or_else(|| extract_title(&content));
+    let names = backlink_names(note_id, &title, &content);
 
-    Ok(UrlMetadata {
-        title,
-        description,
-        image,
-        favicon,
-        domain,
-    })
+    let bl_index = state.backlinks_index.read().expect("backlinks read lock");
+    Ok(collect_backlinks(&bl_index, &names))
 }
 
-// Git commands - run blocking git operations off the main thread
+#[tauri::command]
+fn get_backlinks_by_id(note_id: String, state: State<AppState>) -> Result<Vec<BacklinkEntry>, String> {
+    backlinks_by_id_impl(&note_id, &state)
+}
+
+// Number of distinct notes among backlink entries (a note linking twice counts once)
+fn count_distinct_sources(entries: &[BacklinkEntry]) -> usize {
+    entries.iter().map(|e| e.note_id.as_str()).collect::<HashSet<_>>().len()
+}
 
 #[tauri::command]
-async fn git_is_available() -> bool {
-    tauri::async_runtime::spawn_blocking(git::is_available)
-        .await
-        .unwrap_or(false)
+fn count_backlinks(note_title: String, state: State<AppState>) -> usize {
+    let bl_index = state.backlinks_index.read().expect("backlinks read lock");
+    bl_index
+        .links
+        .get(&note_title.to_lowercase())
+        .map(|entries| count_distinct_sources(entries))
+        .unwrap_or(0)
 }
 
 #[tauri::command]
-async fn git_get_status(state: State<'_, AppState>) -> Result<git::GitStatus, String> {
+fn count_backlinks_by_id(note_id: String, state: State<AppState>) -> Result<usize, String> {
+    Ok(count_distinct_sources(&backlinks_by_id_impl(&note_id, &state)?))
+}
+
+// A note is orphaned when no other note links to any of its names
+fn is_orphan(index: &BacklinksIndex, note_id: &str, names: &[String]) -> bool {
+    collect_backlinks(index, names).iter().all(|entry| entry.note_id == note_id)
+}
+
+/// Notes nothing else links to. Links from a note to itself don't count.
+pub async fn list_orphan_notes_impl(state: &AppState) -> Result<Vec<NoteMetadata>, String> {
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
-        app_config.notes_folder.clone()
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
+    let notes = list_notes_impl(state, None, true).await?;
 
-    match folder {
-        Some(path) => {
-            tauri::async_runtime::spawn_blocking(move || {
-                git::get_status(&PathBuf::from(path))
-            })
-            .await
-            .map_err(|e| e.to_string())
+    let mut names_by_id = HashMap::new();
+    for note in &notes {
+        let content = resolve_note_path(&folder, &note.id)
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        names_by_id.insert(note.id.clone(), backlink_names(&note.id, &note.title, &content));
+    }
+
+    let bl_index = state.backlinks_index.read().expect("backlinks read lock");
+    Ok(notes
+        .into_iter()
+        .filter(|note| is_orphan(&bl_index, &note.id, &names_by_id[&note.id]))
+        .collect())
+}
+
+#[tauri::command]
+async fn list_orphan_notes(state: State<'_, AppState>) -> Result<Vec<NoteMetadata>, String> {
+    list_orphan_notes_impl(&state).await
+}
+
+// Max edit distance for a broken link's suggested replacement
+const LINK_FIX_MAX_DISTANCE: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkFix {
+    pub source_id: String,
+    pub line: usize, // 1-based
+    pub broken_target: String,
+    pub suggestion: Option<String>, // closest existing title, if within LINK_FIX_MAX_DISTANCE
+}
+
+// Closest title to `target` by case-insensitive edit distance. Short targets need a
+// proportionally closer match so e.g. "ab" doesn't suggest "xy".
+fn suggest_link_fix(target: &str, titles: &[String]) -> Option<String> {
+    let target_lower = target.to_lowercase();
+    let max_distance = LINK_FIX_MAX_DISTANCE.min(target.chars().count() / 2);
+    titles
+        .iter()
+        .map(|t| (levenshtein_distance(&target_lower, &t.to_lowercase()), t))
+        .filter(|(d, _)| *d <= max_distance)
+        .min_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)))
+        .map(|(_, t)| t.clone())
+}
+
+// Wikilinks in `content` whose target (before any `|alias` or `#heading`) isn't in
+// `known` (lowercased names). Fenced code and asset embeds like `![[pic.png]]` are skipped.
+fn find_broken_links(source_id: &str, content: &str, known: &HashSet<String>, titles: &[String]) -> Vec<LinkFix> {
+    let re = regex::Regex::new(r"(!?)\[\[([^\]]+)\]\]").unwrap();
+    let mut fixes = Vec::new();
+    let mut in_fence = false;
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        for cap in re.captures_iter(line) {
+            let inner = &cap[2];
+            let target = inner.split('|').next().unwrap_or("");
+            let target = target.split('#').next().unwrap_or("").trim();
+            if target.is_empty() || known.contains(&target.to_lowercase()) {
+                continue;
+            }
+            let is_asset = !cap[1].is_empty()
+                && std::path::Path::new(target).extension().is_some_and(|e| !e.eq_ignore_ascii_case("md"));
+            if is_asset {
+                continue;
+            }
+            fixes.push(LinkFix {
+                source_id: source_id.to_string(),
+                line: i + 1,
+                broken_target: target.to_string(),
+                suggestion: suggest_link_fix(target, titles),
+            });
         }
-        None => Ok(git::GitStatus::default()),
     }
+    fixes
 }
 
+/// Every wikilink that doesn't resolve to a note, with the closest existing title when one is near.
 #[tauri::command]
-async fn git_init_repo(state: State<'_, AppState>) -> Result<(), String> {
+fn suggest_link_fixes(state: State<AppState>) -> Result<Vec<LinkFix>, String> {
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
+    let base = PathBuf::from(&folder);
+
+    let mut notes = Vec::new();
+    let mut known = HashSet::new();
+    let mut titles = Vec::new();
+    for file_path in walk_md_files_sync(&base, &base)? {
+        let Some(id) = path_to_note_id(&base, &file_path) else { continue };
+        let content = std::fs::read_to_string(&file_path).unwrap_or_default();
+        let title = extract_title(&content);
+        known.extend(backlink_names(&id, &title, &content).iter().map(|n| n.to_lowercase()));
+        titles.push(title);
+        notes.push((id, content));
+    }
+    notes.sort_by(|a, b| a.0.cmp(&b.0));
 
-    tauri::async_runtime::spawn_blocking(move || {
-        git::git_init(&PathBuf::from(folder))
-    })
-    .await
-    .map_err(|e| e.to_string())?
+    Ok(notes
+        .iter()
+        .flat_map(|(id, content)| find_broken_links(id, content, &known, &titles))
+        .collect())
 }
 
-#[tauri::command]
-async fn git_commit(message: String, state: State<'_, AppState>) -> Result<git::GitResult, String> {
-    let folder = {
-        let app_config = state.app_config.read().expect("app_config read lock");
-        app_config.notes_folder.clone()
-    };
+// Retarget `[[old]]` links on one 1-based line, keeping any `#heading` or `|alias` suffix
+fn rewrite_link_on_line(content: &str, line: usize, old_target: &str, new_target: &str) -> Result<String, String> {
+    if new_target.trim().is_empty() || new_target.contains(['[', ']', '|']) {
+        return Err(format!("Invalid link target: '{}'", new_target));
+    }
+    let mut lines: Vec<String> = content.split('\n').map(|l| l.to_string()).collect();
+    let target_line = line
+        .checked_sub(1)
+        .and_then(|i| lines.get_mut(i))
+        .ok_or_else(|| format!("Line {} is out of range", line))?;
 
-    match folder {
-        Some(path) => {
-            tauri::async_runtime::spawn_blocking(move || {
-                git::commit_all(&PathBuf::from(path), &message)
-            })
-            .await
-            .map_err(|e| e.to_string())
-        }
-        None => Ok(git::GitResult {
-            success: false,
-            message: None,
-            error: Some("Notes folder not set".to_string()),
-        }),
+    let re = regex::Regex::new(&format!(
+        r"\[\[\s*{}\s*((?:#[^\]|]*)?(?:\|[^\]]*)?)\]\]",
+        regex::escape(old_target)
+    ))
+    .map_err(|e| e.to_string())?;
+    if !re.is_match(target_line) {
+        return Err(format!("Line {} has no link to '{}'", line, old_target));
     }
+    *target_line = re
+        .replace_all(target_line, |caps: &regex::Captures| format!("[[{}{}]]", new_target.trim(), &caps[1]))
+        .into_owned();
+    Ok(lines.join("\n"))
 }
 
+/// Rewrite one broken link (as reported by suggest_link_fixes) to a new target and save.
 #[tauri::command]
-async fn git_push(state: State<'_, AppState>) -> Result<git::GitResult, String> {
+async fn apply_link_fix(
+    source_id: String,
+    line: usize,
+    old_target: String,
+    new_target: String,
+    state: State<'_, AppState>,
+) -> Result<Note, String> {
+    let note = read_note_impl(source_id.clone(), &state).await?;
+    let new_content = rewrite_link_on_line(&note.content, line, &old_target, &new_target)?;
+    save_note_impl(Some(source_id), new_content, &state).await
+}
+
+#[tauri::command]
+fn rebuild_backlinks(state: State<AppState>) -> Result<(), String> {
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
-        app_config.notes_folder.clone()
+        app_config
+            .notes_folder
+            .clone()
+            .ok_or("Notes folder not set")?
     };
 
-    match folder {
-        Some(path) => {
-            tauri::async_runtime::spawn_blocking(move || {
-                git::push(&PathBuf::from(path))
-            })
-            .await
-            .map_err(|e| e.to_string())
+    let new_index = rebuild_backlinks_index_from_folder(&folder);
+    let mut bl_index = state.backlinks_index.write().expect("backlinks write lock");
+    *bl_index = new_index;
+
+    Ok(())
+}
+
+// Quote a string for a Graphviz DOT id or label
+fn dot_quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+/// Render the backlinks index as a DOT digraph. Nodes are notes (labeled by title) and
+/// edges run from the linking note to the linked one. Links to notes that don't exist
+/// get a dashed placeholder node. Each note comes with its `backlink_names`, so links
+/// by alias, id or file stem resolve like they do for backlinks; titles win ties.
+fn link_graph_dot(notes: &[(String, String, Vec<String>)], index: &BacklinksIndex) -> String {
+    let mut by_name: HashMap<String, &str> = HashMap::new();
+    for (id, title, _) in notes {
+        by_name.entry(title.to_lowercase()).or_insert(id);
+    }
+    for (id, _, names) in notes {
+        for name in names {
+            by_name.entry(name.to_lowercase()).or_insert(id);
         }
-        None => Ok(git::GitResult {
-            success: false,
-            message: None,
-            error: Some("Notes folder not set".to_string()),
-        }),
     }
+
+    let mut edges: Vec<(String, String)> = Vec::new();
+    let mut missing: Vec<&str> = Vec::new();
+    for (target, entries) in &index.links {
+        let target_id = match by_name.get(target) {
+            Some(id) => id.to_string(),
+            None => {
+                missing.push(target);
+                format!("missing:{}", target)
+            }
+        };
+        for entry in entries {
+            edges.push((entry.note_id.clone(), target_id.clone()));
+        }
+    }
+    edges.sort();
+    edges.dedup();
+    missing.sort();
+
+    let mut out = String::from("digraph notes {\n    node [shape=box];\n");
+    for (id, title, _) in notes {
+        out.push_str(&format!("    {} [label={}];\n", dot_quote(id), dot_quote(title)));
+    }
+    for target in missing {
+        out.push_str(&format!(
+            "    {} [label={}, style=dashed];\n",
+            dot_quote(&format!("missing:{}", target)),
+            dot_quote(target)
+        ));
+    }
+    for (from, to) in &edges {
+        out.push_str(&format!("    {} -> {};\n", dot_quote(from), dot_quote(to)));
+    }
+    out.push_str("}\n");
+    out
 }
 
+/// Write the note link graph as a Graphviz `.dot` file.
 #[tauri::command]
-async fn git_add_remote(url: String, state: State<'_, AppState>) -> Result<git::GitResult, String> {
+fn export_link_graph_dot(dest: String, state: State<AppState>) -> Result<(), String> {
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
-        app_config.notes_folder.clone()
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
+    let base = PathBuf::from(&folder);
+    let mut notes: Vec<(String, String, Vec<String>)> = walk_md_files_sync(&base, &base)?
+        .iter()
+        .filter_map(|path| {
+            let id = path_to_note_id(&base, path)?;
+            let content = std::fs::read_to_string(path).ok()?;
+            let title = extract_title(&content);
+            let names = backlink_names(&id, &title, &content);
+            Some((id, title, names))
+        })
+        .collect();
+    notes.sort();
 
-    match folder {
-        Some(path) => {
-            tauri::async_runtime::spawn_blocking(move || {
-                git::add_remote(&PathBuf::from(path), &url)
-            })
-            .await
-            .map_err(|e| e.to_string())
-        }
-        None => Ok(git::GitResult {
-            success: false,
-            message: None,
-            error: Some("Notes folder not set".to_string()),
-        }),
-    }
+    let dot = {
+        let bl_index = state.backlinks_index.read().expect("backlinks read lock");
+        link_graph_dot(&notes, &bl_index)
+    };
+    std::fs::write(&dest, dot).map_err(|e| format!("Failed to write file: {}", e))
 }
 
+/// Refresh backlinks from a single note, e.g. after it was edited outside Scratch.
 #[tauri::command]
-async fn git_push_with_upstream(state: State<'_, AppState>) -> Result<git::GitResult, String> {
+fn rebuild_backlinks_for(id: String, state: State<AppState>) -> Result<(), String> {
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
-        app_config.notes_folder.clone()
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
+    let id = validate_note_id(&id)?;
 
-    match folder {
-        Some(path) => {
-            tauri::async_runtime::spawn_blocking(move || {
-                // Get current branch first
-                let status = git::get_status(&PathBuf::from(&path));
-                match status.current_branch {
-                    Some(branch) => git::push_with_upstream(&PathBuf::from(&path), &branch),
-                    None => git::GitResult {
-                        success: false,
-                        message: None,
-                        error: Some("No current branch found".to_string()),
-                    },
-                }
-            })
-            .await
-            .map_err(|e| e.to_string())
-        }
-        None => Ok(git::GitResult {
-            success: false,
-            message: None,
-            error: Some("Notes folder not set".to_string()),
-        }),
-    }
+    let mut bl_index = state.backlinks_index.write().expect("backlinks write lock");
+    refresh_backlinks_for_note(&mut bl_index, &folder, &id);
+    save_backlinks_index(&folder, &bl_index).map_err(|e| e.to_string())
 }
 
-// Check if Claude CLI is installed
-fn get_expanded_path() -> String {
-    let system_path = std::env::var("PATH").unwrap_or_default();
-    let home = std::env::var("HOME").unwrap_or_else(|_| String::new());
+// UI helper commands - wrap Tauri plugins for consistent invoke-based API
 
-    if home.is_empty() {
-        return system_path;
+#[tauri::command]
+async fn open_folder_dialog(
+    app: AppHandle,
+    default_path: Option<String>,
+) -> Result<Option<String>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    // Run blocking dialog on a separate thread to avoid blocking the async runtime
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let mut builder = app.dialog().file().set_can_create_directories(true);
+
+        if let Some(path) = default_path {
+            builder = builder.set_directory(path);
+        }
+
+        builder.blocking_pick_folder()
+    })
+    .await
+    .map_err(|e| format!("Dialog task failed: {}", e))?;
+
+    Ok(result.map(|p| p.to_string()))
+}
+
+fn existing_note_path(folder: &str, id: &str) -> Result<String, String> {
+    let path = resolve_note_path(folder, id)?;
+    if !path.is_file() {
+        return Err(format!("Note not found: {}", id));
     }
+    Ok(path.to_string_lossy().to_string())
+}
 
-    // Common locations for node-installed CLIs (nvm, volta, fnm, homebrew, global npm)
-    let candidate_dirs = vec![
-        format!("{home}/.nvm/versions/node"),
-        format!("{home}/.fnm/node-versions"),
-    ];
-    let static_dirs = vec![
-        format!("{home}/.volta/bin"),
-        format!("{home}/.local/bin"),
-        "/usr/local/bin".to_string(),
-        "/opt/homebrew/bin".to_string(),
-    ];
+/// Absolute path of a note's file, without reading it (e.g. for reveal_in_file_manager).
+#[tauri::command]
+fn get_note_path(id: String, state: State<AppState>) -> Result<String, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    existing_note_path(&folder, &id)
+}
 
-    let mut expanded = Vec::new();
+// ---- Note Checksums ----
 
-    // For nvm/fnm, scan for node version dirs containing a bin/ folder
-    for base in &candidate_dirs {
-        if let Ok(entries) = std::fs::read_dir(base) {
-            for entry in entries.flatten() {
-                let bin_path = entry.path().join("bin");
-                if bin_path.exists() {
-                    expanded.push(bin_path.to_string_lossy().to_string());
-                }
-            }
+// Most entries kept in the checksum cache before it is cleared
+const MAX_CHECKSUM_CACHE_ENTRIES: usize = 4096;
+
+// Etag cached alongside the file mtime and size it was computed from
+pub struct CachedChecksum {
+    modified: std::time::SystemTime,
+    len: u64,
+    etag: String,
+}
+
+// Same format as story/database etags (stories::compute_etag over the file contents).
+// The file is only re-read when its mtime or size changed since the cached value.
+fn note_checksum(folder: &str, id: &str, state: &AppState) -> Result<String, String> {
+    let file_path = resolve_note_path(folder, id)?;
+    let meta = std::fs::metadata(&file_path)
+        .ok()
+        .filter(|m| m.is_file())
+        .ok_or_else(|| format!("Note not found: {}", id))?;
+    let modified = meta.modified().map_err(|e| e.to_string())?;
+
+    if let Some(cached) = state.checksum_cache.lock().expect("checksum cache mutex").get(&file_path) {
+        if cached.modified == modified && cached.len == meta.len() {
+            return Ok(cached.etag.clone());
         }
     }
 
-    for dir in static_dirs {
-        expanded.push(dir);
+    let content = std::fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
+    let etag = stories::compute_etag(&content);
+    let mut cache = state.checksum_cache.lock().expect("checksum cache mutex");
+    // Entries are cheap to recompute, so a full cache is simply dropped
+    if cache.len() >= MAX_CHECKSUM_CACHE_ENTRIES && !cache.contains_key(&file_path) {
+        cache.clear();
     }
-
-    expanded.push(system_path);
-    expanded.join(":")
+    cache.insert(
+        file_path,
+        CachedChecksum {
+            modified,
+            len: meta.len(),
+            etag: etag.clone(),
+        },
+    );
+    Ok(etag)
 }
 
 #[tauri::command]
-async fn ai_check_claude_cli() -> Result<bool, String> {
-    use std::process::Command;
-
-    let path = get_expanded_path();
-    let which_cmd = if cfg!(target_os = "windows") {
-        "where"
-    } else {
-        "which"
+fn get_note_checksum(id: String, state: State<AppState>) -> Result<String, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
+    note_checksum(&folder, &id, &state)
+}
 
-    let check_output = Command::new(which_cmd)
-        .arg("claude")
-        .env("PATH", &path)
-        .output()
-        .map_err(|e| format!("Failed to check for claude CLI: {}", e))?;
-
-    Ok(check_output.status.success())
+/// Checksums for several notes at once; ids that don't resolve to a note are omitted.
+#[tauri::command]
+fn get_checksums(ids: Vec<String>, state: State<AppState>) -> Result<HashMap<String, String>, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    Ok(ids
+        .into_iter()
+        .filter_map(|id| {
+            let etag = note_checksum(&folder, &id, &state).ok()?;
+            Some((id, etag))
+        })
+        .collect())
 }
 
-// AI execute command
 #[tauri::command]
-async fn ai_execute_claude(
-    file_path: String,
-    prompt: String,
-) -> Result<AiExecutionResult, String> {
-    use std::process::{Child, Command, Stdio};
-    use std::io::Write;
+async fn reveal_in_file_manager(path: String) -> Result<(), String> {
+    let path_buf = PathBuf::from(&path);
+    if !path_buf.exists() {
+        return Err("Path does not exist".to_string());
+    }
 
-    // Check if claude CLI exists
-    let path = get_expanded_path();
-    let which_cmd = if cfg!(target_os = "windows") {
-        "where"
-    } else {
-        "which"
-    };
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open")
+            .args(["-R", &path])
+            .spawn()
+            .map_err(|e| e.to_string())?;
+    }
 
-    let check_output = Command::new(which_cmd)
-        .arg("claude")
-        .env("PATH", &path)
-        .output()
-        .map_err(|e| format!("Failed to check for claude CLI: {}", e))?;
+    #[cfg(target_os = "windows")]
+    {
+        // Windows explorer /select requires backslashes
+        let windows_path = path.replace("/", "\\");
+        std::process::Command::new("explorer")
+            .args(["/select,", &windows_path])
+            .spawn()
+            .map_err(|e| e.to_string())?;
+    }
 
-    if !check_output.status.success() {
-        return Ok(AiExecutionResult {
-            success: false,
-            output: String::new(),
-            error: Some(
-                "Claude CLI not found. Please install it from https://claude.ai/code".to_string(),
-            ),
-        });
+    #[cfg(target_os = "linux")]
+    {
+        // Linux: open containing directory (most file managers don't support selecting)
+        let parent = path_buf
+            .parent()
+            .ok_or_else(|| "Cannot determine parent directory".to_string())?;
+        let parent_str = parent
+            .to_str()
+            .ok_or_else(|| "Path contains invalid UTF-8".to_string())?;
+        std::process::Command::new("xdg-open")
+            .arg(parent_str)
+            .spawn()
+            .map_err(|e| e.to_string())?;
     }
 
-    // Execute: echo "prompt" | claude <file> --permission-mode bypassPermissions --print
-    let timeout_duration = std::time::Duration::from_secs(300); // 5 minute timeout
-    let shared_child: Arc<Mutex<Option<Child>>> = Arc::new(Mutex::new(None));
-    let child_for_task = Arc::clone(&shared_child);
-    let mut task = tauri::async_runtime::spawn_blocking(move || {
-        let child = Command::new("claude")
-            .env("PATH", &path)
-            .arg(&file_path)
-            .arg("--permission-mode")
-            .arg("bypassPermissions")
-            .arg("--print")
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn();
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        return Err("Unsupported platform".to_string());
+    }
 
-        match child {
-            Ok(process) => {
-                if let Ok(mut child_guard) = child_for_task.lock() {
-                    *child_guard = Some(process);
-                } else {
-                    return AiExecutionResult {
-                        success: false,
-                        output: String::new(),
-                        error: Some("Failed to lock claude child process handle".to_string()),
-                    };
-                }
+    Ok(())
+}
 
-                // Work with the process by taking it from the shared handle.
-                let mut process = match child_for_task.lock() {
-                    Ok(mut child_guard) => match child_guard.take() {
-                        Some(process) => process,
-                        None => {
-                            return AiExecutionResult {
-                                success: false,
-                                output: String::new(),
-                                error: Some("Claude process handle was unexpectedly missing".to_string()),
-                            };
-                        }
-                    },
-                    Err(_) => {
-                        return AiExecutionResult {
-                            success: false,
-                            output: String::new(),
-                            error: Some("Failed to lock claude child process handle".to_string()),
-                        };
-                    }
-                };
+/// Open a note with the system's default handler for `.md` files. Changes made there
+/// come back through the file watcher.
+#[tauri::command]
+async fn open_in_external_editor(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let path = existing_note_path(&folder, &id)?;
 
-                // Write prompt to stdin, surfacing errors
-                if let Some(mut stdin) = process.stdin.take() {
-                    if let Err(e) = stdin.write_all(prompt.as_bytes()) {
-                        let _ = process.kill();
-                        let _ = process.wait();
-                        return AiExecutionResult {
-                            success: false,
-                            output: String::new(),
-                            error: Some(format!("Failed to write prompt to claude stdin: {}", e)),
-                        };
-                    }
-                } else {
-                    let _ = process.kill();
-                    let _ = process.wait();
-                    return AiExecutionResult {
-                        success: false,
-                        output: String::new(),
-                        error: Some("Failed to open stdin for claude process".to_string()),
-                    };
-                }
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    {
+        open::that_detached(&path).map_err(|e| format!("Failed to open in external editor: {}", e))?;
+    }
 
-                // Wait for completion and get output
-                match process.wait_with_output() {
-                    Ok(output) => {
-                        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-                        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        let _ = path;
+        return Err("Unsupported platform".to_string());
+    }
 
-                        if output.status.success() {
-                            AiExecutionResult {
-                                success: true,
-                                output: stdout,
-                                error: None,
-                            }
-                        } else {
-                            AiExecutionResult {
-                                success: false,
-                                output: stdout,
-                                error: Some(stderr),
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        AiExecutionResult {
-                            success: false,
-                            output: String::new(),
-                            error: Some(format!("Failed to wait for claude: {}", e)),
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                AiExecutionResult {
-                    success: false,
-                    output: String::new(),
-                    error: Some(format!("Failed to execute claude: {}", e)),
-                }
-            }
-        }
-    });
-
-    let result = match tokio::time::timeout(timeout_duration, &mut task).await {
-        Ok(join_result) => {
-            join_result.map_err(|e| format!("Failed to join Claude blocking task: {}", e))?
-        }
-        Err(_) => {
-            if let Ok(mut child_guard) = shared_child.lock() {
-                if let Some(mut process) = child_guard.take() {
-                    let _ = process.kill();
-                    let _ = process.wait();
-                }
-            }
+    Ok(())
+}
 
-            match tokio::time::timeout(std::time::Duration::from_secs(5), task).await {
-                Ok(join_result) => {
-                    if let Err(e) = join_result {
-                        return Err(format!(
-                            "Failed to join Claude blocking task after timeout: {}",
-                            e
-                        ));
-                    }
-                }
-                Err(_) => {
-                    return Err(
-                        "Claude CLI timed out and failed to exit after kill signal".to_string()
-                    );
-                }
-            }
+#[tauri::command]
+async fn open_url_safe(url: String) -> Result<(), String> {
+    // Validate URL scheme - only allow http, https, mailto
+    let parsed = url::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
 
-            AiExecutionResult {
-                success: false,
-                output: String::new(),
-                error: Some("Claude CLI timed out after 5 minutes".to_string()),
-            }
+    match parsed.scheme() {
+        "http" | "https" | "mailto" => {}
+        scheme => {
+            return Err(format!(
+                "URL scheme '{}' is not allowed. Only http, https, and mailto are permitted.",
+                scheme
+            ))
         }
-    };
+    }
 
-    Ok(result)
+    // Use system opener
+    open::that(&url).map_err(|e| format!("Failed to open URL: {}", e))
 }
 
-// MCP server status
+/// URL metadata returned by fetch_url_metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct McpStatus {
-    pub running: bool,
-    pub port: u16,
+pub struct UrlMetadata {
+    pub title: String,
+    pub description: String,
+    pub image: String,
+    pub favicon: String,
+    pub domain: String,
 }
 
-#[tauri::command]
-fn mcp_get_status(state: State<AppState>) -> McpStatus {
-    let settings = state.settings.read().expect("settings read lock");
-    let port = settings.mcp_port.unwrap_or(3921);
-    let enabled = settings.mcp_enabled.unwrap_or(false);
-
-    let running = if enabled {
-        // Check if server handle exists
-        let handle = state.mcp_server_handle.lock().expect("mcp handle mutex");
-        handle.is_some()
-    } else {
-        false
-    };
-
-    McpStatus { running, port }
+/// HTTP client shared by URL fetching commands
+fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .user_agent("Mozilla/5.0 (compatible; Scratch/1.0)")
+        .redirect(reqwest::redirect::Policy::limited(5))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
 }
 
 #[tauri::command]
-async fn mcp_restart(state: State<'_, AppState>) -> Result<McpStatus, String> {
-    // Stop existing server if running
-    {
-        let mut handle = state.mcp_server_handle.lock().expect("mcp handle mutex");
-        if let Some(h) = handle.take() {
-            h.abort();
+async fn fetch_url_metadata(url: String) -> Result<UrlMetadata, String> {
+    // Validate URL scheme
+    let parsed = url::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
+    match parsed.scheme() {
+        "http" | "https" => {}
+        scheme => {
+            return Err(format!(
+                "URL scheme '{}' is not allowed. Only http and https are permitted.",
+                scheme
+            ))
         }
     }
 
-    let settings = state.settings.read().expect("settings read lock").clone();
-    let enabled = settings.mcp_enabled.unwrap_or(false);
-    let port = settings.mcp_port.unwrap_or(3921);
+    let domain = parsed.host_str().unwrap_or("").to_string();
 
-    if enabled {
-        let app_state = AppState(Arc::clone(&state.0));
-        let server_handle = mcp::start_mcp_server(app_state, port);
-        let mut handle = state.mcp_server_handle.lock().expect("mcp handle mutex");
-        *handle = Some(server_handle);
+    // Build a favicon URL from the domain
+    let favicon_default = format!(
+        "https://www.google.com/s2/favicons?domain={}&sz=32",
+        domain
+    );
+
+    // Fetch the HTML
+    let client = http_client()?;
+
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch URL: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("HTTP error: {}", response.status()));
     }
 
-    Ok(McpStatus {
-        running: enabled,
-        port,
+    let html = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+
+    // Parse HTML with scraper
+    let document = scraper::Html::parse_document(&html);
+
+    // Helper to get meta content by property or name
+    let get_meta = |attr: &str, value: &str| -> Option<String> {
+        let selector_str = format!("meta[{}=\"{}\"]", attr, value);
+        let result = if let Ok(selector) = scraper::Selector::parse(&selector_str) {
+            document
+                .select(&selector)
+                .next()
+                .and_then(|el| el.value().attr("content"))
+                .map(|s| s.to_string())
+        } else {
+            None
+        };
+        result
+    };
+
+    // Extract title: og:title > twitter:title > <title>
+    let title = get_meta("property", "og:title")
+        .or_else(|| get_meta("name", "twitter:title"))
+        .or_else(|| {
+            scraper::Selector::parse("title")
+                .ok()
+                .and_then(|sel| document.select(&sel).next())
+                .map(|el| el.text().collect::<String>())
+        })
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    // Extract description: og:description > twitter:description > meta description
+    let description = get_meta("property", "og:description")
+        .or_else(|| get_meta("name", "twitter:description"))
+        .or_else(|| get_meta("name", "description"))
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    // Extract image: og:image > twitter:image
+    let image = get_meta("property", "og:image")
+        .or_else(|| get_meta("name", "twitter:image"))
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    // Extract favicon: link[rel~=icon] > default
+    let favicon = scraper::Selector::parse("link[rel~=\"icon\"], link[rel=\"shortcut icon\"]")
+        .ok()
+        .and_then(|sel| {
+            document
+                .select(&sel)
+                .next()
+                .and_then(|el| el.value().attr("href"))
+                .map(|href| {
+                    // Resolve relative URLs
+                    if href.starts_with("http") {
+                        href.to_string()
+                    } else if href.starts_with("//") {
+                        format!("https:{}", href)
+                    } else {
+                        format!("{}://{}{}", parsed.scheme(), domain, if href.starts_with('/') { href.to_string() } else { format!("/{}", href) })
+                    }
+                })
+        })
+        .unwrap_or(favicon_default);
+
+    Ok(UrlMetadata {
+        title,
+        description,
+        image,
+        favicon,
+        domain,
     })
 }
 
+// ---- Link Checking ----
+
+/// An external link found in a note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkRef {
+    pub note_id: String,
+    pub line: usize, // 1-based
+    pub url: String,
+    pub text: String, // link text, or the URL itself for bare links
+}
+
+/// Collect markdown links and bare http(s) URLs from content, skipping fenced code
+fn extract_links_from(note_id: &str, content: &str) -> Vec<LinkRef> {
+    let md_re = regex::Regex::new(r"\[([^\]]*)\]\((https?://[^)\s]+)(?:\s+[^)]*)?\)").unwrap();
+    let bare_re = regex::Regex::new(r#"https?://[^\s<>()\[\]"'`]+"#).unwrap();
+
+    let mut links = Vec::new();
+    let mut in_fence = false;
+    for (idx, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut found: Vec<(usize, LinkRef)> = Vec::new();
+        let mut spans = Vec::new();
+        for caps in md_re.captures_iter(line) {
+            let whole = caps.get(0).unwrap();
+            spans.push(whole.range());
+            found.push((
+                whole.start(),
+                LinkRef {
+                    note_id: note_id.to_string(),
+                    line: idx + 1,
+                    url: caps[2].to_string(),
+                    text: caps[1].to_string(),
+                },
+            ));
+        }
+        for m in bare_re.find_iter(line) {
+            if spans.iter().any(|span| span.contains(&m.start())) {
+                continue;
+            }
+            let url = m.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?']);
+            found.push((
+                m.start(),
+                LinkRef {
+                    note_id: note_id.to_string(),
+                    line: idx + 1,
+                    url: url.to_string(),
+                    text: url.to_string(),
+                },
+            ));
+        }
+        found.sort_by_key(|(start, _)| *start);
+        links.extend(found.into_iter().map(|(_, link)| link));
+    }
+    links
+}
+
 #[tauri::command]
-fn webhook_get_log(state: State<AppState>) -> Vec<webhooks::WebhookLogEntry> {
+async fn extract_links(id: Option<String>, state: State<'_, AppState>) -> Result<Vec<LinkRef>, String> {
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
-        app_config.notes_folder.clone()
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
-    match folder {
-        Some(f) => webhooks::get_webhook_log(&f),
-        None => Vec::new(),
-    }
-}
-
-// ---- Import / Export Commands ----
 
-/// Strip YAML frontmatter (---...---) from markdown content for clean export.
-fn strip_frontmatter(content: &str) -> String {
-    let lines: Vec<&str> = content.lines().collect();
-    if lines.is_empty() || lines[0].trim() != "---" {
-        return content.to_string();
-    }
-    for i in 1..lines.len() {
-        if lines[i].trim() == "---" {
-            // Return everything after the closing ---
-            let rest = &lines[i + 1..];
-            let result = rest.join("\n");
-            return result.trim_start_matches('\n').to_string();
+    let notes: Vec<(String, PathBuf)> = match id {
+        Some(id) => {
+            let path = resolve_note_path(&folder, &id)?;
+            if !path.exists() {
+                return Err(format!("Note not found: {}", id));
+            }
+            vec![(id, path)]
         }
+        None => {
+            let base = PathBuf::from(&folder);
+            let mut notes: Vec<(String, PathBuf)> = walk_md_files(&base, &base)
+                .await?
+                .into_iter()
+                .filter_map(|p| path_to_note_id(&base, &p).map(|id| (id, p)))
+                .collect();
+            notes.sort();
+            notes
+        }
+    };
+
+    let mut links = Vec::new();
+    for (note_id, path) in notes {
+        let Ok(content) = fs::read_to_string(&path).await else {
+            continue;
+        };
+        links.extend(extract_links_from(&note_id, &content));
     }
-    content.to_string()
+    Ok(links)
 }
 
-/// Convert markdown to a styled HTML document.
-fn markdown_to_html_doc(title: &str, md_content: &str) -> String {
-    // Simple markdown to HTML: use basic conversion
-    // For proper rendering, we convert common patterns
-    let mut html_body = String::new();
-    let mut in_code_block = false;
+/// Result of checking one URL; `status` is None when the request failed outright
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkStatus {
+    pub url: String,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
 
-    for line in md_content.lines() {
-        if line.starts_with("```") {
-            if in_code_block {
-                html_body.push_str("</code></pre>\n");
-                in_code_block = false;
-            } else {
-                html_body.push_str("<pre><code>");
-                in_code_block = true;
-            }
-            continue;
-        }
-        if in_code_block {
-            html_body.push_str(&line.replace('<', "&lt;").replace('>', "&gt;"));
-            html_body.push('\n');
-            continue;
-        }
+async fn check_link(client: reqwest::Client, url: String) -> LinkStatus {
+    if !matches!(url::Url::parse(&url).map(|u| u.scheme().to_string()).as_deref(), Ok("http" | "https")) {
+        return LinkStatus { url, status: None, error: Some("Only http and https URLs can be checked".to_string()) };
+    }
 
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            html_body.push_str("<br>\n");
-        } else if trimmed.starts_with("# ") {
-            html_body.push_str(&format!("<h1>{}</h1>\n", &trimmed[2..]));
-        } else if trimmed.starts_with("## ") {
-            html_body.push_str(&format!("<h2>{}</h2>\n", &trimmed[3..]));
-        } else if trimmed.starts_with("### ") {
-            html_body.push_str(&format!("<h3>{}</h3>\n", &trimmed[4..]));
-        } else if trimmed.starts_with("- [ ] ") {
-            html_body.push_str(&format!("<p><input type=\"checkbox\" disabled> {}</p>\n", &trimmed[6..]));
-        } else if trimmed.starts_with("- [x] ") {
-            html_body.push_str(&format!("<p><input type=\"checkbox\" checked disabled> {}</p>\n", &trimmed[6..]));
-        } else if trimmed.starts_with("- ") || trimmed.starts_with("* ") {
-            html_body.push_str(&format!("<li>{}</li>\n", &trimmed[2..]));
-        } else if trimmed.starts_with("> ") {
-            html_body.push_str(&format!("<blockquote>{}</blockquote>\n", &trimmed[2..]));
-        } else if trimmed == "---" {
-            html_body.push_str("<hr>\n");
-        } else {
-            html_body.push_str(&format!("<p>{}</p>\n", trimmed));
-        }
+    let mut result = client.head(&url).send().await;
+    // Some servers don't implement HEAD; fall back to GET before reporting an error
+    if matches!(&result, Ok(r) if r.status() == reqwest::StatusCode::METHOD_NOT_ALLOWED) {
+        result = client.get(&url).send().await;
     }
-    if in_code_block {
-        html_body.push_str("</code></pre>\n");
+    match result {
+        Ok(response) => LinkStatus { url, status: Some(response.status().as_u16()), error: None },
+        Err(e) => LinkStatus { url, status: None, error: Some(e.to_string()) },
     }
+}
 
-    // Apply inline formatting: bold, italic, code, links
-    let html_body = html_body
-        .replace("**", "<strong>") // Simple toggle (imperfect but functional)
-        .replace("__", "<strong>");
+// Most link checks in flight at once
+const LINK_CHECK_CONCURRENCY: usize = 8;
 
-    format!(
-        r#"<!DOCTYPE html>
-<html>
-<head>
-<meta charset="utf-8">
-<title>{title}</title>
-<style>
-  body {{ font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; max-width: 720px; margin: 2em auto; padding: 0 1em; line-height: 1.6; color: #333; }}
-  h1, h2, h3 {{ margin-top: 1.5em; }}
-  pre {{ background: #f5f5f5; padding: 1em; border-radius: 6px; overflow-x: auto; }}
-  code {{ background: #f5f5f5; padding: 0.2em 0.4em; border-radius: 3px; font-size: 0.9em; }}
-  pre code {{ background: none; padding: 0; }}
-  blockquote {{ border-left: 3px solid #ddd; margin: 1em 0; padding-left: 1em; color: #666; }}
-  hr {{ border: none; border-top: 1px solid #eee; margin: 2em 0; }}
-  li {{ margin: 0.25em 0; }}
-  img {{ max-width: 100%; }}
-</style>
-</head>
-<body>
-{html_body}
-</body>
-</html>"#,
-        title = title.replace('<', "&lt;").replace('>', "&gt;"),
-        html_body = html_body
-    )
+/// Check each distinct URL once (in first-seen order), a few at a time.
+#[tauri::command]
+async fn check_links(urls: Vec<String>) -> Result<Vec<LinkStatus>, String> {
+    let client = http_client()?;
+    let mut seen = HashSet::new();
+    let urls: Vec<String> = urls.into_iter().filter(|url| seen.insert(url.clone())).collect();
+
+    let permits = Arc::new(tokio::sync::Semaphore::new(LINK_CHECK_CONCURRENCY));
+    let mut tasks = tokio::task::JoinSet::new();
+    for (idx, url) in urls.into_iter().enumerate() {
+        let client = client.clone();
+        let permits = Arc::clone(&permits);
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            (idx, check_link(client, url).await)
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        results.push(joined.map_err(|e| format!("Link check failed: {}", e))?);
+    }
+    results.sort_by_key(|(idx, _)| *idx);
+    Ok(results.into_iter().map(|(_, status)| status).collect())
 }
 
+// Git commands - run blocking git operations off the main thread
+
 #[tauri::command]
-async fn export_note_markdown(id: String, dest: String, include_frontmatter: bool, state: State<'_, AppState>) -> Result<(), String> {
-    let note = read_note_impl(id, &state).await?;
-    let content = if include_frontmatter {
-        note.content.clone()
-    } else {
-        strip_frontmatter(&note.content)
-    };
-    std::fs::write(&dest, content).map_err(|e| format!("Failed to write file: {}", e))
+async fn git_is_available() -> bool {
+    tauri::async_runtime::spawn_blocking(git::is_available)
+        .await
+        .unwrap_or(false)
 }
 
 #[tauri::command]
-async fn export_note_html(id: String, dest: String, state: State<'_, AppState>) -> Result<(), String> {
-    let note = read_note_impl(id, &state).await?;
-    let clean = strip_frontmatter(&note.content);
-    let title = extract_title(&note.content);
-    let html = markdown_to_html_doc(&title, &clean);
-    std::fs::write(&dest, html).map_err(|e| format!("Failed to write file: {}", e))
+async fn git_get_status(state: State<'_, AppState>) -> Result<git::GitStatus, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone()
+    };
+
+    match folder {
+        Some(path) => {
+            tauri::async_runtime::spawn_blocking(move || {
+                git::get_status(&PathBuf::from(path))
+            })
+            .await
+            .map_err(|e| e.to_string())
+        }
+        None => Ok(git::GitStatus::default()),
+    }
+}
+
+// Map git status entries for markdown files onto note ids
+fn note_status_map(entries: Vec<(String, String)>) -> HashMap<String, String> {
+    entries
+        .into_iter()
+        .filter_map(|(code, file)| {
+            let id = file.strip_suffix(".md")?;
+            if id.split('/').any(|part| part.starts_with('.')) {
+                return None;
+            }
+            Some((id.to_string(), code))
+        })
+        .collect()
 }
 
+/// Note id -> porcelain status code (e.g. " M", "A ", "??"); empty outside a git repo.
 #[tauri::command]
-async fn export_all_zip(dest: String, state: State<'_, AppState>) -> Result<usize, String> {
-    let notes_folder = {
+async fn git_status_map(state: State<'_, AppState>) -> Result<HashMap<String, String>, String> {
+    let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
-        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+        app_config.notes_folder.clone()
     };
-    let base = PathBuf::from(&notes_folder);
-    let files = walk_md_files_sync(&base, &base)?;
-
-    let zip_file = std::fs::File::create(&dest).map_err(|e| format!("Failed to create zip: {}", e))?;
-    let mut zip = zip::ZipWriter::new(zip_file);
-    let options = zip::write::SimpleFileOptions::default()
-        .compression_method(zip::CompressionMethod::Deflated);
 
-    let mut count = 0;
-    for file_path in &files {
-        if let Ok(relative) = file_path.strip_prefix(&base) {
-            let name = relative.to_string_lossy().replace('\\', "/");
-            let content = std::fs::read_to_string(file_path).unwrap_or_default();
-            zip.start_file(&name, options).map_err(|e| format!("Zip error: {}", e))?;
-            use std::io::Write;
-            zip.write_all(content.as_bytes()).map_err(|e| format!("Zip write error: {}", e))?;
-            count += 1;
+    match folder {
+        Some(path) => {
+            tauri::async_runtime::spawn_blocking(move || {
+                note_status_map(git::status_entries(&PathBuf::from(path)))
+            })
+            .await
+            .map_err(|e| e.to_string())
         }
+        None => Ok(HashMap::new()),
     }
-    zip.finish().map_err(|e| format!("Zip finish error: {}", e))?;
-    Ok(count)
 }
 
 #[tauri::command]
-async fn import_notes(paths: Vec<String>, state: State<'_, AppState>) -> Result<usize, String> {
-    let notes_folder = {
+async fn git_init_repo(state: State<'_, AppState>) -> Result<(), String> {
+    let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
-    let dest_dir = PathBuf::from(&notes_folder);
-    let mut count = 0;
 
-    for path_str in &paths {
-        let src = PathBuf::from(path_str);
-        let ext = src.extension().and_then(|e| e.to_str()).unwrap_or("");
-
-        match ext {
-            "md" | "txt" => {
-                // Copy directly, renaming .txt to .md
-                let filename = src.file_stem().unwrap_or_default();
-                let dest_name = format!("{}.md", filename.to_string_lossy());
-                let dest_path = dest_dir.join(&dest_name);
-                // Avoid overwriting - add suffix if exists
-                let dest_path = unique_path(dest_path);
-                std::fs::copy(&src, &dest_path).map_err(|e| format!("Copy failed: {}", e))?;
-                count += 1;
-            }
-            "html" | "htm" => {
-                // Basic HTML to markdown conversion
-                let html_content = std::fs::read_to_string(&src).map_err(|e| format!("Read failed: {}", e))?;
-                let md_content = html_to_markdown(&html_content);
-                let filename = src.file_stem().unwrap_or_default();
-                let dest_name = format!("{}.md", filename.to_string_lossy());
-                let dest_path = dest_dir.join(&dest_name);
-                let dest_path = unique_path(dest_path);
-                std::fs::write(&dest_path, md_content).map_err(|e| format!("Write failed: {}", e))?;
-                count += 1;
-            }
-            _ => {} // Skip unsupported formats
-        }
-    }
-    Ok(count)
+    tauri::async_runtime::spawn_blocking(move || {
+        git::git_init(&PathBuf::from(folder))
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-async fn import_zip(path: String, state: State<'_, AppState>) -> Result<usize, String> {
-    let notes_folder = {
+async fn git_ensure_gitignore(state: State<'_, AppState>) -> Result<(), String> {
+    let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
-    let dest_dir = PathBuf::from(&notes_folder);
 
-    let file = std::fs::File::open(&path).map_err(|e| format!("Failed to open zip: {}", e))?;
-    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Invalid zip: {}", e))?;
-    let mut count = 0;
+    tauri::async_runtime::spawn_blocking(move || {
+        git::ensure_gitignore(&PathBuf::from(folder))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
-    for i in 0..archive.len() {
-        let mut entry = archive.by_index(i).map_err(|e| format!("Zip entry error: {}", e))?;
-        let name = entry.name().to_string();
+#[tauri::command]
+async fn git_commit(message: String, state: State<'_, AppState>) -> Result<git::GitResult, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone()
+    };
 
-        // Skip directories and non-markdown files, skip __MACOSX and hidden files
-        if entry.is_dir() || name.starts_with("__MACOSX") || name.contains("/.")  {
-            continue;
+    match folder {
+        Some(path) => {
+            tauri::async_runtime::spawn_blocking(move || {
+                git::commit_all(&PathBuf::from(path), &message)
+            })
+            .await
+            .map_err(|e| e.to_string())
         }
+        None => Ok(git::GitResult {
+            success: false,
+            message: None,
+            error: Some("Notes folder not set".to_string()),
+        }),
+    }
+}
 
-        let ext = name.rsplit('.').next().unwrap_or("");
-        if ext != "md" && ext != "txt" && ext != "html" && ext != "htm" {
-            continue;
-        }
+// Repo-relative paths for note ids, failing on the first id that doesn't resolve
+fn note_ids_to_repo_paths(folder: &str, ids: &[String]) -> Result<Vec<String>, String> {
+    let base = PathBuf::from(folder);
+    ids.iter()
+        .map(|id| {
+            let path = resolve_note_path(folder, id).map_err(|e| format!("Cannot resolve note '{}': {}", id, e))?;
+            let relative = path.strip_prefix(&base).map_err(|e| e.to_string())?;
+            Ok(relative.to_string_lossy().replace('\\', "/"))
+        })
+        .collect()
+}
 
-        // Preserve folder structure from zip
-        let relative = PathBuf::from(&name);
-        let dest_path = dest_dir.join(&relative);
+/// Stage and commit only the given notes.
+#[tauri::command]
+async fn git_commit_notes(ids: Vec<String>, message: String, state: State<'_, AppState>) -> Result<git::GitResult, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let files = note_ids_to_repo_paths(&folder, &ids)?;
 
-        // Create parent dirs
-        if let Some(parent) = dest_path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| format!("Mkdir failed: {}", e))?;
-        }
+    tauri::async_runtime::spawn_blocking(move || {
+        git::commit_paths(&PathBuf::from(folder), &files, &message)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
 
-        let mut content = String::new();
-        use std::io::Read;
-        entry.read_to_string(&mut content).map_err(|e| format!("Read zip entry failed: {}", e))?;
+#[tauri::command]
+async fn git_push(state: State<'_, AppState>) -> Result<git::GitResult, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone()
+    };
 
-        // Convert HTML to markdown if needed
-        if ext == "html" || ext == "htm" {
+    match folder {
+        Some(path) => {
+            tauri::async_runtime::spawn_blocking(move || {
+                git::push(&PathBuf::from(path))
+            })
+            .await
+            .map_err(|e| e.to_string())
+        }
+        None => Ok(git::GitResult {
+            success: false,
+            message: None,
+            error: Some("Notes folder not set".to_string()),
+        }),
+    }
+}
+
+#[tauri::command]
+async fn git_add_remote(url: String, state: State<'_, AppState>) -> Result<git::GitResult, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone()
+    };
+
+    match folder {
+        Some(path) => {
+            tauri::async_runtime::spawn_blocking(move || {
+                git::add_remote(&PathBuf::from(path), &url)
+            })
+            .await
+            .map_err(|e| e.to_string())
+        }
+        None => Ok(git::GitResult {
+            success: false,
+            message: None,
+            error: Some("Notes folder not set".to_string()),
+        }),
+    }
+}
+
+#[tauri::command]
+async fn git_push_with_upstream(state: State<'_, AppState>) -> Result<git::GitResult, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone()
+    };
+
+    match folder {
+        Some(path) => {
+            tauri::async_runtime::spawn_blocking(move || {
+                // Get current branch first
+                let status = git::get_status(&PathBuf::from(&path));
+                match status.current_branch {
+                    Some(branch) => git::push_with_upstream(&PathBuf::from(&path), &branch),
+                    None => git::GitResult {
+                        success: false,
+                        message: None,
+                        error: Some("No current branch found".to_string()),
+                    },
+                }
+            })
+            .await
+            .map_err(|e| e.to_string())
+        }
+        None => Ok(git::GitResult {
+            success: false,
+            message: None,
+            error: Some("Notes folder not set".to_string()),
+        }),
+    }
+}
+
+// Note ids for repo-relative paths a pull will change, skipping non-notes and dot folders
+fn pull_changed_note_ids(changed: &[String]) -> Vec<String> {
+    changed
+        .iter()
+        .filter(|f| f.ends_with(".md"))
+        .filter(|f| !f.split('/').any(|part| part.starts_with('.')))
+        .map(|f| f.trim_end_matches(".md").to_string())
+        .collect()
+}
+
+#[tauri::command]
+async fn git_pull(state: State<'_, AppState>) -> Result<git::GitResult, String> {
+    let (folder, snapshot) = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        let settings = state.settings.read().expect("settings read lock");
+        (app_config.notes_folder.clone(), settings.snapshot_on_pull.unwrap_or(true))
+    };
+
+    match folder {
+        Some(path) => {
+            tauri::async_runtime::spawn_blocking(move || {
+                let repo = PathBuf::from(&path);
+                if snapshot {
+                    // Fetch first so the pre-pull content of overwritten notes can be kept
+                    let fetched = git::fetch(&repo);
+                    if !fetched.success {
+                        return fetched;
+                    }
+                    for id in pull_changed_note_ids(&git::incoming_changed_files(&repo)) {
+                        if let Ok(content) = std::fs::read_to_string(repo.join(format!("{}.md", id))) {
+                            snapshot_before_pull(&path, &id, &content);
+                        }
+                    }
+                }
+                git::pull(&repo)
+            })
+            .await
+            .map_err(|e| e.to_string())
+        }
+        None => Ok(git::GitResult {
+            success: false,
+            message: None,
+            error: Some("Notes folder not set".to_string()),
+        }),
+    }
+}
+
+#[tauri::command]
+async fn git_list_branches(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+
+    tauri::async_runtime::spawn_blocking(move || git::list_branches(&PathBuf::from(folder)))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn git_create_branch(name: String, state: State<'_, AppState>) -> Result<git::GitResult, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+
+    tauri::async_runtime::spawn_blocking(move || git::create_branch(&PathBuf::from(folder), &name))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Switch branches; the search index is rebuilt afterwards since note files may have changed.
+#[tauri::command]
+async fn git_checkout_branch(name: String, state: State<'_, AppState>) -> Result<git::GitResult, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let state = state.inner().clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = git::checkout_branch(&PathBuf::from(&folder), &name);
+        if result.success {
+            let index = state.search_index.lock().expect("search index mutex");
+            if let Some(ref search_index) = *index {
+                if let Err(e) = search_index.rebuild_index(&PathBuf::from(&folder)) {
+                    eprintln!("Failed to rebuild search index after checkout: {}", e);
+                }
+            }
+            drop(index);
+
+            let new_index = rebuild_backlinks_index_from_folder(&folder);
+            let mut bl_index = state.backlinks_index.write().expect("backlinks write lock");
+            *bl_index = new_index;
+        }
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+// Check if Claude CLI is installed
+fn get_expanded_path() -> String {
+    let system_path = std::env::var("PATH").unwrap_or_default();
+    let home = std::env::var("HOME").unwrap_or_else(|_| String::new());
+
+    if home.is_empty() {
+        return system_path;
+    }
+
+    // Common locations for node-installed CLIs (nvm, volta, fnm, homebrew, global npm)
+    let candidate_dirs = vec![
+        format!("{home}/.nvm/versions/node"),
+        format!("{home}/.fnm/node-versions"),
+    ];
+    let static_dirs = vec![
+        format!("{home}/.volta/bin"),
+        format!("{home}/.local/bin"),
+        "/usr/local/bin".to_string(),
+        "/opt/homebrew/bin".to_string(),
+    ];
+
+    let mut expanded = Vec::new();
+
+    // For nvm/fnm, scan for node version dirs containing a bin/ folder
+    for base in &candidate_dirs {
+        if let Ok(entries) = std::fs::read_dir(base) {
+            for entry in entries.flatten() {
+                let bin_path = entry.path().join("bin");
+                if bin_path.exists() {
+                    expanded.push(bin_path.to_string_lossy().to_string());
+                }
+            }
+        }
+    }
+
+    for dir in static_dirs {
+        expanded.push(dir);
+    }
+
+    expanded.push(system_path);
+    expanded.join(":")
+}
+
+#[tauri::command]
+async fn ai_check_claude_cli() -> Result<bool, String> {
+    use std::process::Command;
+
+    let path = get_expanded_path();
+    let which_cmd = if cfg!(target_os = "windows") {
+        "where"
+    } else {
+        "which"
+    };
+
+    let check_output = Command::new(which_cmd)
+        .arg("claude")
+        .env("PATH", &path)
+        .output()
+        .map_err(|e| format!("Failed to check for claude CLI: {}", e))?;
+
+    Ok(check_output.status.success())
+}
+
+// AI execute command
+#[tauri::command]
+async fn ai_execute_claude(
+    file_path: String,
+    prompt: String,
+) -> Result<AiExecutionResult, String> {
+    use std::process::{Child, Command, Stdio};
+    use std::io::Write;
+
+    // Check if claude CLI exists
+    let path = get_expanded_path();
+    let which_cmd = if cfg!(target_os = "windows") {
+        "where"
+    } else {
+        "which"
+    };
+
+    let check_output = Command::new(which_cmd)
+        .arg("claude")
+        .env("PATH", &path)
+        .output()
+        .map_err(|e| format!("Failed to check for claude CLI: {}", e))?;
+
+    if !check_output.status.success() {
+        return Ok(AiExecutionResult {
+            success: false,
+            output: String::new(),
+            error: Some(
+                "Claude CLI not found. Please install it from https://claude.ai/code".to_string(),
+            ),
+        });
+    }
+
+    // Execute: echo "prompt" | claude <file> --permission-mode bypassPermissions --print
+    let timeout_duration = std::time::Duration::from_secs(300); // 5 minute timeout
+    let shared_child: Arc<Mutex<Option<Child>>> = Arc::new(Mutex::new(None));
+    let child_for_task = Arc::clone(&shared_child);
+    let mut task = tauri::async_runtime::spawn_blocking(move || {
+        let child = Command::new("claude")
+            .env("PATH", &path)
+            .arg(&file_path)
+            .arg("--permission-mode")
+            .arg("bypassPermissions")
+            .arg("--print")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+
+        match child {
+            Ok(process) => {
+                if let Ok(mut child_guard) = child_for_task.lock() {
+                    *child_guard = Some(process);
+                } else {
+                    return AiExecutionResult {
+                        success: false,
+                        output: String::new(),
+                        error: Some("Failed to lock claude child process handle".to_string()),
+                    };
+                }
+
+                // Work with the process by taking it from the shared handle.
+                let mut process = match child_for_task.lock() {
+                    Ok(mut child_guard) => match child_guard.take() {
+                        Some(process) => process,
+                        None => {
+                            return AiExecutionResult {
+                                success: false,
+                                output: String::new(),
+                                error: Some("Claude process handle was unexpectedly missing".to_string()),
+                            };
+                        }
+                    },
+                    Err(_) => {
+                        return AiExecutionResult {
+                            success: false,
+                            output: String::new(),
+                            error: Some("Failed to lock claude child process handle".to_string()),
+                        };
+                    }
+                };
+
+                // Write prompt to stdin, surfacing errors
+                if let Some(mut stdin) = process.stdin.take() {
+                    if let Err(e) = stdin.write_all(prompt.as_bytes()) {
+                        let _ = process.kill();
+                        let _ = process.wait();
+                        return AiExecutionResult {
+                            success: false,
+                            output: String::new(),
+                            error: Some(format!("Failed to write prompt to claude stdin: {}", e)),
+                        };
+                    }
+                } else {
+                    let _ = process.kill();
+                    let _ = process.wait();
+                    return AiExecutionResult {
+                        success: false,
+                        output: String::new(),
+                        error: Some("Failed to open stdin for claude process".to_string()),
+                    };
+                }
+
+                // Wait for completion and get output
+                match process.wait_with_output() {
+                    Ok(output) => {
+                        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+                        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+
+                        if output.status.success() {
+                            AiExecutionResult {
+                                success: true,
+                                output: stdout,
+                                error: None,
+                            }
+                        } else {
+                            AiExecutionResult {
+                                success: false,
+                                output: stdout,
+                                error: Some(stderr),
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        AiExecutionResult {
+                            success: false,
+                            output: String::new(),
+                            error: Some(format!("Failed to wait for claude: {}", e)),
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                AiExecutionResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(format!("Failed to execute claude: {}", e)),
+                }
+            }
+        }
+    });
+
+    let result = match tokio::time::timeout(timeout_duration, &mut task).await {
+        Ok(join_result) => {
+            join_result.map_err(|e| format!("Failed to join Claude blocking task: {}", e))?
+        }
+        Err(_) => {
+            if let Ok(mut child_guard) = shared_child.lock() {
+                if let Some(mut process) = child_guard.take() {
+                    let _ = process.kill();
+                    let _ = process.wait();
+                }
+            }
+
+            match tokio::time::timeout(std::time::Duration::from_secs(5), task).await {
+                Ok(join_result) => {
+                    if let Err(e) = join_result {
+                        return Err(format!(
+                            "Failed to join Claude blocking task after timeout: {}",
+                            e
+                        ));
+                    }
+                }
+                Err(_) => {
+                    return Err(
+                        "Claude CLI timed out and failed to exit after kill signal".to_string()
+                    );
+                }
+            }
+
+            AiExecutionResult {
+                success: false,
+                output: String::new(),
+                error: Some("Claude CLI timed out after 5 minutes".to_string()),
+            }
+        }
+    };
+
+    Ok(result)
+}
+
+// MCP server status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpStatus {
+    pub running: bool,
+    pub host: String,
+    pub port: u16,
+    pub auth_enabled: bool,
+    pub error: Option<String>,
+}
+
+#[tauri::command]
+fn mcp_get_status(state: State<AppState>) -> McpStatus {
+    let settings = state.settings.read().expect("settings read lock");
+    let port = settings.mcp_port.unwrap_or(3921);
+    let enabled = settings.mcp_enabled.unwrap_or(false);
+    let auth_enabled = settings.mcp_token.as_ref().is_some_and(|t| !t.is_empty());
+    let (host, error) = match mcp::resolve_bind_host(
+        settings.mcp_host.as_deref(),
+        settings.mcp_allow_remote.unwrap_or(false),
+    ) {
+        Ok(host) => (host, None),
+        Err(e) => (mcp::DEFAULT_MCP_HOST.to_string(), Some(e)),
+    };
+
+    let running = if enabled {
+        // Check if server handle exists
+        let handle = state.mcp_server_handle.lock().expect("mcp handle mutex");
+        handle.is_some()
+    } else {
+        false
+    };
+
+    McpStatus {
+        running,
+        host,
+        port,
+        auth_enabled,
+        error,
+    }
+}
+
+#[tauri::command]
+async fn mcp_restart(state: State<'_, AppState>) -> Result<McpStatus, String> {
+    restart_mcp_server(&state)
+}
+
+// Stop the MCP server if running, then start it again if settings enable it
+fn restart_mcp_server(state: &AppState) -> Result<McpStatus, String> {
+    // Stop existing server if running
+    {
+        let mut handle = state.mcp_server_handle.lock().expect("mcp handle mutex");
+        if let Some(h) = handle.take() {
+            h.abort();
+        }
+    }
+
+    let settings = state.settings.read().expect("settings read lock").clone();
+    let enabled = settings.mcp_enabled.unwrap_or(false);
+    let port = settings.mcp_port.unwrap_or(3921);
+    let auth_enabled = settings.mcp_token.as_ref().is_some_and(|t| !t.is_empty());
+    let host = mcp::resolve_bind_host(
+        settings.mcp_host.as_deref(),
+        settings.mcp_allow_remote.unwrap_or(false),
+    )?;
+
+    if enabled {
+        let app_state = AppState(Arc::clone(&state.0));
+        let server_handle = mcp::start_mcp_server(app_state, host.clone(), port);
+        let mut handle = state.mcp_server_handle.lock().expect("mcp handle mutex");
+        *handle = Some(server_handle);
+    }
+
+    Ok(McpStatus {
+        running: enabled,
+        host,
+        port,
+        auth_enabled,
+        error: None,
+    })
+}
+
+// Apply a change to the MCP settings, persist them, and restart the server to match
+fn update_mcp_settings(state: &AppState, change: impl FnOnce(&mut Settings)) -> Result<McpStatus, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    {
+        let mut settings = state.settings.write().expect("settings write lock");
+        change(&mut settings);
+        save_settings(&folder, &settings).map_err(|e| e.to_string())?;
+    }
+    restart_mcp_server(state)
+}
+
+#[tauri::command]
+async fn mcp_set_enabled(enabled: bool, state: State<'_, AppState>) -> Result<McpStatus, String> {
+    update_mcp_settings(&state, |settings| settings.mcp_enabled = Some(enabled))
+}
+
+/// Change the MCP port; a running server is rebound to it.
+#[tauri::command]
+async fn mcp_set_port(port: u16, state: State<'_, AppState>) -> Result<McpStatus, String> {
+    if port == 0 {
+        return Err("Port must be between 1 and 65535".to_string());
+    }
+    update_mcp_settings(&state, |settings| settings.mcp_port = Some(port))
+}
+
+#[tauri::command]
+fn webhook_get_log(state: State<AppState>) -> Vec<webhooks::WebhookLogEntry> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone()
+    };
+    match folder {
+        Some(f) => webhooks::get_webhook_log(&f),
+        None => Vec::new(),
+    }
+}
+
+/// Installed plugins from `.scratch/plugins/`, including invalid or disabled ones.
+#[tauri::command]
+fn list_plugins(state: State<AppState>) -> Result<Vec<plugins::PluginInfo>, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    Ok(plugins::get_plugin_info(&folder))
+}
+
+/// Persist a plugin's `enabled` flag. Plugin tools are loaded per MCP request,
+/// so the change applies without a restart.
+#[tauri::command]
+fn set_plugin_enabled(name: String, enabled: bool, state: State<AppState>) -> Result<(), String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    plugins::toggle_plugin(&folder, &name, enabled)
+}
+
+/// Run a named transform (e.g. "tidy-markdown") over a note and save the result.
+#[tauri::command]
+async fn run_plugin_transform(
+    plugin: String,
+    note_id: String,
+    state: State<'_, AppState>,
+) -> Result<Note, String> {
+    let transform =
+        plugins::find_transform(&plugin).ok_or_else(|| format!("Unknown transform: {}", plugin))?;
+    let note = read_note_impl(note_id.clone(), &state).await?;
+    let content = transform.transform(&note.content)?;
+    if content == note.content {
+        return Ok(note);
+    }
+    save_note_impl(Some(note_id), content, &state).await
+}
+
+/// Most recent MCP tool calls, newest first. Only metadata is recorded.
+#[tauri::command]
+fn mcp_call_log(limit: usize, state: State<AppState>) -> Vec<mcp::McpCall> {
+    mcp::recent_calls(&state, limit)
+}
+
+// ---- Markdown Lint Commands ----
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintIssue {
+    pub line: usize,
+    pub severity: String, // "error" | "warning"
+    pub message: String,
+}
+
+/// Collect lowercase titles and ids of every note, used to resolve wikilinks.
+fn collect_known_note_titles(notes_folder: &str) -> HashSet<String> {
+    let folder_path = PathBuf::from(notes_folder);
+    let mut titles = HashSet::new();
+
+    let files = walk_md_files_sync(&folder_path, &folder_path).unwrap_or_default();
+    for file_path in &files {
+        let Some(id) = path_to_note_id(&folder_path, file_path) else { continue };
+        let content = std::fs::read_to_string(file_path).unwrap_or_default();
+        // Same names backlinks resolve through: title, aliases, id and file stem
+        for name in backlink_names(&id, &extract_title(&content), &content) {
+            titles.insert(name.trim().to_lowercase());
+        }
+    }
+
+    titles
+}
+
+/// Lint markdown content. `known_titles` holds lowercase note names (titles, aliases, ids) for the wikilink check.
+fn lint_content(content: &str, known_titles: &HashSet<String>) -> Vec<LintIssue> {
+    // Compiled once and reused across lint runs
+    static CODE_RE: LazyLock<regex::Regex> = LazyLock::new(|| regex::Regex::new(r"`[^`]*`").unwrap());
+    static EMPTY_LINK_RE: LazyLock<regex::Regex> =
+        LazyLock::new(|| regex::Regex::new(r"\[([^\]]*)\]\(\s*\)").unwrap());
+    static WIKILINK_RE: LazyLock<regex::Regex> =
+        LazyLock::new(|| regex::Regex::new(r"(!?)\[\[([^\]]+)\]\]").unwrap());
+
+    let mut issues = Vec::new();
+    let mut issue = |line: usize, severity: &str, message: String| {
+        issues.push(LintIssue {
+            line,
+            severity: severity.to_string(),
+            message,
+        });
+    };
+
+    let mut in_frontmatter = false;
+    let mut in_fence = false;
+    let mut last_heading_level = 0;
+    let mut open_math_line: Option<usize> = None;
+
+    for (idx, line) in content.lines().enumerate() {
+        let line_no = idx + 1;
+        let trimmed = line.trim();
+
+        // Skip YAML frontmatter and fenced code blocks
+        if idx == 0 && trimmed == "---" {
+            in_frontmatter = true;
+            continue;
+        }
+        if in_frontmatter {
+            if trimmed == "---" {
+                in_frontmatter = false;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        // Block math may span lines, so track the unmatched opener
+        if line.matches("$$").count() % 2 == 1 {
+            open_math_line = match open_math_line {
+                Some(_) => None,
+                None => Some(line_no),
+            };
+        }
+
+        if line.matches('`').count() % 2 == 1 {
+            issue(line_no, "error", "Unbalanced inline code backtick (`)".to_string());
+        }
+
+        // Everything below ignores the contents of inline code spans
+        let without_code = CODE_RE.replace_all(line, "");
+
+        if without_code.matches("**").count() % 2 == 1 {
+            issue(line_no, "error", "Unbalanced bold marker (**)".to_string());
+        }
+
+        for cap in EMPTY_LINK_RE.captures_iter(&without_code) {
+            issue(line_no, "error", format!("Empty link target for [{}]()", &cap[1]));
+        }
+
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            if last_heading_level > 0 && level > last_heading_level + 1 {
+                issue(
+                    line_no,
+                    "warning",
+                    format!("Heading skips a level (H{} after H{})", level, last_heading_level),
+                );
+            }
+            last_heading_level = level;
+        }
+
+        for cap in WIKILINK_RE.captures_iter(&without_code) {
+            // `![[file]]` embeds point at assets, not notes
+            if &cap[1] == "!" {
+                continue;
+            }
+            // Resolve like the backlinks index: alias and #heading stripped, case-insensitive
+            let target = cap[2].split('|').next().unwrap_or("");
+            let target = target.split('#').next().unwrap_or("").trim();
+            if !target.is_empty() && !known_titles.contains(&target.to_lowercase()) {
+                issue(line_no, "warning", format!("Wikilink to missing note [[{}]]", target));
+            }
+        }
+    }
+
+    if let Some(line_no) = open_math_line {
+        issue(line_no, "error", "Unclosed math block ($$)".to_string());
+    }
+
+    issues
+}
+
+#[tauri::command]
+async fn lint_note(id: String, state: State<'_, AppState>) -> Result<Vec<LintIssue>, String> {
+    let note = read_note_impl(id, &state).await?;
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let known_titles = collect_known_note_titles(&folder);
+    Ok(lint_content(&note.content, &known_titles))
+}
+
+/// Why a note's frontmatter failed to parse. `line`/`column` are 1-based positions in the
+/// note file, when the parser reports one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrontmatterError {
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl FrontmatterError {
+    fn new(message: String) -> Self {
+        FrontmatterError { message, line: None, column: None }
+    }
+
+    /// Map a serde_yaml error onto file positions (the YAML starts on line 2).
+    fn from_yaml(err: serde_yaml::Error) -> Self {
+        let mut message = err.to_string();
+        if let Some(pos) = message.find(" at line ") {
+            message.truncate(pos);
+        }
+        let location = err.location();
+        FrontmatterError {
+            message,
+            line: location.as_ref().map(|l| l.line() + 1),
+            column: location.as_ref().map(|l| l.column()),
+        }
+    }
+}
+
+/// Parse the leading `---` block. Notes without frontmatter are valid. Database
+/// `_schema.md` files are also checked against the schema rules.
+fn check_frontmatter(content: &str, is_schema: bool) -> Result<(), FrontmatterError> {
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.first().map(|l| l.trim()) != Some("---") {
+        if is_schema {
+            return Err(FrontmatterError::new("Missing YAML frontmatter (no opening ---)".to_string()));
+        }
+        return Ok(());
+    }
+    let close = lines.iter().skip(1).position(|l| l.trim() == "---").ok_or(FrontmatterError {
+        message: "Missing closing --- for frontmatter".to_string(),
+        line: Some(1),
+        column: Some(1),
+    })? + 1;
+    let yaml = lines[1..close].join("\n");
+
+    if is_schema {
+        serde_yaml::from_str::<database::DatabaseSchema>(&yaml).map_err(FrontmatterError::from_yaml)?;
+        return database::parse_schema(content).map(|_| ()).map_err(FrontmatterError::new);
+    }
+
+    match serde_yaml::from_str::<serde_yaml::Value>(&yaml).map_err(FrontmatterError::from_yaml)? {
+        serde_yaml::Value::Mapping(_) | serde_yaml::Value::Null => Ok(()),
+        _ => Err(FrontmatterError {
+            message: "Frontmatter must be a list of key: value pairs".to_string(),
+            line: Some(2),
+            column: Some(1),
+        }),
+    }
+}
+
+#[tauri::command]
+async fn validate_frontmatter(id: String, state: State<'_, AppState>) -> Result<(), FrontmatterError> {
+    let note = read_note_impl(id.clone(), &state).await.map_err(FrontmatterError::new)?;
+    let is_schema = id.rsplit('/').next() == Some("_schema");
+    check_frontmatter(&note.content, is_schema)
+}
+
+// ---- Table Formatting ----
+
+#[derive(Clone, Copy, PartialEq)]
+enum TableAlign {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+// Split a table row into trimmed cells, honoring `\|` escapes and dropping the outer pipes
+fn split_table_row(line: &str) -> Vec<String> {
+    let trimmed = line.trim();
+    let inner = trimmed.strip_prefix('|').unwrap_or(trimmed);
+    let inner = if inner.ends_with('|') && !inner.ends_with("\\|") { &inner[..inner.len() - 1] } else { inner };
+
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push_str("\\|");
+                chars.next();
+            }
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+// Alignments for a delimiter row like `| :--- | :-: | --: |`, or None if it isn't one
+fn parse_table_separator(line: &str) -> Option<Vec<TableAlign>> {
+    if !line.contains('-') {
+        return None;
+    }
+    split_table_row(line)
+        .iter()
+        .map(|cell| {
+            let left = cell.starts_with(':');
+            let right = cell.ends_with(':');
+            let dashes = cell.trim_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                return None;
+            }
+            Some(match (left, right) {
+                (true, true) => TableAlign::Center,
+                (true, false) => TableAlign::Left,
+                (false, true) => TableAlign::Right,
+                (false, false) => TableAlign::None,
+            })
+        })
+        .collect()
+}
+
+fn render_table(indent: &str, header: &[String], aligns: &[TableAlign], rows: &[Vec<String>]) -> Vec<String> {
+    let columns = rows.iter().map(|r| r.len()).chain([header.len(), aligns.len()]).max().unwrap_or(0);
+    let cell = |row: &[String], i: usize| row.get(i).cloned().unwrap_or_default();
+    let align = |i: usize| aligns.get(i).copied().unwrap_or(TableAlign::None);
+
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| {
+            std::iter::once(header)
+                .chain(rows.iter().map(|r| r.as_slice()))
+                .map(|row| cell(row, i).chars().count())
+                .max()
+                .unwrap_or(0)
+                .max(3)
+        })
+        .collect();
+
+    let pad = |text: String, i: usize| {
+        let gap = widths[i] - text.chars().count();
+        match align(i) {
+            TableAlign::Right => format!("{}{}", " ".repeat(gap), text),
+            TableAlign::Center => format!("{}{}{}", " ".repeat(gap / 2), text, " ".repeat(gap - gap / 2)),
+            _ => format!("{}{}", text, " ".repeat(gap)),
+        }
+    };
+    let render_row = |row: &[String]| {
+        let cells: Vec<String> = (0..columns).map(|i| pad(cell(row, i), i)).collect();
+        format!("{}| {} |", indent, cells.join(" | "))
+    };
+
+    let separator: Vec<String> = (0..columns)
+        .map(|i| {
+            let w = widths[i];
+            match align(i) {
+                TableAlign::None => "-".repeat(w),
+                TableAlign::Left => format!(":{}", "-".repeat(w - 1)),
+                TableAlign::Right => format!("{}:", "-".repeat(w - 1)),
+                TableAlign::Center => format!(":{}:", "-".repeat(w - 2)),
+            }
+        })
+        .collect();
+
+    let mut out = vec![render_row(header), format!("{}| {} |", indent, separator.join(" | "))];
+    out.extend(rows.iter().map(|r| render_row(r)));
+    out
+}
+
+/// Pad GFM table columns to equal width and normalize delimiter rows, keeping
+/// alignment markers. Tables in fenced code blocks are left untouched.
+fn format_markdown_tables(content: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut out: Vec<String> = Vec::with_capacity(lines.len());
+    let mut in_fence = false;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        let aligns = if !in_fence && line.contains('|') {
+            lines.get(i + 1).and_then(|next| parse_table_separator(next))
+        } else {
+            None
+        };
+        // GFM: the header row must have as many cells as the delimiter row
+        let header = split_table_row(line);
+        let Some(aligns) = aligns.filter(|aligns| aligns.len() == header.len()) else {
+            out.push(line.to_string());
+            i += 1;
+            continue;
+        };
+
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let mut rows = Vec::new();
+        i += 2;
+        while i < lines.len() && lines[i].contains('|') && !lines[i].trim().is_empty() {
+            rows.push(split_table_row(lines[i]));
+            i += 1;
+        }
+        out.extend(render_table(indent, &header, &aligns, &rows));
+    }
+
+    let mut result = out.join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+#[tauri::command]
+async fn format_tables(id: String, state: State<'_, AppState>) -> Result<Note, String> {
+    let note = read_note_impl(id.clone(), &state).await?;
+    let content = format_markdown_tables(&note.content);
+    if content == note.content {
+        return Ok(note);
+    }
+    save_note_impl(Some(id), content, &state).await
+}
+
+// ---- Tag Commands ----
+
+// Tags compare case-insensitively and ignore a leading '#'
+fn same_tag(a: &str, b: &str) -> bool {
+    a.trim().trim_start_matches('#').to_lowercase() == b.trim().trim_start_matches('#').to_lowercase()
+}
+
+// Utility: Rewrite frontmatter `tags:` as a flow list, or remove it when `tags` is empty
+fn set_note_tags(content: &str, tags: &[String]) -> String {
+    // Drop block-style list items under an existing `tags:` key so the
+    // single-line rewrite below doesn't leave them orphaned
+    let mut lines: Vec<&str> = content.lines().collect();
+    let close = if lines.first().is_some_and(|l| l.trim() == "---") {
+        lines.iter().skip(1).position(|l| l.trim() == "---").map(|i| i + 1)
+    } else {
+        None
+    };
+    if let Some(close) = close {
+        if let Some(key) = lines[1..close].iter().position(|l| l.starts_with("tags:")).map(|i| i + 1) {
+            let items = lines[key + 1..close]
+                .iter()
+                .take_while(|l| l.starts_with(' ') || l.starts_with('\t') || l.starts_with('-'))
+                .count();
+            lines.drain(key + 1..key + 1 + items);
+        }
+    }
+    let mut stripped = lines.join("\n");
+    if content.ends_with('\n') {
+        stripped.push('\n');
+    }
+
+    if tags.is_empty() {
+        return set_frontmatter_field(&stripped, "tags", None);
+    }
+    // JSON strings are valid YAML, so tags with commas or colons stay intact.
+    // Plain tags YAML would read as a number, bool or null get quoted as well.
+    let items: Vec<String> = tags
+        .iter()
+        .map(|t| {
+            let plain = t.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'));
+            let reads_as_string = matches!(
+                serde_yaml::from_str::<serde_yaml::Value>(t),
+                Ok(serde_yaml::Value::String(ref v)) if v == t
+            );
+            if plain && reads_as_string {
+                t.clone()
+            } else {
+                serde_json::to_string(t).unwrap_or_else(|_| t.clone())
+            }
+        })
+        .collect();
+    set_frontmatter_field(&stripped, "tags", Some(&format!("[{}]", items.join(", "))))
+}
+
+fn normalize_tag_arg(tag: &str) -> Result<String, String> {
+    let tag = tag.trim().trim_start_matches('#').trim();
+    if tag.is_empty() {
+        return Err("Tag cannot be empty".to_string());
+    }
+    Ok(tag.to_string())
+}
+
+/// Apply `edit` to each note's tag list, saving only notes whose tags changed.
+async fn edit_tags_on_notes(
+    ids: Vec<String>,
+    state: &AppState,
+    edit: impl Fn(&mut Vec<String>) -> bool,
+) -> Result<usize, String> {
+    let mut changed = 0;
+    for id in ids {
+        let note = read_note_impl(id.clone(), state).await?;
+        let mut tags = extract_tags(&note.content);
+        if !edit(&mut tags) {
+            continue;
+        }
+        save_note_impl(Some(id), set_note_tags(&note.content, &tags), state).await?;
+        changed += 1;
+    }
+    Ok(changed)
+}
+
+#[tauri::command]
+async fn add_tag_to_notes(
+    ids: Vec<String>,
+    tag: String,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let tag = normalize_tag_arg(&tag)?;
+    edit_tags_on_notes(ids, &state, |tags| {
+        if tags.iter().any(|t| same_tag(t, &tag)) {
+            return false;
+        }
+        tags.push(tag.clone());
+        true
+    })
+    .await
+}
+
+#[tauri::command]
+async fn remove_tag_from_notes(
+    ids: Vec<String>,
+    tag: String,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let tag = normalize_tag_arg(&tag)?;
+    edit_tags_on_notes(ids, &state, |tags| {
+        let before = tags.len();
+        tags.retain(|t| !same_tag(t, &tag));
+        tags.len() != before
+    })
+    .await
+}
+
+// ---- Outline Commands ----
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlineSection {
+    pub level: usize,
+    pub title: String,
+    pub start_line: usize, // 1-based, the heading line itself
+    pub end_line: usize,   // 1-based, inclusive
+}
+
+/// Build the heading hierarchy with line ranges. A section runs until the next
+/// heading of equal or higher level, or EOF. Headings inside code fences are ignored.
+fn build_outline(content: &str) -> Vec<OutlineSection> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut sections: Vec<OutlineSection> = Vec::new();
+    let mut in_fence = false;
+
+    for (idx, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if !(1..=6).contains(&level) || !trimmed[level..].starts_with(' ') {
+            continue;
+        }
+
+        let line_no = idx + 1;
+        // Close any open sections of equal or deeper level
+        for section in sections.iter_mut().rev() {
+            if section.end_line == 0 && section.level >= level {
+                section.end_line = line_no - 1;
+            }
+        }
+
+        sections.push(OutlineSection {
+            level,
+            title: strip_markdown(trimmed[level..].trim()),
+            start_line: line_no,
+            end_line: 0,
+        });
+    }
+
+    for section in sections.iter_mut().filter(|s| s.end_line == 0) {
+        section.end_line = lines.len();
+    }
+
+    sections
+}
+
+#[tauri::command]
+async fn get_outline(id: String, state: State<'_, AppState>) -> Result<Vec<OutlineSection>, String> {
+    let note = read_note_impl(id, &state).await?;
+    Ok(build_outline(&note.content))
+}
+
+// Existing section number at the start of heading text ("1. ", "2.3 ", "1.2.1. ")
+const HEADING_NUMBER_PATTERN: &str = r"^\d+\.(?:\d+\.?)*\s+";
+
+/// Prefix `##`+ headings with hierarchical numbers (`1.`, `1.1`, ...), or strip
+/// existing numbers when `enable` is false. `#` title headings, frontmatter and
+/// fenced code are skipped.
+fn number_headings_in(content: &str, enable: bool) -> String {
+    static NUMBER_RE: LazyLock<regex::Regex> = LazyLock::new(|| regex::Regex::new(HEADING_NUMBER_PATTERN).unwrap());
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+
+    let frontmatter_end = if lines.first().is_some_and(|l| l.trim() == "---") {
+        lines.iter().skip(1).position(|l| l.trim() == "---").map_or(0, |i| i + 2)
+    } else {
+        0
+    };
+
+    // (level, count) for each open heading level; skipped levels don't add a component.
+    // A heading shallower than the open one but deeper than its parent (h4 then h3 under
+    // an h2) takes over the closed sibling slot, so numbers keep counting instead of repeating.
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    for section in build_outline(content) {
+        let idx = section.start_line - 1;
+        if idx < frontmatter_end || section.level == 1 {
+            continue;
+        }
+        let mut closed = None;
+        while stack.last().is_some_and(|(level, _)| *level > section.level) {
+            closed = stack.pop().map(|(_, count)| count);
+        }
+        match stack.last_mut() {
+            Some((level, count)) if *level == section.level => *count += 1,
+            _ => stack.push((section.level, closed.map_or(1, |count| count + 1))),
+        }
+
+        let trimmed = lines[idx].trim();
+        let hashes = &trimmed[..section.level];
+        let text = NUMBER_RE.replace(trimmed[section.level..].trim(), "").to_string();
+        lines[idx] = if enable {
+            let parts: Vec<String> = stack.iter().map(|(_, count)| count.to_string()).collect();
+            let number = if parts.len() == 1 { format!("{}.", parts[0]) } else { parts.join(".") };
+            format!("{} {} {}", hashes, number, text)
+        } else {
+            format!("{} {}", hashes, text)
+        };
+    }
+
+    let mut result = lines.join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+#[tauri::command]
+async fn number_headings(id: String, enable: bool, state: State<'_, AppState>) -> Result<Note, String> {
+    let note = read_note_impl(id.clone(), &state).await?;
+    let content = number_headings_in(&note.content, enable);
+    if content == note.content {
+        return Ok(note);
+    }
+    save_note_impl(Some(id), content, &state).await
+}
+
+// ---- Import / Export Commands ----
+
+/// Strip YAML frontmatter (---...---) from markdown content for clean export.
+fn strip_frontmatter(content: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.is_empty() || lines[0].trim() != "---" {
+        return content.to_string();
+    }
+    for i in 1..lines.len() {
+        if lines[i].trim() == "---" {
+            // Return everything after the closing ---
+            let rest = &lines[i + 1..];
+            let result = rest.join("\n");
+            return result.trim_start_matches('\n').to_string();
+        }
+    }
+    content.to_string()
+}
+
+/// Convert markdown to a styled HTML document.
+fn markdown_to_html_doc(title: &str, md_content: &str) -> String {
+    let html_body = markdown_to_html_body(md_content);
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+  body {{ font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; max-width: 720px; margin: 2em auto; padding: 0 1em; line-height: 1.6; color: #333; }}
+  h1, h2, h3 {{ margin-top: 1.5em; }}
+  pre {{ background: #f5f5f5; padding: 1em; border-radius: 6px; overflow-x: auto; }}
+  code {{ background: #f5f5f5; padding: 0.2em 0.4em; border-radius: 3px; font-size: 0.9em; }}
+  pre code {{ background: none; padding: 0; }}
+  blockquote {{ border-left: 3px solid #ddd; margin: 1em 0; padding-left: 1em; color: #666; }}
+  hr {{ border: none; border-top: 1px solid #eee; margin: 2em 0; }}
+  li {{ margin: 0.25em 0; }}
+  img {{ max-width: 100%; }}
+</style>
+</head>
+<body>
+{html_body}
+</body>
+</html>"#,
+        title = title.replace('<', "&lt;").replace('>', "&gt;"),
+        html_body = html_body
+    )
+}
+
+/// Convert markdown to the HTML body markup shared by exports and previews.
+fn markdown_to_html_body(md_content: &str) -> String {
+    // Simple markdown to HTML: use basic conversion
+    // For proper rendering, we convert common patterns
+    let mut html_body = String::new();
+    let mut in_code_block = false;
+
+    for line in md_content.lines() {
+        if line.starts_with("```") {
+            if in_code_block {
+                html_body.push_str("</code></pre>\n");
+                in_code_block = false;
+            } else {
+                html_body.push_str("<pre><code>");
+                in_code_block = true;
+            }
+            continue;
+        }
+        if in_code_block {
+            html_body.push_str(&line.replace('<', "&lt;").replace('>', "&gt;"));
+            html_body.push('\n');
+            continue;
+        }
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            html_body.push_str("<br>\n");
+        } else if trimmed.starts_with("# ") {
+            html_body.push_str(&format!("<h1>{}</h1>\n", &trimmed[2..]));
+        } else if trimmed.starts_with("## ") {
+            html_body.push_str(&format!("<h2>{}</h2>\n", &trimmed[3..]));
+        } else if trimmed.starts_with("### ") {
+            html_body.push_str(&format!("<h3>{}</h3>\n", &trimmed[4..]));
+        } else if trimmed.starts_with("- [ ] ") {
+            html_body.push_str(&format!("<p><input type=\"checkbox\" disabled> {}</p>\n", &trimmed[6..]));
+        } else if trimmed.starts_with("- [x] ") {
+            html_body.push_str(&format!("<p><input type=\"checkbox\" checked disabled> {}</p>\n", &trimmed[6..]));
+        } else if trimmed.starts_with("- ") || trimmed.starts_with("* ") {
+            html_body.push_str(&format!("<li>{}</li>\n", &trimmed[2..]));
+        } else if trimmed.starts_with("> ") {
+            html_body.push_str(&format!("<blockquote>{}</blockquote>\n", &trimmed[2..]));
+        } else if trimmed == "---" {
+            html_body.push_str("<hr>\n");
+        } else {
+            html_body.push_str(&format!("<p>{}</p>\n", trimmed));
+        }
+    }
+    if in_code_block {
+        html_body.push_str("</code></pre>\n");
+    }
+
+    // Apply inline formatting: bold, italic, code, links
+    html_body
+        .replace("**", "<strong>") // Simple toggle (imperfect but functional)
+        .replace("__", "<strong>")
+}
+
+/// Small HTML fragment for hover cards: no frontmatter or title line, and at most
+/// `max_chars` characters of markdown source.
+fn note_preview_html(content: &str, max_chars: usize) -> String {
+    let body = template_body_for_append(content);
+    let source = truncate_chars(&body, max_chars);
+    // Drop trailing blank lines so a cut at a paragraph break doesn't end in <br>
+    markdown_to_html_body(source.trim_end())
+}
+
+#[tauri::command]
+async fn render_note_preview(id: String, max_chars: usize, state: State<'_, AppState>) -> Result<String, String> {
+    let note = read_note_impl(id, &state).await?;
+    Ok(note_preview_html(&note.content, max_chars))
+}
+
+const MAX_TRANSCLUSION_DEPTH: usize = 8;
+
+// Replace `![[Title]]` with the target note's body (frontmatter stripped), recursively.
+// `lookup` maps an embed name to (note id, content); unknown targets, cycles back to
+// a note already being expanded, and embeds past the depth limit stay as written.
+fn resolve_transclusions(content: &str, root_id: &str, lookup: &dyn Fn(&str) -> Option<(String, String)>) -> String {
+    let mut visited = HashSet::from([root_id.to_string()]);
+    expand_transclusions(content, lookup, &mut visited, 0)
+}
+
+fn expand_transclusions(
+    content: &str,
+    lookup: &dyn Fn(&str) -> Option<(String, String)>,
+    visited: &mut HashSet<String>,
+    depth: usize,
+) -> String {
+    // Shared by every recursion level
+    static EMBED_RE: LazyLock<regex::Regex> = LazyLock::new(|| regex::Regex::new(r"!\[\[([^\]]+)\]\]").unwrap());
+    EMBED_RE.replace_all(content, |caps: &regex::Captures| {
+        let literal = caps[0].to_string();
+        if depth >= MAX_TRANSCLUSION_DEPTH {
+            return literal;
+        }
+        let name = caps[1].split('|').next().unwrap_or("").trim();
+        match lookup(name) {
+            Some((id, body)) if !visited.contains(&id) => {
+                visited.insert(id.clone());
+                let expanded = expand_transclusions(&strip_frontmatter(&body), lookup, visited, depth + 1);
+                visited.remove(&id);
+                expanded.trim().to_string()
+            }
+            _ => literal,
+        }
+    })
+    .into_owned()
+}
+
+/// Note content with `![[Title]]` transclusions expanded, for export and preview.
+#[tauri::command]
+async fn read_note_expanded(id: String, state: State<'_, AppState>) -> Result<String, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let note = read_note_impl(id.clone(), &state).await?;
+
+    // Embed names resolve by title, id or file stem (case-insensitive)
+    let base = PathBuf::from(&folder);
+    let mut targets: HashMap<String, (String, PathBuf)> = HashMap::new();
+    for file_path in walk_md_files_sync(&base, &base)? {
+        let Some(note_id) = path_to_note_id(&base, &file_path) else { continue };
+        let content = std::fs::read_to_string(&file_path).unwrap_or_default();
+        let stem = note_id.rsplit('/').next().unwrap_or(&note_id).to_lowercase();
+        for name in [extract_title(&content).to_lowercase(), note_id.to_lowercase(), stem] {
+            targets.entry(name).or_insert_with(|| (note_id.clone(), file_path.clone()));
+        }
+    }
+
+    let lookup = |name: &str| {
+        let (note_id, path) = targets.get(&name.to_lowercase())?;
+        let content = std::fs::read_to_string(path).ok()?;
+        Some((note_id.clone(), content))
+    };
+    Ok(resolve_transclusions(&note.content, &id, &lookup))
+}
+
+#[tauri::command]
+async fn export_note_markdown(id: String, dest: String, include_frontmatter: bool, state: State<'_, AppState>) -> Result<(), String> {
+    let note = read_note_impl(id, &state).await?;
+    let content = if include_frontmatter {
+        note.content.clone()
+    } else {
+        strip_frontmatter(&note.content)
+    };
+    std::fs::write(&dest, content).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+#[tauri::command]
+async fn export_note_html(id: String, dest: String, state: State<'_, AppState>) -> Result<(), String> {
+    let note = read_note_impl(id, &state).await?;
+    let clean = strip_frontmatter(&note.content);
+    let title = extract_title(&note.content);
+    let html = markdown_to_html_doc(&title, &clean);
+    std::fs::write(&dest, html).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+/// GitHub-style heading anchor for `title`, made unique against `used`.
+fn export_anchor(title: &str, used: &mut HashSet<String>) -> String {
+    let mut slug = String::new();
+    for c in title.trim().to_lowercase().chars() {
+        if c.is_alphanumeric() || c == '-' || c == '_' {
+            slug.push(c);
+        } else if c.is_whitespace() {
+            slug.push('-');
+        }
+    }
+    if slug.is_empty() {
+        slug = "note".to_string();
+    }
+    let mut anchor = slug.clone();
+    let mut n = 1;
+    while !used.insert(anchor.clone()) {
+        anchor = format!("{}-{}", slug, n);
+        n += 1;
+    }
+    anchor
+}
+
+/// Concatenate `(note_id, content)` pairs into one markdown document with `# Title`
+/// separators. Wikilinks to notes in the export become in-document anchor links.
+fn build_folder_export(notes: &[(String, String)], include_toc: bool) -> String {
+    let mut used = HashSet::new();
+    if include_toc {
+        used.insert("contents".to_string());
+    }
+
+    let entries: Vec<(String, String)> = notes
+        .iter()
+        .map(|(_, content)| {
+            let title = extract_title(content);
+            let anchor = export_anchor(&title, &mut used);
+            (title, anchor)
+        })
+        .collect();
+
+    // Links may name a note by title or by id; first match wins
+    let mut anchors: HashMap<String, String> = HashMap::new();
+    for ((id, _), (title, anchor)) in notes.iter().zip(&entries) {
+        anchors.entry(title.to_lowercase()).or_insert_with(|| anchor.clone());
+        anchors.entry(id.to_lowercase()).or_insert_with(|| anchor.clone());
+    }
+
+    static WIKILINK_RE: LazyLock<regex::Regex> =
+        LazyLock::new(|| regex::Regex::new(r"(!?)\[\[([^\]]+)\]\]").unwrap());
+    let mut out = String::new();
+
+    if include_toc {
+        out.push_str("# Contents\n\n");
+        for (title, anchor) in &entries {
+            out.push_str(&format!("- [{}](#{})\n", title, anchor));
+        }
+        out.push('\n');
+    }
+
+    for ((_, content), (title, _)) in notes.iter().zip(&entries) {
+        let body = template_body_for_append(content);
+        let body = WIKILINK_RE.replace_all(&body, |caps: &regex::Captures| {
+            let inner = &caps[2];
+            let (target, label) = match inner.find('|') {
+                Some(pos) => (inner[..pos].trim(), inner[pos + 1..].trim()),
+                None => (inner.trim(), inner.trim()),
+            };
+            match anchors.get(&target.to_lowercase()) {
+                Some(anchor) if caps[1].is_empty() => format!("[{}](#{})", label, anchor),
+                _ => caps[0].to_string(),
+            }
+        });
+
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("# {}\n", title));
+        if !body.trim().is_empty() {
+            out.push('\n');
+            out.push_str(body.trim_end());
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+/// Export every note under `folder` (recursively, sorted by path) as one markdown file.
+/// Returns the number of bytes written.
+#[tauri::command]
+async fn export_folder_markdown(folder: String, dest: String, include_toc: bool, state: State<'_, AppState>) -> Result<usize, String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let base = PathBuf::from(&notes_folder);
+    let scan = if folder.trim().is_empty() {
+        base.clone()
+    } else {
+        base.join(validate_note_id(&folder)?)
+    };
+    if !scan.is_dir() {
+        return Err(format!("Folder not found: {}", folder));
+    }
+
+    let mut notes: Vec<(String, String)> = Vec::new();
+    for file_path in walk_md_files_sync(&base, &scan)? {
+        let content = std::fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
+        if let Some(id) = path_to_note_id(&base, &file_path) {
+            notes.push((id, content));
+        }
+    }
+    notes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let document = build_folder_export(&notes, include_toc);
+    std::fs::write(&dest, &document).map_err(|e| format!("Failed to write file: {}", e))?;
+    Ok(document.len())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeBlock {
+    pub language: String, // first word of the fence info string, empty if none
+    pub content: String,
+    pub start_line: usize, // 1-based line of the opening fence
+}
+
+// Fenced code blocks in document order. An unterminated fence runs to the end of the note.
+fn parse_code_blocks(content: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    // (fence char, fence length, language, start line, body lines)
+    let mut open: Option<(char, usize, String, usize, Vec<&str>)> = None;
+
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        let fence_len = fence_char.map_or(0, |c| trimmed.chars().take_while(|x| *x == c).count());
+
+        match open.as_mut() {
+            Some((ch, len, _, _, body)) => {
+                if fence_char == Some(*ch) && fence_len >= *len && trimmed[fence_len..].trim().is_empty() {
+                    let (_, _, language, start_line, body) = open.take().unwrap();
+                    blocks.push(CodeBlock { language, content: body.join("\n"), start_line });
+                } else {
+                    body.push(line);
+                }
+            }
+            None if fence_len >= 3 => {
+                let info = trimmed[fence_len..].trim();
+                let language = info.split_whitespace().next().unwrap_or("").to_string();
+                open = Some((fence_char.unwrap(), fence_len, language, i + 1, Vec::new()));
+            }
+            None => {}
+        }
+    }
+    if let Some((_, _, language, start_line, body)) = open {
+        blocks.push(CodeBlock { language, content: body.join("\n"), start_line });
+    }
+    blocks
+}
+
+// File extension for a fence language, "txt" when unknown
+fn code_block_extension(language: &str) -> &'static str {
+    match language.to_lowercase().as_str() {
+        "rust" | "rs" => "rs",
+        "python" | "py" => "py",
+        "javascript" | "js" => "js",
+        "typescript" | "ts" => "ts",
+        "tsx" => "tsx",
+        "jsx" => "jsx",
+        "bash" | "sh" | "shell" | "zsh" => "sh",
+        "json" => "json",
+        "yaml" | "yml" => "yml",
+        "toml" => "toml",
+        "go" => "go",
+        "c" => "c",
+        "cpp" | "c++" => "cpp",
+        "java" => "java",
+        "ruby" | "rb" => "rb",
+        "sql" => "sql",
+        "html" => "html",
+        "css" => "css",
+        "swift" => "swift",
+        "kotlin" | "kt" => "kt",
+        _ => "txt",
+    }
+}
+
+fn note_code_blocks(state: &AppState, id: &str, lang: Option<&str>) -> Result<Vec<CodeBlock>, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let content = std::fs::read_to_string(resolve_note_path(&folder, id)?).map_err(|e| e.to_string())?;
+
+    let mut blocks = parse_code_blocks(&content);
+    if let Some(lang) = lang {
+        blocks.retain(|b| b.language.eq_ignore_ascii_case(lang));
+    }
+    Ok(blocks)
+}
+
+#[tauri::command]
+fn extract_code_blocks(id: String, lang: Option<String>, state: State<AppState>) -> Result<Vec<CodeBlock>, String> {
+    note_code_blocks(&state, &id, lang.as_deref())
+}
+
+/// Write each fenced code block of a note to `dest_dir` as `<index>.<ext>`. Returns the count.
+#[tauri::command]
+fn export_code_blocks(id: String, dest_dir: String, state: State<AppState>) -> Result<usize, String> {
+    let blocks = note_code_blocks(&state, &id, None)?;
+    let dest = PathBuf::from(&dest_dir);
+    std::fs::create_dir_all(&dest).map_err(|e| format!("Failed to create folder: {}", e))?;
+
+    for (i, block) in blocks.iter().enumerate() {
+        let file_name = format!("{}.{}", i + 1, code_block_extension(&block.language));
+        let mut body = block.content.clone();
+        body.push('\n');
+        std::fs::write(dest.join(file_name), body).map_err(|e| format!("Failed to write file: {}", e))?;
+    }
+    Ok(blocks.len())
+}
+
+/// Zip `files` into `dest`, naming entries by their path relative to `base`.
+fn write_zip(dest: &std::path::Path, base: &PathBuf, files: &[PathBuf]) -> Result<usize, String> {
+    use std::io::Write;
+    let zip_file = std::fs::File::create(dest).map_err(|e| format!("Failed to create zip: {}", e))?;
+    let mut zip = zip::ZipWriter::new(zip_file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    let mut count = 0;
+    for file_path in files {
+        if let Ok(relative) = file_path.strip_prefix(base) {
+            let name = relative.to_string_lossy().replace('\\', "/");
+            let content = std::fs::read(file_path)
+                .map_err(|e| format!("Failed to read {}: {}", file_path.display(), e))?;
+            zip.start_file(&name, options).map_err(|e| format!("Zip error: {}", e))?;
+            zip.write_all(&content).map_err(|e| format!("Zip write error: {}", e))?;
+            count += 1;
+        }
+    }
+    zip.finish().map_err(|e| format!("Zip finish error: {}", e))?;
+    Ok(count)
+}
+
+#[tauri::command]
+async fn export_all_zip(dest: String, state: State<'_, AppState>) -> Result<usize, String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let base = PathBuf::from(&notes_folder);
+    let files = walk_md_files_sync(&base, &base)?;
+    write_zip(std::path::Path::new(&dest), &base, &files)
+}
+
+const BACKUP_PREFIX: &str = "scratch-backup-";
+
+// Every file under `dir`, recursively (missing dir yields nothing).
+fn walk_all_files(dir: &PathBuf) -> Vec<PathBuf> {
+    let mut results = Vec::new();
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            results.append(&mut walk_all_files(&path));
+        } else {
+            results.push(path);
+        }
+    }
+    results
+}
+
+/// Zip all notes plus `assets/` into a timestamped archive in `dest_dir`.
+/// With `prune_days`, earlier backups in `dest_dir` older than that are deleted.
+fn backup_vault_to(
+    base: &PathBuf,
+    dest_dir: &PathBuf,
+    now: chrono::DateTime<chrono::Local>,
+    prune_days: Option<u64>,
+) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dest_dir).map_err(|e| e.to_string())?;
+
+    let mut files = walk_md_files_sync(base, base)?;
+    files.extend(walk_all_files(&base.join("assets")));
+    let dest = dest_dir.join(format!("{}{}.zip", BACKUP_PREFIX, now.format("%Y-%m-%d-%H%M%S")));
+    if let Err(e) = write_zip(&dest, base, &files) {
+        // Don't leave an incomplete archive that looks like a good backup
+        let _ = std::fs::remove_file(&dest);
+        return Err(e);
+    }
+
+    // A cutoff before the epoch (or past u64 seconds) means nothing is old enough to prune
+    let cutoff = prune_days.and_then(|days| {
+        let age = std::time::Duration::from_secs(days.checked_mul(86_400)?);
+        std::time::SystemTime::from(now).checked_sub(age)
+    });
+    if let Some(cutoff) = cutoff {
+        for entry in std::fs::read_dir(dest_dir).map_err(|e| e.to_string())?.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if path == dest || !name.starts_with(BACKUP_PREFIX) || !name.ends_with(".zip") {
+                continue;
+            }
+            let modified = entry.metadata().and_then(|m| m.modified());
+            if modified.is_ok_and(|m| m < cutoff) {
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+    }
+    Ok(dest)
+}
+
+#[tauri::command]
+async fn backup_vault(
+    dest_dir: String,
+    prune_days: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let base = PathBuf::from(&notes_folder);
+    let dest_dir = PathBuf::from(dest_dir);
+    let dest = tauri::async_runtime::spawn_blocking(move || {
+        backup_vault_to(&base, &dest_dir, chrono::Local::now(), prune_days)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Convert CRLF and lone CR line endings to LF.
+fn to_lf_line_endings(content: &str) -> String {
+    content.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+#[tauri::command]
+async fn normalize_line_endings(id: String, state: State<'_, AppState>) -> Result<Note, String> {
+    let note = read_note_impl(id.clone(), &state).await?;
+    if !note.content.contains('\r') {
+        return Ok(note);
+    }
+    let content = to_lf_line_endings(&note.content);
+    save_note_impl(Some(id), content, &state).await
+}
+
+/// Counts returned by note imports; `skipped` are duplicates of existing notes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub skipped: usize,
+}
+
+#[tauri::command]
+async fn import_notes(
+    paths: Vec<String>,
+    dedupe: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<ImportReport, String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let dest_dir = PathBuf::from(&notes_folder);
+    let mut dedupe = ImportDedupe::load(dedupe.as_deref(), &dest_dir)?;
+    let mut report = ImportReport::default();
+
+    for path_str in &paths {
+        match import_file(std::path::Path::new(path_str), &dest_dir, dedupe.as_mut())? {
+            ImportOutcome::Imported => report.imported += 1,
+            ImportOutcome::Duplicate => report.skipped += 1,
+            ImportOutcome::Unsupported => {}
+        }
+    }
+    Ok(report)
+}
+
+// Characters compared by similarity dedupe; bounds the Levenshtein cost per pair
+const DEDUPE_PREFIX_CHARS: usize = 256;
+
+/// Existing vault content that imports are checked against. "exact" skips files whose
+/// content matches a note; "similar" also skips ones whose opening
+/// `DEDUPE_PREFIX_CHARS` characters are within 5% edit distance of a note's.
+struct ImportDedupe {
+    similar: bool,
+    hashes: HashSet<String>,
+    prefixes: Vec<String>,
+}
+
+impl ImportDedupe {
+    fn load(mode: Option<&str>, notes_folder: &std::path::Path) -> Result<Option<Self>, String> {
+        let similar = match mode {
+            None | Some("off") => return Ok(None),
+            Some("exact") => false,
+            Some("similar") => true,
+            Some(other) => {
+                return Err(format!(
+                    "Unknown dedupe mode '{}'. Use 'exact' or 'similar'.",
+                    other
+                ))
+            }
+        };
+        let mut dedupe = ImportDedupe { similar, hashes: HashSet::new(), prefixes: Vec::new() };
+        let base = notes_folder.to_path_buf();
+        for path in walk_md_files_sync(&base, &base)? {
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                dedupe.insert(&content);
+            }
+        }
+        Ok(Some(dedupe))
+    }
+
+    fn normalize(content: &str) -> String {
+        to_lf_line_endings(content).trim().to_string()
+    }
+
+    fn prefix(normalized: &str) -> String {
+        normalized.chars().take(DEDUPE_PREFIX_CHARS).collect()
+    }
+
+    fn is_duplicate(&self, content: &str) -> bool {
+        let normalized = Self::normalize(content);
+        if self.hashes.contains(&stories::compute_etag(&normalized)) {
+            return true;
+        }
+        if !self.similar {
+            return false;
+        }
+        let prefix = Self::prefix(&normalized);
+        let len = prefix.chars().count();
+        let max_distance = len / 20;
+        self.prefixes.iter().any(|existing| {
+            // Length difference is a lower bound on edit distance, so skip hopeless pairs
+            existing.chars().count().abs_diff(len) <= max_distance
+                && levenshtein_distance(existing, &prefix) <= max_distance
+        })
+    }
+
+    fn insert(&mut self, content: &str) {
+        let normalized = Self::normalize(content);
+        self.hashes.insert(stories::compute_etag(&normalized));
+        if self.similar {
+            self.prefixes.push(Self::prefix(&normalized));
+        }
+    }
+}
+
+enum ImportOutcome {
+    Imported,
+    Duplicate,
+    Unsupported,
+}
+
+/// Import a single .md/.txt/.html file into `dest_dir` as markdown, never overwriting.
+/// With `dedupe`, files matching existing (or already imported) notes are skipped.
+fn import_file(
+    src: &std::path::Path,
+    dest_dir: &std::path::Path,
+    dedupe: Option<&mut ImportDedupe>,
+) -> Result<ImportOutcome, String> {
+    let ext = src.extension().and_then(|e| e.to_str()).unwrap_or("");
+    // Read bytes and decode lossily so one non-UTF-8 file (e.g. Latin-1) doesn't abort a batch
+    let read_text = || {
+        std::fs::read(src)
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .map_err(|e| format!("Read failed: {}", e))
+    };
+    let content = match ext {
+        // Copied as-is, renaming .txt to .md
+        "md" | "txt" => read_text()?,
+        // Basic HTML to markdown conversion
+        "html" | "htm" => html_to_markdown(&read_text()?),
+        _ => return Ok(ImportOutcome::Unsupported), // Skip unsupported formats
+    };
+    let content = to_lf_line_endings(&content);
+
+    if let Some(dedupe) = dedupe {
+        if dedupe.is_duplicate(&content) {
+            return Ok(ImportOutcome::Duplicate);
+        }
+        dedupe.insert(&content);
+    }
+
+    let filename = src.file_stem().unwrap_or_default();
+    let dest_name = format!("{}.md", filename.to_string_lossy());
+    // Avoid overwriting - add suffix if exists
+    let dest_path = unique_path(dest_dir.join(&dest_name));
+    std::fs::write(&dest_path, content).map_err(|e| format!("Write failed: {}", e))?;
+    Ok(ImportOutcome::Imported)
+}
+
+/// Recursively import every supported file under `source` into `dest_dir`.
+/// Hidden files and folders are skipped; with `preserve_structure` subfolders are
+/// recreated, otherwise everything lands flat in `dest_dir`.
+fn import_folder_into(
+    source: &std::path::Path,
+    dest_dir: &std::path::Path,
+    preserve_structure: bool,
+    dedupe: Option<&str>,
+) -> Result<ImportReport, String> {
+    let source = source
+        .canonicalize()
+        .map_err(|e| format!("Source folder not found: {}", e))?;
+    if !source.is_dir() {
+        return Err("Source is not a folder".to_string());
+    }
+    let dest = dest_dir.canonicalize().map_err(|e| e.to_string())?;
+    if dest.starts_with(&source) || source.starts_with(&dest) {
+        return Err("Source folder overlaps the notes folder".to_string());
+    }
+
+    // Collect everything up front so files written during the import are never revisited
+    let mut files = Vec::new();
+    let mut pending = vec![source.clone()];
+    while let Some(dir) = pending.pop() {
+        let entries = std::fs::read_dir(&dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+
+    let mut dedupe = ImportDedupe::load(dedupe, &dest)?;
+    let mut report = ImportReport::default();
+    for file in files {
+        let target_dir = match file.parent().and_then(|p| p.strip_prefix(&source).ok()) {
+            Some(relative) if preserve_structure => dest.join(relative),
+            _ => dest.clone(),
+        };
+        let supported = matches!(
+            file.extension().and_then(|e| e.to_str()),
+            Some("md" | "txt" | "html" | "htm")
+        );
+        if !supported {
+            continue;
+        }
+        std::fs::create_dir_all(&target_dir).map_err(|e| format!("Mkdir failed: {}", e))?;
+        match import_file(&file, &target_dir, dedupe.as_mut())? {
+            ImportOutcome::Imported => report.imported += 1,
+            ImportOutcome::Duplicate => report.skipped += 1,
+            ImportOutcome::Unsupported => {}
+        }
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+async fn import_folder(
+    source_dir: String,
+    preserve_structure: bool,
+    dedupe: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<ImportReport, String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        import_folder_into(
+            &PathBuf::from(source_dir),
+            &PathBuf::from(notes_folder),
+            preserve_structure,
+            dedupe.as_deref(),
+        )
+    })
+    .await
+    .map_err(|e| format!("Import task failed: {}", e))?
+}
+
+#[tauri::command]
+async fn import_zip(path: String, state: State<'_, AppState>) -> Result<usize, String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let dest_dir = PathBuf::from(&notes_folder);
+
+    let file = std::fs::File::open(&path).map_err(|e| format!("Failed to open zip: {}", e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Invalid zip: {}", e))?;
+    let mut count = 0;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| format!("Zip entry error: {}", e))?;
+        let name = entry.name().to_string();
+
+        // Skip directories and non-markdown files, skip __MACOSX and hidden files
+        if entry.is_dir() || name.starts_with("__MACOSX") || name.contains("/.")  {
+            continue;
+        }
+
+        let ext = name.rsplit('.').next().unwrap_or("");
+        if ext != "md" && ext != "txt" && ext != "html" && ext != "htm" {
+            continue;
+        }
+
+        // Preserve folder structure from zip
+        let relative = PathBuf::from(&name);
+        let dest_path = dest_dir.join(&relative);
+
+        // Create parent dirs
+        if let Some(parent) = dest_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Mkdir failed: {}", e))?;
+        }
+
+        let mut content = String::new();
+        use std::io::Read;
+        entry.read_to_string(&mut content).map_err(|e| format!("Read zip entry failed: {}", e))?;
+
+        let content = to_lf_line_endings(&content);
+
+        // Convert HTML to markdown if needed
+        if ext == "html" || ext == "htm" {
             let md = html_to_markdown(&content);
             let md_path = dest_path.with_extension("md");
             let md_path = unique_path(md_path);
@@ -4967,611 +9442,1284 @@ async fn import_zip(path: String, state: State<'_, AppState>) -> Result<usize, S
             let dest_path = unique_path(dest_path);
             std::fs::write(&dest_path, content).map_err(|e| format!("Write failed: {}", e))?;
         }
-        count += 1;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Generate a unique file path by appending (1), (2) etc. if file exists.
+fn unique_path(path: PathBuf) -> PathBuf {
+    if !path.exists() {
+        return path;
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let ext = path.extension().unwrap_or_default().to_string_lossy().to_string();
+    let parent = path.parent().unwrap_or(&path);
+    for i in 1..1000 {
+        let new_name = if ext.is_empty() {
+            format!("{} ({})", stem, i)
+        } else {
+            format!("{} ({}).{}", stem, i, ext)
+        };
+        let new_path = parent.join(new_name);
+        if !new_path.exists() {
+            return new_path;
+        }
+    }
+    path
+}
+
+/// Basic HTML to markdown converter using scraper.
+fn html_to_markdown(html: &str) -> String {
+    // Use scraper to extract text content with basic formatting
+    let document = scraper::Html::parse_document(html);
+
+    // Try to get body content, fall back to full document
+    let body_sel = scraper::Selector::parse("body").unwrap();
+    let root = document.select(&body_sel).next();
+
+    let mut md = String::new();
+    if let Some(body) = root {
+        convert_node_to_md(&body, &mut md);
+    } else {
+        // Just extract all text
+        md = document.root_element().text().collect::<Vec<_>>().join(" ");
+    }
+    md.trim().to_string()
+}
+
+fn convert_node_to_md(element: &scraper::ElementRef, out: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            scraper::node::Node::Text(text) => {
+                let t = text.text.trim();
+                if !t.is_empty() {
+                    out.push_str(t);
+                }
+            }
+            scraper::node::Node::Element(el) => {
+                if let Some(child_ref) = scraper::ElementRef::wrap(child) {
+                    match el.name() {
+                        "h1" => {
+                            out.push_str("\n# ");
+                            convert_node_to_md(&child_ref, out);
+                            out.push_str("\n\n");
+                        }
+                        "h2" => {
+                            out.push_str("\n## ");
+                            convert_node_to_md(&child_ref, out);
+                            out.push_str("\n\n");
+                        }
+                        "h3" => {
+                            out.push_str("\n### ");
+                            convert_node_to_md(&child_ref, out);
+                            out.push_str("\n\n");
+                        }
+                        "p" | "div" => {
+                            convert_node_to_md(&child_ref, out);
+                            out.push_str("\n\n");
+                        }
+                        "br" => out.push('\n'),
+                        "strong" | "b" => {
+                            out.push_str("**");
+                            convert_node_to_md(&child_ref, out);
+                            out.push_str("**");
+                        }
+                        "em" | "i" => {
+                            out.push('*');
+                            convert_node_to_md(&child_ref, out);
+                            out.push('*');
+                        }
+                        "code" => {
+                            out.push('`');
+                            convert_node_to_md(&child_ref, out);
+                            out.push('`');
+                        }
+                        "pre" => {
+                            out.push_str("\n```\n");
+                            convert_node_to_md(&child_ref, out);
+                            out.push_str("\n```\n\n");
+                        }
+                        "a" => {
+                            let href = el.attr("href").unwrap_or("#");
+                            out.push('[');
+                            convert_node_to_md(&child_ref, out);
+                            out.push_str("](");
+                            out.push_str(href);
+                            out.push(')');
+                        }
+                        "li" => {
+                            out.push_str("- ");
+                            convert_node_to_md(&child_ref, out);
+                            out.push('\n');
+                        }
+                        "blockquote" => {
+                            out.push_str("> ");
+                            convert_node_to_md(&child_ref, out);
+                            out.push('\n');
+                        }
+                        "hr" => out.push_str("\n---\n\n"),
+                        "img" => {
+                            let src = el.attr("src").unwrap_or("");
+                            let alt = el.attr("alt").unwrap_or("image");
+                            out.push_str(&format!("![{}]({})", alt, src));
+                        }
+                        _ => convert_node_to_md(&child_ref, out),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// ---- Activity Log ----
+
+// One line of .scratch/activity.jsonl
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityEvent {
+    pub timestamp: String, // RFC 3339, UTC
+    pub action: String,    // "create" | "save" | "delete" | "trash" | "restore" | "move" | ...
+    pub note_id: String,   // note id, or folder path for folder actions
+    #[serde(default)]
+    pub details: serde_json::Value,
+}
+
+const MAX_ACTIVITY_LOG_ENTRIES: usize = 200;
+
+fn activity_log_path(notes_folder: &str) -> PathBuf {
+    PathBuf::from(notes_folder).join(".scratch").join("activity.jsonl")
+}
+
+// Append an event, keeping only the most recent entries; best-effort, errors are
+// ignored so the operation itself never fails
+fn log_activity(notes_folder: &str, action: &str, note_id: &str, details: serde_json::Value) {
+    use std::io::Write;
+    let event = ActivityEvent {
+        timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        action: action.to_string(),
+        note_id: note_id.to_string(),
+        details,
+    };
+    let Ok(line) = serde_json::to_string(&event) else { return };
+    let path = activity_log_path(notes_folder);
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(mut file) = std::fs::OpenOptions::new().create(true).append(true).open(&path) {
+        let _ = writeln!(file, "{}", line);
+    }
+
+    let Ok(content) = std::fs::read_to_string(&path) else { return };
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.len() > MAX_ACTIVITY_LOG_ENTRIES {
+        let kept = lines[lines.len() - MAX_ACTIVITY_LOG_ENTRIES..].join("\n") + "\n";
+        let _ = write_file_atomic(&path, kept.as_bytes());
+    }
+}
+
+// Newest-first events at or after `since` (RFC 3339); unparseable lines are skipped
+fn read_activity_log(notes_folder: &str, limit: usize, since: Option<&str>) -> Result<Vec<ActivityEvent>, String> {
+    let since = since
+        .map(|s| chrono::DateTime::parse_from_rfc3339(s).map_err(|e| format!("Invalid since timestamp: {}", e)))
+        .transpose()?;
+    let content = match std::fs::read_to_string(activity_log_path(notes_folder)) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.to_string()),
+    };
+    Ok(content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<ActivityEvent>(line).ok())
+        .filter(|event| match since {
+            Some(since) => chrono::DateTime::parse_from_rfc3339(&event.timestamp)
+                .map(|ts| ts >= since)
+                .unwrap_or(false),
+            None => true,
+        })
+        .take(limit)
+        .collect())
+}
+
+#[tauri::command]
+fn get_activity_log(
+    limit: usize,
+    since: Option<String>,
+    state: State<AppState>,
+) -> Result<Vec<ActivityEvent>, String> {
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    read_activity_log(&folder, limit, since.as_deref())
+}
+
+// ---- Trash Bin Commands ----
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrashMeta {
+    original_path: String,
+    deleted_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrashedNote {
+    id: String,
+    title: String,
+    original_path: String,
+    deleted_at: String,
+    preview: String,
+}
+
+fn get_trash_dir(notes_folder: &str) -> PathBuf {
+    PathBuf::from(notes_folder).join(".scratch").join("trash")
+}
+
+fn ensure_trash_dir(notes_folder: &str) -> Result<PathBuf, String> {
+    let trash = get_trash_dir(notes_folder);
+    std::fs::create_dir_all(&trash).map_err(|e| format!("Failed to create trash dir: {}", e))?;
+    Ok(trash)
+}
+
+/// Auto-purge trashed notes older than 30 days.
+fn purge_old_trash(notes_folder: &str) {
+    let trash_dir = get_trash_dir(notes_folder);
+    if !trash_dir.exists() {
+        return;
+    }
+    let now = chrono::Utc::now();
+    let cutoff = chrono::Duration::days(30);
+
+    if let Ok(entries) = std::fs::read_dir(&trash_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|e| e == "json") && path.to_string_lossy().ends_with(".meta.json") {
+                if let Ok(content) = std::fs::read_to_string(&path) {
+                    if let Ok(meta) = serde_json::from_str::<TrashMeta>(&content) {
+                        if let Ok(deleted) = chrono::DateTime::parse_from_rfc3339(&meta.deleted_at) {
+                            if now.signed_duration_since(deleted) > cutoff {
+                                // Remove note file and meta
+                                // note file path derived from meta path
+                                // meta file name: foo.meta.json -> remove "foo.meta.json"
+                                let _ = std::fs::remove_file(&path);
+                                // The note file: strip ".meta.json" -> stem = "foo.meta", but we stored as "foo.md"
+                                let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+                                let note_stem = stem.strip_suffix(".meta").unwrap_or(&stem);
+                                let note_path = trash_dir.join(format!("{}.md", note_stem));
+                                let _ = std::fs::remove_file(&note_path);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[tauri::command]
+async fn trash_note(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+
+    let file_path = resolve_note_path(&notes_folder, &id)?;
+    if !file_path.exists() {
+        return Err("Note not found".to_string());
+    }
+
+    let trash_dir = ensure_trash_dir(&notes_folder)?;
+
+    // Compute a safe filename for trash (flatten path separators)
+    let safe_name = id.replace('/', "__");
+    let trash_file = trash_dir.join(format!("{}.md", &safe_name));
+    let meta_file = trash_dir.join(format!("{}.meta.json", &safe_name));
+
+    // Move file to trash
+    std::fs::rename(&file_path, &trash_file)
+        .or_else(|_| {
+            // rename can fail across filesystems, fall back to copy + delete
+            std::fs::copy(&file_path, &trash_file)?;
+            std::fs::remove_file(&file_path)
+        })
+        .map_err(|e| format!("Failed to move to trash: {}", e))?;
+
+    // Write metadata
+    let meta = TrashMeta {
+        original_path: format!("{}.md", id),
+        deleted_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let meta_json = serde_json::to_string_pretty(&meta).map_err(|e| e.to_string())?;
+    std::fs::write(&meta_file, meta_json).map_err(|e| format!("Failed to write trash meta: {}", e))?;
+
+    // Update search index
+    {
+        let index = state.search_index.lock().expect("search index mutex");
+        if let Some(ref search_index) = *index {
+            let _ = search_index.delete_note(&id);
+        }
+    }
+
+    // Remove from cache
+    {
+        let mut cache = state.notes_cache.write().expect("cache write lock");
+        cache.remove(&id);
+    }
+
+    // Remove backlinks
+    {
+        let mut bl_index = state.backlinks_index.write().expect("backlinks write lock");
+        remove_backlinks_for_note(&mut bl_index, &id);
+        let _ = save_backlinks_index(&notes_folder, &bl_index);
+    }
+
+    log_activity(&notes_folder, "trash", &id, serde_json::Value::Null);
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn list_trash(state: State<'_, AppState>) -> Result<Vec<TrashedNote>, String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+
+    let trash_dir = get_trash_dir(&notes_folder);
+    if !trash_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut result = Vec::new();
+    for entry in std::fs::read_dir(&trash_dir).map_err(|e| e.to_string())?.flatten() {
+        let path = entry.path();
+        if !path.to_string_lossy().ends_with(".meta.json") {
+            continue;
+        }
+
+        let content = std::fs::read_to_string(&path).unwrap_or_default();
+        let meta: TrashMeta = match serde_json::from_str(&content) {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let note_stem = stem.strip_suffix(".meta").unwrap_or(&stem);
+        let note_path = trash_dir.join(format!("{}.md", note_stem));
+
+        if !note_path.exists() {
+            continue;
+        }
+
+        let note_content = std::fs::read_to_string(&note_path).unwrap_or_default();
+        let title = extract_title(&note_content);
+        let preview = generate_preview(&note_content);
+
+        result.push(TrashedNote {
+            id: note_stem.to_string(),
+            title,
+            original_path: meta.original_path,
+            deleted_at: meta.deleted_at,
+            preview,
+        });
+    }
+
+    // Sort by deletion date, newest first
+    result.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(result)
+}
+
+#[tauri::command]
+async fn restore_note(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+
+    let trash_dir = get_trash_dir(&notes_folder);
+    let note_path = trash_dir.join(format!("{}.md", &id));
+    let meta_path = trash_dir.join(format!("{}.meta.json", &id));
+
+    if !note_path.exists() {
+        return Err("Trashed note not found".to_string());
+    }
+
+    // Read meta to get original path
+    let meta_content = std::fs::read_to_string(&meta_path).map_err(|e| e.to_string())?;
+    let meta: TrashMeta = serde_json::from_str(&meta_content).map_err(|e| e.to_string())?;
+
+    let dest = PathBuf::from(&notes_folder).join(&meta.original_path);
+    let dest = unique_path(dest);
+
+    // Ensure parent dir exists
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create dir: {}", e))?;
+    }
+
+    // Move back
+    std::fs::rename(&note_path, &dest)
+        .or_else(|_| {
+            std::fs::copy(&note_path, &dest)?;
+            std::fs::remove_file(&note_path)
+        })
+        .map_err(|e| format!("Failed to restore: {}", e))?;
+
+    // Remove meta
+    let _ = std::fs::remove_file(&meta_path);
+
+    // Re-index the note
+    let base = PathBuf::from(&notes_folder);
+    if let Some(note_id) = path_to_note_id(&base, &dest) {
+        let content = std::fs::read_to_string(&dest).unwrap_or_default();
+        let index = state.search_index.lock().expect("search index mutex");
+        if let Some(ref search_index) = *index {
+            let title = extract_title(&content);
+            let modified = dest.metadata()
+                .and_then(|m| m.modified())
+                .map(|t| t.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs() as i64)
+                .unwrap_or(0);
+            let _ = search_index.index_note(&note_id, &title, &content, modified);
+        }
+        log_activity(&notes_folder, "restore", &note_id, serde_json::Value::Null);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn delete_permanently(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+
+    let trash_dir = get_trash_dir(&notes_folder);
+    let note_path = trash_dir.join(format!("{}.md", &id));
+    let meta_path = trash_dir.join(format!("{}.meta.json", &id));
+
+    if note_path.exists() {
+        std::fs::remove_file(&note_path).map_err(|e| format!("Failed to delete: {}", e))?;
     }
-    Ok(count)
+    if meta_path.exists() {
+        std::fs::remove_file(&meta_path).map_err(|e| format!("Failed to delete meta: {}", e))?;
+    }
+    Ok(())
 }
 
-/// Generate a unique file path by appending (1), (2) etc. if file exists.
-fn unique_path(path: PathBuf) -> PathBuf {
-    if !path.exists() {
-        return path;
+#[tauri::command]
+async fn empty_trash(state: State<'_, AppState>) -> Result<usize, String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+
+    let trash_dir = get_trash_dir(&notes_folder);
+    if !trash_dir.exists() {
+        return Ok(0);
     }
-    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
-    let ext = path.extension().unwrap_or_default().to_string_lossy().to_string();
-    let parent = path.parent().unwrap_or(&path);
-    for i in 1..1000 {
-        let new_name = if ext.is_empty() {
-            format!("{} ({})", stem, i)
-        } else {
-            format!("{} ({}).{}", stem, i, ext)
-        };
-        let new_path = parent.join(new_name);
-        if !new_path.exists() {
-            return new_path;
+
+    let mut count = 0;
+    for entry in std::fs::read_dir(&trash_dir).map_err(|e| e.to_string())?.flatten() {
+        let path = entry.path();
+        if path.is_file() {
+            let _ = std::fs::remove_file(&path);
+            if path.extension().is_some_and(|e| e == "md") {
+                count += 1;
+            }
         }
     }
-    path
+    Ok(count)
 }
 
-/// Basic HTML to markdown converter using scraper.
-fn html_to_markdown(html: &str) -> String {
-    // Use scraper to extract text content with basic formatting
-    let document = scraper::Html::parse_document(html);
+// ---- Version History Commands ----
 
-    // Try to get body content, fall back to full document
-    let body_sel = scraper::Selector::parse("body").unwrap();
-    let root = document.select(&body_sel).next();
+fn get_history_dir(notes_folder: &str) -> PathBuf {
+    PathBuf::from(notes_folder).join(".scratch").join("history")
+}
 
-    let mut md = String::new();
-    if let Some(body) = root {
-        convert_node_to_md(&body, &mut md);
-    } else {
-        // Just extract all text
-        md = document.root_element().text().collect::<Vec<_>>().join(" ");
-    }
-    md.trim().to_string()
+fn get_note_history_dir(notes_folder: &str, note_id: &str) -> PathBuf {
+    let safe_name = note_id.replace('/', "__");
+    get_history_dir(notes_folder).join(safe_name)
 }
 
-fn convert_node_to_md(element: &scraper::ElementRef, out: &mut String) {
-    for child in element.children() {
-        match child.value() {
-            scraper::node::Node::Text(text) => {
-                let t = text.text.trim();
-                if !t.is_empty() {
-                    out.push_str(t);
+/// Create a snapshot of a note's content for version history.
+/// Debounced: skips if last snapshot was less than 5 minutes ago.
+fn maybe_snapshot_note(notes_folder: &str, note_id: &str, content: &str) {
+    let history_dir = get_note_history_dir(notes_folder, note_id);
+
+    // Check if last snapshot is < 5 minutes old
+    if history_dir.exists() {
+        if let Ok(entries) = std::fs::read_dir(&history_dir) {
+            let mut latest: Option<std::time::SystemTime> = None;
+            for entry in entries.flatten() {
+                if let Ok(meta) = entry.metadata() {
+                    if let Ok(modified) = meta.modified() {
+                        latest = Some(latest.map_or(modified, |l: std::time::SystemTime| l.max(modified)));
+                    }
                 }
             }
-            scraper::node::Node::Element(el) => {
-                if let Some(child_ref) = scraper::ElementRef::wrap(child) {
-                    match el.name() {
-                        "h1" => {
-                            out.push_str("\n# ");
-                            convert_node_to_md(&child_ref, out);
-                            out.push_str("\n\n");
-                        }
-                        "h2" => {
-                            out.push_str("\n## ");
-                            convert_node_to_md(&child_ref, out);
-                            out.push_str("\n\n");
-                        }
-                        "h3" => {
-                            out.push_str("\n### ");
-                            convert_node_to_md(&child_ref, out);
-                            out.push_str("\n\n");
-                        }
-                        "p" | "div" => {
-                            convert_node_to_md(&child_ref, out);
-                            out.push_str("\n\n");
-                        }
-                        "br" => out.push('\n'),
-                        "strong" | "b" => {
-                            out.push_str("**");
-                            convert_node_to_md(&child_ref, out);
-                            out.push_str("**");
-                        }
-                        "em" | "i" => {
-                            out.push('*');
-                            convert_node_to_md(&child_ref, out);
-                            out.push('*');
-                        }
-                        "code" => {
-                            out.push('`');
-                            convert_node_to_md(&child_ref, out);
-                            out.push('`');
-                        }
-                        "pre" => {
-                            out.push_str("\n```\n");
-                            convert_node_to_md(&child_ref, out);
-                            out.push_str("\n```\n\n");
-                        }
-                        "a" => {
-                            let href = el.attr("href").unwrap_or("#");
-                            out.push('[');
-                            convert_node_to_md(&child_ref, out);
-                            out.push_str("](");
-                            out.push_str(href);
-                            out.push(')');
-                        }
-                        "li" => {
-                            out.push_str("- ");
-                            convert_node_to_md(&child_ref, out);
-                            out.push('\n');
-                        }
-                        "blockquote" => {
-                            out.push_str("> ");
-                            convert_node_to_md(&child_ref, out);
-                            out.push('\n');
-                        }
-                        "hr" => out.push_str("\n---\n\n"),
-                        "img" => {
-                            let src = el.attr("src").unwrap_or("");
-                            let alt = el.attr("alt").unwrap_or("image");
-                            out.push_str(&format!("![{}]({})", alt, src));
+            if let Some(last) = latest {
+                if let Ok(elapsed) = last.elapsed() {
+                    if elapsed < Duration::from_secs(300) {
+                        return; // Less than 5 minutes since last snapshot
+                    }
+                }
+            }
+        }
+    }
+
+    // Create snapshot
+    let _ = std::fs::create_dir_all(&history_dir);
+    let timestamp = chrono::Utc::now().format("%Y-%m-%dT%H-%M-%SZ").to_string();
+    let snapshot_path = history_dir.join(format!("{}.md", timestamp));
+    let _ = std::fs::write(&snapshot_path, content);
+
+    // Enforce 50-version limit
+    purge_note_history(&history_dir, 50);
+}
+
+fn purge_note_history(history_dir: &PathBuf, max_versions: usize) {
+    if let Ok(entries) = std::fs::read_dir(history_dir) {
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "md"))
+            .map(|e| e.path())
+            .collect();
+        // Sort by name (timestamp-based, so alphabetical = chronological)
+        files.sort();
+        // Remove oldest if over limit
+        while files.len() > max_versions {
+            if let Some(oldest) = files.first() {
+                let _ = std::fs::remove_file(oldest);
+                files.remove(0);
+            }
+        }
+    }
+}
+
+/// Purge version history to stay under 100MB total.
+fn purge_history_size(notes_folder: &str) {
+    let history_dir = get_history_dir(notes_folder);
+    if !history_dir.exists() {
+        return;
+    }
+
+    let max_bytes: u64 = 100 * 1024 * 1024; // 100MB
+
+    // Collect all history files with their sizes and modified times
+    let mut all_files: Vec<(PathBuf, u64, std::time::SystemTime)> = Vec::new();
+    if let Ok(note_dirs) = std::fs::read_dir(&history_dir) {
+        for dir_entry in note_dirs.flatten() {
+            if dir_entry.path().is_dir() {
+                if let Ok(files) = std::fs::read_dir(dir_entry.path()) {
+                    for file in files.flatten() {
+                        let path = file.path();
+                        if path.extension().is_some_and(|e| e == "md") {
+                            if let Ok(meta) = path.metadata() {
+                                let modified = meta.modified().unwrap_or(std::time::UNIX_EPOCH);
+                                all_files.push((path, meta.len(), modified));
+                            }
                         }
-                        _ => convert_node_to_md(&child_ref, out),
                     }
                 }
             }
-            _ => {}
         }
     }
+
+    let total: u64 = all_files.iter().map(|(_, size, _)| size).sum();
+    if total <= max_bytes {
+        return;
+    }
+
+    // Sort by modified time, oldest first
+    all_files.sort_by_key(|(_, _, t)| *t);
+
+    let mut current_total = total;
+    for (path, size, _) in &all_files {
+        if current_total <= max_bytes {
+            break;
+        }
+        let _ = std::fs::remove_file(path);
+        current_total -= size;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VersionEntry {
+    id: String,          // timestamp filename (without .md)
+    timestamp: String,   // human-readable ISO timestamp
+    size: u64,           // file size in bytes
+}
+
+#[tauri::command]
+async fn list_versions(note_id: String, state: State<'_, AppState>) -> Result<Vec<VersionEntry>, String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+
+    let history_dir = get_note_history_dir(&notes_folder, &note_id);
+    if !history_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut versions = Vec::new();
+    for entry in std::fs::read_dir(&history_dir).map_err(|e| e.to_string())?.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|e| e == "md") {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+            let size = path.metadata().map(|m| m.len()).unwrap_or(0);
+            // Convert filename timestamp (2026-02-14T04-30-00Z) to ISO (2026-02-14T04:30:00Z)
+            let parts: Vec<&str> = stem.splitn(2, 'T').collect();
+            let timestamp = if parts.len() == 2 {
+                format!("{}T{}", parts[0], parts[1].replace('-', ":"))
+            } else {
+                stem.clone()
+            };
+
+            versions.push(VersionEntry { id: stem, timestamp, size });
+        }
+    }
+
+    // Sort newest first
+    versions.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(versions)
 }
 
-// ---- Trash Bin Commands ----
+#[tauri::command]
+async fn read_version(note_id: String, version_id: String, state: State<'_, AppState>) -> Result<String, String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct TrashMeta {
-    original_path: String,
-    deleted_at: String,
+    let history_dir = get_note_history_dir(&notes_folder, &note_id);
+    let version_path = history_dir.join(format!("{}.md", version_id));
+
+    if !version_path.exists() {
+        return Err("Version not found".to_string());
+    }
+
+    std::fs::read_to_string(&version_path).map_err(|e| e.to_string())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct TrashedNote {
-    id: String,
-    title: String,
-    original_path: String,
-    deleted_at: String,
-    preview: String,
+#[tauri::command]
+async fn restore_version(note_id: String, version_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+
+    // Read the version content
+    let history_dir = get_note_history_dir(&notes_folder, &note_id);
+    let version_path = history_dir.join(format!("{}.md", version_id));
+    let version_content = std::fs::read_to_string(&version_path).map_err(|e| e.to_string())?;
+
+    // Snapshot current content before restoring
+    let current_path = resolve_note_path(&notes_folder, &note_id)?;
+    if current_path.exists() {
+        let current_content = std::fs::read_to_string(&current_path).unwrap_or_default();
+        force_snapshot_note(&notes_folder, &note_id, &current_content);
+    }
+
+    // Write version content to the note file
+    std::fs::write(&current_path, version_content).map_err(|e| format!("Failed to restore: {}", e))
 }
 
-fn get_trash_dir(notes_folder: &str) -> PathBuf {
-    PathBuf::from(notes_folder).join(".scratch").join("trash")
+/// Snapshot a note regardless of the 5-minute debounce (used before restores and pulls).
+/// A snapshot already taken this second is never overwritten; the timestamp is bumped instead.
+fn force_snapshot_note(notes_folder: &str, note_id: &str, content: &str) {
+    let history_dir = get_note_history_dir(notes_folder, note_id);
+    let _ = std::fs::create_dir_all(&history_dir);
+    let mut time = chrono::Utc::now();
+    let mut snapshot_path = history_dir.join(format!("{}.md", time.format("%Y-%m-%dT%H-%M-%SZ")));
+    while snapshot_path.exists() {
+        time += chrono::Duration::seconds(1);
+        snapshot_path = history_dir.join(format!("{}.md", time.format("%Y-%m-%dT%H-%M-%SZ")));
+    }
+    let _ = std::fs::write(&snapshot_path, content);
 }
 
-fn ensure_trash_dir(notes_folder: &str) -> Result<PathBuf, String> {
-    let trash = get_trash_dir(notes_folder);
-    std::fs::create_dir_all(&trash).map_err(|e| format!("Failed to create trash dir: {}", e))?;
-    Ok(trash)
+// Newest snapshot's content for a note, if any
+fn latest_snapshot(notes_folder: &str, note_id: &str) -> Option<String> {
+    let entries = std::fs::read_dir(get_note_history_dir(notes_folder, note_id)).ok()?;
+    let latest = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+        .max()?;
+    std::fs::read_to_string(latest).ok()
 }
 
-/// Auto-purge trashed notes older than 30 days.
-fn purge_old_trash(notes_folder: &str) {
-    let trash_dir = get_trash_dir(notes_folder);
-    if !trash_dir.exists() {
+/// Snapshot a note that a pull is about to overwrite, unless its newest snapshot already matches.
+fn snapshot_before_pull(notes_folder: &str, note_id: &str, content: &str) {
+    if latest_snapshot(notes_folder, note_id).as_deref() == Some(content) {
         return;
     }
-    let now = chrono::Utc::now();
-    let cutoff = chrono::Duration::days(30);
+    force_snapshot_note(notes_folder, note_id, content);
+    purge_note_history(&get_note_history_dir(notes_folder, note_id), 50);
+}
 
-    if let Ok(entries) = std::fs::read_dir(&trash_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.extension().is_some_and(|e| e == "json") && path.to_string_lossy().ends_with(".meta.json") {
-                if let Ok(content) = std::fs::read_to_string(&path) {
-                    if let Ok(meta) = serde_json::from_str::<TrashMeta>(&content) {
-                        if let Ok(deleted) = chrono::DateTime::parse_from_rfc3339(&meta.deleted_at) {
-                            if now.signed_duration_since(deleted) > cutoff {
-                                // Remove note file and meta
-                                // note file path derived from meta path
-                                // meta file name: foo.meta.json -> remove "foo.meta.json"
-                                let _ = std::fs::remove_file(&path);
-                                // The note file: strip ".meta.json" -> stem = "foo.meta", but we stored as "foo.md"
-                                let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
-                                let note_stem = stem.strip_suffix(".meta").unwrap_or(&stem);
-                                let note_path = trash_dir.join(format!("{}.md", note_stem));
-                                let _ = std::fs::remove_file(&note_path);
-                            }
-                        }
-                    }
-                }
+// Newest snapshot (version id, content) in any of the history dirs that differs from `current`
+fn previous_version(notes_folder: &str, history_ids: &[String], current: &str) -> Option<(String, String)> {
+    for history_id in history_ids {
+        let Ok(entries) = std::fs::read_dir(get_note_history_dir(notes_folder, history_id)) else {
+            continue;
+        };
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+            .collect();
+        files.sort();
+        for path in files.iter().rev() {
+            let Ok(content) = std::fs::read_to_string(path) else { continue };
+            if content != current {
+                let version_id = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+                return Some((version_id, content));
             }
         }
     }
+    None
+}
+
+// Note that undo acts on, plus the ids its history may be stored under:
+// the latest save/undo in the activity log, else the most recently modified note
+fn undo_target(notes_folder: &str) -> Result<Option<(String, Vec<String>)>, String> {
+    for event in read_activity_log(notes_folder, MAX_ACTIVITY_LOG_ENTRIES, None)? {
+        if event.action != "save" && event.action != "undo" {
+            continue;
+        }
+        if !resolve_note_path(notes_folder, &event.note_id).is_ok_and(|p| p.is_file()) {
+            continue;
+        }
+        let mut history_ids = vec![event.note_id.clone()];
+        if let Some(old_id) = event.details.get("renamedFrom").and_then(|v| v.as_str()) {
+            history_ids.push(old_id.to_string());
+        }
+        return Ok(Some((event.note_id, history_ids)));
+    }
+
+    let base = PathBuf::from(notes_folder);
+    let latest = walk_md_files_sync(&base, &base)?
+        .into_iter()
+        .filter_map(|path| {
+            let modified = path.metadata().and_then(|m| m.modified()).ok()?;
+            Some((modified, path_to_note_id(&base, &path)?))
+        })
+        .max_by_key(|(modified, _)| *modified);
+    Ok(latest.map(|(_, id)| (id.clone(), vec![id])))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoResult {
+    pub note: Option<Note>, // None when there was nothing to undo
+    pub message: String,
 }
 
+/// Coarse-grained undo: restore the previous snapshot of the last changed note.
+/// The current content is snapshotted first, so an undo can itself be undone.
 #[tauri::command]
-async fn trash_note(id: String, state: State<'_, AppState>) -> Result<(), String> {
+async fn undo_last_change(state: State<'_, AppState>) -> Result<UndoResult, String> {
+    undo_last_change_impl(&state).await
+}
+
+async fn undo_last_change_impl(state: &AppState) -> Result<UndoResult, String> {
     let notes_folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
 
-    let file_path = resolve_note_path(&notes_folder, &id)?;
-    if !file_path.exists() {
-        return Err("Note not found".to_string());
-    }
+    let Some((note_id, history_ids)) = undo_target(&notes_folder)? else {
+        return Ok(UndoResult {
+            note: None,
+            message: "Nothing to undo: no notes have been changed".to_string(),
+        });
+    };
+    let current_path = resolve_note_path(&notes_folder, &note_id)?;
+    let current_content = std::fs::read_to_string(&current_path).map_err(|e| e.to_string())?;
 
-    let trash_dir = ensure_trash_dir(&notes_folder)?;
+    let Some((version_id, version_content)) = previous_version(&notes_folder, &history_ids, &current_content) else {
+        return Ok(UndoResult {
+            note: None,
+            message: format!("Nothing to undo: '{}' has no earlier snapshot", note_id),
+        });
+    };
 
-    // Compute a safe filename for trash (flatten path separators)
-    let safe_name = id.replace('/', "__");
-    let trash_file = trash_dir.join(format!("{}.md", &safe_name));
-    let meta_file = trash_dir.join(format!("{}.meta.json", &safe_name));
+    force_snapshot_note(&notes_folder, &note_id, &current_content);
+    // Saved like an edit, so search, backlinks and the cache follow the restored content
+    let note = save_note_impl(Some(note_id.clone()), version_content, state).await?;
+    log_activity(&notes_folder, "undo", &note.id, serde_json::json!({ "version": version_id }));
 
-    // Move file to trash
-    std::fs::rename(&file_path, &trash_file)
-        .or_else(|_| {
-            // rename can fail across filesystems, fall back to copy + delete
-            std::fs::copy(&file_path, &trash_file)?;
-            std::fs::remove_file(&file_path)
+    Ok(UndoResult {
+        message: format!("Restored '{}' to the version from {}", note_id, version_id),
+        note: Some(note),
+    })
+}
+
+// ---- Note Diff ----
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub kind: String, // "equal" | "insert" | "delete"
+    pub content: String,
+    pub old_line: Option<usize>, // 1-based line in the old text
+    pub new_line: Option<usize>, // 1-based line in the new text
+}
+
+// Line-level diff of two texts
+fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    use similar::{ChangeTag, TextDiff};
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            kind: match change.tag() {
+                ChangeTag::Equal => "equal",
+                ChangeTag::Insert => "insert",
+                ChangeTag::Delete => "delete",
+            }
+            .to_string(),
+            content: change.value().trim_end_matches(['\r', '\n']).to_string(),
+            old_line: change.old_index().map(|i| i + 1),
+            new_line: change.new_index().map(|i| i + 1),
         })
-        .map_err(|e| format!("Failed to move to trash: {}", e))?;
+        .collect()
+}
 
-    // Write metadata
-    let meta = TrashMeta {
-        original_path: format!("{}.md", id),
-        deleted_at: chrono::Utc::now().to_rfc3339(),
-    };
-    let meta_json = serde_json::to_string_pretty(&meta).map_err(|e| e.to_string())?;
-    std::fs::write(&meta_file, meta_json).map_err(|e| format!("Failed to write trash meta: {}", e))?;
+/// Line diff between two different notes, ignoring their frontmatter.
+#[tauri::command]
+async fn diff_notes(id_a: String, id_b: String, state: State<'_, AppState>) -> Result<Vec<DiffLine>, String> {
+    let a = read_note_impl(id_a, &state).await?;
+    let b = read_note_impl(id_b, &state).await?;
+    Ok(diff_lines(&strip_frontmatter(&a.content), &strip_frontmatter(&b.content)))
+}
 
-    // Update search index
-    {
-        let index = state.search_index.lock().expect("search index mutex");
-        if let Some(ref search_index) = *index {
-            let _ = search_index.delete_note(&id);
+// ---- Reminder Commands ----
+
+// Reminder syntax: `@remind(2025-06-01)` or `@remind(2025-06-01 09:30)`.
+// Group 1 is the date, group 2 the optional time.
+const REMINDER_PATTERN: &str = r"@remind\((\d{4}-\d{2}-\d{2})(?:[ T](\d{2}:\d{2}))?\)";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reminder {
+    pub note_id: String,
+    pub note_title: String,
+    pub line: usize,
+    // Unix seconds, local time (midnight when no time is given)
+    pub date: i64,
+    pub text: String,
+}
+
+/// Find reminders in one note. Malformed dates, fenced code and completed tasks are skipped.
+fn extract_reminders(note_id: &str, note_title: &str, content: &str, re: &regex::Regex) -> Vec<Reminder> {
+    let mut reminders = Vec::new();
+    let mut in_fence = false;
+    for (idx, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence || trimmed.starts_with("- [x] ") || trimmed.starts_with("- [X] ") {
+            continue;
         }
-    }
 
-    // Remove from cache
-    {
-        let mut cache = state.notes_cache.write().expect("cache write lock");
-        cache.remove(&id);
-    }
+        let text = re.replace_all(trimmed, "");
+        let text = text.trim_start_matches("- [ ] ").trim_start_matches("- ").trim_start_matches("* ");
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
 
-    // Remove backlinks
-    {
-        let mut bl_index = state.backlinks_index.write().expect("backlinks write lock");
-        remove_backlinks_for_note(&mut bl_index, &id);
-        let _ = save_backlinks_index(&notes_folder, &bl_index);
+        for cap in re.captures_iter(line) {
+            let Ok(date) = chrono::NaiveDate::parse_from_str(&cap[1], "%Y-%m-%d") else {
+                continue;
+            };
+            let time = match cap.get(2) {
+                Some(t) => match chrono::NaiveTime::parse_from_str(t.as_str(), "%H:%M") {
+                    Ok(time) => time,
+                    Err(_) => continue,
+                },
+                None => chrono::NaiveTime::MIN,
+            };
+            let Some(when) = date.and_time(time).and_local_timezone(chrono::Local).earliest() else {
+                continue;
+            };
+            reminders.push(Reminder {
+                note_id: note_id.to_string(),
+                note_title: note_title.to_string(),
+                line: idx + 1,
+                date: when.timestamp(),
+                text: text.clone(),
+            });
+        }
     }
-
-    Ok(())
+    reminders
 }
 
+/// Reminders dated within `from..=to` (unix seconds) across all notes, soonest first.
 #[tauri::command]
-async fn list_trash(state: State<'_, AppState>) -> Result<Vec<TrashedNote>, String> {
+fn list_reminders(from: i64, to: i64, state: State<AppState>) -> Result<Vec<Reminder>, String> {
     let notes_folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
+    let base = PathBuf::from(&notes_folder);
+    let re = regex::Regex::new(REMINDER_PATTERN).map_err(|e| e.to_string())?;
 
-    let trash_dir = get_trash_dir(&notes_folder);
-    if !trash_dir.exists() {
-        return Ok(Vec::new());
-    }
-
-    let mut result = Vec::new();
-    for entry in std::fs::read_dir(&trash_dir).map_err(|e| e.to_string())?.flatten() {
-        let path = entry.path();
-        if !path.to_string_lossy().ends_with(".meta.json") {
+    let mut reminders = Vec::new();
+    for file_path in walk_md_files_sync(&base, &base)? {
+        let Ok(content) = std::fs::read_to_string(&file_path) else {
+            continue;
+        };
+        if !re.is_match(&content) {
             continue;
         }
+        let id = path_to_note_id(&base, &file_path).unwrap_or_else(|| "unknown".to_string());
+        let title = extract_title(&content);
+        reminders.extend(
+            extract_reminders(&id, &title, &content, &re)
+                .into_iter()
+                .filter(|r| r.date >= from && r.date <= to),
+        );
+    }
 
-        let content = std::fs::read_to_string(&path).unwrap_or_default();
-        let meta: TrashMeta = match serde_json::from_str(&content) {
-            Ok(m) => m,
-            Err(_) => continue,
-        };
+    reminders.sort_by(|a, b| {
+        a.date.cmp(&b.date)
+            .then_with(|| a.note_id.cmp(&b.note_id))
+            .then_with(|| a.line.cmp(&b.line))
+    });
+    Ok(reminders)
+}
 
-        let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
-        let note_stem = stem.strip_suffix(".meta").unwrap_or(&stem);
-        let note_path = trash_dir.join(format!("{}.md", note_stem));
+// ---- Daily Note Rollup Commands ----
 
-        if !note_path.exists() {
+const DEFAULT_DAILY_NOTE_FORMAT: &str = "YYYY-MM-DD";
+const MAX_ROLLUP_DAYS: i64 = 366;
+
+// Expected daily note id for `date`, using the template date tokens (YYYY, MM, DD, MMMM, ...)
+fn daily_note_name(format: &str, date: chrono::NaiveDate) -> String {
+    use chrono::Datelike;
+    let month_names = ["January", "February", "March", "April", "May", "June",
+                       "July", "August", "September", "October", "November", "December"];
+    let month_full = month_names[date.month0() as usize];
+    format_date_pattern(format, date.year(), date.month(), date.day(), month_full, &month_full[..3])
+}
+
+/// Markdown summary of one daily note: its `Today's Goals` section plus open tasks elsewhere.
+/// Empty when there is nothing to carry over.
+fn rollup_daily_note(heading: &str, content: &str) -> String {
+    let goals = build_outline(content)
+        .into_iter()
+        .find(|s| s.title.trim().eq_ignore_ascii_case("Today's Goals"));
+    // Blank list items are template placeholders, not goals
+    let is_placeholder = |t: &str| t.trim_start_matches("- [ ]").trim_start_matches('-').trim().is_empty();
+
+    let mut goal_lines = Vec::new();
+    let mut open_tasks = Vec::new();
+    for (idx, line) in content.lines().enumerate() {
+        let line_no = idx + 1;
+        let trimmed = line.trim_end();
+        if trimmed.trim().is_empty() || is_placeholder(trimmed.trim()) {
             continue;
         }
+        match &goals {
+            Some(section) if line_no > section.start_line && line_no <= section.end_line => {
+                goal_lines.push(trimmed.to_string());
+            }
+            _ if trimmed.trim_start().starts_with("- [ ] ") => open_tasks.push(trimmed.to_string()),
+            _ => {}
+        }
+    }
 
-        let note_content = std::fs::read_to_string(&note_path).unwrap_or_default();
-        let title = extract_title(&note_content);
-        let preview = generate_preview(&note_content);
-
-        result.push(TrashedNote {
-            id: note_stem.to_string(),
-            title,
-            original_path: meta.original_path,
-            deleted_at: meta.deleted_at,
-            preview,
-        });
+    if goal_lines.is_empty() && open_tasks.is_empty() {
+        return String::new();
+    }
+    let mut out = format!("## {}\n", heading);
+    if !goal_lines.is_empty() {
+        out.push_str(&format!("\n### Today's Goals\n\n{}\n", goal_lines.join("\n")));
+    }
+    if !open_tasks.is_empty() {
+        out.push_str(&format!("\n### Open Tasks\n\n{}\n", open_tasks.join("\n")));
     }
-
-    // Sort by deletion date, newest first
-    result.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
-    Ok(result)
+    out
 }
 
+/// Summarize goals and incomplete tasks from daily notes dated `start..=end` (YYYY-MM-DD).
 #[tauri::command]
-async fn restore_note(id: String, state: State<'_, AppState>) -> Result<(), String> {
+fn rollup_daily_notes(start: String, end: String, state: State<AppState>) -> Result<String, String> {
     let notes_folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
-
-    let trash_dir = get_trash_dir(&notes_folder);
-    let note_path = trash_dir.join(format!("{}.md", &id));
-    let meta_path = trash_dir.join(format!("{}.meta.json", &id));
-
-    if !note_path.exists() {
-        return Err("Trashed note not found".to_string());
+    let format = {
+        let settings = state.settings.read().expect("settings read lock");
+        settings
+            .daily_note_format
+            .clone()
+            .filter(|f| !f.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_DAILY_NOTE_FORMAT.to_string())
+    };
+    let parse = |d: &str| {
+        chrono::NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").map_err(|_| format!("Invalid date: {}", d))
+    };
+    let (start, end) = (parse(&start)?, parse(&end)?);
+    if end < start {
+        return Err("End date is before start date".to_string());
     }
-
-    // Read meta to get original path
-    let meta_content = std::fs::read_to_string(&meta_path).map_err(|e| e.to_string())?;
-    let meta: TrashMeta = serde_json::from_str(&meta_content).map_err(|e| e.to_string())?;
-
-    let dest = PathBuf::from(&notes_folder).join(&meta.original_path);
-    let dest = unique_path(dest);
-
-    // Ensure parent dir exists
-    if let Some(parent) = dest.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create dir: {}", e))?;
+    if (end - start).num_days() >= MAX_ROLLUP_DAYS {
+        return Err(format!("Date range is limited to {} days", MAX_ROLLUP_DAYS));
     }
 
-    // Move back
-    std::fs::rename(&note_path, &dest)
-        .or_else(|_| {
-            std::fs::copy(&note_path, &dest)?;
-            std::fs::remove_file(&note_path)
-        })
-        .map_err(|e| format!("Failed to restore: {}", e))?;
-
-    // Remove meta
-    let _ = std::fs::remove_file(&meta_path);
-
-    // Re-index the note
+    // Daily notes may live in a subfolder, so match on the full id or the file stem
     let base = PathBuf::from(&notes_folder);
-    if let Some(note_id) = path_to_note_id(&base, &dest) {
-        let content = std::fs::read_to_string(&dest).unwrap_or_default();
-        let index = state.search_index.lock().expect("search index mutex");
-        if let Some(ref search_index) = *index {
-            let title = extract_title(&content);
-            let modified = dest.metadata()
-                .and_then(|m| m.modified())
-                .map(|t| t.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs() as i64)
-                .unwrap_or(0);
-            let _ = search_index.index_note(&note_id, &title, &content, modified);
+    let mut by_name: HashMap<String, PathBuf> = HashMap::new();
+    for file_path in walk_md_files_sync(&base, &base)? {
+        if let Some(id) = path_to_note_id(&base, &file_path) {
+            if let Some(stem) = id.rsplit('/').next() {
+                by_name.entry(stem.to_string()).or_insert_with(|| file_path.clone());
+            }
+            by_name.insert(id, file_path);
         }
     }
 
-    Ok(())
+    let mut sections = Vec::new();
+    for date in start.iter_days().take_while(|d| *d <= end) {
+        let name = daily_note_name(&format, date);
+        let path = by_name
+            .get(&name)
+            .or_else(|| name.rsplit('/').next().and_then(|stem| by_name.get(stem)));
+        let Some(content) = path.and_then(|p| std::fs::read_to_string(p).ok()) else {
+            continue;
+        };
+        let section = rollup_daily_note(&name, &content);
+        if !section.is_empty() {
+            sections.push(section);
+        }
+    }
+    Ok(sections.join("\n"))
+}
+
+// ---- Scratchpad Commands ----
+
+// Quick-capture text kept under .scratch/, so walks, search and backlinks never see it
+fn scratchpad_path(notes_folder: &str) -> PathBuf {
+    PathBuf::from(notes_folder).join(".scratch").join("scratchpad.md")
 }
 
 #[tauri::command]
-async fn delete_permanently(id: String, state: State<'_, AppState>) -> Result<(), String> {
-    let notes_folder = {
+fn get_scratchpad(state: State<AppState>) -> Result<String, String> {
+    let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
-
-    let trash_dir = get_trash_dir(&notes_folder);
-    let note_path = trash_dir.join(format!("{}.md", &id));
-    let meta_path = trash_dir.join(format!("{}.meta.json", &id));
-
-    if note_path.exists() {
-        std::fs::remove_file(&note_path).map_err(|e| format!("Failed to delete: {}", e))?;
-    }
-    if meta_path.exists() {
-        std::fs::remove_file(&meta_path).map_err(|e| format!("Failed to delete meta: {}", e))?;
+    match std::fs::read_to_string(scratchpad_path(&folder)) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e.to_string()),
     }
-    Ok(())
 }
 
 #[tauri::command]
-async fn empty_trash(state: State<'_, AppState>) -> Result<usize, String> {
-    let notes_folder = {
+fn set_scratchpad(content: String, state: State<AppState>) -> Result<(), String> {
+    let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
-
-    let trash_dir = get_trash_dir(&notes_folder);
-    if !trash_dir.exists() {
-        return Ok(0);
+    let path = scratchpad_path(&folder);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
-
-    let mut count = 0;
-    for entry in std::fs::read_dir(&trash_dir).map_err(|e| e.to_string())?.flatten() {
-        let path = entry.path();
-        if path.is_file() {
-            let _ = std::fs::remove_file(&path);
-            if path.extension().is_some_and(|e| e == "md") {
-                count += 1;
-            }
-        }
-    }
-    Ok(count)
+    write_file_atomic(&path, content.as_bytes()).map_err(|e| e.to_string())
 }
 
-// ---- Version History Commands ----
+// ---- Note UI State Commands ----
 
-fn get_history_dir(notes_folder: &str) -> PathBuf {
-    PathBuf::from(notes_folder).join(".scratch").join("history")
-}
+// Opaque per-note UI state (open toggles, scroll position), keyed by note id.
+// The frontend owns the shape of each value.
+type NoteUiStateMap = HashMap<String, serde_json::Value>;
 
-fn get_note_history_dir(notes_folder: &str, note_id: &str) -> PathBuf {
-    let safe_name = note_id.replace('/', "__");
-    get_history_dir(notes_folder).join(safe_name)
+fn get_ui_state_path(notes_folder: &str) -> PathBuf {
+    PathBuf::from(notes_folder).join(".scratch").join("ui_state.json")
 }
 
-/// Create a snapshot of a note's content for version history.
-/// Debounced: skips if last snapshot was less than 5 minutes ago.
-fn maybe_snapshot_note(notes_folder: &str, note_id: &str, content: &str) {
-    let history_dir = get_note_history_dir(notes_folder, note_id);
+fn load_ui_state(notes_folder: &str) -> NoteUiStateMap {
+    std::fs::read_to_string(get_ui_state_path(notes_folder))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
 
-    // Check if last snapshot is < 5 minutes old
-    if history_dir.exists() {
-        if let Ok(entries) = std::fs::read_dir(&history_dir) {
-            let mut latest: Option<std::time::SystemTime> = None;
-            for entry in entries.flatten() {
-                if let Ok(meta) = entry.metadata() {
-                    if let Ok(modified) = meta.modified() {
-                        latest = Some(latest.map_or(modified, |l: std::time::SystemTime| l.max(modified)));
-                    }
-                }
-            }
-            if let Some(last) = latest {
-                if let Ok(elapsed) = last.elapsed() {
-                    if elapsed < Duration::from_secs(300) {
-                        return; // Less than 5 minutes since last snapshot
-                    }
-                }
-            }
-        }
+fn save_ui_state(notes_folder: &str, map: &NoteUiStateMap) -> Result<(), String> {
+    let path = get_ui_state_path(notes_folder);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
+    let content = serde_json::to_string_pretty(map).map_err(|e| e.to_string())?;
+    write_file_atomic(&path, content.as_bytes()).map_err(|e| format!("Failed to save UI state: {}", e))
+}
 
-    // Create snapshot
-    let _ = std::fs::create_dir_all(&history_dir);
-    let timestamp = chrono::Utc::now().format("%Y-%m-%dT%H-%M-%SZ").to_string();
-    let snapshot_path = history_dir.join(format!("{}.md", timestamp));
-    let _ = std::fs::write(&snapshot_path, content);
+/// Drop UI state for notes that no longer exist. Only writes when something changed.
+fn prune_ui_state(notes_folder: &str, existing_ids: &HashSet<String>) -> Result<usize, String> {
+    let mut map = load_ui_state(notes_folder);
+    let before = map.len();
+    map.retain(|id, _| existing_ids.contains(id));
+    let removed = before - map.len();
+    if removed > 0 {
+        save_ui_state(notes_folder, &map)?;
+    }
+    Ok(removed)
+}
 
-    // Enforce 50-version limit
-    purge_note_history(&history_dir, 50);
+#[tauri::command]
+fn get_note_ui_state(id: String, state: State<AppState>) -> Result<Option<serde_json::Value>, String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let id = validate_note_id(&id)?;
+    Ok(load_ui_state(&notes_folder).remove(&id))
 }
 
-fn purge_note_history(history_dir: &PathBuf, max_versions: usize) {
-    if let Ok(entries) = std::fs::read_dir(history_dir) {
-        let mut files: Vec<PathBuf> = entries
-            .flatten()
-            .filter(|e| e.path().extension().is_some_and(|ext| ext == "md"))
-            .map(|e| e.path())
-            .collect();
-        // Sort by name (timestamp-based, so alphabetical = chronological)
-        files.sort();
-        // Remove oldest if over limit
-        while files.len() > max_versions {
-            if let Some(oldest) = files.first() {
-                let _ = std::fs::remove_file(oldest);
-                files.remove(0);
-            }
+/// Store UI state for a note; `null` clears it.
+#[tauri::command]
+fn set_note_ui_state(id: String, state: serde_json::Value, app_state: State<AppState>) -> Result<(), String> {
+    let notes_folder = {
+        let app_config = app_state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let id = validate_note_id(&id)?;
+    let mut map = load_ui_state(&notes_folder);
+    if state.is_null() {
+        if map.remove(&id).is_none() {
+            return Ok(());
         }
+    } else {
+        map.insert(id, state);
     }
+    save_ui_state(&notes_folder, &map)
 }
 
-/// Purge version history to stay under 100MB total.
-fn purge_history_size(notes_folder: &str) {
-    let history_dir = get_history_dir(notes_folder);
-    if !history_dir.exists() {
-        return;
-    }
+// ---- Saved Search Commands ----
 
-    let max_bytes: u64 = 100 * 1024 * 1024; // 100MB
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>, // only return notes under this folder
+}
 
-    // Collect all history files with their sizes and modified times
-    let mut all_files: Vec<(PathBuf, u64, std::time::SystemTime)> = Vec::new();
-    if let Ok(note_dirs) = std::fs::read_dir(&history_dir) {
-        for dir_entry in note_dirs.flatten() {
-            if dir_entry.path().is_dir() {
-                if let Ok(files) = std::fs::read_dir(dir_entry.path()) {
-                    for file in files.flatten() {
-                        let path = file.path();
-                        if path.extension().is_some_and(|e| e == "md") {
-                            if let Ok(meta) = path.metadata() {
-                                let modified = meta.modified().unwrap_or(std::time::UNIX_EPOCH);
-                                all_files.push((path, meta.len(), modified));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
+fn get_saved_searches_path(notes_folder: &str) -> PathBuf {
+    PathBuf::from(notes_folder).join(".scratch").join("saved_searches.json")
+}
 
-    let total: u64 = all_files.iter().map(|(_, size, _)| size).sum();
-    if total <= max_bytes {
-        return;
-    }
+fn load_saved_searches(notes_folder: &str) -> Vec<SavedSearch> {
+    std::fs::read_to_string(get_saved_searches_path(notes_folder))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
 
-    // Sort by modified time, oldest first
-    all_files.sort_by_key(|(_, _, t)| *t);
+fn save_saved_searches(notes_folder: &str, searches: &[SavedSearch]) -> Result<(), String> {
+    let path = get_saved_searches_path(notes_folder);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(searches).map_err(|e| e.to_string())?;
+    write_file_atomic(&path, content.as_bytes()).map_err(|e| format!("Failed to save searches: {}", e))
+}
 
-    let mut current_total = total;
-    for (path, size, _) in &all_files {
-        if current_total <= max_bytes {
-            break;
-        }
-        let _ = std::fs::remove_file(path);
-        current_total -= size;
+// Replace the search with the same name in place, or append a new one
+fn upsert_saved_search(searches: &mut Vec<SavedSearch>, search: SavedSearch) {
+    match searches.iter_mut().find(|s| s.name == search.name) {
+        Some(existing) => *existing = search,
+        None => searches.push(search),
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct VersionEntry {
-    id: String,          // timestamp filename (without .md)
-    timestamp: String,   // human-readable ISO timestamp
-    size: u64,           // file size in bytes
+fn in_search_folder(note_id: &str, folder: Option<&str>) -> bool {
+    match folder {
+        Some(folder) => note_id.starts_with(&format!("{}/", folder)),
+        None => true,
+    }
 }
 
+/// Save (or overwrite) a named search. `folder` limits results to that subfolder.
 #[tauri::command]
-async fn list_versions(note_id: String, state: State<'_, AppState>) -> Result<Vec<VersionEntry>, String> {
+fn save_search(
+    name: String,
+    query: String,
+    folder: Option<String>,
+    state: State<AppState>,
+) -> Result<SavedSearch, String> {
     let notes_folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
-
-    let history_dir = get_note_history_dir(&notes_folder, &note_id);
-    if !history_dir.exists() {
-        return Ok(Vec::new());
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Search name is required".to_string());
     }
-
-    let mut versions = Vec::new();
-    for entry in std::fs::read_dir(&history_dir).map_err(|e| e.to_string())?.flatten() {
-        let path = entry.path();
-        if path.extension().is_some_and(|e| e == "md") {
-            let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
-            let size = path.metadata().map(|m| m.len()).unwrap_or(0);
-            // Convert filename timestamp (2026-02-14T04-30-00Z) to ISO (2026-02-14T04:30:00Z)
-            let parts: Vec<&str> = stem.splitn(2, 'T').collect();
-            let timestamp = if parts.len() == 2 {
-                format!("{}T{}", parts[0], parts[1].replace('-', ":"))
-            } else {
-                stem.clone()
-            };
-
-            versions.push(VersionEntry { id: stem, timestamp, size });
-        }
+    if query.trim().is_empty() {
+        return Err("Search query is required".to_string());
     }
+    let folder = folder
+        .map(|f| f.trim_matches('/').to_string())
+        .filter(|f| !f.is_empty() && f != ".")
+        .map(|f| validate_note_id(&f))
+        .transpose()?;
 
-    // Sort newest first
-    versions.sort_by(|a, b| b.id.cmp(&a.id));
-    Ok(versions)
+    let search = SavedSearch { name, query, folder };
+    let mut searches = load_saved_searches(&notes_folder);
+    upsert_saved_search(&mut searches, search.clone());
+    save_saved_searches(&notes_folder, &searches)?;
+    Ok(search)
 }
 
 #[tauri::command]
-async fn read_version(note_id: String, version_id: String, state: State<'_, AppState>) -> Result<String, String> {
+fn list_saved_searches(state: State<AppState>) -> Result<Vec<SavedSearch>, String> {
     let notes_folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
+    Ok(load_saved_searches(&notes_folder))
+}
 
-    let history_dir = get_note_history_dir(&notes_folder, &note_id);
-    let version_path = history_dir.join(format!("{}.md", version_id));
-
-    if !version_path.exists() {
-        return Err("Version not found".to_string());
+#[tauri::command]
+fn delete_saved_search(name: String, state: State<AppState>) -> Result<(), String> {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone().ok_or("Notes folder not set")?
+    };
+    let mut searches = load_saved_searches(&notes_folder);
+    let before = searches.len();
+    searches.retain(|s| s.name != name);
+    if searches.len() == before {
+        return Err(format!("Saved search not found: {}", name));
     }
-
-    std::fs::read_to_string(&version_path).map_err(|e| e.to_string())
+    save_saved_searches(&notes_folder, &searches)
 }
 
 #[tauri::command]
-async fn restore_version(note_id: String, version_id: String, state: State<'_, AppState>) -> Result<(), String> {
+async fn run_saved_search(name: String, state: State<'_, AppState>) -> Result<Vec<SearchResult>, String> {
     let notes_folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
+    let search = load_saved_searches(&notes_folder)
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| format!("Saved search not found: {}", name))?;
 
-    // Read the version content
-    let history_dir = get_note_history_dir(&notes_folder, &note_id);
-    let version_path = history_dir.join(format!("{}.md", version_id));
-    let version_content = std::fs::read_to_string(&version_path).map_err(|e| e.to_string())?;
-
-    // Snapshot current content before restoring
-    let current_path = resolve_note_path(&notes_folder, &note_id)?;
-    if current_path.exists() {
-        let current_content = std::fs::read_to_string(&current_path).unwrap_or_default();
-        // Force snapshot regardless of time
-        let _ = std::fs::create_dir_all(&history_dir);
-        let timestamp = chrono::Utc::now().format("%Y-%m-%dT%H-%M-%SZ").to_string();
-        let snapshot_path = history_dir.join(format!("{}.md", timestamp));
-        let _ = std::fs::write(&snapshot_path, &current_content);
-    }
-
-    // Write version content to the note file
-    std::fs::write(&current_path, version_content).map_err(|e| format!("Failed to restore: {}", e))
+    let mut results = search_notes_impl(search.query, &state).await?;
+    results.retain(|r| in_search_folder(&r.id, search.folder.as_deref()));
+    Ok(results)
 }
 
 // ---- Database Tauri Commands ----
@@ -5591,6 +10739,13 @@ fn db_list(state: State<AppState>) -> Result<Vec<database::DatabaseInfo>, String
     database::scan_databases(&folder)
 }
 
+/// All databases with their schemas, saving a db_get_schema call per database.
+#[tauri::command]
+fn db_list_with_schemas(state: State<AppState>) -> Result<Vec<database::DatabaseWithSchema>, String> {
+    let folder = get_notes_folder_path(&state)?;
+    database::scan_databases_with_schemas(&folder)
+}
+
 #[tauri::command]
 fn db_create(
     name: String,
@@ -5606,13 +10761,55 @@ fn db_create(
 struct DatabaseGetResult {
     schema: database::DatabaseSchema,
     rows: Vec<database::DatabaseRow>,
+    active_view: Option<String>, // view to open with (last used, else the first)
 }
 
 #[tauri::command]
 fn db_get(db_id: String, state: State<AppState>) -> Result<DatabaseGetResult, String> {
     let folder = get_notes_folder_path(&state)?;
     let (schema, rows) = database::get_database(&folder, &db_id)?;
-    Ok(DatabaseGetResult { schema, rows })
+    let active_view = schema.active_view().map(|v| v.to_string());
+    Ok(DatabaseGetResult { schema, rows, active_view })
+}
+
+#[tauri::command]
+async fn db_query_view(
+    db_id: String,
+    view_id: String,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    db_query_view_impl(db_id, view_id, limit.unwrap_or(usize::MAX), offset.unwrap_or(0), &state).await
+}
+
+#[derive(Serialize, Deserialize)]
+struct DatabasePageResult {
+    schema: database::DatabaseSchema,
+    rows: Vec<database::DatabaseRow>,
+    total: usize,
+}
+
+#[tauri::command]
+fn db_get_page(
+    db_id: String,
+    limit: usize,
+    offset: usize,
+    state: State<AppState>,
+) -> Result<DatabasePageResult, String> {
+    let folder = get_notes_folder_path(&state)?;
+    let (schema, rows, total) = database::get_database_page(&folder, &db_id, limit, offset)?;
+    Ok(DatabasePageResult { schema, rows, total })
+}
+
+#[tauri::command]
+fn db_set_active_view(
+    db_id: String,
+    view_id: String,
+    state: State<AppState>,
+) -> Result<database::DatabaseSchema, String> {
+    let folder = get_notes_folder_path(&state)?;
+    database::set_default_view(&folder, &db_id, &view_id)
 }
 
 #[tauri::command]
@@ -5622,12 +10819,247 @@ fn db_get_schema(db_id: String, state: State<AppState>) -> Result<database::Data
     database::load_schema(&db_folder)
 }
 
+/// A note line embedding a database via `[database:<name>](view:...)`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatabaseReference {
+    pub note_id: String,
+    pub line: usize, // 1-based
+}
+
+const DATABASE_EMBED_PATTERN: &str = r"\[database:([^\]]+)\]";
+
+fn embed_names_database(name: &str, db_id: &str) -> bool {
+    let name = name.trim();
+    name == db_id || database::slugify(name) == database::slugify(db_id)
+}
+
+/// Lines (1-based) of `content` that embed `db_id`. References may use the folder
+/// slug or a display name, so both sides are compared through `database::slugify`.
+fn database_reference_lines(content: &str, db_id: &str) -> Vec<usize> {
+    // Runs on every note in the vault, so the pattern is compiled once
+    static EMBED_RE: LazyLock<regex::Regex> = LazyLock::new(|| regex::Regex::new(DATABASE_EMBED_PATTERN).unwrap());
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| EMBED_RE.captures_iter(line).any(|caps| embed_names_database(&caps[1], db_id)))
+        .map(|(idx, _)| idx + 1)
+        .collect()
+}
+
+// Utility: Point every `[database:...]` embed of `old_id` at `new_id`
+fn retarget_database_embeds(content: &str, old_id: &str, new_id: &str) -> String {
+    static EMBED_RE: LazyLock<regex::Regex> = LazyLock::new(|| regex::Regex::new(DATABASE_EMBED_PATTERN).unwrap());
+    EMBED_RE.replace_all(content, |caps: &regex::Captures| {
+        if embed_names_database(&caps[1], old_id) {
+            format!("[database:{}]", new_id)
+        } else {
+            caps[0].to_string()
+        }
+    })
+    .into_owned()
+}
+
+fn find_database_references_in(
+    notes_folder: &PathBuf,
+    db_id: &str,
+) -> Result<Vec<DatabaseReference>, String> {
+    let mut files = walk_md_files_sync(notes_folder, notes_folder)?;
+    files.sort();
+    let mut refs = Vec::new();
+    for path in files {
+        let Some(note_id) = path_to_note_id(notes_folder, &path) else {
+            continue;
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        refs.extend(
+            database_reference_lines(&content, db_id)
+                .into_iter()
+                .map(|line| DatabaseReference { note_id: note_id.clone(), line }),
+        );
+    }
+    Ok(refs)
+}
+
+#[tauri::command]
+fn find_database_references(
+    db_id: String,
+    state: State<AppState>,
+) -> Result<Vec<DatabaseReference>, String> {
+    let folder = get_notes_folder_path(&state)?;
+    find_database_references_in(&folder, &db_id)
+}
+
+/// Move the search and backlinks entries of every note under a renamed folder from
+/// `old_prefix/...` ids to `new_prefix/...`.
+fn reindex_moved_folder(state: &AppState, notes_folder: &PathBuf, old_prefix: &str, new_prefix: &str) -> Result<(), String> {
+    let moved_folder = notes_folder.join(new_prefix);
+    let mut moved: Vec<(String, String, String, i64)> = Vec::new();
+    for path in walk_md_files_sync(notes_folder, &moved_folder)? {
+        let Some(new_id) = path_to_note_id(notes_folder, &path) else { continue };
+        let Ok(content) = std::fs::read_to_string(&path) else { continue };
+        let old_id = format!("{}{}", old_prefix, &new_id[new_prefix.len()..]);
+        let modified = path
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        moved.push((old_id, new_id, content, modified));
+    }
+
+    {
+        let index = state.search_index.lock().expect("search index mutex");
+        if let Some(ref search_index) = *index {
+            for (old_id, new_id, content, modified) in &moved {
+                let _ = search_index.delete_note(old_id);
+                let _ = search_index.index_note(new_id, &extract_title(content), content, *modified);
+            }
+        }
+    }
+    {
+        let mut bl_index = state.backlinks_index.write().expect("backlinks write lock");
+        for (old_id, new_id, content, _) in &moved {
+            remove_backlinks_for_note(&mut bl_index, old_id);
+            update_backlinks_for_note(&mut bl_index, new_id, &extract_title(content), content);
+        }
+        let _ = save_backlinks_index(&notes_folder.to_string_lossy(), &bl_index);
+    }
+    {
+        let mut cache = state.notes_cache.write().expect("cache write lock");
+        for (old_id, _, _, _) in &moved {
+            cache.remove(old_id);
+        }
+    }
+    Ok(())
+}
+
+async fn db_rename_impl(
+    db_id: &str,
+    new_name: &str,
+    rename_folder: bool,
+    state: &AppState,
+) -> Result<database::DatabaseInfo, String> {
+    let folder = get_notes_folder_path(state)?;
+    let info = database::rename_database(&folder, db_id, new_name, rename_folder)?;
+    if info.id == db_id {
+        return Ok(info);
+    }
+    reindex_moved_folder(state, &folder, db_id, &info.id)?;
+
+    let mut note_ids: Vec<String> = find_database_references_in(&folder, db_id)?
+        .into_iter()
+        .map(|r| r.note_id)
+        .collect();
+    note_ids.dedup();
+    for note_id in note_ids {
+        let note = read_note_impl(note_id.clone(), state).await?;
+        let content = retarget_database_embeds(&note.content, db_id, &info.id);
+        if content != note.content {
+            save_note_impl(Some(note_id), content, state).await?;
+        }
+    }
+    Ok(info)
+}
+
+/// Rename a database's display name and, with `rename_folder`, its folder slug. Notes
+/// embedding the old id are rewritten to the new one.
+#[tauri::command]
+async fn db_rename(
+    db_id: String,
+    new_name: String,
+    rename_folder: bool,
+    state: State<'_, AppState>,
+) -> Result<database::DatabaseInfo, String> {
+    db_rename_impl(&db_id, &new_name, rename_folder, &state).await
+}
+
+/// Notes outside the database that would be left with a dangling embed if it were deleted.
+fn notes_embedding_database(notes_folder: &PathBuf, db_id: &str) -> Result<Vec<String>, String> {
+    let own_prefix = format!("{}/", db_id.trim_end_matches('/'));
+    let mut note_ids: Vec<String> = find_database_references_in(notes_folder, db_id)?
+        .into_iter()
+        .map(|r| r.note_id)
+        .filter(|id| !id.starts_with(&own_prefix))
+        .collect();
+    note_ids.dedup();
+    Ok(note_ids)
+}
+
+#[tauri::command]
+fn db_can_delete(db_id: String, state: State<AppState>) -> Result<Vec<String>, String> {
+    let folder = get_notes_folder_path(&state)?;
+    notes_embedding_database(&folder, &db_id)
+}
+
+/// Delete a database. Refuses while other notes embed it unless `force` is set.
 #[tauri::command]
-fn db_delete(db_id: String, state: State<AppState>) -> Result<(), String> {
+fn db_delete(db_id: String, force: Option<bool>, state: State<AppState>) -> Result<(), String> {
     let folder = get_notes_folder_path(&state)?;
+    if !force.unwrap_or(false) {
+        let referencing = notes_embedding_database(&folder, &db_id)?;
+        if !referencing.is_empty() {
+            return Err(format!(
+                "Database '{}' is embedded in {} note(s): {}. Delete with force to remove it anyway.",
+                db_id,
+                referencing.len(),
+                referencing.join(", ")
+            ));
+        }
+    }
     database::delete_database(&folder, &db_id)
 }
 
+#[tauri::command]
+fn db_duplicate(
+    db_id: String,
+    new_name: String,
+    include_rows: bool,
+    state: State<AppState>,
+) -> Result<database::DatabaseInfo, String> {
+    let folder = get_notes_folder_path(&state)?;
+    database::duplicate_database(&folder, &db_id, &new_name, include_rows)
+}
+
+/// Write a database (schema + rows) to a single JSON file.
+#[tauri::command]
+fn db_export_json(db_id: String, dest: String, state: State<AppState>) -> Result<(), String> {
+    let folder = get_notes_folder_path(&state)?;
+    let json = database::export_database_json(&folder, &db_id)?;
+    std::fs::write(&dest, json).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+/// Recreate a database from a JSON export; a taken folder name gets a numeric suffix.
+#[tauri::command]
+fn db_import_json(path: String, state: State<AppState>) -> Result<database::DatabaseInfo, String> {
+    let folder = get_notes_folder_path(&state)?;
+    let json = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read file: {}", e))?;
+    database::import_database_json(&folder, &json)
+}
+
+/// Relation values in this database that point at missing rows.
+#[tauri::command]
+fn db_check_relations(
+    db_id: String,
+    state: State<AppState>,
+) -> Result<Vec<database::RelationIssue>, String> {
+    let folder = get_notes_folder_path(&state)?;
+    database::check_relations(&folder, &db_id)
+}
+
+/// Clear or flag dangling relation values; `strategy` is "clear" or "flag".
+#[tauri::command]
+fn db_repair_relations(
+    db_id: String,
+    strategy: String,
+    state: State<AppState>,
+) -> Result<Vec<database::RelationIssue>, String> {
+    let folder = get_notes_folder_path(&state)?;
+    database::repair_relations(&folder, &db_id, &strategy)
+}
+
 #[tauri::command]
 fn db_create_row(
     db_id: String,
@@ -5651,6 +11083,67 @@ fn db_update_row(
     database::update_row(&folder, &db_id, &row_id, fields, body)
 }
 
+/// Apply `patch` to every row matching `filter` (db_query filter syntax). Returns the count updated.
+#[tauri::command]
+fn db_bulk_update(
+    db_id: String,
+    filter: serde_json::Value,
+    patch: std::collections::HashMap<String, serde_json::Value>,
+    state: State<AppState>,
+) -> Result<usize, String> {
+    let folder = get_notes_folder_path(&state)?;
+    let (schema, mut rows) = database::get_database(&folder, &db_id)?;
+    database::validate_fields(&schema, &patch)?;
+    filter_db_rows(&mut rows, &filter)?;
+
+    for row in &rows {
+        database::update_row(&folder, &db_id, &row.id, patch.clone(), None)?;
+    }
+    Ok(rows.len())
+}
+
+// Utility: A filter that would match every row (missing, not an array, or empty)
+fn is_match_all_filter(filter: &serde_json::Value) -> bool {
+    filter.as_array().is_none_or(|arr| arr.is_empty())
+}
+
+/// Delete every row matching `filter`. An empty filter is refused unless `confirm_all` is set.
+#[tauri::command]
+fn db_bulk_delete(
+    db_id: String,
+    filter: serde_json::Value,
+    confirm_all: Option<bool>,
+    state: State<AppState>,
+) -> Result<usize, String> {
+    if is_match_all_filter(&filter) && !confirm_all.unwrap_or(false) {
+        return Err("Refusing to delete all rows without a filter; pass confirm_all to proceed".to_string());
+    }
+    let folder = get_notes_folder_path(&state)?;
+    let (_, mut rows) = database::get_database(&folder, &db_id)?;
+    filter_db_rows(&mut rows, &filter)?;
+
+    let ids: Vec<String> = rows.iter().map(|row| row.id.clone()).collect();
+    database::delete_rows(&folder, &db_id, &ids)?;
+    Ok(ids.len())
+}
+
+#[tauri::command]
+async fn db_get_row(db_id: String, row_id: String, state: State<'_, AppState>) -> Result<serde_json::Value, String> {
+    db_get_row_impl(db_id, row_id, &state).await
+}
+
+/// Move a row to `new_index` in the manual (position) order.
+#[tauri::command]
+fn db_reorder_row(
+    db_id: String,
+    row_id: String,
+    new_index: usize,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let folder = get_notes_folder_path(&state)?;
+    database::reorder_row(&folder, &db_id, &row_id, new_index)
+}
+
 #[tauri::command]
 fn db_delete_row(db_id: String, row_id: String, state: State<AppState>) -> Result<(), String> {
     let folder = get_notes_folder_path(&state)?;
@@ -5695,6 +11188,18 @@ fn db_rename_column(
     )
 }
 
+#[tauri::command]
+fn db_change_column_type(
+    db_id: String,
+    column_id: String,
+    new_type: database::ColumnType,
+    force: Option<bool>,
+    state: State<AppState>,
+) -> Result<database::ColumnTypeChange, String> {
+    let folder = get_notes_folder_path(&state)?;
+    database::change_column_type(&folder, &db_id, &column_id, new_type, force.unwrap_or(false))
+}
+
 #[tauri::command]
 fn db_update_schema(
     db_id: String,
@@ -5713,6 +11218,7 @@ struct RowTemplateInfo {
     title: Option<String>,
     fields: std::collections::HashMap<String, serde_json::Value>,
     body: Option<String>,
+    variables: Vec<String>, // `{{name}}` tokens the UI should prompt for
 }
 
 #[tauri::command]
@@ -5723,6 +11229,7 @@ fn db_list_templates(
     let folder = get_notes_folder_path(&state)?;
     let templates = database::list_row_templates(&folder, &db_id)?;
     Ok(templates.into_iter().map(|(id, t)| RowTemplateInfo {
+        variables: database::template_variables(&t),
         id,
         name: t.name,
         title: t.title,
@@ -5792,6 +11299,10 @@ pub fn run() {
 
             let mcp_enabled = settings.mcp_enabled.unwrap_or(false);
             let mcp_port = settings.mcp_port.unwrap_or(3921);
+            let mcp_host = mcp::resolve_bind_host(
+                settings.mcp_host.as_deref(),
+                settings.mcp_allow_remote.unwrap_or(false),
+            );
 
             let state = AppState(Arc::new(AppStateInner {
                 app_config: RwLock::new(app_config),
@@ -5800,17 +11311,24 @@ pub fn run() {
                 file_watcher: Mutex::new(None),
                 search_index: Mutex::new(search_index),
                 backlinks_index: RwLock::new(backlinks_index),
-                debounce_map: Arc::new(Mutex::new(HashMap::new())),
+                watch_coalescer: Arc::new(Mutex::new(WatchCoalescer::default())),
                 mcp_server_handle: Mutex::new(None),
                 story_index: Mutex::new(None),
+                mcp_call_log: Mutex::new(VecDeque::new()),
+                checksum_cache: Mutex::new(HashMap::new()),
             }));
 
             // Start MCP server if enabled
             if mcp_enabled {
-                let mcp_state = state.clone();
-                let server_handle = mcp::start_mcp_server(mcp_state, mcp_port);
-                let mut handle = state.mcp_server_handle.lock().expect("mcp handle mutex");
-                *handle = Some(server_handle);
+                match mcp_host {
+                    Ok(mcp_host) => {
+                        let mcp_state = state.clone();
+                        let server_handle = mcp::start_mcp_server(mcp_state, mcp_host, mcp_port);
+                        let mut handle = state.mcp_server_handle.lock().expect("mcp handle mutex");
+                        *handle = Some(server_handle);
+                    }
+                    Err(e) => eprintln!("MCP server not started: {}", e),
+                }
             }
 
             app.manage(state);
@@ -5876,6 +11394,7 @@ pub fn run() {
             list_notes,
             list_folders,
             list_notes_in_folder,
+            set_note_order,
             create_folder,
             rename_folder,
             delete_folder,
@@ -5885,18 +11404,54 @@ pub fn run() {
             delete_note,
             create_note,
             create_note_in_folder,
+            append_to_note,
+            insert_under_heading,
             get_settings,
             update_settings,
+            list_theme_presets,
+            apply_theme_preset,
+            list_available_fonts,
+            check_editor_font,
+            export_settings,
+            import_settings,
             search_notes,
+            export_search_results,
+            quick_switch,
+            preview_for,
+            lint_note,
+            validate_frontmatter,
+            get_outline,
             start_file_watcher,
             rebuild_search_index,
+            reindex_note,
+            reindex_deleted,
+            search_index_diagnostics,
             copy_to_clipboard,
+            copy_note_as_html,
             copy_image_to_assets,
+            rename_asset,
+            list_orphaned_assets,
+            delete_orphaned_assets,
+            get_asset_thumbnail,
             save_clipboard_image,
             open_folder_dialog,
+            get_note_path,
+            get_note_checksum,
+            get_checksums,
+            get_activity_log,
+            get_scratchpad,
+            set_scratchpad,
+            save_search,
+            list_saved_searches,
+            delete_saved_search,
+            run_saved_search,
+            replace_in_folder,
             reveal_in_file_manager,
+            open_in_external_editor,
             open_url_safe,
             fetch_url_metadata,
+            extract_links,
+            check_links,
             git_is_available,
             git_get_status,
             git_init_repo,
@@ -5904,34 +11459,94 @@ pub fn run() {
             git_push,
             git_add_remote,
             git_push_with_upstream,
+            git_ensure_gitignore,
+            git_commit_notes,
+            git_pull,
+            git_status_map,
+            git_list_branches,
+            git_create_branch,
+            git_checkout_branch,
             ai_check_claude_cli,
             ai_execute_claude,
             mcp_get_status,
             mcp_restart,
+            mcp_set_enabled,
+            mcp_set_port,
             webhook_get_log,
+            list_plugins,
+            set_plugin_enabled,
+            run_plugin_transform,
+            format_tables,
+            number_headings,
+            add_tag_to_notes,
+            remove_tag_from_notes,
+            mcp_call_log,
             get_backlinks,
+            get_backlinks_by_id,
+            count_backlinks,
+            count_backlinks_by_id,
+            export_link_graph_dot,
+            list_orphan_notes,
+            suggest_link_fixes,
+            apply_link_fix,
             rebuild_backlinks,
+            rebuild_backlinks_for,
+            list_reminders,
+            rollup_daily_notes,
+            get_note_ui_state,
+            set_note_ui_state,
             db_list,
+            db_list_with_schemas,
             db_create,
             db_get,
+            db_get_page,
+            db_set_active_view,
+            db_query_view,
             db_get_schema,
+            find_database_references,
+            db_can_delete,
             db_delete,
+            db_duplicate,
+            db_rename,
+            db_export_json,
+            db_import_json,
+            db_check_relations,
+            db_repair_relations,
             db_create_row,
             db_update_row,
+            db_get_row,
+            db_bulk_update,
+            db_bulk_delete,
             db_delete_row,
+            db_reorder_row,
             db_add_column,
             db_remove_column,
             db_rename_column,
+            db_change_column_type,
             db_update_schema,
             db_list_templates,
             db_create_row_from_template,
             list_templates,
             read_template,
             create_note_from_template,
+            apply_template_to_note,
+            list_snippets,
+            get_snippet,
+            save_template,
+            delete_template,
+            create_template_from_note,
             export_note_markdown,
             export_note_html,
+            render_note_preview,
+            read_note_expanded,
+            export_folder_markdown,
+            extract_code_blocks,
+            export_code_blocks,
             export_all_zip,
+            backup_vault,
             import_notes,
+            import_folder,
+            normalize_line_endings,
             import_zip,
             trash_note,
             list_trash,
@@ -5941,7 +11556,1627 @@ pub fn run() {
             list_versions,
             read_version,
             restore_version,
+            undo_last_change,
+            diff_notes,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+// ---- Tests ----
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_skips_title_line() {
+        let content = "# My Note\n\nFirst **real** line\nSecond line";
+        assert_eq!(generate_preview(content), "First real line");
+    }
+
+    #[test]
+    fn test_preview_note_starting_with_code_fence() {
+        let content = "```rust\nfn main() {}\n```\n\nActual prose here";
+        assert_eq!(generate_preview(content), "Actual prose here");
+    }
+
+    #[test]
+    fn test_preview_skips_code_fence_after_title() {
+        let content = "# Snippets\n\n~~~\nlet x = 1;\n~~~\nExplanation follows";
+        assert_eq!(generate_preview(content), "Explanation follows");
+    }
+
+    #[test]
+    fn test_preview_skips_callout_marker() {
+        let content = "# Title\n\n> [!NOTE]\n> Remember this";
+        assert_eq!(generate_preview(content), "Remember this");
+    }
+
+    #[test]
+    fn test_strip_markdown_preserves_inline_math() {
+        assert_eq!(strip_markdown("Product $x*y*z$ is *big*"), "Product x*y*z is big");
+    }
+
+    #[test]
+    fn test_strip_markdown_preserves_inline_code() {
+        assert_eq!(
+            strip_markdown("Call `snake_case_name` from _here_"),
+            "Call snake_case_name from here"
+        );
+    }
+
+    #[test]
+    fn test_strip_markdown_leaves_dollar_amounts() {
+        assert_eq!(strip_markdown("Costs $5 and $10"), "Costs $5 and $10");
+    }
+
+    #[test]
+    fn test_wikilink_context_preserves_code() {
+        let content = "See [[Target]] and `a_b_c`";
+        assert_eq!(extract_wikilink_context(content, 4), "See [[Target]] and a_b_c");
+    }
+
+    #[test]
+    fn test_lint_clean_note() {
+        let known: HashSet<String> = ["other note".to_string()].into_iter().collect();
+        let content = "# Title\n\n## Section\n\nSee [[Other Note]] and **bold**.\n";
+        assert!(lint_content(content, &known).is_empty());
+    }
+
+    #[test]
+    fn test_lint_flags_common_issues() {
+        let known = HashSet::new();
+        let content = "# Title\n\n### Skipped\n\nHalf **bold and `code\n\n[click]() then [[Missing|alias]]\n\n$$\nx^2\n";
+        let issues = lint_content(content, &known);
+        let lines: Vec<(usize, &str)> = issues.iter().map(|i| (i.line, i.severity.as_str())).collect();
+        assert_eq!(
+            lines,
+            vec![(3, "warning"), (5, "error"), (5, "error"), (7, "error"), (7, "warning"), (9, "error")]
+        );
+        assert!(issues[4].message.contains("[[Missing]]"));
+    }
+
+    #[test]
+    fn test_lint_wikilinks_resolve_like_backlinks() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-lint-links-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("projects")).unwrap();
+        std::fs::write(dir.join("projects/roadmap.md"), "---\naliases: [Plan]\n---\n# Road Map\n").unwrap();
+        let known = collect_known_note_titles(&dir.to_string_lossy());
+
+        let content = "# Title\n\n![[image.png]] [[plan]] [[Road Map#Goals|goals]] [[roadmap]] [[#Local]]\n\n[[Nowhere#x]]\n";
+        let issues = lint_content(content, &known);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, 5);
+        assert!(issues[0].message.contains("[[Nowhere]]"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_lint_ignores_code_fences() {
+        let known = HashSet::new();
+        let content = "# Title\n\n```\n**unbalanced [[nowhere]]\n```\n";
+        assert!(lint_content(content, &known).is_empty());
+    }
+
+    #[test]
+    fn test_outline_section_ranges() {
+        let content = "# Title\nintro\n## A\na body\n### A.1\ndeep\n## B\n```\n# not a heading\n```\nb body";
+        let outline = build_outline(content);
+        let ranges: Vec<(usize, &str, usize, usize)> = outline
+            .iter()
+            .map(|s| (s.level, s.title.as_str(), s.start_line, s.end_line))
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (1, "Title", 1, 11),
+                (2, "A", 3, 6),
+                (3, "A.1", 5, 6),
+                (2, "B", 7, 11),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_file_atomic_replaces_via_temp_file() {
+        let dir = std::env::temp_dir().join(format!("scratch-atomic-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("note.md");
+        std::fs::write(&target, "old content").unwrap();
+
+        write_file_atomic(&target, b"new content").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "new content");
+        // The temp file was renamed away, not left behind
+        assert!(!dir.join(".note.md.tmp").exists());
+        let entries: Vec<_> = std::fs::read_dir(&dir).unwrap().flatten().collect();
+        assert_eq!(entries.len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rewrite_asset_references() {
+        let content = "![shot](assets/my shot.png)\n![enc](assets/my%20shot.png)\n![[my shot.png|200]]\n![other](assets/my shot.png.bak)\n![angle](<assets/my shot.png>)";
+        let rewritten = rewrite_asset_references(content, "my shot.png", "diagram.png");
+        assert_eq!(
+            rewritten,
+            "![shot](assets/diagram.png)\n![enc](assets/diagram.png)\n![[diagram.png|200]]\n![other](assets/my shot.png.bak)\n![angle](<assets/diagram.png>)"
+        );
+    }
+
+    #[test]
+    fn test_validate_asset_name() {
+        assert_eq!(validate_asset_name("assets/a.png").unwrap(), "a.png");
+        assert!(validate_asset_name("../secret.png").is_err());
+        assert!(validate_asset_name("sub/a.png").is_err());
+        assert!(validate_asset_name("").is_err());
+    }
+
+    #[test]
+    fn test_extract_asset_references_decodes_names() {
+        let content = "![a](assets/my%20photo.png) and <img src=\"assets/b.jpg\"> plus ![[c.gif|100]] and ![[assets/d.png]]";
+        let refs: HashSet<String> = extract_asset_references(content).into_iter().collect();
+        let expected: HashSet<String> = ["my photo.png", "b.jpg", "c.gif", "d.png"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(refs, expected);
+    }
+
+    #[test]
+    fn test_asset_with_space_survives_orphan_deletion() {
+        let content = "![](assets/my photo.png) ![](<assets/other pic.png>) ![x](assets/third one.png \"Title\") <img src='assets/four th.png'>";
+        let refs: HashSet<String> = extract_asset_references(content).into_iter().collect();
+        for name in ["my photo.png", "other pic.png", "third one.png", "four th.png"] {
+            assert!(refs.contains(name), "missing {}", name);
+        }
+
+        let folder = std::env::temp_dir().join(format!("scratch-orphan-space-{}", std::process::id()));
+        let assets = folder.join("assets");
+        std::fs::create_dir_all(&assets).unwrap();
+        std::fs::write(folder.join("note.md"), "# Note\n\n![](assets/my photo.png)\n").unwrap();
+        std::fs::write(assets.join("my photo.png"), "png").unwrap();
+        std::fs::write(assets.join("unused.png"), "png").unwrap();
+
+        assert_eq!(delete_orphaned_assets_in(&folder.to_string_lossy(), false).unwrap(), 1);
+        assert!(assets.join("my photo.png").exists());
+        assert!(!assets.join("unused.png").exists());
+
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn test_downscale_image_preserves_aspect_and_format() {
+        let img = image::RgbaImage::from_pixel(200, 100, image::Rgba([10, 20, 30, 255]));
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgba8(img)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let out = downscale_image_bytes(png.clone(), 50, 85);
+        assert_eq!(image::guess_format(&out).unwrap(), image::ImageFormat::Png);
+        let resized = image::load_from_memory(&out).unwrap();
+        assert_eq!((resized.width(), resized.height()), (50, 25));
+
+        // Already within bounds: bytes untouched
+        assert_eq!(downscale_image_bytes(png.clone(), 400, 85), png);
+    }
+
+    #[test]
+    fn test_downscale_image_applies_exif_orientation() {
+        // Little-endian TIFF block with a single Orientation = 6 (rotate 90° clockwise) entry
+        let exif: Vec<u8> = [
+            b"II*\0".as_slice(),
+            &[8, 0, 0, 0, 1, 0],
+            &[0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0],
+            &[0, 0, 0, 0],
+        ]
+        .concat();
+        let img = image::RgbImage::from_pixel(200, 100, image::Rgb([10, 20, 30]));
+        let mut jpeg = Vec::new();
+        let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 90);
+        image::ImageEncoder::set_exif_metadata(&mut encoder, exif).unwrap();
+        image::DynamicImage::ImageRgb8(img).write_with_encoder(encoder).unwrap();
+
+        // Stored 200x100, displayed 100x200: the downscaled copy is upright
+        let out = downscale_image_bytes(jpeg.clone(), 50, 85);
+        let resized = image::load_from_memory(&out).unwrap();
+        assert_eq!((resized.width(), resized.height()), (50, 100));
+        assert_eq!(downscale_image_bytes(jpeg.clone(), 100, 85), jpeg);
+    }
+
+    #[test]
+    fn test_downscale_image_falls_back_on_undecodable_data() {
+        let garbage = b"not an image".to_vec();
+        assert_eq!(downscale_image_bytes(garbage.clone(), 50, 85), garbage);
+    }
+
+    #[test]
+    fn test_encode_webp_round_trips() {
+        let img = image::RgbaImage::from_pixel(8, 4, image::Rgba([200, 100, 50, 255]));
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgba8(img)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let webp = encode_webp(&png).unwrap();
+        assert_eq!(image::guess_format(&webp).unwrap(), image::ImageFormat::WebP);
+        let decoded = image::load_from_memory(&webp).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (8, 4));
+        assert!(encode_webp(b"garbage").is_none());
+    }
+
+    #[test]
+    fn test_asset_thumbnail_cached_and_rejects_non_images() {
+        let folder = std::env::temp_dir().join(format!("scratch-thumbs-{}", std::process::id()));
+        let assets = folder.join("assets");
+        std::fs::create_dir_all(&assets).unwrap();
+        image::RgbaImage::from_pixel(100, 50, image::Rgba([0, 0, 0, 255]))
+            .save_with_format(assets.join("pic.png"), image::ImageFormat::Png)
+            .unwrap();
+        std::fs::write(assets.join("doc.txt"), "hello").unwrap();
+        let folder_str = folder.to_string_lossy().to_string();
+
+        let thumb = asset_thumbnail_bytes(&folder_str, "pic.png", 20).unwrap();
+        let decoded = image::load_from_memory(&thumb).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (20, 10));
+        let cached: Vec<_> = std::fs::read_dir(folder.join(".scratch").join("thumbnails")).unwrap().flatten().collect();
+        assert_eq!(cached.len(), 1);
+
+        assert!(asset_thumbnail_bytes(&folder_str, "doc.txt", 20).is_err());
+
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn test_append_note_content_keeps_trailing_newline() {
+        assert_eq!(append_note_content("# Log\n", "- entry"), "# Log\n- entry\n");
+        assert_eq!(append_note_content("# Log", "- entry"), "# Log\n- entry");
+        assert_eq!(append_note_content("# Log\n", "- entry\n"), "# Log\n- entry\n");
+        assert_eq!(append_note_content("", "- entry"), "- entry");
+    }
+
+    #[test]
+    fn test_insert_under_heading_start_and_end() {
+        let content = "# Note\n\n## Tasks\n- one\n### Sub\n- nested\n\n## Done\n- old\n";
+        assert_eq!(
+            insert_under_heading_content(content, "## Tasks", "- new", "end").unwrap(),
+            "# Note\n\n## Tasks\n- one\n### Sub\n- nested\n- new\n\n## Done\n- old\n"
+        );
+        assert_eq!(
+            insert_under_heading_content(content, "tasks", "- first", "start").unwrap(),
+            "# Note\n\n## Tasks\n- first\n- one\n### Sub\n- nested\n\n## Done\n- old\n"
+        );
+        assert_eq!(
+            insert_under_heading_content(content, "Done", "- newer", "end").unwrap(),
+            "# Note\n\n## Tasks\n- one\n### Sub\n- nested\n\n## Done\n- old\n- newer\n"
+        );
+    }
+
+    #[test]
+    fn test_insert_under_missing_heading_lists_available() {
+        let err = insert_under_heading_content("# Note\n## Tasks\n", "Ideas", "x", "end").unwrap_err();
+        assert!(err.contains("# Note"));
+        assert!(err.contains("## Tasks"));
+    }
+
+    #[test]
+    fn test_watch_coalescer_suppresses_self_write_echo() {
+        let mut coalescer = WatchCoalescer::default();
+        let path = PathBuf::from("/notes/todo.md");
+        let now = Instant::now();
+
+        coalescer.note_self_write(path.clone(), now);
+        let needs_flush = coalescer.record("todo".into(), path.clone(), WatchChangeKind::Modified, now);
+        assert!(!needs_flush);
+        assert!(coalescer.drain_ready(now + WATCH_COALESCE_WINDOW).is_empty());
+
+        // Once the echo window passes, external edits are picked up again
+        let later = now + SELF_WRITE_SUPPRESS_WINDOW;
+        assert!(coalescer.record("todo".into(), path, WatchChangeKind::Modified, later));
+        assert_eq!(coalescer.drain_ready(later + WATCH_COALESCE_WINDOW).len(), 1);
+    }
+
+    #[test]
+    fn test_watch_coalescer_merges_events_per_note() {
+        let mut coalescer = WatchCoalescer::default();
+        let path = PathBuf::from("/notes/new.md");
+        let now = Instant::now();
+
+        assert!(coalescer.record("new".into(), path.clone(), WatchChangeKind::Created, now));
+        assert!(!coalescer.record("new".into(), path.clone(), WatchChangeKind::Modified, now));
+        assert!(!coalescer.record("new".into(), path.clone(), WatchChangeKind::Modified, now));
+
+        // Still inside the window: nothing is ready yet
+        assert!(coalescer.drain_ready(now).is_empty());
+
+        let ready = coalescer.drain_ready(now + WATCH_COALESCE_WINDOW);
+        assert_eq!(
+            ready,
+            vec![WatchChange {
+                note_id: "new".into(),
+                path,
+                kind: WatchChangeKind::Created,
+            }]
+        );
+        assert!(coalescer.pending.is_empty());
+    }
+
+    #[test]
+    fn test_notes_changed_batch_groups_ids_by_kind() {
+        let change = |id: &str, kind| WatchChange {
+            note_id: id.to_string(),
+            path: PathBuf::from(format!("/notes/{}.md", id)),
+            kind,
+        };
+        let batch = NotesChangedBatch::from_changes(&[
+            change("b", WatchChangeKind::Modified),
+            change("new", WatchChangeKind::Created),
+            change("a", WatchChangeKind::Modified),
+            change("gone", WatchChangeKind::Deleted),
+        ]);
+        assert_eq!(batch.created, vec!["new"]);
+        assert_eq!(batch.modified, vec!["a", "b"]);
+        assert_eq!(batch.deleted, vec!["gone"]);
+    }
+
+    #[test]
+    fn test_rank_quick_matches() {
+        let m = |kind: &str, id: &str, title: &str| QuickMatch {
+            kind: kind.to_string(),
+            id: id.to_string(),
+            title: title.to_string(),
+            score: 0,
+        };
+        let candidates = vec![
+            m("note", "groceries", "Groceries"),
+            m("note", "roadmap", "Roadmap for the project"),
+            m("folder", "projects", "projects"),
+            m("template", "project-brief", "Project Brief"),
+            m("database", "tasks", "Tasks"),
+        ];
+
+        let ranked = rank_quick_matches("project", candidates.clone(), 10);
+        let ids: Vec<&str> = ranked.iter().map(|r| r.id.as_str()).collect();
+        // Prefix matches first (folder, template), then the note whose title contains it
+        assert_eq!(ids, vec!["projects", "project-brief", "roadmap"]);
+        assert_eq!(ranked[0].kind, "folder");
+
+        // Typos still match through edit distance
+        let ranked = rank_quick_matches("grocries", candidates.clone(), 10);
+        assert_eq!(ranked[0].id, "groceries");
+        assert!(ranked[0].score > 2);
+
+        // Empty query keeps input order, limited
+        let ranked = rank_quick_matches("", candidates, 2);
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].id, "groceries");
+    }
+
+    #[test]
+    fn test_template_body_for_append() {
+        let (rendered, _) = substitute_template_variables(
+            "---\nicon: \"x\"\n---\n# {{title}}\n\n## What Went Well\n\n- {{cursor}}\n",
+            "Sprint 12",
+            None,
+        );
+        assert_eq!(template_body_for_append(&rendered), "## What Went Well\n\n- ");
+
+        // Templates without a title heading are appended whole
+        assert_eq!(template_body_for_append("## Notes\n\ntext\n"), "## Notes\n\ntext");
+    }
+
+    #[test]
+    fn test_templatize_title() {
+        assert_eq!(
+            templatize_title("---\nicon: \"x\"\n---\n# Retro\n\n## Notes\n# Other\n"),
+            "---\nicon: \"x\"\n---\n# {{title}}\n\n## Notes\n# Other\n"
+        );
+        assert_eq!(templatize_title("No heading"), "No heading");
+        assert!(validate_template_id("../escape").is_err());
+        assert!(validate_template_id(" ").is_err());
+        assert!(validate_template_id("weekly-retro").is_ok());
+    }
+
+    #[test]
+    fn test_template_unique_variables() {
+        let (result, _) = substitute_template_variables(
+            "{{uuid}}\n{{uuid}}\n{{random:12}}\n{{random:12}}\n{{random}}",
+            "T",
+            None,
+        );
+        let lines: Vec<&str> = result.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0].len(), 36);
+        assert_eq!(lines[0].chars().nth(14), Some('4'));
+        assert_ne!(lines[0], lines[1]);
+        assert_eq!(lines[2].len(), 12);
+        assert!(lines[2].chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(lines[2], lines[3]);
+        assert_eq!(lines[4].len(), 8);
+    }
+
+    #[test]
+    fn test_random_alphanumeric_is_uniform() {
+        // Expect ~1000 of each character; fixed UUID bits would push some well past 1150
+        let sample = random_alphanumeric(62_000);
+        let mut counts: HashMap<char, usize> = HashMap::new();
+        for c in sample.chars() {
+            *counts.entry(c).or_default() += 1;
+        }
+        assert_eq!(counts.len(), 62);
+        assert!(counts.values().all(|&n| (850..=1150).contains(&n)), "{:?}", counts);
+    }
+
+    #[test]
+    fn test_template_selection_and_cursor() {
+        let (result, cursor) = substitute_template_variables(
+            "# {{title}}\n\n> {{selection}}\n\n{{cursor}}\n",
+            "Quote",
+            Some("line one {{title}}\nline two"),
+        );
+        // Selected text is inserted verbatim, cursor stays on its marker
+        assert_eq!(result, "# Quote\n\n> line one {{title}}\nline two\n\n\n");
+        assert_eq!(cursor, Some(5));
+
+        // Without {{cursor}}, the cursor lands after the selection
+        let (result, cursor) = substitute_template_variables("# T\n{{selection}}\nend", "T", Some("a\nb"));
+        assert_eq!(result, "# T\na\nb\nend");
+        assert_eq!(cursor, Some(2));
+
+        let (result, cursor) = substitute_template_variables("# T\n{{selection}}", "T", None);
+        assert_eq!(result, "# T\n");
+        assert_eq!(cursor, Some(1));
+    }
+
+    #[test]
+    fn test_normalize_view_filters() {
+        let filters = serde_json::json!([
+            { "column": "priority", "operator": "equals", "value": "High" },
+            { "field": "due", "operator": "before", "value": "2026-01-01" },
+            { "column": "notes", "operator": "is_empty" },
+            { "operator": "eq" }
+        ]);
+        assert_eq!(
+            normalize_view_filters(&filters),
+            serde_json::json!([
+                { "field": "priority", "operator": "eq", "value": "High" },
+                { "field": "due", "operator": "lt", "value": "2026-01-01" },
+                { "field": "notes", "operator": "is_empty" }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_prune_ui_state() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-uistate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let folder = dir.to_string_lossy().to_string();
+
+        let mut map = NoteUiStateMap::new();
+        map.insert("kept".to_string(), serde_json::json!({ "open": [1, 3], "scroll": 120 }));
+        map.insert("gone".to_string(), serde_json::json!({ "scroll": 0 }));
+        save_ui_state(&folder, &map).unwrap();
+
+        let existing: HashSet<String> = ["kept".to_string()].into_iter().collect();
+        assert_eq!(prune_ui_state(&folder, &existing).unwrap(), 1);
+        let loaded = load_ui_state(&folder);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded["kept"]["scroll"], 120);
+        assert_eq!(prune_ui_state(&folder, &existing).unwrap(), 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_build_folder_export() {
+        let notes = vec![
+            ("project/a".to_string(), "---\ntags: [x]\n---\n# Alpha\n\nSee [[Beta]] and [[project/b|the other]].\n".to_string()),
+            ("project/b".to_string(), "# Beta\n\nBack to [[alpha]], not [[Missing]] or ![[Alpha]].".to_string()),
+            ("project/c".to_string(), "# Beta\n".to_string()),
+        ];
+        let doc = build_folder_export(&notes, true);
+        assert!(doc.starts_with("# Contents\n\n- [Alpha](#alpha)\n- [Beta](#beta)\n- [Beta](#beta-1)\n"));
+        assert!(doc.contains("# Alpha\n\nSee [Beta](#beta) and [the other](#beta)."));
+        assert!(doc.contains("Back to [alpha](#alpha), not [[Missing]] or ![[Alpha]]."));
+        assert!(!doc.contains("tags:"));
+        assert!(doc.ends_with("# Beta\n"));
+
+        let no_toc = build_folder_export(&notes, false);
+        assert!(no_toc.starts_with("# Alpha\n"));
+    }
+
+    #[test]
+    fn test_extract_order_and_manual_sort() {
+        assert_eq!(extract_order("---\norder: 3\n---\n# A"), Some(3.0));
+        assert_eq!(extract_order("---\norder: \"1.5\"\n---\n# A"), Some(1.5));
+        assert_eq!(extract_order("---\norder: soon\n---\n# A"), None);
+        assert_eq!(extract_order("# A\norder: 3"), None);
+
+        let meta = |title: &str, order: Option<f64>| NoteMetadata {
+            id: title.to_lowercase(),
+            title: title.to_string(),
+            preview: String::new(),
+            modified: 0,
+            icon: None,
+            order,
+        };
+        let mut notes = vec![meta("Zeta", None), meta("Beta", Some(2.0)), meta("alpha", None), meta("Gamma", Some(1.0))];
+        sort_notes_manual(&mut notes);
+        let titles: Vec<&str> = notes.iter().map(|n| n.title.as_str()).collect();
+        assert_eq!(titles, vec!["Gamma", "Beta", "alpha", "Zeta"]);
+    }
+
+    #[test]
+    fn test_set_frontmatter_field() {
+        assert_eq!(set_frontmatter_field("# A\n", "order", Some("3")), "---\norder: 3\n---\n# A\n");
+        assert_eq!(
+            set_frontmatter_field("---\nicon: \"x\"\norder: 1\n---\n# A\n", "order", Some("2")),
+            "---\nicon: \"x\"\norder: 2\n---\n# A\n"
+        );
+        assert_eq!(
+            set_frontmatter_field("---\nicon: \"x\"\n---\n# A", "order", Some("2")),
+            "---\nicon: \"x\"\norder: 2\n---\n# A"
+        );
+        assert_eq!(
+            set_frontmatter_field("---\nicon: \"x\"\norder: 1\n---\n# A\n", "order", None),
+            "---\nicon: \"x\"\n---\n# A\n"
+        );
+        assert_eq!(set_frontmatter_field("---\norder: 1\n---\n# A\n", "order", None), "# A\n");
+        assert_eq!(set_frontmatter_field("# A\n", "order", None), "# A\n");
+    }
+
+    #[test]
+    fn test_check_frontmatter() {
+        assert!(check_frontmatter("# No frontmatter\n", false).is_ok());
+        assert!(check_frontmatter("---\nicon: \"x\"\ntags: [a, b]\n---\n# A\n", false).is_ok());
+
+        let err = check_frontmatter("---\nicon: x\ntags: [a, b\n---\n# A\n", false).unwrap_err();
+        assert!(err.line.is_some_and(|l| l >= 3), "{:?}", err);
+        assert!(!err.message.contains(" at line "));
+
+        let err = check_frontmatter("---\nicon: x\n# A\n", false).unwrap_err();
+        assert_eq!(err.line, Some(1));
+
+        let schema = "---\nname: Tasks\ncolumns:\n  - id: status\n    name: Status\n    type: select\nviews: []\nnext_row_id: 1\n---\n";
+        let err = check_frontmatter(schema, true).unwrap_err();
+        assert!(err.message.contains("must have options"), "{}", err.message);
+        let err = check_frontmatter("---\nname: Tasks\ncolumns: 3\n---\n", true).unwrap_err();
+        assert_eq!(err.line, Some(3));
+    }
+
+    #[test]
+    fn test_expand_snippet() {
+        let expanded = expand_snippet("Due {{date}} for {{title}}\n{{cursor}}");
+        assert!(!expanded.contains("{{date}}"));
+        assert!(!expanded.contains("{{cursor}}"));
+        assert!(expanded.contains("for {{title}}"));
+        assert!(expanded.ends_with('\n'));
+        assert_eq!(expand_snippet(BUILTIN_SNIPPETS[1].1), "\n---\n\n");
+    }
+
+    #[test]
+    fn test_collect_backlinks_by_aliases() {
+        assert_eq!(extract_aliases("---\naliases: [Proj, \"P-1\"]\n---\n# Project"), vec!["Proj", "P-1"]);
+        assert_eq!(extract_aliases("---\naliases: Proj\n---\n"), vec!["Proj"]);
+        assert!(extract_aliases("# Project\naliases: Proj").is_empty());
+
+        let entry = |id: &str, context: &str| BacklinkEntry {
+            note_id: id.to_string(),
+            note_title: id.to_string(),
+            context: context.to_string(),
+        };
+        let mut index = BacklinksIndex::default();
+        index.links.insert("project".to_string(), vec![entry("a", "see [[Project]]"), entry("b", "x")]);
+        index.links.insert("proj".to_string(), vec![entry("a", "see [[Project]]"), entry("c", "[[proj]]")]);
+        index.links.insert("other".to_string(), vec![entry("d", "y")]);
+
+        let names = vec!["Project".to_string(), "Proj".to_string(), "project".to_string()];
+        let ids: Vec<String> = collect_backlinks(&index, &names).into_iter().map(|e| e.note_id).collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_extract_reminders() {
+        let re = regex::Regex::new(REMINDER_PATTERN).unwrap();
+        let content = "# Plans\n- [ ] Renew passport @remind(2025-06-01)\nCall @remind(2025-13-40) back\n- [x] Done already @remind(2025-05-01)\n```\n@remind(2025-01-01)\n```\nStandup @remind(2025-06-02 09:30)\n";
+        let reminders = extract_reminders("plans", "Plans", content, &re);
+        assert_eq!(reminders.len(), 2);
+        assert_eq!(reminders[0].line, 2);
+        assert_eq!(reminders[0].text, "Renew passport");
+        assert_eq!(reminders[1].line, 8);
+        assert_eq!(reminders[1].text, "Standup");
+        assert_eq!(reminders[1].date - reminders[0].date, 24 * 3600 + 9 * 3600 + 30 * 60);
+    }
+
+    #[test]
+    fn test_rollup_daily_note() {
+        let date = chrono::NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        assert_eq!(daily_note_name(DEFAULT_DAILY_NOTE_FORMAT, date), "2025-06-01");
+        assert_eq!(daily_note_name("daily/YYYY/MMM-DD", date), "daily/2025/Jun-01");
+
+        let content = "# Daily Journal\n\n### Gratitude\n\n- Coffee\n\n### Today's Goals\n\n- [ ] Ship rollup\n- [x] Review PR\n- [ ] \n\n### Notes\n\n- [ ] Email Sam\n- [x] Lunch\n";
+        assert_eq!(
+            rollup_daily_note("2025-06-01", content),
+            "## 2025-06-01\n\n### Today's Goals\n\n- [ ] Ship rollup\n- [x] Review PR\n\n### Open Tasks\n\n- [ ] Email Sam\n"
+        );
+        assert_eq!(rollup_daily_note("2025-06-02", "# Daily Journal\n\n### Today's Goals\n\n- [ ] \n"), "");
+    }
+
+    #[test]
+    fn test_link_graph_dot() {
+        let note = |id: &str, title: &str, content: &str| (id.to_string(), title.to_string(), backlink_names(id, title, content));
+        let notes = vec![
+            note("a", "Alpha", "# Alpha"),
+            note("b", "Say \"hi\"", "# Say \"hi\""),
+            note("work/c", "Gamma", "---\naliases: [G]\n---\n# Gamma"),
+        ];
+        let entry = |id: &str| BacklinkEntry {
+            note_id: id.to_string(),
+            note_title: String::new(),
+            context: String::new(),
+        };
+        let mut index = BacklinksIndex::default();
+        index.links.insert("say \"hi\"".to_string(), vec![entry("a"), entry("a")]);
+        index.links.insert("ghost".to_string(), vec![entry("b")]);
+        index.links.insert("g".to_string(), vec![entry("a")]);
+        index.links.insert("c".to_string(), vec![entry("b")]);
+
+        let dot = link_graph_dot(&notes, &index);
+        // Alias and file-stem links resolve to the note instead of a placeholder
+        assert!(dot.contains("    \"a\" -> \"work/c\";\n"));
+        assert!(dot.contains("    \"b\" -> \"work/c\";\n"));
+        assert!(!dot.contains("\"missing:g\""));
+        assert!(dot.starts_with("digraph notes {\n"));
+        assert!(dot.contains("    \"b\" [label=\"Say \\\"hi\\\"\"];\n"));
+        assert!(dot.contains("    \"missing:ghost\" [label=\"ghost\", style=dashed];\n"));
+        assert_eq!(dot.matches("\"a\" -> \"b\";").count(), 1);
+        assert!(dot.contains("    \"b\" -> \"missing:ghost\";\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn test_is_orphan() {
+        let entry = |id: &str| BacklinkEntry {
+            note_id: id.to_string(),
+            note_title: String::new(),
+            context: String::new(),
+        };
+        let mut index = BacklinksIndex::default();
+        index.links.insert("proj".to_string(), vec![entry("other")]);
+        index.links.insert("self".to_string(), vec![entry("notes/self")]);
+
+        let names = backlink_names("work/project", "Project", "---\naliases: [Proj]\n---\n# Project");
+        assert!(!is_orphan(&index, "work/project", &names));
+        let names = backlink_names("notes/self", "Self", "# Self");
+        assert!(is_orphan(&index, "notes/self", &names));
+        let names = backlink_names("lonely", "Lonely", "# Lonely");
+        assert!(is_orphan(&index, "lonely", &names));
+    }
+
+    #[test]
+    fn test_refresh_backlinks_for_note() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-refresh-bl-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let folder = dir.to_string_lossy().to_string();
+        std::fs::write(dir.join("a.md"), "# A\n\nSee [[B]].\n").unwrap();
+
+        let mut index = BacklinksIndex::default();
+        index.links.insert("c".to_string(), vec![BacklinkEntry {
+            note_id: "a".to_string(),
+            note_title: "A".to_string(),
+            context: "stale".to_string(),
+        }]);
+
+        assert!(refresh_backlinks_for_note(&mut index, &folder, "a"));
+        assert!(!index.links.contains_key("c"));
+        assert_eq!(index.links["b"][0].note_id, "a");
+
+        std::fs::remove_file(dir.join("a.md")).unwrap();
+        assert!(!refresh_backlinks_for_note(&mut index, &folder, "a"));
+        assert!(index.links.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_external_edit_updates_backlinks() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-watch-bl-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let folder = dir.to_string_lossy().to_string();
+        let path = dir.join("ideas.md");
+        std::fs::write(&path, "# Ideas\n\nLinks to [[Roadmap]].\n").unwrap();
+        let mut index = rebuild_backlinks_index_from_folder(&folder);
+        assert_eq!(index.links["roadmap"].len(), 1);
+
+        let mut coalescer = WatchCoalescer::default();
+        let now = Instant::now();
+
+        // Scratch's own save is suppressed, so backlinks are left to the save path
+        coalescer.note_self_write(path.clone(), now);
+        coalescer.record("ideas".into(), path.clone(), WatchChangeKind::Modified, now);
+        let changes = coalescer.drain_ready(now + WATCH_COALESCE_WINDOW);
+        assert!(changes.is_empty());
+
+        // An external editor rewrites the note with a different link
+        std::fs::write(&path, "# Ideas\n\nNow see [[Backlog]].\n").unwrap();
+        let later = now + SELF_WRITE_SUPPRESS_WINDOW;
+        coalescer.record("ideas".into(), path.clone(), WatchChangeKind::Modified, later);
+        let changes = coalescer.drain_ready(later + WATCH_COALESCE_WINDOW);
+        apply_watch_changes_to_backlinks(&mut index, &folder, &changes);
+        assert!(!index.links.contains_key("roadmap"));
+        assert_eq!(index.links["backlog"][0].note_id, "ideas");
+
+        // And then deletes it
+        std::fs::remove_file(&path).unwrap();
+        let latest = later + SELF_WRITE_SUPPRESS_WINDOW;
+        coalescer.record("ideas".into(), path, WatchChangeKind::Deleted, latest);
+        let changes = coalescer.drain_ready(latest + WATCH_COALESCE_WINDOW);
+        apply_watch_changes_to_backlinks(&mut index, &folder, &changes);
+        assert!(index.links.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_generate_preview_with_length() {
+        let content = "# Title\n\nこんにちは世界、これは長いプレビューです 🎉🎉🎉\n";
+        assert_eq!(generate_preview_with_length(content, 5), "こんにちは");
+        assert!(generate_preview_with_length(content, 1000).ends_with("です 🎉🎉🎉"));
+        let long = format!("# T\n\n{}", "a".repeat(300));
+        assert_eq!(generate_preview(&long).len(), DEFAULT_PREVIEW_LENGTH);
+        assert_eq!(generate_preview_with_length(&long, 250).len(), 250);
+    }
+
+    #[test]
+    fn test_truncate_chars_multibyte() {
+        assert_eq!(truncate_chars("héllo", 2), "hé");
+        assert_eq!(truncate_chars("🎉🎉", 5), "🎉🎉");
+        assert_eq!(truncate_chars("", 3), "");
+
+        // Byte 120 falls inside a multi-byte character for all of these
+        for unit in ["é", "日本語", "🎉", "a🎉"] {
+            let line = format!("[[Target]] {}", unit.repeat(200));
+            let context = extract_wikilink_context(&line, 0);
+            assert!(context.ends_with("..."));
+            assert_eq!(context.trim_end_matches("...").chars().count(), 120);
+        }
+        let short = extract_wikilink_context("See [[Target]] 日本語", 4);
+        assert!(!short.ends_with("..."));
+    }
+
+    #[test]
+    fn test_index_diagnostics_divergence() {
+        let healthy = IndexDiagnostics::new(10, 2, 4096, 10);
+        assert!(healthy.in_sync);
+        assert_eq!(healthy.divergence, None);
+
+        let stale = IndexDiagnostics::new(12, 3, 4096, 10);
+        assert!(!stale.in_sync);
+        assert_eq!(stale.divergence, Some(2));
+        assert_eq!(IndexDiagnostics::new(7, 1, 0, 10).divergence, Some(-3));
+
+        let dir = std::env::temp_dir().join(format!("scratch-test-dirsize-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("a.bin"), [0u8; 10]).unwrap();
+        std::fs::write(dir.join("nested").join("b.bin"), [0u8; 5]).unwrap();
+        assert_eq!(dir_size(&dir), 15);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_note_preview_html() {
+        let content = "---\nicon: \"x\"\n---\n# Title\n\n## Section\n\nSome text here.\n\n```\nlet a = 1 < 2;\nmore\n```\n";
+        let html = note_preview_html(content, 1000);
+        assert!(!html.contains("<h1>"));
+        assert!(!html.contains("icon"));
+        assert!(!html.contains("<html"));
+        assert!(html.starts_with("<h2>Section</h2>\n"));
+        assert!(html.contains("1 &lt; 2"));
+
+        // A cut inside the code block still closes it
+        let cut = note_preview_html(content, 40);
+        assert!(!cut.contains("more"));
+        assert!(cut.ends_with("</code></pre>\n"));
+    }
+
+    #[test]
+    fn test_count_distinct_sources() {
+        let entry = |id: &str, context: &str| BacklinkEntry {
+            note_id: id.to_string(),
+            note_title: id.to_string(),
+            context: context.to_string(),
+        };
+        let entries = vec![entry("a", "one"), entry("a", "two"), entry("b", "one")];
+        assert_eq!(count_distinct_sources(&entries), 2);
+        assert_eq!(count_distinct_sources(&[]), 0);
+    }
+
+    #[test]
+    fn test_snapshot_before_pull_ignores_debounce() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-pull-snapshot-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let folder = dir.to_string_lossy().to_string();
+        let history = get_note_history_dir(&folder, "a");
+
+        maybe_snapshot_note(&folder, "a", "# A\nsaved");
+        snapshot_before_pull(&folder, "a", "# A\nlocal edit");
+        assert_eq!(std::fs::read_dir(&history).unwrap().count(), 2);
+        assert_eq!(latest_snapshot(&folder, "a").unwrap(), "# A\nlocal edit");
+
+        // Unchanged content isn't snapshotted twice
+        snapshot_before_pull(&folder, "a", "# A\nlocal edit");
+        assert_eq!(std::fs::read_dir(&history).unwrap().count(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_settings_export_import_keeps_mcp_token_local() {
+        let current = Settings {
+            mcp_token: Some("secret".to_string()),
+            mcp_port: Some(4000),
+            preview_length: Some(80),
+            ..Default::default()
+        };
+        let exported = serde_json::to_string_pretty(&settings_for_export(&current)).unwrap();
+        assert!(!exported.contains("secret"));
+
+        let imported: Settings = serde_json::from_str(&exported).unwrap();
+        let merged = merge_imported_settings(imported, &current);
+        assert_eq!(merged.mcp_token.as_deref(), Some("secret"));
+        assert_eq!(merged.preview_length, Some(80));
+        assert!(!mcp_settings_changed(&current, &merged));
+
+        let other = Settings { mcp_port: Some(5000), ..Default::default() };
+        let merged = merge_imported_settings(other, &current);
+        assert_eq!(merged.mcp_token.as_deref(), Some("secret"));
+        assert!(mcp_settings_changed(&current, &merged));
+    }
+
+    #[test]
+    fn test_pull_changed_note_ids() {
+        let changed = vec![
+            "a.md".to_string(),
+            "sub/b.md".to_string(),
+            "image.png".to_string(),
+            ".scratch/history/a/x.md".to_string(),
+        ];
+        assert_eq!(pull_changed_note_ids(&changed), vec!["a", "sub/b"]);
+    }
+
+    #[test]
+    fn test_note_status_map() {
+        let out = "R  new.md\0old.md\0 M sub/a.md\0?? b.md\0?? img.png\0 M .scratch/settings.json\0";
+        let entries = git::parse_porcelain_z(out);
+        assert_eq!(entries.len(), 5);
+        let map = note_status_map(entries);
+        assert_eq!(map.len(), 3);
+        assert_eq!(map["new"], "R ");
+        assert_eq!(map["sub/a"], " M");
+        assert_eq!(map["b"], "??");
+    }
+
+    #[test]
+    fn test_note_ids_to_repo_paths() {
+        let paths = note_ids_to_repo_paths("/notes", &["a".to_string(), "sub/b".to_string()]).unwrap();
+        assert_eq!(paths, vec!["a.md", "sub/b.md"]);
+        let err = note_ids_to_repo_paths("/notes", &["a".to_string(), "../x".to_string()]).unwrap_err();
+        assert!(err.contains("../x"));
+    }
+
+    #[test]
+    fn test_parse_checkout_conflicts() {
+        let stderr = "error: Your local changes to the following files would be overwritten by checkout:\n\ta.md\n\tsub/b.md\nPlease commit your changes or stash them before you switch branches.\nAborting\n";
+        assert_eq!(git::parse_checkout_conflicts(stderr), vec!["a.md", "sub/b.md"]);
+        assert!(git::parse_checkout_conflicts("error: pathspec 'x' did not match").is_empty());
+    }
+
+    #[test]
+    fn test_ensure_gitignore_appends_missing() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-gitignore-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(".gitignore"), "node_modules/\n.DS_Store").unwrap();
+        git::ensure_gitignore(&dir).unwrap();
+        git::ensure_gitignore(&dir).unwrap();
+
+        let content = std::fs::read_to_string(dir.join(".gitignore")).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(content.starts_with("node_modules/\n.DS_Store\n"));
+        assert_eq!(content.matches(".DS_Store").count(), 1);
+        assert_eq!(content.matches(".scratch/search_index/").count(), 1);
+        assert!(git::missing_gitignore_entries(&content).is_empty());
+    }
+
+    #[test]
+    fn test_parse_code_blocks() {
+        let content = "# Note\n\n```rust title\nfn main() {}\n```\n\n    indented code\n\n~~~~\n```\nnot a close\n~~~~\n```py\nprint(1)";
+        let blocks = parse_code_blocks(content);
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0], CodeBlock { language: "rust".into(), content: "fn main() {}".into(), start_line: 3 });
+        assert_eq!(blocks[1].language, "");
+        assert_eq!(blocks[1].content, "```\nnot a close");
+        assert_eq!(blocks[2].content, "print(1)");
+        assert_eq!(code_block_extension("Rust"), "rs");
+        assert_eq!(code_block_extension(""), "txt");
+    }
+
+    #[test]
+    fn test_resolve_transclusions() {
+        let notes: HashMap<&str, (&str, &str)> = HashMap::from([
+            ("part", ("part", "---\ntags: [x]\n---\nPart body ![[Loop]]\n")),
+            ("loop", ("loop", "Loop body ![[Root]] ![[Part]]")),
+            ("root", ("root", "unused")),
+        ]);
+        let lookup = |name: &str| {
+            notes
+                .get(name.to_lowercase().as_str())
+                .map(|(id, content)| (id.to_string(), content.to_string()))
+        };
+
+        let out = resolve_transclusions("Start ![[Part|alias]] and ![[missing.png]]", "root", &lookup);
+        assert_eq!(out, "Start Part body Loop body ![[Root]] ![[Part]] and ![[missing.png]]");
+    }
+
+    #[test]
+    fn test_compute_aggregates() {
+        let schema = database::parse_schema(
+            "---\nname: Points\ncolumns:\n  - id: points\n    name: Points\n    type: number\n  - id: owner\n    name: Owner\n    type: text\n---\n",
+        )
+        .unwrap();
+        let row = |points: serde_json::Value, owner: &str| database::DatabaseRow {
+            id: String::new(),
+            fields: HashMap::from([("points".to_string(), points), ("owner".to_string(), serde_json::json!(owner))]),
+            body: String::new(),
+            path: String::new(),
+            modified: 0,
+            created_at: None,
+            updated_at: None,
+            position: None,
+        };
+        let rows = vec![row(serde_json::json!(2), "ann"), row(serde_json::json!(4.5), ""), row(serde_json::json!("n/a"), "bo")];
+        let specs = serde_json::json!([
+            { "column": "points", "fn": "sum" },
+            { "column": "points", "fn": "avg" },
+            { "column": "points", "fn": "max" },
+            { "column": "owner", "fn": "count" },
+            { "column": "owner", "fn": "min" },
+        ]);
+        let out = compute_aggregates(&schema, &rows, &specs).unwrap();
+        let values: Vec<&serde_json::Value> = out.as_array().unwrap().iter().map(|a| &a["value"]).collect();
+        assert_eq!(values, vec![&serde_json::json!(6.5), &serde_json::json!(3.25), &serde_json::json!(4.5), &serde_json::json!(2), &serde_json::Value::Null]);
+
+        assert!(compute_aggregates(&schema, &rows, &serde_json::json!([{ "column": "points", "fn": "median" }])).is_err());
+        assert!(compute_aggregates(&schema, &rows, &serde_json::json!([{ "column": "nope", "fn": "sum" }])).is_err());
+    }
+
+    #[test]
+    fn test_filter_db_rows() {
+        let row = |id: &str, status: &str| database::DatabaseRow {
+            id: id.to_string(),
+            fields: HashMap::from([("status".to_string(), serde_json::json!(status))]),
+            body: String::new(),
+            path: String::new(),
+            modified: 0,
+            created_at: None,
+            updated_at: None,
+            position: None,
+        };
+        let mut rows = vec![row("row-001", "Todo"), row("row-002", "Done"), row("row-003", "Todo")];
+        filter_db_rows(&mut rows, &serde_json::json!([{ "field": "status", "operator": "eq", "value": "Todo" }])).unwrap();
+        let ids: Vec<&str> = rows.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["row-001", "row-003"]);
+        assert!(filter_db_rows(&mut rows, &serde_json::json!([{ "operator": "eq" }])).is_err());
+    }
+
+    #[test]
+    fn test_is_match_all_filter() {
+        assert!(is_match_all_filter(&serde_json::json!([])));
+        assert!(is_match_all_filter(&serde_json::Value::Null));
+        assert!(!is_match_all_filter(&serde_json::json!([{ "field": "done", "operator": "eq", "value": true }])));
+    }
+
+    #[test]
+    fn test_find_broken_links() {
+        let titles = vec!["Project Plan".to_string(), "Ideas".to_string()];
+        let known: HashSet<String> = ["project plan", "ideas", "notes/ideas"].iter().map(|s| s.to_string()).collect();
+        let content = "# Source\n[[Project Pan]] and [[ideas#Later|later]]\n```\n[[In Code]]\n```\n![[photo.png]] [[Zzz]]";
+
+        let fixes = find_broken_links("src", content, &known, &titles);
+        assert_eq!(fixes.len(), 2);
+        assert_eq!(fixes[0], LinkFix {
+            source_id: "src".to_string(),
+            line: 2,
+            broken_target: "Project Pan".to_string(),
+            suggestion: Some("Project Plan".to_string()),
+        });
+        assert_eq!((fixes[1].line, fixes[1].broken_target.as_str()), (6, "Zzz"));
+        assert_eq!(fixes[1].suggestion, None);
+    }
+
+    #[test]
+    fn test_rewrite_link_on_line() {
+        let content = "# Note\r\nSee [[Project Pan]] and [[Project Pan#Goals|the goals]].\r\n[[Project Pan]]\r\n";
+        let out = rewrite_link_on_line(content, 2, "Project Pan", "Project Plan").unwrap();
+        assert_eq!(
+            out,
+            "# Note\r\nSee [[Project Plan]] and [[Project Plan#Goals|the goals]].\r\n[[Project Pan]]\r\n"
+        );
+        assert!(rewrite_link_on_line(content, 1, "Project Pan", "Project Plan").is_err());
+        assert!(rewrite_link_on_line(content, 9, "Project Pan", "Project Plan").is_err());
+        assert!(rewrite_link_on_line(content, 2, "Project Pan", "a|b").is_err());
+    }
+
+    #[test]
+    fn test_existing_note_path() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-note-path-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub/a.md"), "# A").unwrap();
+        let folder = dir.to_string_lossy().to_string();
+
+        assert_eq!(existing_note_path(&folder, "sub/a").unwrap(), dir.join("sub/a.md").to_string_lossy());
+        assert!(existing_note_path(&folder, "missing").is_err());
+        assert!(existing_note_path(&folder, "../escape").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_apply_theme_preset_keeps_mode() {
+        let mut theme = ThemeSettings {
+            mode: "light".to_string(),
+            custom_light_colors: None,
+            custom_dark_colors: None,
+        };
+        apply_theme_preset_to(&mut theme, "nord", "dark").unwrap();
+        assert_eq!(theme.mode, "light");
+        assert!(theme.custom_light_colors.is_none());
+        assert_eq!(theme.custom_dark_colors.as_ref().unwrap().bg.as_deref(), Some("#2e3440"));
+
+        assert!(apply_theme_preset_to(&mut theme, "Nord", "system").is_err());
+        assert!(apply_theme_preset_to(&mut theme, "Missing", "light").is_err());
+    }
+
+    #[test]
+    fn test_unavailable_font_warning() {
+        let available = vec!["Helvetica Neue".to_string(), "Menlo".to_string()];
+        assert!(unavailable_font_warning("serif", &available).is_none());
+        assert!(unavailable_font_warning("menlo", &available).is_none());
+        assert!(unavailable_font_warning("  ", &available).is_none());
+        assert!(unavailable_font_warning("Comic Sans MS", &available)
+            .unwrap()
+            .contains("Comic Sans MS"));
+    }
+
+    #[test]
+    fn test_activity_log_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-activity-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let folder = dir.to_string_lossy().to_string();
+        assert!(read_activity_log(&folder, 10, None).unwrap().is_empty());
+
+        log_activity(&folder, "create", "a", serde_json::Value::Null);
+        log_activity(&folder, "move", "sub/a", serde_json::json!({ "from": "a" }));
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(activity_log_path(&folder))
+            .and_then(|mut f| std::io::Write::write_all(&mut f, b"not json\n"))
+            .unwrap();
+
+        let events = read_activity_log(&folder, 10, None).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].action, "move");
+        assert_eq!(events[0].details["from"], "a");
+        assert_eq!(read_activity_log(&folder, 1, None).unwrap().len(), 1);
+        assert!(read_activity_log(&folder, 10, Some("2999-01-01T00:00:00Z")).unwrap().is_empty());
+        assert!(read_activity_log(&folder, 10, Some("yesterday")).is_err());
+
+        // Only the most recent entries are kept
+        for i in 0..MAX_ACTIVITY_LOG_ENTRIES + 5 {
+            log_activity(&folder, "save", &format!("note-{}", i), serde_json::Value::Null);
+        }
+        let events = read_activity_log(&folder, usize::MAX, None).unwrap();
+        assert_eq!(events.len(), MAX_ACTIVITY_LOG_ENTRIES);
+        assert_eq!(events[0].note_id, format!("note-{}", MAX_ACTIVITY_LOG_ENTRIES + 4));
+        assert_eq!(events.last().unwrap().note_id, "note-5");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_undo_target_and_previous_version() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-undo-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.md"), "# A\nv3").unwrap();
+        std::fs::write(dir.join("b.md"), "# B").unwrap();
+        let folder = dir.to_string_lossy().to_string();
+
+        let history = get_note_history_dir(&folder, "a");
+        std::fs::create_dir_all(&history).unwrap();
+        std::fs::write(history.join("2026-01-01T00-00-00Z.md"), "# A\nv1").unwrap();
+        std::fs::write(history.join("2026-01-02T00-00-00Z.md"), "# A\nv2").unwrap();
+        std::fs::write(history.join("2026-01-03T00-00-00Z.md"), "# A\nv3").unwrap();
+
+        log_activity(&folder, "save", "a", serde_json::Value::Null);
+        log_activity(&folder, "trash", "b", serde_json::Value::Null);
+        let (id, history_ids) = undo_target(&folder).unwrap().unwrap();
+        assert_eq!(id, "a");
+
+        // Snapshots identical to the current content are skipped
+        let (version, content) = previous_version(&folder, &history_ids, "# A\nv3").unwrap();
+        assert_eq!(version, "2026-01-02T00-00-00Z");
+        assert_eq!(content, "# A\nv2");
+        assert!(previous_version(&folder, &["b".to_string()], "# B").is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_undo_last_change_updates_search() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-undo-search-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let folder = dir.to_string_lossy().to_string();
+        std::fs::write(dir.join("plan.md"), "# Plan\nyak shaving").unwrap();
+        let history = get_note_history_dir(&folder, "plan");
+        std::fs::create_dir_all(&history).unwrap();
+        std::fs::write(history.join("2026-01-01T00-00-00Z.md"), "# Plan\nzebra goals").unwrap();
+        log_activity(&folder, "save", "plan", serde_json::Value::Null);
+
+        let state = AppState::default();
+        state.app_config.write().unwrap().notes_folder = Some(folder.clone());
+        let search_index = SearchIndex::new(&dir.join(".scratch").join("index")).unwrap();
+        search_index.rebuild_index(&dir).unwrap();
+        *state.search_index.lock().unwrap() = Some(search_index);
+
+        let result = tauri::async_runtime::block_on(undo_last_change_impl(&state)).unwrap();
+        assert_eq!(result.note.unwrap().content, "# Plan\nzebra goals");
+
+        let index = state.search_index.lock().unwrap();
+        let index = index.as_ref().unwrap();
+        index.reader.reload().unwrap();
+        let ids: Vec<String> = index.search("zebra", 10, 100).unwrap().into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec!["plan".to_string()]);
+        assert!(index.search("yak", 10, 100).unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let diff = diff_lines("# A\nkeep\nold\n", "# A\nkeep\nnew\nmore\n");
+        let kinds: Vec<&str> = diff.iter().map(|d| d.kind.as_str()).collect();
+        assert_eq!(kinds, vec!["equal", "equal", "delete", "insert", "insert"]);
+        assert_eq!(diff[2].content, "old");
+        assert_eq!((diff[2].old_line, diff[2].new_line), (Some(3), None));
+        assert_eq!((diff[4].old_line, diff[4].new_line), (None, Some(4)));
+        assert!(diff_lines("same", "same").iter().all(|d| d.kind == "equal"));
+    }
+
+    #[test]
+    fn test_upsert_saved_search_and_folder_scope() {
+        let search = |name: &str, query: &str| SavedSearch {
+            name: name.to_string(),
+            query: query.to_string(),
+            folder: None,
+        };
+        let mut searches = vec![search("todo", "TODO"), search("bugs", "bug")];
+        upsert_saved_search(&mut searches, search("todo", "TODO OR FIXME"));
+        upsert_saved_search(&mut searches, search("ideas", "idea"));
+        let names: Vec<&str> = searches.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["todo", "bugs", "ideas"]);
+        assert_eq!(searches[0].query, "TODO OR FIXME");
+
+        assert!(in_search_folder("work/plan", Some("work")));
+        assert!(!in_search_folder("workshop/plan", Some("work")));
+        assert!(!in_search_folder("work", Some("work")));
+        assert!(in_search_folder("anything", None));
+    }
+
+    #[test]
+    fn test_regex_replace_capture_groups() {
+        let content = "2024-01-05 and 2025-12-31";
+        let (out, count) = regex_replace_all(content, r"(\d{4})-(\d{2})-(\d{2})", "$3/$2/$1", true, false).unwrap();
+        assert_eq!(out, "05/01/2024 and 31/12/2025");
+        assert_eq!(count, 2);
+
+        let (out, _) = regex_replace_all(content, r"(?P<year>\d{4})-\d{2}", "${year}y", true, false).unwrap();
+        assert_eq!(out, "2024y-05 and 2025y-31");
+
+        // Out-of-range and unknown groups are rejected instead of silently expanding to ""
+        assert!(regex_replace_all(content, r"(\d{4})", "$2", true, false).is_err());
+        assert!(regex_replace_all(content, r"(\d{4})", "$1x", true, false).is_err());
+        assert!(regex_replace_all(content, r"(\d{4})", "$$1 ${1}x", true, false).is_ok());
+
+        let (out, _) = regex_replace_all("price: X", "X", "$5.00", true, true).unwrap();
+        assert_eq!(out, "price: $5.00");
+    }
+
+    #[test]
+    fn test_whole_word_matching() {
+        let re = whole_word_regex("cat", false).unwrap();
+        assert!(re.find("category and concat").is_none());
+        assert_eq!(re.find("The Cat sat").map(|m| m.start()), Some(4));
+        assert!(whole_word_regex("#tag", true).unwrap().is_match("see #tag here"));
+
+        assert!(fuzzy_match_line("cat", "category", 1, false, true).is_none());
+        assert!(fuzzy_match_line("cat", "category", 0, false, false).is_some());
+        assert_eq!(fuzzy_match_line("cat", "a cot here", 1, false, true), Some((1, 2, 5)));
+    }
+
+    #[test]
+    fn test_match_offsets_with_multibyte_text() {
+        let line = "私は東京に住んでいます";
+        let (dist, start, end) = fuzzy_match_line("東京", line, 0, false, false).unwrap();
+        assert_eq!(dist, 0);
+        assert_eq!(&line[start..end], "東京");
+        assert_eq!(char_columns(line, start, end), (2, 4));
+
+        // 'İ' lowercases to two chars; offsets must still index the original line
+        let line = "İstanbul café";
+        let (_, start, end) = fuzzy_match_line("CAFÉ", line, 0, false, false).unwrap();
+        assert_eq!(&line[start..end], "café");
+        assert_eq!(char_columns(line, start, end), (9, 13));
+        assert_eq!(char_columns("abc", 1, 2), (1, 2));
+    }
+
+    #[test]
+    fn test_replace_in_content_modes() {
+        let content = "Cat and cat and CAT";
+        assert_eq!(replace_in_content(content, "cat", "dog", "all", true, false).unwrap(), ("Cat and dog and CAT".to_string(), 1));
+        assert_eq!(replace_in_content(content, "cat", "dog", "all", false, false).unwrap().1, 3);
+        assert_eq!(replace_in_content(content, "cat", "dog", "first", false, false).unwrap().0, "dog and cat and CAT");
+        assert_eq!(replace_in_content(content, "cat", "$0", "all", false, false).unwrap().0, "$0 and $0 and $0");
+        assert!(replace_in_content(content, "cat", "dog", "bogus", true, false).is_err());
+    }
+
+    #[test]
+    fn test_format_markdown_tables() {
+        let input = "Intro\n|Name|Qty|Note|\n|:-|:-:|--:|\n|apple|3|fresh|\n| kiwi | 12 |\n\n```\n|a|b|\n|-|-|\n```\n";
+        let expected = "Intro\n\
+| Name  | Qty |  Note |\n\
+| :---- | :-: | ----: |\n\
+| apple |  3  | fresh |\n\
+| kiwi  | 12  |       |\n\
+\n```\n|a|b|\n|-|-|\n```\n";
+        assert_eq!(format_markdown_tables(input), expected);
+        assert_eq!(format_markdown_tables(expected), expected);
+
+        // Escaped pipes stay inside their cell; non-tables are untouched
+        assert_eq!(split_table_row(r"| a \| b | c |"), vec![r"a \| b", "c"]);
+        assert_eq!(format_markdown_tables("a | b\nnot a separator\n"), "a | b\nnot a separator\n");
+        // A setext heading or rule under a line with a pipe isn't a table
+        assert_eq!(format_markdown_tables("a | b\n---\n"), "a | b\n---\n");
+        assert_eq!(format_markdown_tables("a | b | c\n|-|-|\n"), "a | b | c\n|-|-|\n");
+    }
+
+    #[test]
+    fn test_number_headings() {
+        let input = "---\n# yaml comment\n---\n# Title\n## Intro\n### Scope\n### Goals\n```\n## not a heading\n```\n## 2024 Plans\n#### Deep\n";
+        let numbered = number_headings_in(input, true);
+        assert_eq!(
+            numbered,
+            "---\n# yaml comment\n---\n# Title\n## 1. Intro\n### 1.1 Scope\n### 1.2 Goals\n```\n## not a heading\n```\n## 2. 2024 Plans\n#### 2.1 Deep\n"
+        );
+        // Re-numbering is stable and disabling strips only the numbers
+        assert_eq!(number_headings_in(&numbered, true), numbered);
+        assert_eq!(number_headings_in(&numbered, false), input);
+
+        // Skipped levels: a later shallower heading continues the count instead of repeating it
+        let skipped = "## A\n#### Deep\n### Mid\n### Next\n## B\n";
+        assert_eq!(
+            number_headings_in(skipped, true),
+            "## 1. A\n#### 1.1 Deep\n### 1.2 Mid\n### 1.3 Next\n## 2. B\n"
+        );
+        assert_eq!(number_headings_in("### Early\n## Top\n### Sub\n", true), "### 1. Early\n## 2. Top\n### 2.1 Sub\n");
+    }
+
+    #[test]
+    fn test_extract_links_from() {
+        let content = "See [docs](https://docs.rs/regex \"Regex\") and https://example.com/a.\n```\nhttps://ignored.dev\n```\n<https://auto.link> [local](other.md)\n";
+        let links = extract_links_from("n", content);
+        let found: Vec<(usize, &str, &str)> =
+            links.iter().map(|l| (l.line, l.url.as_str(), l.text.as_str())).collect();
+        assert_eq!(
+            found,
+            vec![
+                (1, "https://docs.rs/regex", "docs"),
+                (1, "https://example.com/a", "https://example.com/a"),
+                (5, "https://auto.link", "https://auto.link"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_backup_vault_includes_assets_and_prunes() {
+        let root = std::env::temp_dir().join(format!("scratch-backup-test-{}", std::process::id()));
+        let vault = root.join("vault");
+        let backups = root.join("backups");
+        std::fs::create_dir_all(vault.join("sub")).unwrap();
+        std::fs::create_dir_all(vault.join("assets")).unwrap();
+        std::fs::create_dir_all(&backups).unwrap();
+        std::fs::write(vault.join("a.md"), "# A").unwrap();
+        std::fs::write(vault.join("sub/b.md"), "# B").unwrap();
+        std::fs::write(vault.join("assets/pic.png"), [0u8, 159, 146, 150]).unwrap();
+        let old = backups.join("scratch-backup-2000-01-01-000000.zip");
+        std::fs::write(&old, "old").unwrap();
+        std::fs::write(backups.join("keep.zip"), "other").unwrap();
+
+        let now = chrono::Local::now();
+        let kept = backup_vault_to(&vault, &backups, now, None).unwrap();
+        assert!(old.exists());
+        let later = now + chrono::Duration::days(30);
+        let dest = backup_vault_to(&vault, &backups, later, Some(7)).unwrap();
+        assert!(dest.file_name().unwrap().to_string_lossy().starts_with("scratch-backup-"));
+        assert!(!old.exists());
+        assert!(!kept.exists());
+        assert!(backups.join("keep.zip").exists());
+        assert!(backup_vault_to(&vault, &backups, later, Some(u64::MAX)).is_ok());
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&dest).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(String::from).collect();
+        names.sort();
+        assert_eq!(names, vec!["a.md", "assets/pic.png", "sub/b.md"]);
+        let mut bytes = Vec::new();
+        std::io::Read::read_to_end(&mut archive.by_name("assets/pic.png").unwrap(), &mut bytes).unwrap();
+        assert_eq!(bytes, vec![0u8, 159, 146, 150]);
+
+        // An unreadable note fails the backup instead of leaving an empty entry
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("missing.md"), vault.join("broken.md")).unwrap();
+            let fresh = root.join("fresh");
+            assert!(backup_vault_to(&vault, &fresh, later, None).is_err());
+            assert_eq!(std::fs::read_dir(&fresh).unwrap().count(), 0);
+        }
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_import_folder_into() {
+        let root = std::env::temp_dir().join(format!("scratch-import-folder-{}", std::process::id()));
+        let source = root.join("source");
+        let vault = root.join("vault");
+        std::fs::create_dir_all(source.join("sub")).unwrap();
+        std::fs::create_dir_all(source.join(".hidden")).unwrap();
+        std::fs::create_dir_all(&vault).unwrap();
+        std::fs::write(source.join("a.md"), "# A").unwrap();
+        std::fs::write(source.join("sub/a.txt"), "plain").unwrap();
+        std::fs::write(source.join("sub/page.html"), "<h1>Page</h1>").unwrap();
+        std::fs::write(source.join("sub/image.png"), "png").unwrap();
+        std::fs::write(source.join(".hidden/secret.md"), "x").unwrap();
+
+        assert_eq!(import_folder_into(&source, &vault, true, None).unwrap().imported, 3);
+        assert!(vault.join("a.md").exists());
+        assert!(vault.join("sub/a.md").exists());
+        assert!(vault.join("sub/page.md").exists());
+        assert!(!vault.join(".hidden").exists());
+
+        // Flat import keeps both a.md and sub/a.txt by suffixing the second
+        std::fs::remove_dir_all(&vault).unwrap();
+        std::fs::create_dir_all(&vault).unwrap();
+        assert_eq!(import_folder_into(&source, &vault, false, None).unwrap().imported, 3);
+        assert!(vault.join("a.md").exists());
+        assert!(vault.join("a (1).md").exists());
+
+        assert!(import_folder_into(&root, &vault, true, None).is_err());
+
+        // Non-UTF-8 text is decoded lossily instead of failing the batch
+        std::fs::write(source.join("latin1.txt"), b"caf\xe9").unwrap();
+        let latin = root.join("latin");
+        std::fs::create_dir_all(&latin).unwrap();
+        assert_eq!(import_folder_into(&source, &latin, false, None).unwrap().imported, 4);
+        assert_eq!(std::fs::read_to_string(latin.join("latin1.md")).unwrap(), "caf\u{FFFD}");
+        std::fs::remove_file(source.join("latin1.txt")).unwrap();
+
+        // Re-importing with dedupe skips everything already in the vault
+        let report = import_folder_into(&source, &vault, false, Some("exact")).unwrap();
+        assert_eq!(report, ImportReport { imported: 0, skipped: 3 });
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_import_dedupe_modes() {
+        let base = "# Meeting notes\n\nDiscussed the roadmap for the next quarter and assigned owners.\n";
+        let mut exact = ImportDedupe { similar: false, hashes: HashSet::new(), prefixes: Vec::new() };
+        exact.insert(base);
+        assert!(exact.is_duplicate(&format!("{}\r\n\n", base.replace('\n', "\r\n"))));
+        let tweaked = base.replace("owners", "owner");
+        assert!(!exact.is_duplicate(&tweaked));
+
+        let mut similar = ImportDedupe { similar: true, hashes: HashSet::new(), prefixes: Vec::new() };
+        similar.insert(base);
+        assert!(similar.is_duplicate(&tweaked));
+        assert!(!similar.is_duplicate("# Shopping\n\nEggs, milk, bread and a very different list of things.\n"));
+    }
+
+    #[test]
+    fn test_to_lf_line_endings() {
+        assert_eq!(to_lf_line_endings("a\r\nb\rc\n"), "a\nb\nc\n");
+        assert_eq!(to_lf_line_endings("no newline"), "no newline");
+        assert_eq!(to_lf_line_endings("end\r\n"), "end\n");
+    }
+
+    #[test]
+    fn test_set_note_tags() {
+        let block = "---\ntitle: A\ntags:\n  - work\n  - \"read later\"\nicon: x\n---\n# A\n";
+        let tags = extract_tags(block);
+        assert_eq!(tags, vec!["work", "read later"]);
+        assert!(same_tag("#Work", "work"));
+
+        let mut tags = tags;
+        tags.push("q3/plan".to_string());
+        assert_eq!(
+            set_note_tags(block, &tags),
+            "---\ntitle: A\ntags: [work, \"read later\", q3/plan]\nicon: x\n---\n# A\n"
+        );
+        assert_eq!(set_note_tags(block, &[]), "---\ntitle: A\nicon: x\n---\n# A\n");
+        assert_eq!(set_note_tags("# B\n", &["x".to_string()]), "---\ntags: [x]\n---\n# B\n");
+
+        // Non-string scalars are read as tags and written back quoted
+        let numeric = "---\ntags: [2024, work, true]\n---\n# C\n";
+        let mut tags = extract_tags(numeric);
+        assert_eq!(tags, vec!["2024", "work", "true"]);
+        tags.push("null".to_string());
+        let updated = set_note_tags(numeric, &tags);
+        assert_eq!(updated, "---\ntags: [\"2024\", work, \"true\", \"null\"]\n---\n# C\n");
+        assert_eq!(extract_tags(&updated), tags);
+    }
+
+    #[test]
+    fn test_database_reference_lines() {
+        let content = "# Plan\n[database:project-tasks](view:table)\ntext\n[database:Project Tasks](view:board) and [database:other](view:table)\n[database:project-tasks-2](view:table)\n";
+        assert_eq!(database_reference_lines(content, "project-tasks"), vec![2, 4]);
+        assert_eq!(database_reference_lines(content, "other"), vec![4]);
+        assert!(database_reference_lines(content, "missing").is_empty());
+    }
+
+    #[test]
+    fn test_notes_embedding_database_ignores_own_rows() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-db-refs-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("tasks")).unwrap();
+        std::fs::write(dir.join("dashboard.md"), "# Dash\n[database:tasks](view:table)\n[database:Tasks](view:board)\n").unwrap();
+        std::fs::write(dir.join("tasks/row-001.md"), "---\n---\n[database:tasks](view:table)\n").unwrap();
+        std::fs::write(dir.join("other.md"), "# Other\n").unwrap();
+
+        assert_eq!(notes_embedding_database(&dir, "tasks").unwrap(), vec!["dashboard"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_retarget_database_embeds() {
+        let content = "[database:tasks](view:table)\n[database:Tasks](view:board) [database:tasks-archive](view:table)\n";
+        assert_eq!(
+            retarget_database_embeds(content, "tasks", "team-tasks"),
+            "[database:team-tasks](view:table)\n[database:team-tasks](view:board) [database:tasks-archive](view:table)\n"
+        );
+    }
+
+    #[test]
+    fn test_db_get_row_impl() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-db-get-row-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let title = database::ColumnDef {
+            id: "title".to_string(),
+            name: "Title".to_string(),
+            col_type: database::ColumnType::Text,
+            options: None,
+            target: None,
+            on_delete: None,
+        };
+        let info = database::create_database(&dir, "Tasks", vec![title], None).unwrap();
+        let fields = HashMap::from([("title".to_string(), serde_json::json!("First"))]);
+        let row = database::create_row(&dir, &info.id, fields, None).unwrap();
+
+        let state = AppState::default();
+        state.app_config.write().unwrap().notes_folder = Some(dir.to_string_lossy().to_string());
+        let get = |db_id: &str, row_id: &str| {
+            tauri::async_runtime::block_on(db_get_row_impl(db_id.to_string(), row_id.to_string(), &state))
+        };
+
+        let found = get(&info.id, &row.id).unwrap();
+        assert_eq!(found["id"], row.id.as_str());
+        assert_eq!(found["fields"]["title"], "First");
+        assert!(found["etag"].is_string());
+        assert!(get(&info.id, "row-999").unwrap_err().contains("not found"));
+        assert!(get(&info.id, "../row-001").is_err());
+        assert!(get("missing", &row.id).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_note_checksum_cache_follows_mtime() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-checksum-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("a.md");
+        std::fs::write(&file, "# A\none").unwrap();
+        let folder = dir.to_string_lossy().to_string();
+        let state = AppState::default();
+
+        let first = note_checksum(&folder, "a", &state).unwrap();
+        assert_eq!(first, stories::compute_etag("# A\none"));
+
+        // Same size and mtime: the cached etag is served without re-reading
+        let mtime = std::fs::metadata(&file).unwrap().modified().unwrap();
+        std::fs::write(&file, "# A\ntwo").unwrap();
+        let set_mtime = |time| std::fs::File::options().write(true).open(&file).unwrap().set_modified(time).unwrap();
+        set_mtime(mtime);
+        assert_eq!(note_checksum(&folder, "a", &state).unwrap(), first);
+
+        // A changed mtime invalidates it
+        set_mtime(mtime + Duration::from_secs(5));
+        assert_eq!(note_checksum(&folder, "a", &state).unwrap(), stories::compute_etag("# A\ntwo"));
+        assert!(note_checksum(&folder, "missing", &state).is_err());
+        assert_eq!(state.checksum_cache.lock().unwrap().len(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_reindex_note_and_deleted() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-reindex-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("plan.md"), "# Plan\nquarterly zebra goals").unwrap();
+
+        let state = AppState::default();
+        state.app_config.write().unwrap().notes_folder = Some(dir.to_string_lossy().to_string());
+        *state.search_index.lock().unwrap() = Some(SearchIndex::new(&dir.join(".scratch").join("index")).unwrap());
+        let search = |state: &AppState| {
+            let index = state.search_index.lock().unwrap();
+            let index = index.as_ref().unwrap();
+            index.reader.reload().unwrap();
+            index.search("zebra", 10, 100).unwrap()
+        };
+
+        assert!(search(&state).is_empty());
+        reindex_note_impl("plan", &state).unwrap();
+        let results = search(&state);
+        assert_eq!(results.len(), 1);
+        assert_eq!((results[0].id.as_str(), results[0].title.as_str()), ("plan", "Plan"));
+
+        reindex_deleted_impl("plan", &state).unwrap();
+        assert!(search(&state).is_empty());
+        assert!(reindex_note_impl("missing", &state).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_db_rename_reindexes_moved_rows() {
+        let dir = std::env::temp_dir().join(format!("scratch-test-db-rename-index-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let title = database::ColumnDef {
+            id: "title".to_string(),
+            name: "Title".to_string(),
+            col_type: database::ColumnType::Text,
+            options: None,
+            target: None,
+            on_delete: None,
+        };
+        let info = database::create_database(&dir, "Tasks", vec![title], None).unwrap();
+        database::create_row(&dir, &info.id, HashMap::new(), Some("zebra errand, see [[Plan]]".to_string())).unwrap();
+
+        let state = AppState::default();
+        state.app_config.write().unwrap().notes_folder = Some(dir.to_string_lossy().to_string());
+        let search_index = SearchIndex::new(&dir.join(".scratch").join("index")).unwrap();
+        search_index.rebuild_index(&dir).unwrap();
+        *state.search_index.lock().unwrap() = Some(search_index);
+        *state.backlinks_index.write().unwrap() = rebuild_backlinks_index_from_folder(&dir.to_string_lossy());
+
+        let renamed = tauri::async_runtime::block_on(db_rename_impl(&info.id, "Chores", true, &state)).unwrap();
+        assert_eq!(renamed.id, "chores");
+
+        let index = state.search_index.lock().unwrap();
+        let index = index.as_ref().unwrap();
+        index.reader.reload().unwrap();
+        let ids: Vec<String> = index.search("zebra", 10, 100).unwrap().into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec!["chores/row-001".to_string()]);
+
+        let bl_index = state.backlinks_index.read().unwrap();
+        let sources: Vec<&str> = bl_index.links["plan"].iter().map(|e| e.note_id.as_str()).collect();
+        assert_eq!(sources, vec!["chores/row-001"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,16 +1,57 @@
 use axum::{
-    extract::State as AxumState,
-    http::StatusCode,
-    response::Json,
+    extract::{ConnectInfo, Path as AxumPath, Query, Request, State as AxumState},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        StatusCode,
+    },
+    middleware::{self, Next},
+    response::{IntoResponse, Json, Response},
     routing::{get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tower_http::cors::CorsLayer;
 
 use crate::AppState;
 
+// Most recent tool calls kept in memory; older entries are dropped
+pub const MAX_CALL_LOG_ENTRIES: usize = 200;
+
+/// Metadata for one MCP tool call. Arguments and results are not stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpCall {
+    pub timestamp: String,
+    pub tool: String,
+    pub success: bool,
+}
+
+fn push_call(log: &mut VecDeque<McpCall>, call: McpCall) {
+    while log.len() >= MAX_CALL_LOG_ENTRIES {
+        log.pop_front();
+    }
+    log.push_back(call);
+}
+
+fn record_call(state: &AppState, tool: &str, success: bool) {
+    let call = McpCall {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        tool: tool.to_string(),
+        success,
+    };
+    push_call(&mut state.mcp_call_log.lock().expect("mcp call log mutex"), call);
+}
+
+/// Newest-first view of the recorded tool calls, capped at `limit`.
+pub fn recent_calls(state: &AppState, limit: usize) -> Vec<McpCall> {
+    let log = state.mcp_call_log.lock().expect("mcp call log mutex");
+    log.iter().rev().take(limit).cloned().collect()
+}
+
 // JSON-RPC request/response types
 #[derive(Debug, Deserialize)]
 struct JsonRpcRequest {
@@ -164,7 +205,7 @@ fn get_tools() -> Value {
         },
         {
             "name": "scratch_append_to_note",
-            "description": "Append content to the end of an existing note.",
+            "description": "Append content to the end of an existing note on a new line (e.g. for logging). The note's trailing newline is preserved and the search index is updated.",
             "inputSchema": {
                 "type": "object",
                 "properties": {
@@ -180,6 +221,33 @@ fn get_tools() -> Value {
                 "required": ["id", "content"]
             }
         },
+        {
+            "name": "scratch_insert_under_heading",
+            "description": "Insert content under a specific heading in a note, at the start or end of that heading's section (the section ends at the next heading of the same or higher level). Errors with the list of available headings if not found.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "The note ID to insert into."
+                    },
+                    "heading": {
+                        "type": "string",
+                        "description": "Heading text to match, case-insensitive (e.g. 'Tasks'). Include # markers to match a specific level (e.g. '## Tasks')."
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "The markdown content to insert"
+                    },
+                    "position": {
+                        "type": "string",
+                        "enum": ["start", "end"],
+                        "description": "Insert right after the heading ('start') or at the end of its section ('end'). Defaults to 'end'."
+                    }
+                },
+                "required": ["id", "heading", "content"]
+            }
+        },
         {
             "name": "scratch_get_info",
             "description": "Get information about the Scratch notes setup: notes folder path, total note count, and current settings.",
@@ -293,6 +361,10 @@ fn get_tools() -> Value {
                     "max_distance": {
                         "type": "integer",
                         "description": "Maximum edit distance for fuzzy mode. Defaults to ~30% of query length (minimum 2)."
+                    },
+                    "whole_word": {
+                        "type": "boolean",
+                        "description": "Exact and fuzzy modes: only match whole words, so 'cat' doesn't match 'category'. Defaults to false."
                     }
                 },
                 "required": ["query"]
@@ -314,7 +386,7 @@ fn get_tools() -> Value {
                     },
                     "replace": {
                         "type": "string",
-                        "description": "The replacement text. For regex mode, supports backreferences ($1, $2, etc.)."
+                        "description": "The replacement text. For regex mode, supports backreferences ($1, ${name}; $$ for a literal $)."
                     },
                     "mode": {
                         "type": "string",
@@ -324,6 +396,10 @@ fn get_tools() -> Value {
                     "case_sensitive": {
                         "type": "boolean",
                         "description": "Whether the search is case-sensitive. Defaults to true."
+                    },
+                    "literal": {
+                        "type": "boolean",
+                        "description": "Regex mode only: insert the replacement verbatim, without expanding $ references. Defaults to false."
                     }
                 },
                 "required": ["id", "find", "replace"]
@@ -586,6 +662,18 @@ fn get_tools() -> Value {
                         },
                         "required": ["field"]
                     },
+                    "aggregates": {
+                        "type": "array",
+                        "description": "Summary values computed over all filtered rows (before pagination), returned as 'aggregates'. sum/avg/min/max use numeric values; count counts non-empty values.",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "column": { "type": "string", "description": "Column ID to aggregate" },
+                                "fn": { "type": "string", "enum": ["sum", "avg", "count", "min", "max"], "description": "Aggregate function" }
+                            },
+                            "required": ["column", "fn"]
+                        }
+                    },
                     "limit": {
                         "type": "integer",
                         "description": "Maximum number of rows to return. Defaults to 50."
@@ -650,6 +738,24 @@ fn get_tools() -> Value {
                 "required": ["database_id", "row_id", "etag", "fields"]
             }
         },
+        {
+            "name": "db_get_row",
+            "description": "Fetch a single database row with its current etag. Use this to refetch a row after an etag conflict from db_update_row.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "database_id": {
+                        "type": "string",
+                        "description": "Database folder path relative to notes root."
+                    },
+                    "row_id": {
+                        "type": "string",
+                        "description": "Row ID to fetch (e.g. 'row-001')."
+                    }
+                },
+                "required": ["database_id", "row_id"]
+            }
+        },
         {
             "name": "db_delete_row",
             "description": "Delete a row from a database by its ID. This permanently removes the row's markdown file.",
@@ -700,17 +806,137 @@ fn get_tools() -> Value {
     ])
 }
 
+// Per-IP rate limit (fixed window) to protect against runaway agents
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+const RATE_LIMIT_MAX_REQUESTS: u32 = 600;
+
+// State for the auth / rate-limit middleware
+#[derive(Clone)]
+struct RequestGuard {
+    state: AppState,
+    hits: Arc<Mutex<HashMap<IpAddr, (Instant, u32)>>>,
+}
+
+impl RequestGuard {
+    /// Count a request from `ip`; returns false once it exceeds the window budget.
+    fn allow(&self, ip: IpAddr) -> bool {
+        let mut hits = self.hits.lock().expect("rate limit mutex");
+        let now = Instant::now();
+
+        // Clean up stale windows periodically
+        if hits.len() > 1000 {
+            hits.retain(|_, (start, _)| now.duration_since(*start) < RATE_LIMIT_WINDOW);
+        }
+
+        let entry = hits.entry(ip).or_insert((now, 0));
+        if now.duration_since(entry.0) >= RATE_LIMIT_WINDOW {
+            *entry = (now, 0);
+        }
+        entry.1 += 1;
+        entry.1 <= RATE_LIMIT_MAX_REQUESTS
+    }
+}
+
+// Rate limiting + bearer-token auth (when `mcpToken` is set) for every route
+async fn guard_request(
+    AxumState(guard): AxumState<RequestGuard>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !guard.allow(addr.ip()) {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({ "error": "Rate limit exceeded" })),
+        )
+            .into_response();
+    }
+
+    // /health stays open so clients can probe the connection before authenticating
+    let token = if request.uri().path() == "/health" {
+        None
+    } else {
+        let settings = guard.state.settings.read().expect("settings read lock");
+        settings.mcp_token.clone().filter(|t| !t.is_empty())
+    };
+    if let Some(token) = token {
+        // Calendar apps can't send headers, so feeds may pass the token as ?token=
+        let query_token = is_calendar_feed_path(request.uri().path())
+            .then(|| request.uri().query())
+            .flatten()
+            .and_then(|q| {
+                url::form_urlencoded::parse(q.as_bytes())
+                    .find(|(k, _)| k == "token")
+                    .map(|(_, v)| v.into_owned())
+            });
+        let provided = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::to_string)
+            .or(query_token)
+            .unwrap_or_default();
+        if !crate::webhooks::constant_time_eq(provided.as_bytes(), token.as_bytes()) {
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(json!({ "error": "Missing or invalid bearer token" })),
+            )
+                .into_response();
+        }
+    }
+
+    next.run(request).await
+}
+
+fn is_calendar_feed_path(path: &str) -> bool {
+    path.starts_with("/db/") && path.ends_with("/calendar.ics")
+}
+
+pub const DEFAULT_MCP_HOST: &str = "127.0.0.1";
+
+/// Resolve the bind host from settings; non-loopback hosts require `allow_remote`.
+pub fn resolve_bind_host(host: Option<&str>, allow_remote: bool) -> Result<String, String> {
+    let host = host.map(str::trim).filter(|h| !h.is_empty()).unwrap_or(DEFAULT_MCP_HOST);
+    let is_loopback = host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().map(|ip| ip.is_loopback()).unwrap_or(false);
+
+    if !is_loopback && !allow_remote {
+        return Err(format!(
+            "Refusing to bind MCP server to '{}': enable mcpAllowRemote to expose it beyond this machine",
+            host
+        ));
+    }
+    Ok(host.to_string())
+}
+
 // Start the MCP HTTP server on a tokio task
-pub fn start_mcp_server(state: AppState, port: u16) -> tauri::async_runtime::JoinHandle<()> {
+pub fn start_mcp_server(
+    state: AppState,
+    host: String,
+    port: u16,
+) -> tauri::async_runtime::JoinHandle<()> {
     tauri::async_runtime::spawn(async move {
+        let guard = RequestGuard {
+            state: state.clone(),
+            hits: Arc::new(Mutex::new(HashMap::new())),
+        };
+
+        // CORS stays outermost so preflight requests are answered without auth
         let app = Router::new()
             .route("/mcp", post(handle_mcp))
             .route("/health", get(handle_health))
+            .route("/capabilities", get(handle_capabilities))
             .route("/webhooks/{plugin_name}", post(crate::webhooks::handle_webhook))
+            .route("/db/{db_id}/calendar.ics", get(handle_db_calendar))
+            .layer(middleware::from_fn_with_state(guard, guard_request))
             .layer(CorsLayer::permissive())
             .with_state(state);
 
-        let addr = format!("127.0.0.1:{}", port);
+        let addr = match host.parse::<IpAddr>() {
+            Ok(ip) => SocketAddr::new(ip, port).to_string(),
+            Err(_) => format!("{}:{}", host, port),
+        };
         let listener = match tokio::net::TcpListener::bind(&addr).await {
             Ok(l) => l,
             Err(e) => {
@@ -721,7 +947,7 @@ pub fn start_mcp_server(state: AppState, port: u16) -> tauri::async_runtime::Joi
 
         eprintln!("MCP server listening on http://{}", addr);
 
-        if let Err(e) = axum::serve(listener, app).await {
+        if let Err(e) = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await {
             eprintln!("MCP server error: {}", e);
         }
     })
@@ -729,25 +955,79 @@ pub fn start_mcp_server(state: AppState, port: u16) -> tauri::async_runtime::Joi
 
 // Health check endpoint
 async fn handle_health(AxumState(state): AxumState<AppState>) -> Json<Value> {
-    let folder = {
+    let notes_folder_set = {
         let app_config = state.app_config.read().expect("app_config read lock");
-        app_config.notes_folder.clone()
+        app_config.notes_folder.is_some()
     };
 
-    let note_count = {
-        let cache = state.notes_cache.read().expect("cache read lock");
-        cache.len()
-    };
+    Json(json!({
+        "ok": true,
+        "server": SERVER_NAME,
+        "version": SERVER_VERSION,
+        "notes_folder_set": notes_folder_set,
+        "tool_count": list_all_tools(&state).len(),
+    }))
+}
+
+// Capabilities endpoint — tool names and input schemas for connection validation
+async fn handle_capabilities(AxumState(state): AxumState<AppState>) -> Json<Value> {
+    let tools: Vec<Value> = list_all_tools(&state)
+        .into_iter()
+        .map(|tool| {
+            json!({
+                "name": tool.get("name").cloned().unwrap_or(Value::Null),
+                "inputSchema": tool.get("inputSchema").cloned().unwrap_or(Value::Null),
+            })
+        })
+        .collect();
 
     Json(json!({
-        "status": "ok",
         "server": SERVER_NAME,
         "version": SERVER_VERSION,
-        "notes_folder": folder,
-        "note_count": note_count,
+        "protocolVersion": MCP_PROTOCOL_VERSION,
+        "tools": tools,
     }))
 }
 
+/// Column choices for a database calendar feed; `token` is read by the auth guard
+#[derive(Debug, Deserialize)]
+struct CalendarFeedQuery {
+    date: Option<String>,
+    title: Option<String>,
+}
+
+// iCalendar feed of a database's dated rows, for calendar app subscriptions
+async fn handle_db_calendar(
+    AxumState(state): AxumState<AppState>,
+    AxumPath(db_id): AxumPath<String>,
+    Query(query): Query<CalendarFeedQuery>,
+) -> Response {
+    let notes_folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config.notes_folder.clone()
+    };
+    let Some(folder) = notes_folder else {
+        let error = json!({ "error": "Notes folder not set" });
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(error)).into_response();
+    };
+    let folder = std::path::PathBuf::from(folder);
+    if !crate::database::is_database_folder(&folder.join(&db_id)) {
+        let error = format!("'{}' is not a database folder", db_id);
+        return (StatusCode::NOT_FOUND, Json(json!({ "error": error }))).into_response();
+    }
+
+    let ics = crate::database::database_to_ics(
+        &folder,
+        &db_id,
+        query.date.as_deref(),
+        query.title.as_deref(),
+    );
+    match ics {
+        Ok(ics) => ([(CONTENT_TYPE, "text/calendar; charset=utf-8")], ics).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response(),
+    }
+}
+
 // Main MCP JSON-RPC handler
 async fn handle_mcp(
     AxumState(state): AxumState<AppState>,
@@ -803,8 +1083,8 @@ fn handle_initialize(id: Value) -> JsonRpcResponse {
     )
 }
 
-// MCP tools/list — includes built-in tools + plugin-defined tools
-fn handle_tools_list(id: Value, state: &AppState) -> JsonRpcResponse {
+// Built-in tools + plugin-defined tools
+fn list_all_tools(state: &AppState) -> Vec<Value> {
     let mut all_tools: Vec<Value> = serde_json::from_value(get_tools()).unwrap_or_default();
 
     // Load plugin-defined tools
@@ -818,7 +1098,12 @@ fn handle_tools_list(id: Value, state: &AppState) -> JsonRpcResponse {
         all_tools.extend(plugin_tools);
     }
 
-    JsonRpcResponse::success(id, json!({ "tools": all_tools }))
+    all_tools
+}
+
+// MCP tools/list
+fn handle_tools_list(id: Value, state: &AppState) -> JsonRpcResponse {
+    JsonRpcResponse::success(id, json!({ "tools": list_all_tools(state) }))
 }
 
 // MCP resources/list
@@ -862,6 +1147,7 @@ async fn handle_tools_call(
         "scratch_delete_note" => tool_delete_note(state, &arguments).await,
         "scratch_search_notes" => tool_search_notes(state, &arguments).await,
         "scratch_append_to_note" => tool_append_to_note(state, &arguments).await,
+        "scratch_insert_under_heading" => tool_insert_under_heading(state, &arguments).await,
         "scratch_get_info" => tool_get_info(state).await,
         "scratch_list_folders" => tool_list_folders(state, &arguments).await,
         "scratch_create_folder" => tool_create_folder(state, &arguments).await,
@@ -886,6 +1172,7 @@ async fn handle_tools_call(
         "db_query" => tool_db_query(state, &arguments).await,
         "db_insert_row" => tool_db_insert_row(state, &arguments).await,
         "db_update_row" => tool_db_update_row(state, &arguments).await,
+        "db_get_row" => tool_db_get_row(state, &arguments).await,
         "db_delete_row" => tool_db_delete_row(state, &arguments).await,
         "db_create" => tool_db_create(state, &arguments).await,
         // Plugin-defined tools (prefixed with "plugin_")
@@ -897,6 +1184,8 @@ async fn handle_tools_call(
         }
     };
 
+    record_call(state, tool_name, result.is_ok());
+
     match result {
         Ok(content) => JsonRpcResponse::success(
             id,
@@ -999,13 +1288,36 @@ async fn tool_append_to_note(state: &AppState, args: &Value) -> Result<String, S
         .and_then(|v| v.as_str())
         .ok_or("Missing required parameter: content")?;
 
-    // Read existing note
-    let existing = crate::read_note_impl(id.clone(), state).await?;
+    let note = crate::append_to_note_impl(id, append_content.to_string(), state).await?;
+    serde_json::to_string_pretty(&note).map_err(|e| e.to_string())
+}
+
+async fn tool_insert_under_heading(state: &AppState, args: &Value) -> Result<String, String> {
+    let id = args
+        .get("id")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing required parameter: id")?
+        .to_string();
 
-    // Append content
-    let new_content = format!("{}\n{}", existing.content, append_content);
+    let heading = args
+        .get("heading")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing required parameter: heading")?
+        .to_string();
 
-    let note = crate::save_note_impl(Some(id), new_content, state).await?;
+    let content = args
+        .get("content")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing required parameter: content")?
+        .to_string();
+
+    let position = args
+        .get("position")
+        .and_then(|v| v.as_str())
+        .unwrap_or("end")
+        .to_string();
+
+    let note = crate::insert_under_heading_impl(id, heading, content, position, state).await?;
     serde_json::to_string_pretty(&note).map_err(|e| e.to_string())
 }
 
@@ -1026,6 +1338,7 @@ async fn tool_get_info(state: &AppState) -> Result<String, String> {
         "notes_folder": folder,
         "note_count": note_count,
         "mcp_enabled": settings.mcp_enabled.unwrap_or(false),
+        "mcp_host": settings.mcp_host.as_deref().unwrap_or(DEFAULT_MCP_HOST),
         "mcp_port": settings.mcp_port.unwrap_or(3921),
         "git_enabled": settings.git_enabled.unwrap_or(false),
     });
@@ -1115,6 +1428,11 @@ async fn tool_find(state: &AppState, args: &Value) -> Result<String, String> {
         .and_then(|v| v.as_u64())
         .map(|v| v as usize);
 
+    let whole_word = args
+        .get("whole_word")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
     let result = crate::find_in_notes_impl(
         query,
         mode,
@@ -1122,6 +1440,7 @@ async fn tool_find(state: &AppState, args: &Value) -> Result<String, String> {
         case_sensitive,
         context_lines,
         max_distance,
+        whole_word,
         state,
     )
     .await?;
@@ -1159,7 +1478,12 @@ async fn tool_replace_in_note(state: &AppState, args: &Value) -> Result<String,
         .and_then(|v| v.as_bool())
         .unwrap_or(true);
 
-    let result = crate::replace_in_note_impl(id, find, replace, mode, case_sensitive, state).await?;
+    let literal = args
+        .get("literal")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let result = crate::replace_in_note_impl(id, find, replace, mode, case_sensitive, literal, state).await?;
     serde_json::to_string_pretty(&result).map_err(|e| e.to_string())
 }
 
@@ -1329,6 +1653,7 @@ async fn tool_db_query(state: &AppState, args: &Value) -> Result<String, String>
 
     let filters = args.get("filters").cloned();
     let sort = args.get("sort").cloned();
+    let aggregates = args.get("aggregates").cloned();
     let limit = args
         .get("limit")
         .and_then(|v| v.as_u64())
@@ -1338,7 +1663,7 @@ async fn tool_db_query(state: &AppState, args: &Value) -> Result<String, String>
         .and_then(|v| v.as_u64())
         .unwrap_or(0) as usize;
 
-    let result = crate::db_query_impl(database_id, filters, sort, limit, offset, state).await?;
+    let result = crate::db_query_impl(database_id, filters, sort, aggregates, limit, offset, state).await?;
     serde_json::to_string_pretty(&result).map_err(|e| e.to_string())
 }
 
@@ -1397,6 +1722,23 @@ async fn tool_db_update_row(state: &AppState, args: &Value) -> Result<String, St
     serde_json::to_string_pretty(&result).map_err(|e| e.to_string())
 }
 
+async fn tool_db_get_row(state: &AppState, args: &Value) -> Result<String, String> {
+    let database_id = args
+        .get("database_id")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing required parameter: database_id")?
+        .to_string();
+
+    let row_id = args
+        .get("row_id")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing required parameter: row_id")?
+        .to_string();
+
+    let result = crate::db_get_row_impl(database_id, row_id, state).await?;
+    serde_json::to_string_pretty(&result).map_err(|e| e.to_string())
+}
+
 async fn tool_db_delete_row(state: &AppState, args: &Value) -> Result<String, String> {
     let database_id = args
         .get("database_id")
@@ -1429,3 +1771,58 @@ async fn tool_db_create(state: &AppState, args: &Value) -> Result<String, String
     let result = crate::db_create_impl(name, columns, state).await?;
     serde_json::to_string_pretty(&result).map_err(|e| e.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_bind_host_defaults_to_loopback() {
+        assert_eq!(resolve_bind_host(None, false).unwrap(), "127.0.0.1");
+        assert_eq!(resolve_bind_host(Some("  "), false).unwrap(), "127.0.0.1");
+        assert_eq!(resolve_bind_host(Some("localhost"), false).unwrap(), "localhost");
+        assert_eq!(resolve_bind_host(Some("::1"), false).unwrap(), "::1");
+    }
+
+    #[test]
+    fn test_resolve_bind_host_requires_allow_remote() {
+        assert!(resolve_bind_host(Some("0.0.0.0"), false).is_err());
+        assert!(resolve_bind_host(Some("::"), false).is_err());
+        assert!(resolve_bind_host(Some("192.168.1.20"), false).is_err());
+        assert_eq!(resolve_bind_host(Some("0.0.0.0"), true).unwrap(), "0.0.0.0");
+    }
+
+    #[test]
+    fn test_list_all_tools_without_notes_folder() {
+        let state = AppState::default();
+        let tools = list_all_tools(&state);
+        assert_eq!(tools.len(), get_tools().as_array().unwrap().len());
+        assert!(tools
+            .iter()
+            .all(|t| t.get("name").is_some() && t.get("inputSchema").is_some()));
+    }
+
+    #[test]
+    fn test_call_log_is_bounded_and_newest_first() {
+        let state = AppState::default();
+        for i in 0..MAX_CALL_LOG_ENTRIES + 5 {
+            record_call(&state, &format!("tool_{}", i), i % 2 == 0);
+        }
+        assert_eq!(state.mcp_call_log.lock().unwrap().len(), MAX_CALL_LOG_ENTRIES);
+
+        let recent = recent_calls(&state, 2);
+        let last = MAX_CALL_LOG_ENTRIES + 4;
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].tool, format!("tool_{}", last));
+        assert!(recent[0].success);
+        assert_eq!(recent[1].tool, format!("tool_{}", last - 1));
+        assert!(!recent[1].success);
+    }
+
+    #[test]
+    fn test_is_calendar_feed_path() {
+        assert!(is_calendar_feed_path("/db/tasks/calendar.ics"));
+        assert!(!is_calendar_feed_path("/mcp"));
+        assert!(!is_calendar_feed_path("/webhooks/calendar.ics"));
+    }
+}
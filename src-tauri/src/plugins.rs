@@ -815,6 +815,96 @@ pub fn get_plugin_info(notes_folder: &str) -> Vec<PluginInfo> {
     infos
 }
 
+// ── Transforms ─────────────────────────────────────────────────────────────
+
+/// A content rewrite that can be applied to a note (e.g. a formatter).
+pub trait PluginTransform: Send + Sync {
+    fn name(&self) -> &str;
+    fn transform(&self, content: &str) -> Result<String, String>;
+}
+
+/// Built-in `tidy-markdown`: one space after heading markers and exactly one
+/// blank line around headings. Frontmatter and fenced code are left alone.
+pub struct TidyMarkdown;
+
+impl PluginTransform for TidyMarkdown {
+    fn name(&self) -> &str {
+        "tidy-markdown"
+    }
+
+    fn transform(&self, content: &str) -> Result<String, String> {
+        let mut out: Vec<String> = Vec::new();
+        let mut lines = content.lines().peekable();
+
+        // Copy frontmatter verbatim (YAML comments look like headings)
+        if lines.peek().is_some_and(|l| l.trim() == "---") {
+            out.push(lines.next().unwrap_or_default().to_string());
+            for line in lines.by_ref() {
+                out.push(line.to_string());
+                if line.trim() == "---" {
+                    break;
+                }
+            }
+        }
+        let body_start = out.len();
+
+        let mut in_fence = false;
+        let mut blank_after_heading = false;
+        for line in lines {
+            let trimmed = line.trim();
+            if blank_after_heading {
+                if trimmed.is_empty() {
+                    continue; // collapsed into the single blank line below
+                }
+                out.push(String::new());
+                blank_after_heading = false;
+            }
+
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+                out.push(line.to_string());
+                continue;
+            }
+            let level = trimmed.chars().take_while(|c| *c == '#').count();
+            let is_heading = !in_fence
+                && (1..=6).contains(&level)
+                && trimmed[level..].starts_with([' ', '\t'])
+                && !trimmed[level..].trim().is_empty();
+            if !is_heading {
+                out.push(line.to_string());
+                continue;
+            }
+
+            while out.len() > body_start + 1
+                && out[out.len() - 1].trim().is_empty()
+                && out[out.len() - 2].trim().is_empty()
+            {
+                out.pop();
+            }
+            if out.len() > body_start && !out[out.len() - 1].trim().is_empty() {
+                out.push(String::new());
+            }
+            out.push(format!("{} {}", &trimmed[..level], trimmed[level..].trim()));
+            blank_after_heading = true;
+        }
+
+        let mut result = out.join("\n");
+        if content.ends_with('\n') {
+            result.push('\n');
+        }
+        Ok(result)
+    }
+}
+
+/// Transforms available to `run_plugin_transform`, by name.
+pub fn builtin_transforms() -> Vec<Box<dyn PluginTransform>> {
+    vec![Box::new(TidyMarkdown)]
+}
+
+pub fn find_transform(name: &str) -> Option<Box<dyn PluginTransform>> {
+    builtin_transforms().into_iter().find(|t| t.name() == name)
+}
+
 // ── Dispatch plugin tools from MCP ─────────────────────────────────────────
 
 /// Attempt to handle a tool call as a plugin tool. Returns None if the tool
@@ -993,4 +1083,17 @@ mod tests {
             .unwrap()
             .starts_with("[github-sync]"));
     }
+
+    #[test]
+    fn test_tidy_markdown_heading_spacing() {
+        let input = "---\n# yaml comment\ntags: [a]\n---\n# Title\nIntro\n##   Section  \n\n\n\ntext\n```\n# not a heading\n```\n#tag stays\n";
+        let out = TidyMarkdown.transform(input).unwrap();
+        assert_eq!(
+            out,
+            "---\n# yaml comment\ntags: [a]\n---\n# Title\n\nIntro\n\n## Section\n\ntext\n```\n# not a heading\n```\n#tag stays\n"
+        );
+        assert_eq!(TidyMarkdown.transform(&out).unwrap(), out);
+        assert!(find_transform("tidy-markdown").is_some());
+        assert!(find_transform("missing").is_none());
+    }
 }
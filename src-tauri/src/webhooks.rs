@@ -138,13 +138,17 @@ fn verify_signature(secret: &str, body: &[u8], signature_header: &str) -> bool {
         .strip_prefix("sha256=")
         .unwrap_or(signature_header);
 
-    // Constant-time comparison
-    if expected_hex.len() != provided.len() {
+    constant_time_eq(expected_hex.as_bytes(), provided.as_bytes())
+}
+
+/// Compare two secrets without short-circuiting on the first differing byte.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
         return false;
     }
     let mut diff = 0u8;
-    for (a, b) in expected_hex.bytes().zip(provided.bytes()) {
-        diff |= a ^ b;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
     }
     diff == 0
 }
@@ -813,6 +817,14 @@ mod tests {
         assert!(!verify_signature(secret, body, "sha256=0000000000000000000000000000000000000000000000000000000000000000"));
     }
 
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret-token", b"secret-token"));
+        assert!(!constant_time_eq(b"secret-token", b"secret-tokem"));
+        assert!(!constant_time_eq(b"secret", b"secret-token"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
     #[test]
     fn test_chrono_now_format() {
         let ts = chrono_now();
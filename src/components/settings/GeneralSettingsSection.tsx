@@ -19,6 +19,7 @@ import {
   ArrowDownToLineIcon,
   UploadIcon,
 } from "../icons";
+import type { ImportReport } from "../../types/note";
 
 // Format remote URL for display - extract user/repo from full URL
 function formatRemoteUrl(url: string | null): string {
@@ -69,6 +70,7 @@ export function GeneralSettingsSection() {
   const [checkingUpdate, setCheckingUpdate] = useState(false);
   const [isExporting, setIsExporting] = useState(false);
   const [isImporting, setIsImporting] = useState(false);
+  const [skipDuplicates, setSkipDuplicates] = useState(false);
 
   useEffect(() => {
     getVersion()
@@ -187,8 +189,14 @@ export function GeneralSettingsSection() {
         return;
       }
       const pathList = Array.isArray(paths) ? paths : [paths];
-      const count = await invoke<number>("import_notes", { paths: pathList });
-      toast.success(`Imported ${count} note${count === 1 ? "" : "s"}`);
+      const { imported, skipped } = await invoke<ImportReport>("import_notes", {
+        paths: pathList,
+        dedupe: skipDuplicates ? "exact" : null,
+      });
+      toast.success(
+        `Imported ${imported} note${imported === 1 ? "" : "s"}` +
+          (skipped > 0 ? ` (${skipped} duplicate${skipped === 1 ? "" : "s"} skipped)` : "")
+      );
     } catch (e) {
       toast.error(`Import failed: ${e}`);
     }
@@ -587,6 +595,26 @@ export function GeneralSettingsSection() {
             <p className="text-xs text-text-muted mt-1.5">
               Import .md, .txt, or .html files. Zip import supports Notion exports.
             </p>
+            <div className="flex items-center justify-between mt-3">
+              <div>
+                <span className="text-sm font-medium">Skip duplicates</span>
+                <p className="text-xs text-text-muted mt-0.5">
+                  Don't import files identical to a note already in this folder
+                </p>
+              </div>
+              <button
+                onClick={() => setSkipDuplicates(!skipDuplicates)}
+                className={`relative w-10 h-[22px] rounded-full transition-colors cursor-pointer ${
+                  skipDuplicates ? "bg-accent" : "bg-bg-muted"
+                }`}
+              >
+                <span
+                  className={`absolute top-[3px] w-4 h-4 rounded-full bg-white transition-transform shadow-sm ${
+                    skipDuplicates ? "translate-x-[21px]" : "translate-x-[3px]"
+                  }`}
+                />
+              </button>
+            </div>
           </div>
         </div>
       </section>
@@ -38,7 +38,7 @@ export function McpSettingsSection() {
 
   const fetchPlugins = useCallback(async () => {
     try {
-      const list = await invoke<PluginInfo[]>("plugins_list");
+      const list = await invoke<PluginInfo[]>("list_plugins");
       setPlugins(list);
     } catch {
       // Plugins not available
@@ -64,7 +64,7 @@ export function McpSettingsSection() {
   const handlePluginToggle = async (pluginName: string, currentEnabled: boolean) => {
     setTogglingPlugin(pluginName);
     try {
-      await invoke("plugins_toggle", { name: pluginName, enabled: !currentEnabled });
+      await invoke("set_plugin_enabled", { name: pluginName, enabled: !currentEnabled });
       await fetchPlugins();
       toast.success(
         `Plugin "${pluginName}" ${!currentEnabled ? "enabled" : "disabled"}`
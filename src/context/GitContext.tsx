@@ -188,7 +188,7 @@ export function GitProvider({ children }: { children: ReactNode }) {
     let unlisten: (() => void) | undefined;
     let debounceTimer: number | undefined;
 
-    listen("file-change", () => {
+    listen("notes-changed-batch", () => {
       // Debounce git status refresh to avoid excessive calls
       if (debounceTimer) {
         clearTimeout(debounceTimer);
@@ -9,7 +9,7 @@ import {
   type ReactNode,
 } from "react";
 import { listen } from "@tauri-apps/api/event";
-import type { Note, NoteMetadata } from "../types/note";
+import type { Note, NoteMetadata, NotesChangedBatch } from "../types/note";
 import * as notesService from "../services/notes";
 import * as templatesService from "../services/templates";
 import type { SearchResult } from "../services/notes";
@@ -365,16 +365,17 @@ export function NotesProvider({ children }: { children: ReactNode }) {
     init();
   }, []);
 
-  // Listen for file change events and notify if current note changed externally
+  // Listen for batched file change events and notify if current note changed externally
   useEffect(() => {
     let isCancelled = false;
     let unlisten: (() => void) | undefined;
 
-    listen<{ changed_ids: string[] }>("file-change", (event) => {
+    listen<NotesChangedBatch>("notes-changed-batch", (event) => {
       // Don't process if effect was cleaned up
       if (isCancelled) return;
 
-      const changedIds = event.payload.changed_ids || [];
+      const { created = [], modified = [], deleted = [] } = event.payload;
+      const changedIds = [...created, ...modified, ...deleted];
 
       // Filter out notes we recently saved ourselves
       const externalChanges = changedIds.filter(
@@ -6,7 +6,8 @@ import {
   useCallback,
   type ReactNode,
 } from "react";
-import { getSettings, updateSettings } from "../services/notes";
+import { toast } from "sonner";
+import { checkEditorFont, getSettings, updateSettings } from "../services/notes";
 import type {
   ThemeSettings,
   EditorFontSettings,
@@ -204,6 +205,11 @@ export function ThemeProvider({ children }: ThemeProviderProps) {
           ...settings,
           editorFont: newFontSettings,
         });
+        const family = newFontSettings.baseFontFamily;
+        if (family && family !== settings.editorFont?.baseFontFamily) {
+          const warning = await checkEditorFont(family);
+          if (warning) toast.warning(warning);
+        }
       } catch (error) {
         console.error("Failed to save font settings:", error);
       }
@@ -1,9 +1,14 @@
 import { invoke } from "@tauri-apps/api/core";
 import type {
   ColumnDef,
+  ColumnType,
+  ColumnTypeChange,
   DatabaseInfo,
+  DatabaseReference,
   DatabaseRow,
   DatabaseSchema,
+  DatabaseWithSchema,
+  RelationIssue,
   RowTemplateInfo,
   ViewDef,
 } from "../types/database";
@@ -15,6 +20,11 @@ export async function listDatabases(): Promise<DatabaseInfo[]> {
   return invoke("db_list");
 }
 
+/** List all databases along with their schemas in one call */
+export async function listDatabasesWithSchemas(): Promise<DatabaseWithSchema[]> {
+  return invoke("db_list_with_schemas");
+}
+
 /** Create a new database */
 export async function createDatabase(
   name: string,
@@ -27,18 +37,98 @@ export async function createDatabase(
 /** Get full database: schema + all rows */
 export async function getDatabase(
   dbId: string
-): Promise<{ schema: DatabaseSchema; rows: DatabaseRow[] }> {
+): Promise<{ schema: DatabaseSchema; rows: DatabaseRow[]; active_view: string | null }> {
   return invoke("db_get", { dbId });
 }
 
+/** Get one page of rows (filename order) plus the total row count */
+export async function getDatabasePage(
+  dbId: string,
+  limit: number,
+  offset: number
+): Promise<{ schema: DatabaseSchema; rows: DatabaseRow[]; total: number }> {
+  return invoke("db_get_page", { dbId, limit, offset });
+}
+
+/** Run a saved view's filters and sort */
+export async function queryView(
+  dbId: string,
+  viewId: string,
+  limit?: number,
+  offset?: number
+): Promise<{ total: number; rows: (Omit<DatabaseRow, "path"> & { etag: string })[]; view_id: string }> {
+  return invoke("db_query_view", { dbId, viewId, limit, offset });
+}
+
+/** Remember the last-used view for a database */
+export async function setActiveView(
+  dbId: string,
+  viewId: string
+): Promise<DatabaseSchema> {
+  return invoke("db_set_active_view", { dbId, viewId });
+}
+
 /** Get just the schema for a database */
 export async function getSchema(dbId: string): Promise<DatabaseSchema> {
   return invoke("db_get_schema", { dbId });
 }
 
-/** Delete an entire database */
-export async function deleteDatabase(dbId: string): Promise<void> {
-  return invoke("db_delete", { dbId });
+/** Notes that embed this database, by slug or display name */
+export async function findDatabaseReferences(dbId: string): Promise<DatabaseReference[]> {
+  return invoke("find_database_references", { dbId });
+}
+
+/** Ids of notes (outside the database) that still embed it */
+export async function canDeleteDatabase(dbId: string): Promise<string[]> {
+  return invoke("db_can_delete", { dbId });
+}
+
+/** Delete an entire database; fails while notes embed it unless `force` is set */
+export async function deleteDatabase(dbId: string, force = false): Promise<void> {
+  return invoke("db_delete", { dbId, force });
+}
+
+/** Copy a database's schema, and optionally its rows, under a new name */
+export async function duplicateDatabase(
+  dbId: string,
+  newName: string,
+  includeRows: boolean
+): Promise<DatabaseInfo> {
+  return invoke("db_duplicate", { dbId, newName, includeRows });
+}
+
+/** Rename a database; with renameFolder its id becomes the new name's slug and embeds follow */
+export async function renameDatabase(
+  dbId: string,
+  newName: string,
+  renameFolder: boolean
+): Promise<DatabaseInfo> {
+  return invoke("db_rename", { dbId, newName, renameFolder });
+}
+
+/** Write a database (schema + rows) to a single JSON file */
+export async function exportDatabaseJson(dbId: string, dest: string): Promise<void> {
+  return invoke("db_export_json", { dbId, dest });
+}
+
+/** Recreate a database from a JSON export */
+export async function importDatabaseJson(path: string): Promise<DatabaseInfo> {
+  return invoke("db_import_json", { path });
+}
+
+// ---- Relation Integrity ----
+
+/** Relation values that point at missing rows */
+export async function checkRelations(dbId: string): Promise<RelationIssue[]> {
+  return invoke("db_check_relations", { dbId });
+}
+
+/** Clear dangling relation values, or flag them with a "missing:" prefix */
+export async function repairRelations(
+  dbId: string,
+  strategy: "clear" | "flag"
+): Promise<RelationIssue[]> {
+  return invoke("db_repair_relations", { dbId, strategy });
 }
 
 // ---- Row CRUD ----
@@ -67,6 +157,37 @@ export async function updateRow(
   });
 }
 
+/** Fetch one row with its current etag (e.g. to retry after an etag conflict) */
+export async function getRow(
+  dbId: string,
+  rowId: string
+): Promise<Omit<DatabaseRow, "path"> & { etag: string }> {
+  return invoke("db_get_row", { dbId, rowId });
+}
+
+/** Apply patch fields to every row matching the filter; returns the count updated */
+export async function bulkUpdateRows(
+  dbId: string,
+  filter: unknown[],
+  patch: Record<string, unknown>
+): Promise<number> {
+  return invoke("db_bulk_update", { dbId, filter, patch });
+}
+
+/** Delete every row matching the filter; an empty filter requires confirmAll */
+export async function bulkDeleteRows(
+  dbId: string,
+  filter: unknown[],
+  confirmAll?: boolean
+): Promise<number> {
+  return invoke("db_bulk_delete", { dbId, filter, confirmAll: confirmAll ?? null });
+}
+
+/** Move a row to newIndex in the manual order (persisted as a hidden position field) */
+export async function reorderRow(dbId: string, rowId: string, newIndex: number): Promise<void> {
+  return invoke("db_reorder_row", { dbId, rowId, newIndex });
+}
+
 /** Delete a row */
 export async function deleteRow(
   dbId: string,
@@ -108,6 +229,21 @@ export async function renameColumn(
   });
 }
 
+/** Change a column's type, converting row values. Lossy conversions need `force`. */
+export async function changeColumnType(
+  dbId: string,
+  columnId: string,
+  newType: ColumnType,
+  force?: boolean
+): Promise<ColumnTypeChange> {
+  return invoke("db_change_column_type", {
+    dbId,
+    columnId,
+    newType,
+    force: force ?? null,
+  });
+}
+
 /** Update the full schema (handles add/remove column migrations) */
 export async function updateSchema(
   dbId: string,
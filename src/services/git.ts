@@ -29,10 +29,18 @@ export async function initGitRepo(): Promise<void> {
   return invoke("git_init_repo");
 }
 
+export async function ensureGitignore(): Promise<void> {
+  return invoke("git_ensure_gitignore");
+}
+
 export async function gitCommit(message: string): Promise<GitResult> {
   return invoke("git_commit", { message });
 }
 
+export async function gitCommitNotes(ids: string[], message: string): Promise<GitResult> {
+  return invoke("git_commit_notes", { ids, message });
+}
+
 export async function gitPush(): Promise<GitResult> {
   return invoke("git_push");
 }
@@ -44,3 +52,24 @@ export async function addRemote(url: string): Promise<GitResult> {
 export async function pushWithUpstream(): Promise<GitResult> {
   return invoke("git_push_with_upstream");
 }
+
+export async function gitPull(): Promise<GitResult> {
+  return invoke("git_pull");
+}
+
+/** Note id -> porcelain status code (e.g. " M", "A ", "??") */
+export async function getGitStatusMap(): Promise<Record<string, string>> {
+  return invoke("git_status_map");
+}
+
+export async function listBranches(): Promise<string[]> {
+  return invoke("git_list_branches");
+}
+
+export async function createBranch(name: string): Promise<GitResult> {
+  return invoke("git_create_branch", { name });
+}
+
+export async function checkoutBranch(name: string): Promise<GitResult> {
+  return invoke("git_checkout_branch", { name });
+}
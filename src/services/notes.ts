@@ -1,5 +1,21 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Note, NoteMetadata, Settings } from "../types/note";
+import type {
+  ActivityEvent,
+  DiffLine,
+  ImportReport,
+  LinkRef,
+  LinkStatus,
+  McpCall,
+  McpStatus,
+  Note,
+  NoteMetadata,
+  ReplaceReport,
+  SavedSearch,
+  Settings,
+  ThemePreset,
+  ThemeSettings,
+  UndoResult,
+} from "../types/note";
 
 export async function getNotesFolder(): Promise<string | null> {
   return invoke("get_notes_folder");
@@ -76,10 +92,61 @@ export async function getBacklinks(noteTitle: string): Promise<BacklinkEntry[]>
   return invoke("get_backlinks", { noteTitle });
 }
 
+/** Backlinks to a note by id, matching its title, aliases, and id */
+export async function getBacklinksById(noteId: string): Promise<BacklinkEntry[]> {
+  return invoke("get_backlinks_by_id", { noteId });
+}
+
+/** Write the note link graph as a Graphviz .dot file */
+export async function exportLinkGraphDot(dest: string): Promise<void> {
+  return invoke("export_link_graph_dot", { dest });
+}
+
+/** Notes that no other note links to */
+export async function listOrphanNotes(): Promise<NoteMetadata[]> {
+  return invoke("list_orphan_notes");
+}
+
+export interface LinkFix {
+  sourceId: string;
+  line: number; // 1-based
+  brokenTarget: string;
+  suggestion: string | null; // closest existing title, if any is near enough
+}
+
+/** Wikilinks that don't resolve to a note, with suggested replacements */
+export async function suggestLinkFixes(): Promise<LinkFix[]> {
+  return invoke("suggest_link_fixes");
+}
+
+/** Retarget a link on the given line, keeping its alias, and save the note */
+export async function applyLinkFix(
+  sourceId: string,
+  line: number,
+  oldTarget: string,
+  newTarget: string
+): Promise<Note> {
+  return invoke("apply_link_fix", { sourceId, line, oldTarget, newTarget });
+}
+
+/** Number of distinct notes linking to the given title */
+export async function countBacklinks(noteTitle: string): Promise<number> {
+  return invoke("count_backlinks", { noteTitle });
+}
+
+export async function countBacklinksById(noteId: string): Promise<number> {
+  return invoke("count_backlinks_by_id", { noteId });
+}
+
 export async function rebuildBacklinks(): Promise<void> {
   return invoke("rebuild_backlinks");
 }
 
+/** Refresh backlinks from one note without rescanning the whole folder */
+export async function rebuildBacklinksFor(id: string): Promise<void> {
+  return invoke("rebuild_backlinks_for", { id });
+}
+
 export async function createFolder(folderPath: string): Promise<string> {
   return invoke("create_folder", { folderPath });
 }
@@ -95,3 +162,280 @@ export async function deleteFolder(folderPath: string): Promise<void> {
 export async function moveNote(id: string, destination: string): Promise<Note> {
   return invoke("move_note", { id, destination });
 }
+
+// Per-note UI state (open toggles, scroll position); the shape is owned by the caller
+export async function getNoteUiState<T = unknown>(id: string): Promise<T | null> {
+  return invoke("get_note_ui_state", { id });
+}
+
+export async function setNoteUiState(id: string, state: unknown): Promise<void> {
+  return invoke("set_note_ui_state", { id, state });
+}
+
+export async function listNotesInFolder(
+  folder: string | null,
+  sort?: "modified" | "manual"
+): Promise<NoteMetadata[]> {
+  return invoke("list_notes_in_folder", { folder, sort: sort ?? null });
+}
+
+/** Set a note's manual sort position; pass null to clear it */
+export async function setNoteOrder(id: string, order: number | null): Promise<Note> {
+  return invoke("set_note_order", { id, order });
+}
+
+/** Resolves when the note's frontmatter parses; rejects with a FrontmatterError otherwise */
+export async function validateFrontmatter(id: string): Promise<void> {
+  return invoke("validate_frontmatter", { id });
+}
+
+export interface Reminder {
+  noteId: string;
+  noteTitle: string;
+  line: number;
+  /** Unix seconds */
+  date: number;
+  text: string;
+}
+
+/** Reminders (`@remind(YYYY-MM-DD)`) dated within [from, to], soonest first */
+export async function listReminders(from: number, to: number): Promise<Reminder[]> {
+  return invoke("list_reminders", { from, to });
+}
+
+/** Markdown summary of goals and open tasks from daily notes in [start, end] (YYYY-MM-DD) */
+export async function rollupDailyNotes(start: string, end: string): Promise<string> {
+  return invoke("rollup_daily_notes", { start, end });
+}
+
+export interface IndexDiagnostics {
+  available: boolean;
+  docCount: number;
+  segmentCount: number;
+  sizeBytes: number;
+  noteCount: number;
+  inSync: boolean;
+  /** docCount - noteCount when they differ */
+  divergence: number | null;
+}
+
+export async function searchIndexDiagnostics(): Promise<IndexDiagnostics> {
+  return invoke("search_index_diagnostics");
+}
+
+/** Absolute file path for a note id, without reading its content */
+export async function getNotePath(id: string): Promise<string> {
+  return invoke("get_note_path", { id });
+}
+
+/** Open a note in the system's default editor for markdown files */
+export async function openInExternalEditor(id: string): Promise<void> {
+  return invoke("open_in_external_editor", { id });
+}
+
+/** HTML fragment of a note's body for hover cards, from at most maxChars of markdown */
+export async function renderNotePreview(id: string, maxChars: number): Promise<string> {
+  return invoke("render_note_preview", { id, maxChars });
+}
+
+/** Note content with ![[Title]] transclusions expanded */
+export async function readNoteExpanded(id: string): Promise<string> {
+  return invoke("read_note_expanded", { id });
+}
+
+export interface CodeBlock {
+  language: string; // empty when the fence has no info string
+  content: string;
+  startLine: number;
+}
+
+export async function extractCodeBlocks(id: string, lang?: string): Promise<CodeBlock[]> {
+  return invoke("extract_code_blocks", { id, lang: lang ?? null });
+}
+
+/** Writes each code block to destDir as <index>.<ext>; returns the count */
+export async function exportCodeBlocks(id: string, destDir: string): Promise<number> {
+  return invoke("export_code_blocks", { id, destDir });
+}
+
+/** Turn the MCP server on or off; the setting is persisted */
+export async function setMcpEnabled(enabled: boolean): Promise<McpStatus> {
+  return invoke("mcp_set_enabled", { enabled });
+}
+
+/** Change the MCP port, rebinding a running server */
+export async function setMcpPort(port: number): Promise<McpStatus> {
+  return invoke("mcp_set_port", { port });
+}
+
+/** Most recent MCP tool calls, newest first */
+export async function getMcpCallLog(limit: number): Promise<McpCall[]> {
+  return invoke("mcp_call_log", { limit });
+}
+
+/** Built-in theme presets (Solarized, Nord, Gruvbox, Sepia) */
+export async function listThemePresets(): Promise<ThemePreset[]> {
+  return invoke("list_theme_presets");
+}
+
+/** Fill the light or dark custom colors from a preset; the theme mode is unchanged */
+export async function applyThemePreset(
+  name: string,
+  mode: "light" | "dark"
+): Promise<ThemeSettings> {
+  return invoke("apply_theme_preset", { name, mode });
+}
+
+/** Write the current folder settings (theme, editor font, ...) to a JSON file */
+export async function exportSettings(dest: string): Promise<void> {
+  return invoke("export_settings", { dest });
+}
+
+/** Load settings from a JSON file exported by exportSettings and apply them */
+export async function importSettings(path: string): Promise<Settings> {
+  return invoke("import_settings", { path });
+}
+
+/** Installed system font family names, sorted */
+export async function listAvailableFonts(): Promise<string[]> {
+  return invoke("list_available_fonts");
+}
+
+/** Warning message when a font family isn't installed, or null */
+export async function checkEditorFont(family: string): Promise<string | null> {
+  return invoke("check_editor_font", { family });
+}
+
+/** Content hash of a note, in the same format as story/database etags */
+export async function getNoteChecksum(id: string): Promise<string> {
+  return invoke("get_note_checksum", { id });
+}
+
+/** Checksums keyed by note id; ids that aren't notes are left out */
+export async function getChecksums(ids: string[]): Promise<Record<string, string>> {
+  return invoke("get_checksums", { ids });
+}
+
+/** Recent note activity, newest first; `since` is an RFC 3339 timestamp */
+export async function getActivityLog(limit: number, since?: string): Promise<ActivityEvent[]> {
+  return invoke("get_activity_log", { limit, since });
+}
+
+/** Restore the previous snapshot of the most recently changed note */
+export async function undoLastChange(): Promise<UndoResult> {
+  return invoke("undo_last_change");
+}
+
+/** Line diff between two notes (frontmatter excluded) */
+export async function diffNotes(idA: string, idB: string): Promise<DiffLine[]> {
+  return invoke("diff_notes", { idA, idB });
+}
+
+/** Unlisted quick-capture text stored in .scratch/scratchpad.md */
+export async function getScratchpad(): Promise<string> {
+  return invoke("get_scratchpad");
+}
+
+export async function setScratchpad(content: string): Promise<void> {
+  return invoke("set_scratchpad", { content });
+}
+
+/** Save a named search; an existing search with the same name is overwritten */
+export async function saveSearch(
+  name: string,
+  query: string,
+  folder?: string
+): Promise<SavedSearch> {
+  return invoke("save_search", { name, query, folder });
+}
+
+export async function listSavedSearches(): Promise<SavedSearch[]> {
+  return invoke("list_saved_searches");
+}
+
+export async function deleteSavedSearch(name: string): Promise<void> {
+  return invoke("delete_saved_search", { name });
+}
+
+export async function runSavedSearch(name: string): Promise<SearchResult[]> {
+  return invoke("run_saved_search", { name });
+}
+
+/** Find and replace in every note under a folder; dryRun only reports matches */
+export async function replaceInFolder(
+  folder: string,
+  find: string,
+  replaceWith: string,
+  mode: "first" | "all" | "regex",
+  caseSensitive: boolean,
+  dryRun: boolean
+): Promise<ReplaceReport> {
+  return invoke("replace_in_folder", { folder, find, replaceWith, mode, caseSensitive, dryRun });
+}
+
+/** Re-index a single note after it was written outside the app */
+export async function reindexNote(id: string): Promise<void> {
+  return invoke("reindex_note", { id });
+}
+
+/** Remove a note from the search index after its file was deleted outside the app */
+export async function reindexDeleted(id: string): Promise<void> {
+  return invoke("reindex_deleted", { id });
+}
+
+/** Run a built-in transform (e.g. "tidy-markdown") on a note and save it */
+export async function runPluginTransform(plugin: string, noteId: string): Promise<Note> {
+  return invoke("run_plugin_transform", { plugin, noteId });
+}
+
+/** Align GFM table columns in a note (code blocks untouched) and save it */
+export async function formatTables(id: string): Promise<Note> {
+  return invoke("format_tables", { id });
+}
+
+export async function numberHeadings(id: string, enable: boolean): Promise<Note> {
+  return invoke("number_headings", { id, enable });
+}
+
+export async function extractLinks(id?: string): Promise<LinkRef[]> {
+  return invoke("extract_links", { id: id ?? null });
+}
+
+export async function checkLinks(urls: string[]): Promise<LinkStatus[]> {
+  return invoke("check_links", { urls });
+}
+
+export async function copyNoteAsHtml(id: string): Promise<void> {
+  return invoke("copy_note_as_html", { id });
+}
+
+// dedupe: "exact" skips identical notes, "similar" also near-identical ones
+export async function importFolder(
+  sourceDir: string,
+  preserveStructure: boolean,
+  dedupe?: "exact" | "similar"
+): Promise<ImportReport> {
+  return invoke("import_folder", {
+    sourceDir,
+    preserveStructure,
+    dedupe: dedupe ?? null,
+  });
+}
+
+// Zips notes and assets/ into destDir; pruneDays deletes older backups there
+export async function backupVault(destDir: string, pruneDays?: number): Promise<string> {
+  return invoke("backup_vault", { destDir, pruneDays: pruneDays ?? null });
+}
+
+export async function normalizeLineEndings(id: string): Promise<Note> {
+  return invoke("normalize_line_endings", { id });
+}
+
+// Both return the number of notes whose tags changed
+export async function addTagToNotes(ids: string[], tag: string): Promise<number> {
+  return invoke("add_tag_to_notes", { ids, tag });
+}
+
+export async function removeTagFromNotes(ids: string[], tag: string): Promise<number> {
+  return invoke("remove_tag_from_notes", { ids, tag });
+}
@@ -18,6 +18,13 @@ export interface TemplateNoteResult {
   cursorLine: number | null;
 }
 
+export interface SnippetInfo {
+  id: string;
+  name: string;
+  preview: string;
+  isBuiltin: boolean;
+}
+
 export async function listTemplates(): Promise<TemplateInfo[]> {
   return invoke("list_templates");
 }
@@ -28,7 +35,17 @@ export async function readTemplate(id: string): Promise<string> {
 
 export async function createNoteFromTemplate(
   templateId: string,
-  title?: string
+  title?: string,
+  selection?: string
 ): Promise<TemplateNoteResult> {
-  return invoke("create_note_from_template", { templateId, title });
+  return invoke("create_note_from_template", { templateId, title, selection });
+}
+
+export async function listSnippets(): Promise<SnippetInfo[]> {
+  return invoke("list_snippets");
+}
+
+/** Snippet text with date/time variables expanded, ready to insert */
+export async function getSnippet(id: string): Promise<string> {
+  return invoke("get_snippet", { id });
 }
@@ -22,6 +22,8 @@ export interface ColumnDef {
   options?: string[];
   /** For relation: target database folder name */
   target?: string;
+  /** For relation: on deleting a target row, clear references (default) or refuse */
+  on_delete?: "set_null" | "restrict";
   /** For rollup: which relation column to aggregate through */
   relation?: string;
   /** For rollup: which column in the target database to aggregate */
@@ -100,6 +102,8 @@ export interface RowTemplateInfo {
   fields: Record<string, unknown>;
   /** Optional markdown body content */
   body?: string;
+  /** Variable names referenced by the template, to prompt for */
+  variables: string[];
 }
 
 // Full database schema (parsed from _schema.md frontmatter)
@@ -110,6 +114,14 @@ export interface DatabaseSchema {
   /** Named row templates for quick row creation */
   templates?: Record<string, RowTemplate>;
   next_row_id: number;
+  /** Last-used view id, restored when the database is reopened */
+  default_view?: string;
+}
+
+// Result of changing a column's type
+export interface ColumnTypeChange extends DatabaseSchema {
+  /** Row ids whose values could not be converted and kept their raw value */
+  unconverted_rows: string[];
 }
 
 // A single database row (parsed from row-NNN.md)
@@ -124,6 +136,27 @@ export interface DatabaseRow {
   path: string;
   /** Last modified timestamp (unix seconds) */
   modified: number;
+  /** Creation time (RFC 3339), kept in the row's frontmatter */
+  created_at?: string;
+  /** Last update time (RFC 3339), kept in the row's frontmatter */
+  updated_at?: string;
+  /** Manual sort position (set by reorderRow); sort by "position" to use it */
+  position?: number;
+}
+
+// A relation value pointing at a row that no longer exists
+export interface RelationIssue {
+  row_id: string;
+  /** Relation column id */
+  column: string;
+  dangling_value: string;
+}
+
+// A note line that embeds a database via [database:name](view:...)
+export interface DatabaseReference {
+  note_id: string;
+  /** 1-based line number */
+  line: number;
 }
 
 // Summary info about a database (for listing)
@@ -140,6 +173,12 @@ export interface DatabaseInfo {
   path: string;
 }
 
+/** Database summary together with its schema */
+export interface DatabaseWithSchema {
+  info: DatabaseInfo;
+  schema: DatabaseSchema;
+}
+
 // Field value types mapped from ColumnType
 export type FieldValue = string | number | boolean | string[] | null;
 
@@ -4,6 +4,8 @@ export interface NoteMetadata {
   preview: string;
   modified: number;
   icon?: string;
+  /** Manual sort position from the `order` frontmatter field */
+  order?: number;
 }
 
 export interface Note {
@@ -14,14 +16,32 @@ export interface Note {
   modified: number;
 }
 
+export interface ThemeColors {
+  bg?: string;
+  bgSecondary?: string;
+  bgMuted?: string;
+  bgEmphasis?: string;
+  text?: string;
+  textMuted?: string;
+  textInverse?: string;
+  border?: string;
+  accent?: string;
+}
+
 export interface ThemeSettings {
   mode: "light" | "dark" | "system";
+  customLightColors?: ThemeColors | null;
+  customDarkColors?: ThemeColors | null;
+}
+
+export interface ThemePreset {
+  name: string;
 }
 
 export type FontFamily = "system-sans" | "serif" | "monospace";
 
 export interface EditorFontSettings {
-  baseFontFamily?: FontFamily;
+  baseFontFamily?: FontFamily | (string & {}); // generic family or an installed font name
   baseFontSize?: number; // in px, default 16
   boldWeight?: number; // 600, 700, 800 for headings and bold text
   lineHeight?: number; // default 1.6
@@ -33,13 +53,40 @@ export interface Settings {
   editorFont?: EditorFontSettings;
   gitEnabled?: boolean;
   pinnedNoteIds?: string[];
+  legacyFileChangeEvents?: boolean; // also emit per-file "file-change" events
   mcpEnabled?: boolean;
   mcpPort?: number;
+  mcpHost?: string; // defaults to 127.0.0.1
+  mcpAllowRemote?: boolean; // required to bind to a non-loopback host
+  mcpToken?: string; // bearer token required by the MCP server when set
+  imageMaxWidth?: number; // downscale pasted/imported images wider than this (px)
+  imageJpegQuality?: number; // 1-100, default 85
+  imageFormat?: "png" | "webp"; // format for pasted images, default "png"
+  dailyNoteFormat?: string; // daily note id pattern using template date tokens, default "YYYY-MM-DD"
+  previewLength?: number; // search result preview length in characters, default 100
+  snapshotOnPull?: boolean; // snapshot notes a git pull will overwrite, default true
 }
 
 export interface McpStatus {
   running: boolean;
+  host: string;
   port: number;
+  authEnabled: boolean;
+  error?: string | null;
+}
+
+// One recorded MCP tool call (metadata only)
+export interface McpCall {
+  timestamp: string;
+  tool: string;
+  success: boolean;
+}
+
+// Payload of the "notes-changed-batch" event emitted by the file watcher
+export interface NotesChangedBatch {
+  created: string[];
+  modified: string[];
+  deleted: string[];
 }
 
 export interface PluginValidation {
@@ -68,3 +115,67 @@ export interface WebhookLogEntry {
   error?: string;
   note_id?: string;
 }
+
+// A frontmatter parse failure; line/column are 1-based positions in the note file
+export interface FrontmatterError {
+  message: string;
+  line: number | null;
+  column: number | null;
+}
+
+// One entry of the vault-wide activity log (.scratch/activity.jsonl)
+export interface ActivityEvent {
+  timestamp: string;
+  action: string; // "create" | "save" | "delete" | "trash" | "restore" | "move" | "rename_folder" | "delete_folder"
+  note_id: string; // folder path for folder actions
+  details: unknown;
+}
+
+// Result of undo_last_change; note is null when there was nothing to undo
+export interface UndoResult {
+  note: Note | null;
+  message: string;
+}
+
+export interface DiffLine {
+  kind: "equal" | "insert" | "delete";
+  content: string;
+  oldLine: number | null; // 1-based
+  newLine: number | null; // 1-based
+}
+
+// Named, re-runnable search stored in .scratch/saved_searches.json
+export interface SavedSearch {
+  name: string;
+  query: string;
+  folder?: string; // only notes under this folder
+}
+
+// Result of replace_in_folder
+export interface ReplaceReport {
+  folder: string;
+  dryRun: boolean;
+  notesScanned: number;
+  totalReplacements: number;
+  changed: { noteId: string; replacements: number }[];
+}
+
+// Result of import_notes / import_folder; skipped files duplicated existing notes
+export interface ImportReport {
+  imported: number;
+  skipped: number;
+}
+
+// External link found by extract_links
+export interface LinkRef {
+  noteId: string;
+  line: number; // 1-based
+  url: string;
+  text: string;
+}
+
+export interface LinkStatus {
+  url: string;
+  status: number | null; // null when the request itself failed
+  error: string | null;
+}